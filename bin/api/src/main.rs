@@ -1,13 +1,29 @@
-use common::config::{self, LoadFromEnv as _};
+use common::{
+    chains::ChainRegistry,
+    config::{self, LoadFromEnv as _},
+};
 use database::connect::connect;
 use eyre::Result;
-use tracing::info;
+use tracing::{info, warn};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
     let cfg = config::ApiConfig::load()?;
-    let primary_db_conn = connect(&cfg.database.url).await?;
+    let primary_db_conn = connect(&cfg.database.url, cfg.database.statement_timeout_ms).await?;
     info!("Connected to Primary Database");
-    api_lib::start_api(primary_db_conn, cfg.port).await
+
+    // Chain names and start heights come from the indexer's section of the config
+    let chains =
+        match config::IndexerConfig::load().and_then(|cfg| ChainRegistry::from_config(&cfg)) {
+            Ok(registry) => registry.iter().cloned().collect(),
+            Err(e) => {
+                warn!(
+                    "No indexer chains configured, sync progress will be empty: {:?}",
+                    e
+                );
+                Vec::new()
+            }
+        };
+    api_lib::start_api(primary_db_conn, &cfg, chains).await
 }
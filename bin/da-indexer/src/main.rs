@@ -11,10 +11,16 @@ async fn main() -> Result<()> {
 
     let cfg = config::DaIndexerConfig::load()?;
 
-    let db_conn = database::connect::connect(&cfg.blockscout.url).await?;
+    let db_conn =
+        database::connect::connect(&cfg.database.url, cfg.database.statement_timeout_ms).await?;
+    info!("Connected to the indexer DB");
+
+    let blockscout_conn =
+        database::connect::connect(&cfg.blockscout.url, cfg.blockscout.statement_timeout_ms)
+            .await?;
     info!("Connected to Blockscout's DB");
 
-    let db_client = DbClient::new(&db_conn);
+    let db_client = DbClient::new(&db_conn).with_blockscout(&blockscout_conn);
 
     let arc_db = Arc::new(db_client);
     let provider = CelestiaProvider::new(cfg.celestia, arc_db).await;
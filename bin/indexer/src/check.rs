@@ -0,0 +1,131 @@
+use common::config::{self, ChainConfig, DatabaseConfig, SvmConfig};
+use evm::provider::EvmProvider;
+use eyre::{eyre, Result};
+use migration::{Migrator, MigratorTrait};
+use svm::provider::SvmProvider;
+use tracing::info;
+
+/// Outcome of checking one resource the indexer depends on
+pub struct CheckResult {
+    pub resource: String,
+    pub outcome: Result<String>,
+}
+
+/// Per-resource connectivity report printed by `--check`
+#[derive(Default)]
+pub struct CheckReport {
+    pub results: Vec<CheckResult>,
+}
+
+impl CheckReport {
+    fn record(&mut self, resource: impl Into<String>, outcome: Result<String>) {
+        self.results.push(CheckResult {
+            resource: resource.into(),
+            outcome,
+        });
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.results.iter().all(|result| result.outcome.is_ok())
+    }
+
+    /// Process exit code, non-zero when any resource failed
+    pub fn exit_code(&self) -> i32 {
+        if self.is_ok() {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// One `OK` or `FAIL` line per resource
+    pub fn lines(&self) -> Vec<String> {
+        self.results
+            .iter()
+            .map(|result| match &result.outcome {
+                Ok(detail) => format!("OK    {:<20} {}", result.resource, detail),
+                Err(e) => format!("FAIL  {:<20} {:#}", result.resource, e),
+            })
+            .collect()
+    }
+
+    pub fn print(&self) {
+        for line in self.lines() {
+            println!("{}", line);
+        }
+    }
+}
+
+/// Checks every configured chain's RPC and both database connections
+/// without starting any indexer
+pub async fn run_checks(cfg: &config::IndexerConfig) -> CheckReport {
+    let mut report = CheckReport::default();
+
+    report.record("indexer db", check_indexer_db(&cfg.database).await);
+    report.record("blockscout db", check_blockscout_db(&cfg.blockscout).await);
+    check_rpcs(cfg, &mut report).await;
+
+    report
+}
+
+/// Records the RPC check of every enabled chain in `report`
+pub async fn check_rpcs(cfg: &config::IndexerConfig, report: &mut CheckReport) {
+    let evm_chains = [
+        ("Twine", &cfg.twine.common),
+        ("Ethereum", &cfg.l1s.ethereum.common),
+        ("Arbitrum", &cfg.l1s.arbitrum.common),
+        ("Base", &cfg.l1s.base.common),
+    ];
+    for (name, chain) in evm_chains {
+        if !cfg.is_chain_enabled(name) {
+            info!("{} indexer disabled, skipping its check", name);
+            continue;
+        }
+        report.record(format!("{} rpc", name), check_evm_rpc(chain).await);
+    }
+
+    if cfg.is_chain_enabled("Solana") {
+        report.record("Solana rpc", check_svm_rpc(&cfg.l1s.solana).await);
+    } else {
+        info!("Solana indexer disabled, skipping its check");
+    }
+}
+
+async fn check_indexer_db(db: &DatabaseConfig) -> Result<String> {
+    let conn = database::connect::connect(&db.url, db.statement_timeout_ms).await?;
+    let pending = Migrator::get_pending_migrations(&conn).await?;
+    if !pending.is_empty() {
+        return Err(eyre!("{} migrations not applied", pending.len()));
+    }
+    Ok("connected, migrations applied".to_string())
+}
+
+async fn check_blockscout_db(db: &DatabaseConfig) -> Result<String> {
+    let conn = database::connect::connect(&db.url, db.statement_timeout_ms).await?;
+    conn.ping().await?;
+    Ok("connected".to_string())
+}
+
+async fn check_evm_rpc(chain: &ChainConfig) -> Result<String> {
+    let provider = EvmProvider::new(&chain.http_rpc_url, chain.chain_id);
+
+    let rpc_chain_id = provider.get_rpc_chain_id().await?;
+    if rpc_chain_id != chain.chain_id {
+        return Err(eyre!(
+            "RPC reports chain id {}, configured {}",
+            rpc_chain_id,
+            chain.chain_id
+        ));
+    }
+
+    let head = provider.get_block_number().await?;
+    Ok(format!("chain id {}, head {}", rpc_chain_id, head))
+}
+
+async fn check_svm_rpc(svm: &SvmConfig) -> Result<String> {
+    // Solana nodes have no chain id to compare, so reaching the head is the check
+    let provider = SvmProvider::new(&svm.common.http_rpc_url, svm.common.chain_id)?
+        .with_commitment(svm.commitment);
+    let slot = provider.get_slot().await?;
+    Ok(format!("head slot {}", slot))
+}
@@ -0,0 +1,280 @@
+use std::path::PathBuf;
+
+use eyre::{eyre, Result};
+use sea_orm::prelude::DateTimeWithTimeZone;
+
+const USAGE: &str =
+    "usage: indexer [--parse-only | --dry-run] --chain <name> --from <block> --to <block>\n       \
+                     indexer [--reconcile-flows <chain> --from <twine block> --to <twine block>]\n       \
+                     indexer [--repair-batch <batch number>]\n       \
+                     indexer [--replay-unparsed <chain>]\n       \
+                     indexer [--export <chain> <from timestamp> <to timestamp> --out <path>]\n       \
+                     indexer [--check]";
+
+/// What the indexer binary was asked to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Index all configured chains
+    Run,
+    /// Parse a block range of one chain and report what would be written,
+    /// without writing anything
+    ParseOnly { chain: String, from: u64, to: u64 },
+    /// Re-index a range of Twine blocks and insert the transaction flows that
+    /// are missing for deposits from `chain`
+    ReconcileFlows { chain: String, from: u64, to: u64 },
+    /// Re-fetch the block range of a batch and tag the L2 blocks and
+    /// transactions that are missing from it
+    RepairBatch { batch_number: u64 },
+    /// Re-process the blocks holding dead-lettered logs of `chain`, oldest first
+    ReplayUnparsed { chain: String },
+    /// Write the bridge transactions `chain` submitted between two RFC 3339
+    /// timestamps to `out` as newline-delimited JSON
+    Export {
+        chain: String,
+        from: DateTimeWithTimeZone,
+        to: DateTimeWithTimeZone,
+        out: PathBuf,
+    },
+    /// Check RPC and database connectivity, report per resource and exit
+    Check,
+}
+
+impl Command {
+    /// Parses the process arguments, a truthy `DRY_RUN` env var defaulting
+    /// to `--dry-run`
+    pub fn from_args() -> Result<Self> {
+        let dry_run = std::env::var("DRY_RUN")
+            .map(|value| matches!(value.trim(), "1" | "true" | "TRUE" | "True"))
+            .unwrap_or(false);
+        Self::parse_with_default_dry_run(std::env::args().skip(1), dry_run)
+    }
+
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        Self::parse_with_default_dry_run(args, false)
+    }
+
+    /// Parses `args` with `dry_run` as the default of `--dry-run`. Any other
+    /// mode given explicitly takes precedence over the default.
+    pub fn parse_with_default_dry_run<I: IntoIterator<Item = String>>(
+        args: I,
+        dry_run: bool,
+    ) -> Result<Self> {
+        let mut parse_only = false;
+        let mut check = false;
+        let mut reconcile_flows = None;
+        let mut repair_batch = None;
+        let mut replay_unparsed = None;
+        let mut export = None;
+        let mut out = None;
+        let mut chain = None;
+        let mut from = None;
+        let mut to = None;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--parse-only" | "--dry-run" => parse_only = true,
+                "--check" => check = true,
+                "--reconcile-flows" => reconcile_flows = Some(value(&arg, args.next())?),
+                "--repair-batch" => repair_batch = Some(block(&arg, args.next())?),
+                "--replay-unparsed" => replay_unparsed = Some(value(&arg, args.next())?),
+                "--export" => {
+                    let chain = value(&arg, args.next())?;
+                    let from = timestamp(&arg, args.next())?;
+                    let to = timestamp(&arg, args.next())?;
+                    export = Some((chain, from, to));
+                }
+                "--out" => out = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--chain" => chain = Some(value(&arg, args.next())?),
+                "--from" => from = Some(block(&arg, args.next())?),
+                "--to" => to = Some(block(&arg, args.next())?),
+                other => return Err(eyre!("Unknown argument '{}'\n{}", other, USAGE)),
+            }
+        }
+
+        let other_mode = check
+            || export.is_some()
+            || reconcile_flows.is_some()
+            || repair_batch.is_some()
+            || replay_unparsed.is_some();
+        let parse_only = parse_only || (dry_run && !other_mode);
+
+        if parse_only && reconcile_flows.is_some() {
+            return Err(eyre!(
+                "--parse-only and --reconcile-flows are mutually exclusive\n{}",
+                USAGE
+            ));
+        }
+
+        if check {
+            if parse_only
+                || reconcile_flows.is_some()
+                || repair_batch.is_some()
+                || replay_unparsed.is_some()
+            {
+                return Err(eyre!(
+                    "--check can't be combined with other modes\n{}",
+                    USAGE
+                ));
+            }
+            return Ok(Command::Check);
+        }
+
+        if let Some((chain, from, to)) = export {
+            if parse_only
+                || check
+                || reconcile_flows.is_some()
+                || repair_batch.is_some()
+                || replay_unparsed.is_some()
+            {
+                return Err(eyre!(
+                    "--export can't be combined with other modes\n{}",
+                    USAGE
+                ));
+            }
+            if from >= to {
+                return Err(eyre!(
+                    "--export start ({}) must be before its end ({})",
+                    from,
+                    to
+                ));
+            }
+            let out = out.ok_or_else(|| eyre!("--export requires --out\n{}", USAGE))?;
+            return Ok(Command::Export {
+                chain,
+                from,
+                to,
+                out,
+            });
+        }
+
+        if let Some(chain) = replay_unparsed {
+            if parse_only || reconcile_flows.is_some() || repair_batch.is_some() {
+                return Err(eyre!(
+                    "--replay-unparsed can't be combined with other modes\n{}",
+                    USAGE
+                ));
+            }
+            return Ok(Command::ReplayUnparsed { chain });
+        }
+
+        if let Some(batch_number) = repair_batch {
+            if parse_only || reconcile_flows.is_some() {
+                return Err(eyre!(
+                    "--repair-batch can't be combined with other modes\n{}",
+                    USAGE
+                ));
+            }
+            return Ok(Command::RepairBatch { batch_number });
+        }
+
+        let mode = if parse_only {
+            "--parse-only"
+        } else if reconcile_flows.is_some() {
+            "--reconcile-flows"
+        } else {
+            return Ok(Command::Run);
+        };
+
+        let from = from.ok_or_else(|| eyre!("{} requires --from\n{}", mode, USAGE))?;
+        let to = to.ok_or_else(|| eyre!("{} requires --to\n{}", mode, USAGE))?;
+        if from > to {
+            return Err(eyre!("--from ({}) must not be after --to ({})", from, to));
+        }
+
+        if let Some(chain) = reconcile_flows {
+            return Ok(Command::ReconcileFlows { chain, from, to });
+        }
+
+        let chain = chain.ok_or_else(|| eyre!("--parse-only requires --chain\n{}", USAGE))?;
+        Ok(Command::ParseOnly { chain, from, to })
+    }
+}
+
+fn value(flag: &str, value: Option<String>) -> Result<String> {
+    value.ok_or_else(|| eyre!("{} expects a value\n{}", flag, USAGE))
+}
+
+fn block(flag: &str, value_arg: Option<String>) -> Result<u64> {
+    let raw = value(flag, value_arg)?;
+    raw.parse()
+        .map_err(|_| eyre!("{} expects a block number, got '{}'", flag, raw))
+}
+
+fn timestamp(flag: &str, value_arg: Option<String>) -> Result<DateTimeWithTimeZone> {
+    let raw = value(flag, value_arg)?;
+    DateTimeWithTimeZone::parse_from_rfc3339(&raw)
+        .map_err(|_| eyre!("{} expects RFC 3339 timestamps, got '{}'", flag, raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn dry_run_default_selects_parse_only() {
+        let command = Command::parse_with_default_dry_run(
+            args(&["--chain", "ethereum", "--from", "1", "--to", "2"]),
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            command,
+            Command::ParseOnly {
+                chain: "ethereum".to_string(),
+                from: 1,
+                to: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn explicit_modes_take_precedence_over_dry_run_default() {
+        assert_eq!(
+            Command::parse_with_default_dry_run(args(&["--check"]), true).unwrap(),
+            Command::Check
+        );
+        assert_eq!(
+            Command::parse_with_default_dry_run(args(&["--repair-batch", "7"]), true).unwrap(),
+            Command::RepairBatch { batch_number: 7 }
+        );
+        assert_eq!(
+            Command::parse_with_default_dry_run(args(&["--replay-unparsed", "solana"]), true)
+                .unwrap(),
+            Command::ReplayUnparsed {
+                chain: "solana".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn dry_run_default_without_a_range_is_rejected() {
+        assert!(Command::parse_with_default_dry_run(Vec::new(), true).is_err());
+        assert_eq!(Command::parse(Vec::new()).unwrap(), Command::Run);
+    }
+
+    #[test]
+    fn dry_run_flag_is_parse_only() {
+        assert_eq!(
+            Command::parse(args(&[
+                "--dry-run",
+                "--chain",
+                "base",
+                "--from",
+                "5",
+                "--to",
+                "5"
+            ]))
+            .unwrap(),
+            Command::ParseOnly {
+                chain: "base".to_string(),
+                from: 5,
+                to: 5,
+            }
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use database::{
+    client::DbClient,
+    entities::{source_transactions, transaction_flows},
+};
+use eyre::{Result, WrapErr};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use serde_json::json;
+use tracing::info;
+
+/// Rows fetched per query while exporting
+const EXPORT_PAGE_SIZE: u64 = 1_000;
+
+/// Writes the bridge transactions `chain_id` submitted in `from..to` to `out`,
+/// one JSON object per line. Rows are read page by page, so memory use does
+/// not grow with the size of the window.
+pub async fn export_bridge_transactions(
+    db: &DbClient,
+    chain_id: i64,
+    from: DateTimeWithTimeZone,
+    to: DateTimeWithTimeZone,
+    out: &Path,
+) -> Result<u64> {
+    let file = File::create(out).wrap_err_with(|| format!("Failed to create {}", out.display()))?;
+    let mut writer = BufWriter::new(file);
+
+    let exported = write_export(db, chain_id, from, to, EXPORT_PAGE_SIZE, &mut writer).await?;
+
+    writer.flush()?;
+    Ok(exported)
+}
+
+/// Writes the export to `writer`, reading `page_size` rows per query
+async fn write_export(
+    db: &DbClient,
+    chain_id: i64,
+    from: DateTimeWithTimeZone,
+    to: DateTimeWithTimeZone,
+    page_size: u64,
+    mut writer: impl Write,
+) -> Result<u64> {
+    let mut after_nonce = None;
+    let mut exported = 0;
+    loop {
+        let page = db
+            .fetch_bridge_export_page(chain_id, from, to, after_nonce, page_size)
+            .await?;
+        let Some((last, _)) = page.last() else {
+            break;
+        };
+        after_nonce = Some(last.nonce);
+
+        for (source_tx, flow) in &page {
+            serde_json::to_writer(&mut writer, &export_row(source_tx, flow.as_ref()))?;
+            writer.write_all(b"\n")?;
+            exported += 1;
+        }
+        info!("Exported {} rows so far", exported);
+
+        if (page.len() as u64) < page_size {
+            break;
+        }
+    }
+
+    Ok(exported)
+}
+
+fn export_row(
+    source_tx: &source_transactions::Model,
+    flow: Option<&transaction_flows::Model>,
+) -> serde_json::Value {
+    json!({
+        "chain_id": source_tx.chain_id,
+        "nonce": source_tx.nonce,
+        "transaction_type": format!("{:?}", source_tx.transaction_type),
+        "source_tx_hash": source_tx.transaction_hash,
+        "block_number": source_tx.block_number,
+        "timestamp": source_tx.timestamp,
+        "destination_chain_id": source_tx.destination_chain_id,
+        "l1_address": source_tx.l1_address,
+        "twine_address": source_tx.twine_address,
+        "l1_token": source_tx.l1_token,
+        "l2_token": source_tx.l2_token,
+        "amount": source_tx.amount.to_string(),
+        "handle_tx_hash": flow.and_then(|flow| flow.handle_tx_hash.clone()),
+        "handle_block_number": flow.and_then(|flow| flow.handle_block_number),
+        "handle_status": flow.and_then(|flow| flow.handle_status),
+        "handled_at": flow.and_then(|flow| flow.handled_at),
+        "execute_tx_hash": flow.and_then(|flow| flow.execute_tx_hash.clone()),
+        "execute_block_number": flow.and_then(|flow| flow.execute_block_number),
+        "executed_at": flow.and_then(|flow| flow.executed_at),
+        "is_completed": flow.and_then(|flow| flow.is_completed),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use database::{entities::sea_orm_active_enums::TransactionTypeEnum, test_db, DbOperations};
+    use sea_orm::{prelude::Decimal, ActiveValue::Set};
+
+    use super::*;
+
+    fn timestamp(value: &str) -> DateTimeWithTimeZone {
+        value.parse().unwrap()
+    }
+
+    fn deposit(chain_id: i64, nonce: i64, sent_at: &str) -> DbOperations {
+        DbOperations::BridgeSourceTransaction(source_transactions::ActiveModel {
+            chain_id: Set(chain_id),
+            nonce: Set(nonce),
+            transaction_type: Set(TransactionTypeEnum::Deposit),
+            block_number: Set(100 + nonce),
+            l1_token: Set("0xl1".to_string()),
+            l2_token: Set("0xl2".to_string()),
+            l1_address: Set("0xfrom".to_string()),
+            twine_address: Set("0xto".to_string()),
+            amount: Set(Decimal::from(1000 * nonce)),
+            transaction_hash: Set(Some(format!("0xsource{}", nonce))),
+            timestamp: Set(Some(timestamp(sent_at))),
+            ..Default::default()
+        })
+    }
+
+    fn handle(chain_id: i64, nonce: i64) -> DbOperations {
+        DbOperations::BridgeDestinationTransactions(transaction_flows::ActiveModel {
+            chain_id: Set(chain_id),
+            nonce: Set(nonce),
+            handle_tx_hash: Set(Some(format!("0xhandle{}", nonce))),
+            handle_block_number: Set(Some(200 + nonce)),
+            is_handled: Set(Some(true)),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn exported_lines_match_the_rows_in_the_window() {
+        let db = test_db::client(false).await;
+        db.process_bulk_l1_database_operations(vec![vec![
+            deposit(1, 1, "2025-10-16T10:00:00+00:00"),
+            deposit(1, 2, "2025-10-16T11:00:00+00:00"),
+            deposit(1, 3, "2025-10-16T12:00:00+00:00"),
+            // Outside the window or on another chain
+            deposit(1, 4, "2025-10-17T10:00:00+00:00"),
+            deposit(2, 5, "2025-10-16T10:00:00+00:00"),
+            handle(1, 2),
+        ]])
+        .await
+        .unwrap();
+
+        let mut out = Vec::new();
+        // Pages of 2 rows, so the cursor has to carry over to a second page
+        let exported = write_export(
+            &db,
+            1,
+            timestamp("2025-10-16T00:00:00+00:00"),
+            timestamp("2025-10-17T00:00:00+00:00"),
+            2,
+            &mut out,
+        )
+        .await
+        .unwrap();
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(exported, 3);
+        let rows: Vec<_> = lines
+            .iter()
+            .map(|line| {
+                (
+                    line["chain_id"].as_i64().unwrap(),
+                    line["nonce"].as_i64().unwrap(),
+                    line["source_tx_hash"].as_str().unwrap().to_string(),
+                    line["amount"].as_str().unwrap().to_string(),
+                    line["handle_tx_hash"].as_str().map(str::to_string),
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (1, 1, "0xsource1".to_string(), "1000".to_string(), None),
+                (
+                    1,
+                    2,
+                    "0xsource2".to_string(),
+                    "2000".to_string(),
+                    Some("0xhandle2".to_string())
+                ),
+                (1, 3, "0xsource3".to_string(), "3000".to_string(), None),
+            ]
+        );
+        assert_eq!(lines[0]["transaction_type"], "Deposit");
+        assert_eq!(lines[1]["is_completed"], true);
+    }
+}
@@ -1,39 +1,112 @@
-use std::sync::Arc;
+mod check;
+mod cli;
+mod export;
 
-use common::config::{self, LoadFromEnv};
-use database::client::DbClient;
+use std::{
+    collections::HashSet,
+    net::{Ipv4Addr, SocketAddrV4},
+    sync::Arc,
+    time::Duration,
+};
+
+use axum::{http::StatusCode, routing::get, Router};
+use cli::Command;
+
+use common::{
+    chains::ChainRegistry,
+    config::{self, IndexerSettings, LoadFromEnv},
+};
+use database::{
+    client::{DbClient, SyncDivergence},
+    entities::{sea_orm_active_enums::HeightKindEnum, unparsed_logs},
+    DbOperations,
+};
 use evm::{
-    ethereum::handlers::EthereumEventHandler, indexer::EvmIndexer, provider::EvmProvider,
-    twine::handlers::TwineEventHandler,
+    ethereum::handlers::EthereumEventHandler, handler::batch_block_range, indexer::EvmIndexer,
+    provider::EvmProvider, twine::handlers::TwineEventHandler,
 };
 use eyre::Result;
-use generic_indexer::indexer::ChainIndexer;
+use generic_indexer::{
+    indexer::ChainIndexer,
+    metrics::{self, PrometheusHandle},
+    readiness,
+    sink::{OperationSink, RecordingSink},
+};
 use svm::{handler::SolanaEventHandler, indexer::SolanaIndexer};
 use tokio::signal;
+use tokio::sync::Semaphore;
 use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 
+/// Extra time given to indexers on shutdown on top of their drain timeout
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+/// Restart backoff stops doubling once it reaches this multiple of the base
+const MAX_RESTART_BACKOFF_FACTOR: u32 = 32;
+
 /// Represents an indexer task with its name and join handle
 struct IndexerTask {
     name: String,
     handle: JoinHandle<Result<()>>,
 }
 
-/// Spawns an indexer task with consistent logging
-fn spawn_indexer<I>(name: &str, mut indexer: I) -> IndexerTask
+/// Spawns an indexer task with consistent logging. The indexer is restarted
+/// with a growing backoff whenever it stops before `shutdown` is cancelled,
+/// and the task only fails once `settings.max_indexer_restarts` is spent.
+fn spawn_indexer<I>(
+    name: &str,
+    mut indexer: I,
+    shutdown: CancellationToken,
+    settings: &IndexerSettings,
+) -> IndexerTask
 where
     I: ChainIndexer + Send + 'static,
     I::EventHandler: Send + Sync + 'static,
 {
     let name_clone = name.to_string();
+    let max_restarts = settings.max_indexer_restarts;
+    let restart_backoff = Duration::from_millis(settings.indexer_restart_backoff_ms);
     let handle = tokio::spawn(async move {
-        info!("Starting {} indexer", name_clone);
-        let result = indexer.run().await;
-        match &result {
-            Ok(_) => info!("{} indexer completed successfully", name_clone),
-            Err(e) => error!("{} indexer failed: {:?}", name_clone, e),
+        let mut restarts = 0;
+        loop {
+            info!("Starting {} indexer", name_clone);
+            let result = indexer.run().await;
+            match &result {
+                Ok(_) => info!("{} indexer completed successfully", name_clone),
+                Err(e) => error!("{} indexer failed: {:?}", name_clone, e),
+            }
+
+            if shutdown.is_cancelled() {
+                return result;
+            }
+            // Live indexing never ends on its own, so stopping cleanly is as
+            // unexpected as failing
+            if restarts >= max_restarts {
+                error!(
+                    "{} indexer stopped and its {} restarts are spent, giving up",
+                    name_clone, max_restarts
+                );
+                return result.and(Err(eyre::eyre!(
+                    "{} indexer stopped unexpectedly",
+                    name_clone
+                )));
+            }
+
+            restarts += 1;
+            let backoff = restart_backoff
+                * 2u32
+                    .saturating_pow(restarts - 1)
+                    .min(MAX_RESTART_BACKOFF_FACTOR);
+            warn!(
+                "Restarting {} indexer in {:?} (restart {} of {})",
+                name_clone, backoff, restarts, max_restarts
+            );
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => {}
+                _ = shutdown.cancelled() => return Ok(()),
+            }
         }
-        result
     });
 
     IndexerTask {
@@ -42,12 +115,137 @@ where
     }
 }
 
+/// Serves the event outcome counters on `/metrics`, liveness on `/healthz`
+/// and readiness on `/readyz`. Readiness waits until every chain is live, a
+/// chain backfilling is healthy but not ready.
+async fn serve_metrics(port: u16, handle: PrometheusHandle) -> Result<()> {
+    let app = Router::new()
+        .route("/metrics", get(move || async move { handle.render() }))
+        .route("/healthz", get(|| async { "OK" }))
+        .route("/readyz", get(readyz));
+    let addr = SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), port);
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("Metrics server running on {}", addr);
+    axum::serve(listener, app)
+        .await
+        .map_err(|e| eyre::eyre!("Metrics server error: {}", e))
+}
+
+async fn readyz() -> (StatusCode, String) {
+    let status = if readiness::is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    let body = readiness::phases()
+        .iter()
+        .map(|(chain, phase)| format!("chain {}: {}\n", chain, phase))
+        .collect();
+    (status, body)
+}
+
+/// Fails unless the Twine RPC serves the configured Twine chain. Every L1
+/// handler fetches committed L2 blocks through it, so an RPC pointing at an
+/// L1 would have them store that L1's blocks as the batch's.
+async fn validate_twine_provider(
+    cfg: &config::IndexerConfig,
+    registry: &ChainRegistry,
+    twine_provider: &EvmProvider,
+) -> Result<()> {
+    let twine_chain_id = cfg.twine.common.chain_id;
+    let rpc_chain_id = twine_provider.get_rpc_chain_id().await?;
+
+    if let Some(l1) = registry
+        .iter()
+        .find(|chain| chain.name != "Twine" && chain.chain_id == rpc_chain_id)
+    {
+        return Err(eyre::eyre!(
+            "Twine RPC {} serves chain id {}, which is the {} chain, not Twine",
+            cfg.twine.common.http_rpc_url,
+            rpc_chain_id,
+            l1.name
+        ));
+    }
+
+    if rpc_chain_id != twine_chain_id {
+        return Err(eyre::eyre!(
+            "Twine RPC {} serves chain id {}, configured Twine chain id is {}",
+            cfg.twine.common.http_rpc_url,
+            rpc_chain_id,
+            twine_chain_id
+        ));
+    }
+
+    Ok(())
+}
+
+/// Warns when blockscout lost batches the primary recorded as committed on
+/// a chain, e.g. after a blockscout-only outage, and optionally rewinds the
+/// chain to just before the first missing batch so it is written again
+async fn check_sync_divergence(
+    cfg: &config::IndexerConfig,
+    registry: &ChainRegistry,
+    db: &DbClient,
+) -> Result<()> {
+    let Some(tolerance) = cfg.settings.sync_divergence_tolerance_blocks else {
+        return Ok(());
+    };
+
+    // Batches are committed on the L1s, Twine writes none to blockscout
+    let chains = registry
+        .iter()
+        .filter(|chain| chain.name != "Twine" && cfg.is_chain_enabled(&chain.name));
+    for chain in chains {
+        let divergence = db.check_sync_divergence(chain.chain_id as i64).await?;
+        metrics::set_sync_divergence(
+            chain.chain_id,
+            divergence.as_ref().map_or(0, SyncDivergence::blocks),
+        );
+        let Some(divergence) = divergence else {
+            continue;
+        };
+        if divergence.blocks() <= tolerance {
+            continue;
+        }
+
+        warn!(
+            "{} batches {:?} committed at or after height {} are missing in blockscout, {} heights behind last_synced {}",
+            chain.name,
+            divergence.missing_batches,
+            divergence.first_missing_block,
+            divergence.blocks(),
+            divergence.last_synced
+        );
+
+        if cfg.settings.rewind_on_sync_divergence {
+            warn!(
+                "Rewinding {} last_synced from {} to {}",
+                chain.name,
+                divergence.last_synced,
+                divergence.safe_height()
+            );
+            db.upsert_last_synced(
+                divergence.chain_id,
+                divergence.height_kind,
+                divergence.safe_height(),
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
 /// Sets up database connections
 async fn setup_databases(cfg: &config::IndexerConfig) -> Result<Arc<DbClient>> {
-    let db_conn = database::connect::connect(&cfg.database.url).await?;
+    let db_conn =
+        database::connect::connect(&cfg.database.url, cfg.database.statement_timeout_ms).await?;
     info!("Connected to Indexer's DB");
 
-    let blockscout_db_conn = database::connect::connect(&cfg.blockscout.url).await?;
+    let blockscout_db_conn =
+        database::connect::connect(&cfg.blockscout.url, cfg.blockscout.statement_timeout_ms)
+            .await?;
     info!("Connected to Blockscout's DB");
 
     let db_client = DbClient::new(db_conn.clone(), Some(blockscout_db_conn.clone()));
@@ -57,64 +255,447 @@ async fn setup_databases(cfg: &config::IndexerConfig) -> Result<Arc<DbClient>> {
 /// Creates and spawns all indexer tasks
 async fn spawn_all_indexers(
     cfg: &config::IndexerConfig,
+    registry: &ChainRegistry,
     arc_db: Arc<DbClient>,
     twine_provider: Arc<EvmProvider>,
+    shutdown: &CancellationToken,
 ) -> Result<Vec<IndexerTask>> {
+    cfg.validate_enabled_chains()?;
+    cfg.validate_block_times()?;
+
     let mut tasks = Vec::new();
+    let mut reloadable = Vec::new();
+    let stagger = Duration::from_millis(cfg.settings.startup_stagger_ms);
+    let backfill_limiter = match cfg.settings.max_concurrent_historical_syncs {
+        0 => None,
+        limit => {
+            info!("Limiting concurrent historical syncs to {}", limit);
+            Some(Arc::new(Semaphore::new(limit)))
+        }
+    };
 
-    // Twine indexer
-    let twine_handler = TwineEventHandler::new(
-        Arc::clone(&arc_db),
-        cfg.twine.clone(),
-        twine_provider.clone(),
-    );
-    let twine_indexer = EvmIndexer::new(twine_handler, Arc::clone(&arc_db), cfg.settings.clone());
-    tasks.push(spawn_indexer("Twine", twine_indexer));
-
-    // Ethereum indexer
-    let eth_handler = EthereumEventHandler::new(
-        Arc::clone(&arc_db),
-        cfg.l1s.ethereum.clone(),
-        twine_provider.clone(),
-    );
-    let eth_indexer = EvmIndexer::new(eth_handler, Arc::clone(&arc_db), cfg.settings.clone());
-    tasks.push(spawn_indexer("Ethereum", eth_indexer));
-
-    // Arbitrum indexer
-    let arbitrum_handler = EthereumEventHandler::new(
-        Arc::clone(&arc_db),
-        cfg.l1s.arbitrum.clone(),
-        twine_provider.clone(),
-    );
-    let arbitrum_indexer =
-        EvmIndexer::new(arbitrum_handler, Arc::clone(&arc_db), cfg.settings.clone());
-    tasks.push(spawn_indexer("Arbitrum", arbitrum_indexer));
-
-    // Base indexer
-    let base_handler = EthereumEventHandler::new(
-        Arc::clone(&arc_db),
-        cfg.l1s.base.clone(),
-        twine_provider.clone(),
+    for chain in registry.iter() {
+        if !cfg.is_chain_enabled(&chain.name) {
+            info!("{} indexer disabled", chain.name);
+            continue;
+        }
+
+        let task = match chain.name.as_str() {
+            "Twine" => {
+                let no_data_timeout = Duration::from_millis(cfg.twine.no_data_timeout_ms);
+                let handler = TwineEventHandler::new(
+                    Arc::clone(&arc_db),
+                    cfg.twine.clone(),
+                    twine_provider.clone(),
+                )?;
+                reloadable.push(ReloadableHandler::Twine(handler.clone()));
+                let indexer = EvmIndexer::new(handler, Arc::clone(&arc_db), cfg.settings.clone())
+                    .with_backfill_limiter(backfill_limiter.clone())
+                    .with_no_data_timeout(no_data_timeout)
+                    .with_shutdown(shutdown.clone());
+                spawn_indexer(&chain.name, indexer, shutdown.clone(), &cfg.settings)
+            }
+            "Ethereum" | "Arbitrum" | "Base" => {
+                let l1_config = match chain.name.as_str() {
+                    "Ethereum" => cfg.l1s.ethereum.clone(),
+                    "Arbitrum" => cfg.l1s.arbitrum.clone(),
+                    _ => cfg.l1s.base.clone(),
+                };
+                let reorg_depth = l1_config.reorg_depth;
+                let no_data_timeout = Duration::from_millis(l1_config.no_data_timeout_ms);
+                let handler = EthereumEventHandler::new(
+                    Arc::clone(&arc_db),
+                    l1_config,
+                    twine_provider.clone(),
+                )?;
+                reloadable.push(ReloadableHandler::L1(chain.name.clone(), handler.clone()));
+                let indexer = EvmIndexer::new(handler, Arc::clone(&arc_db), cfg.settings.clone())
+                    .with_backfill_limiter(backfill_limiter.clone())
+                    .with_reorg_depth(reorg_depth)
+                    .with_no_data_timeout(no_data_timeout)
+                    .with_shutdown(shutdown.clone());
+                spawn_indexer(&chain.name, indexer, shutdown.clone(), &cfg.settings)
+            }
+            "Solana" => {
+                let handler = SolanaEventHandler::new(
+                    Arc::clone(&arc_db),
+                    cfg.l1s.solana.clone(),
+                    twine_provider.clone(),
+                );
+                let indexer =
+                    SolanaIndexer::new(handler, Arc::clone(&arc_db), cfg.settings.clone())?
+                        .with_backfill_limiter(backfill_limiter.clone())
+                        .with_shutdown(shutdown.clone());
+                spawn_indexer(&chain.name, indexer, shutdown.clone(), &cfg.settings)
+            }
+            other => return Err(eyre::eyre!("No indexer for chain '{}'", other)),
+        };
+
+        info!(
+            "Spawned {} indexer for chain id {}",
+            chain.name, chain.chain_id
+        );
+        tasks.push(task);
+        tokio::time::sleep(stagger).await;
+    }
+
+    if !reloadable.is_empty() {
+        tokio::spawn(reload_addresses_on_sighup(
+            reloadable,
+            config::IndexerConfig::load,
+            shutdown.clone(),
+        ));
+    }
+
+    Ok(tasks)
+}
+
+/// A handler whose contract addresses can be swapped while it runs
+#[derive(Clone)]
+enum ReloadableHandler {
+    Twine(TwineEventHandler),
+    /// An L1 EVM chain handler with the name of its chain
+    L1(String, EthereumEventHandler),
+}
+
+/// Reloads the config on every SIGHUP and swaps in the updated contract
+/// addresses, so a redeployed gateway or messenger is picked up without a
+/// restart. The indexers keep their checkpoint, only logs fetched afterwards
+/// use the new addresses. A config that fails to load or validate leaves
+/// them unchanged.
+async fn reload_addresses_on_sighup<F>(
+    handlers: Vec<ReloadableHandler>,
+    load: F,
+    shutdown: CancellationToken,
+) where
+    F: Fn() -> Result<config::IndexerConfig>,
+{
+    #[cfg(unix)]
+    {
+        let mut hangup = match signal::unix::signal(signal::unix::SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                error!("Failed to install SIGHUP handler: {:?}", e);
+                return;
+            }
+        };
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => return,
+                received = hangup.recv() => {
+                    if received.is_none() {
+                        return;
+                    }
+                }
+            }
+
+            info!("Received SIGHUP signal, reloading contract addresses");
+            match load() {
+                Ok(cfg) => reload_addresses(&handlers, &cfg),
+                Err(e) => error!(
+                    "Failed to reload config, keeping current addresses: {:?}",
+                    e
+                ),
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (handlers, load, shutdown);
+    }
+}
+
+/// Swaps the contract addresses of `cfg` into every handler, a handler whose
+/// addresses are rejected keeps its current ones
+fn reload_addresses(handlers: &[ReloadableHandler], cfg: &config::IndexerConfig) {
+    for handler in handlers {
+        let (name, reloaded) = match handler {
+            ReloadableHandler::Twine(handler) => {
+                ("Twine", handler.reload_contract_addresses(&cfg.twine))
+            }
+            ReloadableHandler::L1(name, handler) => {
+                let l1_config = match name.as_str() {
+                    "Ethereum" => &cfg.l1s.ethereum,
+                    "Arbitrum" => &cfg.l1s.arbitrum,
+                    _ => &cfg.l1s.base,
+                };
+                (name.as_str(), handler.reload_contract_addresses(l1_config))
+            }
+        };
+        match reloaded {
+            Ok(true) => {}
+            Ok(false) => info!("{} contract addresses unchanged", name),
+            Err(e) => error!(
+                "Rejected reloaded {} contract addresses, keeping current ones: {:?}",
+                name, e
+            ),
+        }
+    }
+}
+
+/// Parses blocks `from` to `to` of `chain` into `sink` without writing anything
+async fn parse_chain_range(
+    cfg: &config::IndexerConfig,
+    arc_db: Arc<DbClient>,
+    twine_provider: Arc<EvmProvider>,
+    chain: &str,
+    from: u64,
+    to: u64,
+    sink: &dyn OperationSink,
+) -> Result<()> {
+    match chain.to_lowercase().as_str() {
+        "twine" => {
+            let handler =
+                TwineEventHandler::new(Arc::clone(&arc_db), cfg.twine.clone(), twine_provider)?;
+            EvmIndexer::new(handler, arc_db, cfg.settings.clone())
+                .parse_range(from, to, sink)
+                .await?;
+        }
+        "ethereum" | "arbitrum" | "base" => {
+            let l1_config = match chain.to_lowercase().as_str() {
+                "ethereum" => cfg.l1s.ethereum.clone(),
+                "arbitrum" => cfg.l1s.arbitrum.clone(),
+                _ => cfg.l1s.base.clone(),
+            };
+            let handler =
+                EthereumEventHandler::new(Arc::clone(&arc_db), l1_config, twine_provider)?;
+            EvmIndexer::new(handler, arc_db, cfg.settings.clone())
+                .parse_range(from, to, sink)
+                .await?;
+        }
+        "solana" => {
+            let handler = SolanaEventHandler::new(
+                Arc::clone(&arc_db),
+                cfg.l1s.solana.clone(),
+                twine_provider,
+            );
+            // Nothing is written while parsing, cursors and processed marks
+            // would only hide signatures of the range
+            SolanaIndexer::new(handler, arc_db, cfg.settings.clone())?
+                .with_read_only(true)
+                .parse_range(from, to, sink)
+                .await?;
+        }
+        other => return Err(eyre::eyre!("Unknown chain '{}'", other)),
+    }
+
+    Ok(())
+}
+
+/// Parses a block range of a single chain into a recording sink and logs
+/// every operation that would have been written, then a summary per kind
+async fn parse_only(
+    cfg: &config::IndexerConfig,
+    arc_db: Arc<DbClient>,
+    twine_provider: Arc<EvmProvider>,
+    chain: &str,
+    from: u64,
+    to: u64,
+) -> Result<()> {
+    let sink = RecordingSink::new();
+    parse_chain_range(cfg, arc_db, twine_provider, chain, from, to, &sink).await?;
+
+    for operation in sink.operations() {
+        let json = operation.to_json();
+        info!("{}: {}", operation.kind(), json["data"]);
+    }
+
+    let summary = sink.summary();
+    info!(
+        "Dry run of {} blocks {} to {} would write {} operations",
+        chain,
+        from,
+        to,
+        summary.values().sum::<usize>()
     );
-    let base_indexer = EvmIndexer::new(base_handler, Arc::clone(&arc_db), cfg.settings.clone());
-    tasks.push(spawn_indexer("Base", base_indexer));
-
-    // Solana indexer
-    let solana_handler = SolanaEventHandler::new(
-        Arc::clone(&arc_db),
-        cfg.l1s.solana.clone(),
-        twine_provider.clone(),
+    for (kind, count) in summary {
+        info!("  {}: {}", kind, count);
+    }
+
+    Ok(())
+}
+
+/// Re-indexes Twine blocks `from` to `to` and inserts the transaction flows
+/// that are missing for deposits originating on `chain`
+async fn reconcile_flows(
+    cfg: &config::IndexerConfig,
+    registry: &ChainRegistry,
+    arc_db: Arc<DbClient>,
+    twine_provider: Arc<EvmProvider>,
+    chain: &str,
+    from: u64,
+    to: u64,
+) -> Result<()> {
+    let chain_id = match registry.by_name(chain) {
+        Some(entry) if !entry.name.eq_ignore_ascii_case("Twine") => entry.chain_id,
+        _ => return Err(eyre::eyre!("Unknown L1 chain '{}'", chain)),
+    };
+
+    let sink = RecordingSink::new();
+    let handler = TwineEventHandler::new(Arc::clone(&arc_db), cfg.twine.clone(), twine_provider)?;
+    EvmIndexer::new(handler, Arc::clone(&arc_db), cfg.settings.clone())
+        .parse_range(from, to, &sink)
+        .await?;
+
+    let inserted = arc_db
+        .reconcile_missing_flows(chain_id as i64, sink.operations())
+        .await?;
+    let still_missing = arc_db.find_deposits_missing_flows(chain_id as i64).await?;
+
+    info!(
+        "Reconciled {} transaction flows for {} from Twine blocks {} to {}; {} deposits still have no flow",
+        inserted,
+        chain,
+        from,
+        to,
+        still_missing.len()
     );
-    let solana_indexer =
-        SolanaIndexer::new(solana_handler, Arc::clone(&arc_db), cfg.settings.clone());
-    tasks.push(spawn_indexer("Solana", solana_indexer));
 
-    Ok(tasks)
+    Ok(())
+}
+
+/// Re-fetches the block range of `batch_number` from Twine and tags the L2
+/// blocks and transactions blockscout has for it but are not linked yet
+async fn repair_batch(
+    arc_db: Arc<DbClient>,
+    twine_provider: Arc<EvmProvider>,
+    batch_number: u64,
+) -> Result<()> {
+    let before = arc_db
+        .batch_l2_completeness(batch_number as i64)
+        .await?
+        .ok_or_else(|| eyre::eyre!("Batch {} is not in blockscout", batch_number))?;
+
+    if before.is_complete() {
+        info!("Batch {} is already complete: {:?}", batch_number, before);
+        return Ok(());
+    }
+    warn!("Batch {} is incomplete: {:?}", batch_number, before);
+
+    let blocks = twine_provider.get_blocks_in_batch(batch_number).await?;
+    let (start_block, end_block) = batch_block_range(batch_number, &blocks)?;
+
+    arc_db
+        .repair_batch_l2(batch_number as i64, start_block, end_block)
+        .await?;
+
+    let after = arc_db
+        .batch_l2_completeness(batch_number as i64)
+        .await?
+        .ok_or_else(|| eyre::eyre!("Batch {} is not in blockscout", batch_number))?;
+
+    if after.is_complete() {
+        info!("Repaired batch {}: {:?}", batch_number, after);
+    } else {
+        warn!(
+            "Batch {} is still incomplete, blockscout may not have indexed all of blocks {} to {} yet: {:?}",
+            batch_number, start_block, end_block, after
+        );
+    }
+
+    Ok(())
+}
+
+/// Replays the dead-lettered logs of `chain` block by block, in the order
+/// they were emitted. Each block is re-parsed and written before the next,
+/// so a finalize only runs once its commit is stored. Logs that fail again
+/// are dead-lettered anew.
+async fn replay_unparsed(
+    cfg: &config::IndexerConfig,
+    registry: &ChainRegistry,
+    arc_db: Arc<DbClient>,
+    twine_provider: Arc<EvmProvider>,
+    chain: &str,
+) -> Result<()> {
+    let chain_id = registry
+        .by_name(chain)
+        .map(|entry| entry.chain_id)
+        .ok_or_else(|| eyre::eyre!("Unknown chain '{}'", chain))?;
+
+    let rows = arc_db
+        .fetch_unparsed_logs_for_replay(chain_id as i64)
+        .await?;
+    if rows.is_empty() {
+        info!("No dead-lettered logs to replay for {}", chain);
+        return Ok(());
+    }
+
+    // Rows come ordered by block, so grouping consecutive rows keeps that order
+    let mut blocks: Vec<(u64, Vec<unparsed_logs::Model>)> = Vec::new();
+    for row in rows {
+        match blocks.last_mut() {
+            Some((block, block_rows)) if *block == row.block_number as u64 => block_rows.push(row),
+            _ => blocks.push((row.block_number as u64, vec![row])),
+        }
+    }
+
+    let mut replayed = 0;
+    for (block, block_rows) in blocks {
+        let sink = RecordingSink::new();
+        parse_chain_range(
+            cfg,
+            Arc::clone(&arc_db),
+            Arc::clone(&twine_provider),
+            chain,
+            block,
+            block,
+            &sink,
+        )
+        .await?;
+
+        let operations = sink.operations();
+        if operations.is_empty() {
+            warn!(
+                "Block {} of {} produced no events, keeping its {} dead-lettered logs",
+                block,
+                chain,
+                block_rows.len()
+            );
+            continue;
+        }
+
+        // A log that fails again hits its existing row, which has to stay.
+        // Rows without a log index never conflict and are written anew.
+        let failed_again: HashSet<(String, i64)> = operations
+            .iter()
+            .filter_map(|operation| match operation {
+                DbOperations::UnparsedLog(model) => Some((
+                    model.transaction_hash.clone().take()?,
+                    model.log_index.clone().take().flatten()?,
+                )),
+                _ => None,
+            })
+            .collect();
+        let ids = block_rows
+            .into_iter()
+            .filter(|row| {
+                row.log_index.is_none_or(|log_index| {
+                    !failed_again.contains(&(row.transaction_hash.clone(), log_index))
+                })
+            })
+            .map(|row| row.id)
+            .collect();
+
+        arc_db
+            .process_bulk_l1_database_operations(vec![operations])
+            .await?;
+        replayed += arc_db.delete_unparsed_logs(ids).await?;
+    }
+
+    info!("Replayed {} dead-lettered logs for {}", replayed, chain);
+    Ok(())
 }
 
 /// Waits for all indexer tasks to complete and checks their results
-/// Also handles graceful shutdown on receiving termination signals
-async fn wait_for_indexers(tasks: Vec<IndexerTask>) -> Result<()> {
+/// Also handles graceful shutdown on receiving termination signals: the
+/// indexers are cancelled and given `drain_timeout` to persist queued writes
+async fn wait_for_indexers(
+    tasks: Vec<IndexerTask>,
+    shutdown: CancellationToken,
+    drain_timeout: Duration,
+) -> Result<()> {
     info!("Waiting for all indexer tasks to complete...");
     info!("Press Ctrl+C to initiate graceful shutdown");
 
@@ -125,27 +706,33 @@ async fn wait_for_indexers(tasks: Vec<IndexerTask>) -> Result<()> {
     // Spawn shutdown signal handler in the background
     let mut shutdown_handle = tokio::spawn(handle_shutdown_signal());
 
+    let mut all_tasks = std::pin::pin!(async {
+        let mut collected = Vec::new();
+        for handle in task_handles {
+            collected.push(handle.await);
+        }
+        collected
+    });
+
     // Wait for all tasks to complete, or shutdown signal
-    let results = tokio::select! {
-        results = async {
-            let mut collected = Vec::new();
-            for handle in task_handles {
-                collected.push(handle.await);
-            }
-            collected
-        } => {
+    tokio::select! {
+        results = &mut all_tasks => {
             shutdown_handle.abort();
-            results
+            return check_indexer_results(results, &task_names, false, task_count);
         }
         _ = &mut shutdown_handle => {
-            warn!("Shutdown signal received. Tasks will be cancelled when process exits.");
-            // Return early - tasks will be dropped and cancelled
-            return Err(eyre::eyre!("Shutdown signal received"));
+            warn!("Shutdown signal received. Stopping indexers and draining queued writes.");
+            shutdown.cancel();
         }
-    };
+    }
 
-    check_indexer_results(results, &task_names, false, task_count)?;
-    Ok(())
+    match tokio::time::timeout(drain_timeout, &mut all_tasks).await {
+        Ok(results) => check_indexer_results(results, &task_names, true, task_count),
+        Err(_) => Err(eyre::eyre!(
+            "Indexers did not stop within {:?} of the shutdown signal",
+            drain_timeout
+        )),
+    }
 }
 
 /// Checks the results of indexer tasks and logs appropriately
@@ -234,19 +821,294 @@ async fn handle_shutdown_signal() {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
+    let cfg = config::IndexerConfig::load()?;
+    // stdout carries the JSON operations, keep the logs out of it
+    if cfg.settings.emit_json_to_stdout {
+        tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .init();
+    } else {
+        tracing_subscriber::fmt::init();
+    }
     info!("Initializing indexer service...");
 
-    let cfg = config::IndexerConfig::load()?;
+    let command = Command::from_args()?;
+    let registry = ChainRegistry::from_config(&cfg)?;
+    evm::addresses::validate_config(&cfg)?;
+    for chain in registry.iter() {
+        metrics::set_chain_name(chain.chain_id, &chain.name);
+    }
+
+    if command == Command::Check {
+        let report = check::run_checks(&cfg).await;
+        report.print();
+        if !report.is_ok() {
+            std::process::exit(report.exit_code());
+        }
+        return Ok(());
+    }
+
     let arc_db = setup_databases(&cfg).await?;
 
-    let twine_provider = Arc::new(EvmProvider::new(
-        &cfg.twine.common.http_rpc_url,
-        cfg.twine.common.chain_id,
-    ));
+    let twine_provider = Arc::new(
+        EvmProvider::new(&cfg.twine.common.http_rpc_url, cfg.twine.common.chain_id)
+            .with_confirmation_blocks(cfg.twine.common.confirmation_blocks)
+            .with_block_fetch_concurrency(cfg.twine.block_fetch_concurrency),
+    );
+    validate_twine_provider(&cfg, &registry, &twine_provider).await?;
+
+    match command {
+        Command::ParseOnly { chain, from, to } => {
+            return parse_only(&cfg, arc_db, twine_provider, &chain, from, to).await;
+        }
+        Command::ReconcileFlows { chain, from, to } => {
+            return reconcile_flows(&cfg, &registry, arc_db, twine_provider, &chain, from, to)
+                .await;
+        }
+        Command::RepairBatch { batch_number } => {
+            return repair_batch(arc_db, twine_provider, batch_number).await;
+        }
+        Command::ReplayUnparsed { chain } => {
+            return replay_unparsed(&cfg, &registry, arc_db, twine_provider, &chain).await;
+        }
+        Command::Export {
+            chain,
+            from,
+            to,
+            out,
+        } => {
+            let chain_id = registry
+                .by_name(&chain)
+                .map(|entry| entry.chain_id)
+                .ok_or_else(|| eyre::eyre!("Unknown chain '{}'", chain))?;
+            let exported =
+                export::export_bridge_transactions(&arc_db, chain_id as i64, from, to, &out)
+                    .await?;
+            info!(
+                "Exported {} {} bridge transactions to {}",
+                exported,
+                chain,
+                out.display()
+            );
+            return Ok(());
+        }
+        Command::Check => unreachable!("handled before connecting"),
+        Command::Run => {}
+    }
+
+    // Served before the divergence check so the divergence found on startup
+    // is recorded
+    if let Some(port) = cfg.settings.metrics_port {
+        let handle = metrics::install_recorder()?;
+        tokio::spawn(async move {
+            if let Err(e) = serve_metrics(port, handle).await {
+                error!("Metrics server stopped: {:?}", e);
+            }
+        });
+    }
 
-    let tasks = spawn_all_indexers(&cfg, arc_db, twine_provider).await?;
+    check_sync_divergence(&cfg, &registry, &arc_db).await?;
+
+    let shutdown = CancellationToken::new();
+    let tasks = spawn_all_indexers(&cfg, &registry, arc_db, twine_provider, &shutdown).await?;
     info!("Spawned {} indexer tasks", tasks.len());
 
-    wait_for_indexers(tasks).await
+    // Indexers may be mid-request when cancelled, so allow them a little
+    // longer than their own drain timeout
+    let drain_timeout =
+        Duration::from_millis(cfg.settings.shutdown_drain_timeout_ms) + SHUTDOWN_GRACE;
+    wait_for_indexers(tasks, shutdown, drain_timeout).await
+}
+
+#[cfg(test)]
+mod tests {
+    use evm::{addresses::parse_address, handler::EvmEventHandler};
+    use sea_orm::DatabaseConnection;
+    use serde_json::json;
+
+    use super::*;
+
+    const MESSENGER: &str = "0x1000000000000000000000000000000000000001";
+    const REDEPLOYED_MESSENGER: &str = "0x1000000000000000000000000000000000000002";
+    const GATEWAY: &str = "0x2000000000000000000000000000000000000001";
+    const REDEPLOYED_GATEWAY: &str = "0x2000000000000000000000000000000000000002";
+
+    fn chain(chain_id: u64) -> serde_json::Value {
+        json!({
+            "http_rpc_url": format!("http://localhost:8545/{}", chain_id),
+            "chain_id": chain_id,
+            "start_block": 0,
+            "block_sync_batch_size": 100,
+            "block_time_ms": 1000,
+        })
+    }
+
+    fn evm(chain_id: u64, gateway: &str) -> serde_json::Value {
+        json!({
+            "common": chain(chain_id),
+            "chain": "evm",
+            "l1_message_handler_address": "0x3000000000000000000000000000000000000001",
+            "l1_erc20_gateway_addresses": [gateway],
+            "eth_twine_chain_address": "0x4000000000000000000000000000000000000001",
+        })
+    }
+
+    fn config(messenger: &str, gateway: &str) -> config::IndexerConfig {
+        serde_json::from_value(json!({
+            "database": { "url": "postgres://localhost/indexer" },
+            "blockscout": { "url": "postgres://localhost/blockscout" },
+            "settings": {
+                "max_log_batch_size": 1000,
+                "max_log_batch_time": 12,
+                "max_concurrency_for_log_process": 10,
+            },
+            "twine": {
+                "common": chain(1337),
+                "l2_twine_messenger_address": messenger,
+                "uniswap_factory_address": "0x5000000000000000000000000000000000000001",
+                "event_filter": ["SentMessage", "L1TransactionsHandled"],
+            },
+            "l1s": {
+                "ethereum": evm(1, gateway),
+                "arbitrum": evm(42161, gateway),
+                "base": evm(8453, gateway),
+                "solana": {
+                    "common": chain(103),
+                    "chain": "svm",
+                    "tokens_gateway_program_addresses": ["Gateway"],
+                    "twine_chain_program_address": "TwineChain",
+                },
+            },
+        }))
+        .unwrap()
+    }
+
+    fn handlers(cfg: &config::IndexerConfig) -> (TwineEventHandler, EthereumEventHandler) {
+        let db = Arc::new(DbClient::new(DatabaseConnection::Disconnected, None));
+        let twine_provider = Arc::new(EvmProvider::new("http://localhost:8545", 1337));
+        let twine =
+            TwineEventHandler::new(db.clone(), cfg.twine.clone(), twine_provider.clone()).unwrap();
+        let ethereum =
+            EthereumEventHandler::new(db, cfg.l1s.ethereum.clone(), twine_provider).unwrap();
+        (twine, ethereum)
+    }
+
+    #[test]
+    fn invalid_address_fails_the_handler_instead_of_panicking() {
+        let cfg = config("0xnot-an-address", GATEWAY);
+        let db = Arc::new(DbClient::new(DatabaseConnection::Disconnected, None));
+        let twine_provider = Arc::new(EvmProvider::new("http://localhost:8545", 1337));
+        assert!(TwineEventHandler::new(db.clone(), cfg.twine, twine_provider.clone()).is_err());
+
+        let cfg = config(MESSENGER, "0xnot-an-address");
+        assert!(EthereumEventHandler::new(db, cfg.l1s.ethereum, twine_provider).is_err());
+    }
+
+    /// Whether `handler` fetches the logs of `address`
+    async fn watches(handler: &impl EvmEventHandler, address: &str) -> bool {
+        handler
+            .relevant_addresses()
+            .await
+            .contains(&parse_address("address", address).unwrap())
+    }
+
+    #[tokio::test]
+    async fn rejected_addresses_keep_the_current_ones() {
+        let (twine, ethereum) = handlers(&config(MESSENGER, GATEWAY));
+        let reloadable = vec![
+            ReloadableHandler::Twine(twine.clone()),
+            ReloadableHandler::L1("Ethereum".to_string(), ethereum.clone()),
+        ];
+
+        reload_addresses(&reloadable, &config("0x0", "0x0"));
+
+        assert!(watches(&twine, MESSENGER).await);
+        assert!(watches(&ethereum, GATEWAY).await);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn sighup_reloads_twine_and_l1_addresses() {
+        // Registered first, so a SIGHUP sent before the reload task listens
+        // is caught instead of terminating the test process
+        let _hangup = signal::unix::signal(signal::unix::SignalKind::hangup()).unwrap();
+
+        let (twine, ethereum) = handlers(&config(MESSENGER, GATEWAY));
+        let shutdown = CancellationToken::new();
+        tokio::spawn(reload_addresses_on_sighup(
+            vec![
+                ReloadableHandler::Twine(twine.clone()),
+                ReloadableHandler::L1("Ethereum".to_string(), ethereum.clone()),
+            ],
+            || Ok(config(REDEPLOYED_MESSENGER, REDEPLOYED_GATEWAY)),
+            shutdown.clone(),
+        ));
+
+        for _ in 0..100 {
+            std::process::Command::new("kill")
+                .args(["-HUP", &std::process::id().to_string()])
+                .status()
+                .unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            if watches(&twine, REDEPLOYED_MESSENGER).await {
+                break;
+            }
+        }
+        shutdown.cancel();
+
+        assert!(watches(&twine, REDEPLOYED_MESSENGER).await);
+        assert!(!watches(&twine, MESSENGER).await);
+        assert!(watches(&ethereum, REDEPLOYED_GATEWAY).await);
+        assert!(!watches(&ethereum, GATEWAY).await);
+    }
+
+    /// Serves `eth_chainId` and `eth_blockNumber` on a local port, returns its URL
+    async fn serve_evm_rpc(chain_id: u64, head: u64) -> String {
+        let app = Router::new().route(
+            "/",
+            axum::routing::post(
+                move |axum::Json(request): axum::Json<serde_json::Value>| async move {
+                    let result = match request["method"].as_str() {
+                        Some("eth_chainId") => format!("{:#x}", chain_id),
+                        Some("eth_blockNumber") => format!("{:#x}", head),
+                        method => panic!("unexpected {:?} request", method),
+                    };
+                    axum::Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        url
+    }
+
+    /// URL of a local port nothing listens on
+    async fn unreachable_url() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    #[tokio::test]
+    async fn check_reports_each_rpc_and_fails_on_an_unreachable_one() {
+        let mut cfg = config(MESSENGER, GATEWAY);
+        cfg.enabled_chains = vec!["Twine".to_string(), "Ethereum".to_string()];
+        cfg.twine.common.http_rpc_url = serve_evm_rpc(1337, 16).await;
+        cfg.l1s.ethereum.common.http_rpc_url = unreachable_url().await;
+
+        let mut report = check::CheckReport::default();
+        check::check_rpcs(&cfg, &mut report).await;
+
+        let lines = report.lines();
+        assert_eq!(lines.len(), 2, "{lines:?}");
+        assert_eq!(
+            lines[0],
+            format!("OK    {:<20} chain id 1337, head 16", "Twine rpc")
+        );
+        assert!(lines[1].starts_with("FAIL  Ethereum rpc"), "{}", lines[1]);
+        assert_eq!(report.exit_code(), 1);
+
+        report.results.pop();
+        assert_eq!(report.exit_code(), 0);
+    }
 }
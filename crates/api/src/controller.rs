@@ -1,22 +1,37 @@
 use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
 use axum::Json;
 use chrono::{DateTime, FixedOffset, Utc};
-use sea_orm::DbErr;
+use common::chains::ChainEntry;
 use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::{info, instrument};
 
 use crate::{
     error::AppError,
-    pagination::{items_count, BridgeTransactionsPagination, PlaceholderPagination},
+    pagination::{
+        items_count, BridgeTransactionsPagination, BridgeTransactionsPaginationQuery,
+        ChangesPagination, ChangesPaginationQuery, PlaceholderPagination,
+    },
     types::{
+        AddressHistoryResponse, BatchDaReferenceResponse, BatchDaResponse, BatchDetailResponse,
         BatchL2TransactionHashRequest, BatchL2TransactionHashResponse, BridgeTransactionsResponse,
-        L2WithdrawExecuteHashResponse, UserDepositsResponse, UserSwapEventsResponse,
+        ChainStatusResponse, ChangesResponse, DepositResponse, HeightType, L1DepositsFilterQuery,
+        L2WithdrawExecuteHashResponse, NonceAnomaliesResponse, NonceGapResponse,
+        OutOfOrderNonceResponse, OverdueDepositsQuery, SourceTransactionChange,
+        SyncProgressResponse, TokenResponse, TransactionFlowChange, UserDepositsResponse,
+        UserSwapEventsResponse, VersionResponse, WithdrawalTimelineResponse,
+        DEFAULT_DEPOSIT_SLA_SECS,
     },
     ApiResponse, ApiResult, AppState,
 };
 use database::{
-    bridge::FetchBridgeTransactionsParams,
+    bridge::{
+        deposit_flow_status, is_flow_completed, normalize_tx_hash, BridgeCursor, BridgeQueryError,
+        FetchBridgeTransactionsParams, NonceAnomaly,
+    },
+    changes::ChangeCursor,
     client::DbClient,
     entities::{source_transactions, transaction_flows},
 };
@@ -32,16 +47,120 @@ pub async fn health_check(
     })
 }
 
-#[instrument(skip(state), fields(pagination_query = ?pagination_query))]
+/// Sync lag of every chain, answered with 503 when any chain lags by more
+/// than the configured threshold so load balancers take the instance out
+#[instrument(skip_all)]
+pub async fn get_status(
+    State(state): State<AppState>,
+) -> Result<
+    (
+        StatusCode,
+        Json<ApiResponse<Vec<ChainStatusResponse>, PlaceholderPagination>>,
+    ),
+    AppError,
+> {
+    let rows = state
+        .db_client
+        .get_all_last_synced()
+        .await
+        .map_err(AppError::from)?;
+
+    let items: Vec<ChainStatusResponse> = rows
+        .into_iter()
+        .map(|row| ChainStatusResponse {
+            chain_id: row.chain_id,
+            last_synced_block: row.block_number,
+            current_head: row.chain_head,
+            lag: row
+                .chain_head
+                .map(|head| head.saturating_sub(row.block_number).max(0) as u64),
+        })
+        .collect();
+
+    let lagging = state.max_sync_lag_blocks.is_some_and(|max_lag| {
+        items
+            .iter()
+            .any(|chain| chain.lag.is_some_and(|lag| lag > max_lag))
+    });
+    let status = if lagging {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+
+    Ok((
+        status,
+        Json(ApiResponse {
+            success: !lagging,
+            items,
+            next_page_params: None,
+        }),
+    ))
+}
+
+/// Per configured chain, how far indexing is from its start height to the
+/// head its indexer last saw
+#[instrument(skip_all)]
+pub async fn get_sync_progress(
+    State(state): State<AppState>,
+) -> ApiResult<Vec<SyncProgressResponse>, PlaceholderPagination> {
+    let rows = state.db_client.get_all_last_synced().await?;
+
+    let items = state
+        .chains
+        .iter()
+        .map(|chain| {
+            let row = rows
+                .iter()
+                .find(|row| row.chain_id == chain.chain_id as i64);
+            let synced_height = row.map(|row| row.block_number);
+            let head = row.and_then(|row| row.chain_head);
+            SyncProgressResponse {
+                chain: chain.name.clone(),
+                chain_id: chain.chain_id,
+                synced_height,
+                head,
+                percent: head.map(|head| {
+                    sync_percent(
+                        chain.config.start_block as i64,
+                        synced_height.unwrap_or(0),
+                        head,
+                    )
+                }),
+            }
+        })
+        .collect();
+
+    Ok(ApiResponse {
+        success: true,
+        items,
+        next_page_params: None,
+    })
+}
+
+/// `(synced - start) / (head - start)` as a percentage with two decimals,
+/// 100 once caught up
+fn sync_percent(start: i64, synced: i64, head: i64) -> f64 {
+    if synced >= head || head <= start {
+        return 100.0;
+    }
+    let done = synced.saturating_sub(start).max(0) as f64;
+    let percent = done / (head - start) as f64 * 100.0;
+    (percent * 100.0).floor() / 100.0
+}
+
+#[instrument(skip(state), fields(pagination_query = ?pagination_query, filter_query = ?filter_query))]
 pub async fn get_l1_deposits(
     State(state): State<AppState>,
-    Query(pagination_query): Query<BridgeTransactionsPagination>,
+    Query(pagination_query): Query<BridgeTransactionsPaginationQuery>,
+    Query(filter_query): Query<L1DepositsFilterQuery>,
 ) -> ApiResult<Vec<BridgeTransactionsResponse>, BridgeTransactionsPagination> {
+    let filter = filter_query.parse()?;
     let fetch_future = get_paginated_bridge_transactions(
         state,
-        pagination_query,
+        pagination_query.parse()?,
         "L1 Deposits",
-        |client, params| async move { client.fetch_l1_deposits_paginated(params).await },
+        |client, params| async move { client.fetch_l1_deposits_filtered(params, filter).await },
     );
     fetch_future.await
 }
@@ -49,11 +168,11 @@ pub async fn get_l1_deposits(
 #[instrument(skip(state), fields(pagination_query = ?pagination_query))]
 pub async fn get_l2_withdraws(
     State(state): State<AppState>,
-    Query(pagination_query): Query<BridgeTransactionsPagination>,
+    Query(pagination_query): Query<BridgeTransactionsPaginationQuery>,
 ) -> ApiResult<Vec<BridgeTransactionsResponse>, BridgeTransactionsPagination> {
     get_paginated_bridge_transactions(
         state,
-        pagination_query,
+        pagination_query.parse()?,
         "L2 Withdrawals",
         |client, params| async move { client.fetch_l2_withdraws_paginated(params).await },
     )
@@ -63,78 +182,60 @@ pub async fn get_l2_withdraws(
 #[instrument(skip(state), fields(pagination_query = ?pagination_query))]
 pub async fn get_l1_forced_withdraws(
     State(state): State<AppState>,
-    Query(pagination_query): Query<BridgeTransactionsPagination>,
+    Query(pagination_query): Query<BridgeTransactionsPaginationQuery>,
 ) -> ApiResult<Vec<BridgeTransactionsResponse>, BridgeTransactionsPagination> {
     get_paginated_bridge_transactions(
         state,
-        pagination_query,
+        pagination_query.parse()?,
         "L1 Forced Withdrawals",
         |client, params| async move { client.fetch_l1_forced_withdraws_paginated(params).await },
     )
     .await
 }
 
-#[instrument(skip(state), fields(user_address = %user_address))]
+#[instrument(skip(state), fields(user_address = %user_address, pagination_query = ?pagination_query))]
 pub async fn get_user_deposits(
     State(state): State<AppState>,
     Path(user_address): Path<String>,
-) -> ApiResult<Vec<UserDepositsResponse>, PlaceholderPagination> {
+    Query(pagination_query): Query<BridgeTransactionsPaginationQuery>,
+) -> ApiResult<Vec<UserDepositsResponse>, BridgeTransactionsPagination> {
     info!(
         user_address = %user_address,
         "Fetching deposits for user"
     );
 
+    let pagination_query = pagination_query.parse()?;
+    let items_count = items_count(pagination_query.items_count);
+    let db_params = FetchBridgeTransactionsParams {
+        items_count,
+        cursor: BridgeCursor::from_parts(
+            pagination_query.chain_id.map(|id| id as i64),
+            pagination_query.nonce.map(|n| n as i64),
+        ),
+    };
+
     let results = state
         .db_client
-        .fetch_user_deposits(&user_address)
+        .fetch_user_deposits(&user_address, db_params)
         .await
         .map_err(AppError::from)?;
 
+    let next_page_params = if results.len() == items_count as usize {
+        results
+            .last()
+            .map(|(source_tx, _)| BridgeTransactionsPagination {
+                items_count: Some(items_count),
+                chain_id: Some(source_tx.chain_id as u64),
+                nonce: Some(source_tx.nonce as u64),
+            })
+    } else {
+        None
+    };
+
     let response_items: Vec<UserDepositsResponse> = results
         .iter()
         .map(|(source_tx, dest_tx_opt)| {
-            let created_at: DateTime<FixedOffset> = DateTime::from_naive_utc_and_offset(
-                source_tx.timestamp.unwrap_or_default().naive_utc(),
-                FixedOffset::east_opt(0).expect("UTC offset should be valid"),
-            );
-
-            UserDepositsResponse {
-                l1_tx_hash: source_tx.transaction_hash.clone().unwrap_or_default(),
-                l2_tx_hash: dest_tx_opt
-                    .as_ref()
-                    .and_then(|tx| tx.handle_tx_hash.clone()),
-                l1_block_height: Some(source_tx.block_number),
-                l2_block_height: dest_tx_opt.as_ref().and_then(|tx| tx.handle_block_number),
-                status: dest_tx_opt.as_ref().and_then(|tx| tx.handle_status),
-                nonce: source_tx.nonce,
-                chain_id: source_tx.chain_id,
-                l1_token: Some(source_tx.l1_token.clone()),
-                l2_token: Some(source_tx.l2_token.clone()),
-                from: source_tx.l1_address.clone(),
-                to_twine_address: Some(source_tx.twine_address.clone()),
-                amount: Some(source_tx.amount.to_string()),
-                created_at,
-                l2_handled_at: dest_tx_opt.as_ref().and_then(|tx| tx.handled_at),
-                l1_execute_hash: dest_tx_opt
-                    .as_ref()
-                    .and_then(|tx| tx.execute_tx_hash.clone()),
-                l1_execute_block_height: dest_tx_opt
-                    .as_ref()
-                    .and_then(|tx| tx.execute_block_number),
-                l1_executed_at: dest_tx_opt.as_ref().and_then(|tx| tx.executed_at),
-                is_handled: dest_tx_opt
-                    .as_ref()
-                    .and_then(|tx| tx.is_handled)
-                    .unwrap_or(false),
-                is_executed: dest_tx_opt
-                    .as_ref()
-                    .and_then(|tx| tx.is_executed)
-                    .unwrap_or(false),
-                is_completed: dest_tx_opt
-                    .as_ref()
-                    .and_then(|tx| tx.is_completed)
-                    .unwrap_or(false),
-            }
+            to_user_deposit_response(source_tx, dest_tx_opt, &state.chains)
         })
         .collect();
 
@@ -144,6 +245,95 @@ pub async fn get_user_deposits(
         "Fetched user deposits"
     );
 
+    Ok(ApiResponse {
+        success: true,
+        items: response_items,
+        next_page_params,
+    })
+}
+
+#[instrument(skip(state), fields(address = %address, pagination_query = ?pagination_query))]
+pub async fn get_address_history(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+    Query(pagination_query): Query<BridgeTransactionsPaginationQuery>,
+) -> ApiResult<Vec<AddressHistoryResponse>, BridgeTransactionsPagination> {
+    let pagination_query = pagination_query.parse()?;
+    let items_count = items_count(pagination_query.items_count);
+    let db_params = FetchBridgeTransactionsParams {
+        items_count,
+        cursor: BridgeCursor::from_parts(
+            pagination_query.chain_id.map(|id| id as i64),
+            pagination_query.nonce.map(|n| n as i64),
+        ),
+    };
+
+    let results = state
+        .db_client
+        .fetch_address_history(&address, db_params)
+        .await
+        .map_err(AppError::from)?;
+
+    let next_page_params = if results.len() == items_count as usize {
+        results
+            .last()
+            .map(|(source_tx, _)| BridgeTransactionsPagination {
+                items_count: Some(items_count),
+                chain_id: Some(source_tx.chain_id as u64),
+                nonce: Some(source_tx.nonce as u64),
+            })
+    } else {
+        None
+    };
+
+    let response_items: Vec<AddressHistoryResponse> = results
+        .iter()
+        .map(|(source_tx, dest_tx_opt)| AddressHistoryResponse {
+            transaction_type: format!("{:?}", source_tx.transaction_type),
+            transaction: to_user_deposit_response(source_tx, dest_tx_opt, &state.chains),
+        })
+        .collect();
+
+    info!(
+        address = %address,
+        count = response_items.len(),
+        "Fetched address history"
+    );
+
+    Ok(ApiResponse {
+        success: true,
+        items: response_items,
+        next_page_params,
+    })
+}
+
+#[instrument(skip(state), fields(query = ?query))]
+pub async fn get_overdue_deposits(
+    State(state): State<AppState>,
+    Query(query): Query<OverdueDepositsQuery>,
+) -> ApiResult<Vec<UserDepositsResponse>, PlaceholderPagination> {
+    let sla = Duration::from_secs(query.sla_secs.unwrap_or(DEFAULT_DEPOSIT_SLA_SECS));
+    let limit = items_count(query.limit);
+
+    let results = state
+        .db_client
+        .fetch_overdue_deposits(sla, limit)
+        .await
+        .map_err(AppError::from)?;
+
+    let response_items: Vec<UserDepositsResponse> = results
+        .iter()
+        .map(|(source_tx, dest_tx_opt)| {
+            to_user_deposit_response(source_tx, dest_tx_opt, &state.chains)
+        })
+        .collect();
+
+    info!(
+        sla_secs = sla.as_secs(),
+        count = response_items.len(),
+        "Fetched overdue deposits"
+    );
+
     Ok(ApiResponse {
         success: true,
         items: response_items,
@@ -151,6 +341,304 @@ pub async fn get_user_deposits(
     })
 }
 
+/// Build and schema version of the running service
+#[instrument(skip_all)]
+pub async fn get_version(
+    State(state): State<AppState>,
+) -> ApiResult<VersionResponse, PlaceholderPagination> {
+    let latest_migration = state
+        .db_client
+        .latest_migration()
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(ApiResponse {
+        success: true,
+        items: VersionResponse {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha: env!("GIT_SHA").to_string(),
+            latest_migration,
+        },
+        next_page_params: None,
+    })
+}
+
+/// Rows inserted after the given cursor. The returned cursor is always set so
+/// consumers can keep polling from it.
+#[instrument(skip(state), fields(pagination_query = ?pagination_query))]
+pub async fn get_changes(
+    State(state): State<AppState>,
+    Query(pagination_query): Query<ChangesPaginationQuery>,
+) -> ApiResult<ChangesResponse, ChangesPagination> {
+    let pagination_query = pagination_query.parse()?;
+    let items_count = items_count(pagination_query.items_count);
+    let cursor = ChangeCursor {
+        source_id: pagination_query.source_id.unwrap_or(0),
+        flow_id: pagination_query.flow_id.unwrap_or(0),
+    };
+
+    let changes = state
+        .db_client
+        .fetch_changes_since(cursor, items_count)
+        .await
+        .map_err(AppError::from)?;
+
+    let source_transactions = changes
+        .source_transactions
+        .into_iter()
+        .map(|tx| SourceTransactionChange {
+            id: tx.id,
+            chain_id: tx.chain_id,
+            nonce: tx.nonce,
+            transaction_type: format!("{:?}", tx.transaction_type),
+            transaction_hash: tx.transaction_hash,
+            block_number: tx.block_number,
+            l1_address: tx.l1_address,
+            twine_address: tx.twine_address,
+            l1_token: tx.l1_token,
+            l2_token: tx.l2_token,
+            amount: tx.amount.to_string(),
+            timestamp: tx.timestamp,
+        })
+        .collect();
+
+    let transaction_flows = changes
+        .transaction_flows
+        .into_iter()
+        .map(|flow| TransactionFlowChange {
+            id: flow.id,
+            chain_id: flow.chain_id,
+            nonce: flow.nonce,
+            handle_tx_hash: flow.handle_tx_hash,
+            handled_at: flow.handled_at,
+            execute_tx_hash: flow.execute_tx_hash,
+            executed_at: flow.executed_at,
+            is_completed: flow.is_completed,
+        })
+        .collect();
+
+    Ok(ApiResponse {
+        success: true,
+        items: ChangesResponse {
+            source_transactions,
+            transaction_flows,
+        },
+        next_page_params: Some(ChangesPagination {
+            items_count: Some(items_count),
+            source_id: Some(changes.cursor.source_id),
+            flow_id: Some(changes.cursor.flow_id),
+        }),
+    })
+}
+
+#[instrument(skip(state))]
+pub async fn get_withdrawal_timeline(
+    State(state): State<AppState>,
+    Path((chain_id, nonce)): Path<(u64, u64)>,
+) -> ApiResult<WithdrawalTimelineResponse, PlaceholderPagination> {
+    let timeline = state
+        .db_client
+        .withdrawal_timeline(chain_id as i64, nonce as i64)
+        .await
+        .map_err(AppError::from)?
+        .ok_or_else(|| {
+            AppError::NotFound(format!(
+                "No withdrawal with chain_id {} and nonce {}",
+                chain_id, nonce
+            ))
+        })?;
+
+    Ok(ApiResponse {
+        success: true,
+        items: WithdrawalTimelineResponse {
+            chain_id,
+            nonce,
+            transaction_type: format!("{:?}", timeline.transaction_type),
+            source_tx_hash: timeline.source_tx_hash,
+            submitted_at: timeline.submitted_at,
+            handle_tx_hash: timeline.handle_tx_hash,
+            handled_at: timeline.handled_at,
+            execute_tx_hash: timeline.execute_tx_hash,
+            executed_at: timeline.executed_at,
+            is_completed: timeline.is_completed,
+        },
+        next_page_params: None,
+    })
+}
+
+#[instrument(skip(state))]
+pub async fn get_nonce_anomalies(
+    State(state): State<AppState>,
+    Path(chain_id): Path<u64>,
+) -> ApiResult<NonceAnomaliesResponse, PlaceholderPagination> {
+    let anomalies = state
+        .db_client
+        .detect_nonce_anomalies(chain_id as i64)
+        .await
+        .map_err(AppError::from)?;
+
+    let mut gaps = Vec::new();
+    let mut out_of_order = Vec::new();
+    for anomaly in anomalies {
+        let missing = anomaly.missing();
+        match anomaly {
+            NonceAnomaly::Gap { after, before } => gaps.push(NonceGapResponse {
+                after_nonce: after,
+                before_nonce: before,
+                missing,
+            }),
+            NonceAnomaly::OutOfOrder {
+                nonce,
+                block_number,
+                previous_nonce,
+                previous_block_number,
+            } => out_of_order.push(OutOfOrderNonceResponse {
+                nonce,
+                block_number,
+                previous_nonce,
+                previous_block_number,
+            }),
+        }
+    }
+
+    Ok(ApiResponse {
+        success: true,
+        items: NonceAnomaliesResponse {
+            chain_id,
+            gaps,
+            out_of_order,
+        },
+        next_page_params: None,
+    })
+}
+
+#[instrument(skip(state))]
+pub async fn get_deposit_by_hash(
+    State(state): State<AppState>,
+    Path(tx_hash): Path<String>,
+) -> ApiResult<DepositResponse, PlaceholderPagination> {
+    let tx_hash = normalize_tx_hash(&tx_hash);
+    let (source_tx, flow) = state
+        .db_client
+        .find_deposit_by_tx_hash(&tx_hash)
+        .await
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::NotFound(format!("No deposit with tx hash {}", tx_hash)))?;
+
+    let status = deposit_flow_status(flow.as_ref());
+    let is_completed = is_flow_completed(&source_tx.transaction_type, flow.as_ref());
+    let refund_tx_hash = flow
+        .as_ref()
+        .filter(|_| status == "Refunded")
+        .and_then(|flow| flow.execute_tx_hash.clone());
+
+    Ok(ApiResponse {
+        success: true,
+        items: DepositResponse {
+            source_tx_hash: tx_hash,
+            chain_id: source_tx.chain_id,
+            destination_chain_id: source_tx.destination_chain_id,
+            nonce: source_tx.nonce,
+            block_number: source_tx.block_number,
+            submitted_at: source_tx.timestamp,
+            from: source_tx.l1_address,
+            to_twine_address: source_tx.twine_address,
+            l1_token: source_tx.l1_token,
+            l2_token: source_tx.l2_token,
+            amount: source_tx.amount.to_string(),
+            status: status.to_string(),
+            l2_handle_tx_hash: flow.as_ref().and_then(|flow| flow.handle_tx_hash.clone()),
+            l2_handled_at: flow.as_ref().and_then(|flow| flow.handled_at),
+            l1_refund_tx_hash: refund_tx_hash,
+            is_completed,
+        },
+        next_page_params: None,
+    })
+}
+
+#[instrument(skip(state))]
+pub async fn get_batch_detail(
+    State(state): State<AppState>,
+    Path(batch_number): Path<u64>,
+) -> ApiResult<BatchDetailResponse, PlaceholderPagination> {
+    let da_reference = state
+        .db_client
+        .get_batch_da_reference(batch_number as i64)
+        .await
+        .map_err(AppError::from)?
+        .map(|reference| BatchDaReferenceResponse {
+            celestia_height: reference.celestia_height,
+            namespace: reference.namespace,
+            commitment: reference.commitment,
+        });
+
+    Ok(ApiResponse {
+        success: true,
+        items: BatchDetailResponse {
+            batch_number,
+            da_reference,
+        },
+        next_page_params: None,
+    })
+}
+
+#[instrument(skip(state))]
+pub async fn get_batch_da(
+    State(state): State<AppState>,
+    Path(batch_number): Path<u64>,
+) -> ApiResult<BatchDaResponse, PlaceholderPagination> {
+    let reference = state
+        .db_client
+        .get_batch_da_reference(batch_number as i64)
+        .await
+        .map_err(AppError::from)?
+        .ok_or_else(|| {
+            AppError::NotFound(format!(
+                "No Celestia blob is known for batch {}",
+                batch_number
+            ))
+        })?;
+
+    Ok(ApiResponse {
+        success: true,
+        items: BatchDaResponse {
+            batch_number,
+            celestia_height: reference.celestia_height,
+            namespace: reference.namespace,
+            commitment: reference.commitment,
+            linked_at: reference.created_at,
+        },
+        next_page_params: None,
+    })
+}
+
+#[instrument(skip(state))]
+pub async fn get_tokens(
+    State(state): State<AppState>,
+) -> ApiResult<Vec<TokenResponse>, PlaceholderPagination> {
+    let items = state
+        .db_client
+        .distinct_tokens()
+        .await
+        .map_err(AppError::from)?
+        .into_iter()
+        .map(|token| TokenResponse {
+            l1_token: token.l1_token,
+            l2_token: token.l2_token,
+            symbol: token.symbol,
+            first_seen: token.first_seen,
+            last_seen: token.last_seen,
+            transfers: token.transfers,
+        })
+        .collect();
+
+    Ok(ApiResponse {
+        success: true,
+        items,
+        next_page_params: None,
+    })
+}
+
 #[instrument(skip(state, request), fields(request_count = request.l1_transactions.len()))]
 pub async fn get_l2_txns_for_l1_txn(
     State(state): State<AppState>,
@@ -237,15 +725,21 @@ async fn get_paginated_bridge_transactions<F, Fut>(
 ) -> ApiResult<Vec<BridgeTransactionsResponse>, BridgeTransactionsPagination>
 where
     F: FnOnce(Arc<DbClient>, FetchBridgeTransactionsParams) -> Fut,
-    Fut:
-        Future<Output = Result<Vec<(source_transactions::Model, transaction_flows::Model)>, DbErr>>,
+    Fut: Future<
+        Output = Result<
+            Vec<(source_transactions::Model, transaction_flows::Model)>,
+            BridgeQueryError,
+        >,
+    >,
 {
     let items_count = items_count(pagination_query.items_count);
 
     let db_params = FetchBridgeTransactionsParams {
         items_count,
-        cursor_chain_id: pagination_query.chain_id.map(|id| id as i64),
-        cursor_nonce: pagination_query.nonce.map(|n| n as i64),
+        cursor: BridgeCursor::from_parts(
+            pagination_query.chain_id.map(|id| id as i64),
+            pagination_query.nonce.map(|n| n as i64),
+        ),
     };
 
     info!(params = ?db_params, "Fetching bridge transactions");
@@ -266,8 +760,11 @@ where
                 source_tx.timestamp.unwrap_or_default().naive_utc(),
                 FixedOffset::east_opt(0).expect("UTC offset should be valid"),
             );
+            let source_tx_hash = source_tx.transaction_hash.clone().unwrap_or_default();
             BridgeTransactionsResponse {
-                source_tx_hash: source_tx.transaction_hash.clone().unwrap_or_default(),
+                height: Some(source_tx.block_number),
+                height_type: HeightType::for_chain(source_tx.chain_id, &state.chains),
+                source_tx_hash,
                 l2_handle_tx_hash: dest_tx.handle_tx_hash.clone().unwrap_or_default(),
                 source_block_height: Some(source_tx.block_number),
                 l2_handle_block_height: dest_tx.handle_block_number,
@@ -279,11 +776,15 @@ where
                 from: source_tx.l1_address.clone(),
                 to_twine_address: Some(source_tx.twine_address.clone()),
                 amount: Some(source_tx.amount.to_string()),
+                message: source_tx.message.clone(),
+                transaction_output: dest_tx.transaction_output.clone(),
                 created_at,
                 l2_handled_at: dest_tx.handled_at,
                 l1_execute_hash: dest_tx.execute_tx_hash.clone(),
                 l1_execute_block_height: dest_tx.execute_block_number,
                 l1_executed_at: dest_tx.executed_at,
+                l1_is_executed: dest_tx.is_executed.unwrap_or(false),
+                is_completed: is_flow_completed(&source_tx.transaction_type, Some(dest_tx)),
             }
         })
         .collect();
@@ -466,3 +967,138 @@ pub async fn get_execute_tx_hash_for_l2_withdraw(
         next_page_params: None,
     })
 }
+
+fn to_user_deposit_response(
+    source_tx: &source_transactions::Model,
+    dest_tx_opt: &Option<transaction_flows::Model>,
+    chains: &[ChainEntry],
+) -> UserDepositsResponse {
+    let created_at: DateTime<FixedOffset> = DateTime::from_naive_utc_and_offset(
+        source_tx.timestamp.unwrap_or_default().naive_utc(),
+        FixedOffset::east_opt(0).expect("UTC offset should be valid"),
+    );
+
+    let l1_tx_hash = source_tx.transaction_hash.clone().unwrap_or_default();
+
+    UserDepositsResponse {
+        height: Some(source_tx.block_number),
+        height_type: HeightType::for_chain(source_tx.chain_id, chains),
+        l1_tx_hash,
+        l2_tx_hash: dest_tx_opt
+            .as_ref()
+            .and_then(|tx| tx.handle_tx_hash.clone()),
+        l1_block_height: Some(source_tx.block_number),
+        l2_block_height: dest_tx_opt.as_ref().and_then(|tx| tx.handle_block_number),
+        status: dest_tx_opt.as_ref().and_then(|tx| tx.handle_status),
+        nonce: source_tx.nonce,
+        chain_id: source_tx.chain_id,
+        l1_token: Some(source_tx.l1_token.clone()),
+        l2_token: Some(source_tx.l2_token.clone()),
+        from: source_tx.l1_address.clone(),
+        to_twine_address: Some(source_tx.twine_address.clone()),
+        amount: Some(source_tx.amount.to_string()),
+        message: source_tx.message.clone(),
+        transaction_output: dest_tx_opt
+            .as_ref()
+            .and_then(|tx| tx.transaction_output.clone()),
+        created_at,
+        l2_handled_at: dest_tx_opt.as_ref().and_then(|tx| tx.handled_at),
+        l1_execute_hash: dest_tx_opt
+            .as_ref()
+            .and_then(|tx| tx.execute_tx_hash.clone()),
+        l1_execute_block_height: dest_tx_opt.as_ref().and_then(|tx| tx.execute_block_number),
+        l1_executed_at: dest_tx_opt.as_ref().and_then(|tx| tx.executed_at),
+        is_handled: dest_tx_opt
+            .as_ref()
+            .and_then(|tx| tx.is_handled)
+            .unwrap_or(false),
+        is_executed: dest_tx_opt
+            .as_ref()
+            .and_then(|tx| tx.is_executed)
+            .unwrap_or(false),
+        l1_is_executed: dest_tx_opt
+            .as_ref()
+            .and_then(|tx| tx.is_executed)
+            .unwrap_or(false),
+        is_completed: is_flow_completed(&source_tx.transaction_type, dest_tx_opt.as_ref()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use database::entities::sea_orm_active_enums::TransactionTypeEnum;
+    use sea_orm::prelude::Decimal;
+
+    fn forced_withdraw() -> source_transactions::Model {
+        source_transactions::Model {
+            id: 1,
+            chain_id: 1,
+            destination_chain_id: None,
+            nonce: 7,
+            transaction_type: TransactionTypeEnum::ForcedWithdraw,
+            block_number: 100,
+            l1_token: "0xl1".to_string(),
+            l2_token: "0xl2".to_string(),
+            l1_address: "0xfrom".to_string(),
+            twine_address: "0xto".to_string(),
+            amount: Decimal::from(5),
+            message: None,
+            transaction_hash: Some("0xsource".to_string()),
+            timestamp: None,
+            created_at: None,
+            updated_at: None,
+            token_standard: None,
+        }
+    }
+
+    fn handled_flow() -> transaction_flows::Model {
+        transaction_flows::Model {
+            id: 1,
+            chain_id: 1,
+            nonce: 7,
+            handled_at: Some(Utc::now().fixed_offset()),
+            executed_at: None,
+            handle_tx_hash: Some("0xhandle".to_string()),
+            execute_tx_hash: None,
+            handle_block_number: Some(20),
+            execute_block_number: None,
+            handle_status: Some(1),
+            transaction_output: None,
+            is_handled: Some(true),
+            is_executed: Some(false),
+            is_completed: Some(false),
+            created_at: None,
+            updated_at: None,
+            refund_amount: None,
+            refund_token: None,
+        }
+    }
+
+    #[test]
+    fn handled_forced_withdraw_is_not_completed_until_executed() {
+        let response = to_user_deposit_response(&forced_withdraw(), &Some(handled_flow()), &[]);
+
+        assert!(response.is_handled);
+        assert!(!response.l1_is_executed);
+        assert!(!response.is_completed);
+    }
+
+    #[test]
+    fn executed_forced_withdraw_is_completed() {
+        let flow = transaction_flows::Model {
+            executed_at: Some(Utc::now().fixed_offset()),
+            execute_tx_hash: Some("0xexecute".to_string()),
+            execute_block_number: Some(120),
+            is_executed: Some(true),
+            is_completed: Some(true),
+            ..handled_flow()
+        };
+        let response = to_user_deposit_response(&forced_withdraw(), &Some(flow), &[]);
+
+        assert!(response.l1_is_executed);
+        assert!(response.is_completed);
+        assert_eq!(response.l1_execute_block_height, Some(120));
+        assert_eq!(response.l1_execute_hash.as_deref(), Some("0xexecute"));
+    }
+}
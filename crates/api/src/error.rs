@@ -3,7 +3,9 @@ use crate::ApiResponse;
 use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
+    Json,
 };
+use database::{bridge::BridgeQueryError, connect::is_statement_timeout};
 use sea_orm::DbErr;
 use std::fmt;
 
@@ -13,6 +15,8 @@ pub enum AppError {
     #[allow(dead_code)]
     Internal,
     NotFound(String),
+    Unauthorized(String),
+    BadRequest(String),
 }
 
 impl From<DbErr> for AppError {
@@ -21,12 +25,23 @@ impl From<DbErr> for AppError {
     }
 }
 
+impl From<BridgeQueryError> for AppError {
+    fn from(err: BridgeQueryError) -> Self {
+        match err {
+            BridgeQueryError::CursorNotFound(_) => AppError::BadRequest(err.to_string()),
+            BridgeQueryError::Database(err) => AppError::Database(err),
+        }
+    }
+}
+
 impl std::error::Error for AppError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             AppError::Database(err) => Some(err),
             AppError::Internal => None,
             AppError::NotFound(_) => None,
+            AppError::Unauthorized(_) => None,
+            AppError::BadRequest(_) => None,
         }
     }
 }
@@ -37,13 +52,19 @@ impl fmt::Display for AppError {
             AppError::Database(err) => write!(f, "Database error: {}", err),
             AppError::Internal => write!(f, "Internal server error"),
             AppError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            AppError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
+            AppError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
         }
     }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (_status, message) = match self {
+        let (status, message) = match self {
+            AppError::Database(e) if is_statement_timeout(&e) => (
+                StatusCode::GATEWAY_TIMEOUT,
+                "Database query timed out".to_owned(),
+            ),
             AppError::Database(e) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("Database error: {}", e),
@@ -53,13 +74,16 @@ impl IntoResponse for AppError {
                 "Internal Server Error".to_owned(),
             ),
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
         };
 
-        ApiResponse {
+        let body = ApiResponse {
             success: false,
             items: message,
             next_page_params: None::<PlaceholderPagination>,
-        }
-        .into_response()
+        };
+
+        (status, Json(body)).into_response()
     }
 }
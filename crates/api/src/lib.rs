@@ -2,7 +2,9 @@ mod controller;
 pub mod error;
 pub mod pagination;
 mod search;
+mod stats;
 pub mod types;
+mod uniswap;
 
 use axum::{
     http::StatusCode,
@@ -10,16 +12,27 @@ use axum::{
     routing::{get, post},
     Json, Router,
 };
+use common::{chains::ChainEntry, config::ApiConfig};
 use database::client::DbClient;
 use error::AppError;
 use serde::Serialize;
 use std::net::SocketAddrV4;
 use std::sync::Arc;
+use std::time::Duration;
+use tower_http::compression::{
+    predicate::{NotForContentType, SizeAbove},
+    CompressionLayer, Predicate,
+};
 use tracing::info;
 
 #[derive(Clone)]
 struct AppState {
     pub db_client: Arc<DbClient>,
+    pub stats: Arc<stats::StatsCache>,
+    pub stats_refresh_token: Option<String>,
+    pub max_sync_lag_blocks: Option<u64>,
+    /// Chains the indexer runs, for names and start heights
+    pub chains: Arc<Vec<ChainEntry>>,
 }
 
 #[derive(Serialize)]
@@ -43,18 +56,63 @@ impl<T: Serialize, P: Serialize> IntoResponse for ApiResponse<T, P> {
 
 type ApiResult<T, P> = Result<ApiResponse<T, P>, AppError>;
 
-fn make_server(state: AppState) -> Router {
+fn make_server(state: AppState, cfg: &ApiConfig) -> Router {
+    // Compress based on Accept-Encoding, leaving small and streaming responses alone
+    let compression = CompressionLayer::new().compress_when(
+        SizeAbove::new(cfg.compression_min_size)
+            .and(NotForContentType::GRPC)
+            .and(NotForContentType::IMAGES)
+            .and(NotForContentType::SSE),
+    );
+
     Router::new()
         .route("/indexer/l1_deposits", get(controller::get_l1_deposits))
+        .route(
+            "/indexer/l1_deposits/{tx_hash}",
+            get(controller::get_deposit_by_hash),
+        )
         .route(
             "/indexer/get_user_deposits/{user_address}",
             get(controller::get_user_deposits),
         )
+        .route(
+            "/indexer/address/{address}/history",
+            get(controller::get_address_history),
+        )
+        // Same timeline under the name the explorer links to
+        .route(
+            "/indexer/address/{address}/activity",
+            get(controller::get_address_history),
+        )
+        .route(
+            "/indexer/deposits/overdue",
+            get(controller::get_overdue_deposits),
+        )
         .route("/indexer/l2_withdraws", get(controller::get_l2_withdraws))
         .route(
             "/indexer/l1_withdraws",
             get(controller::get_l1_forced_withdraws),
         )
+        .route(
+            "/indexer/withdrawals/{chain_id}/{nonce}/timeline",
+            get(controller::get_withdrawal_timeline),
+        )
+        .route(
+            "/indexer/batches/{batch_number}",
+            get(controller::get_batch_detail),
+        )
+        .route(
+            "/indexer/batches/{batch_number}/da",
+            get(controller::get_batch_da),
+        )
+        .route("/indexer/changes", get(controller::get_changes))
+        .route("/indexer/stats", get(stats::get_stats))
+        .route("/indexer/stats/refresh", post(stats::refresh_stats))
+        .route(
+            "/indexer/stats/reconciliation",
+            get(stats::get_reconciliation),
+        )
+        .route("/indexer/stats/largest", get(stats::get_largest))
         .route("/indexer/search/quick", get(search::quick_search))
         .route(
             "/indexer/get_l2_txns_for_l1_txn",
@@ -68,20 +126,36 @@ fn make_server(state: AppState) -> Router {
             "/indexer/get_execute_tx_hash_for_l2_withdraw/{l2_tx_hash}/{destination_chain_id}",
             get(controller::get_execute_tx_hash_for_l2_withdraw),
         )
-        .route("/indexer/status", get(controller::health_check))
+        .route("/indexer/tokens", get(controller::get_tokens))
+        .route("/indexer/uniswap/pools", get(uniswap::get_pools))
+        .route("/indexer/status", get(controller::get_status))
+        .route("/indexer/sync/progress", get(controller::get_sync_progress))
+        .route(
+            "/debug/nonce-anomalies/{chain_id}",
+            get(controller::get_nonce_anomalies),
+        )
         .route("/health", get(controller::health_check))
+        .route("/version", get(controller::get_version))
+        .layer(compression)
         .with_state(state)
 }
 pub async fn start_api(
     primary_db_conn: sea_orm::DatabaseConnection,
-    port: u16,
+    cfg: &ApiConfig,
+    chains: Vec<ChainEntry>,
 ) -> eyre::Result<()> {
     let db_client_instance = DbClient::new(primary_db_conn, None); // None for blockscout
     let state = AppState {
         db_client: Arc::new(db_client_instance),
+        stats: Arc::new(stats::StatsCache::new(Duration::from_secs(
+            cfg.stats_cache_ttl_secs,
+        ))),
+        stats_refresh_token: cfg.stats_refresh_token.clone(),
+        max_sync_lag_blocks: cfg.max_sync_lag_blocks,
+        chains: Arc::new(chains),
     };
-    let server = make_server(state);
-    let addr = SocketAddrV4::new(std::net::Ipv4Addr::new(0, 0, 0, 0), port);
+    let server = make_server(state, cfg);
+    let addr = SocketAddrV4::new(std::net::Ipv4Addr::new(0, 0, 0, 0), cfg.port);
     let listener = tokio::net::TcpListener::bind(addr).await?;
     info!("API Server running on {}", addr);
     axum::serve(listener, server)
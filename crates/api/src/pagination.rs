@@ -1,4 +1,6 @@
+use crate::error::AppError;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 pub const DEFAULT_PER_PAGE: u64 = 10;
 pub const MAX_PER_PAGE: u64 = 500;
 pub fn items_count(count: Option<u64>) -> u64 {
@@ -23,3 +25,155 @@ pub struct BridgeTransactionsPagination {
 }
 
 impl Pagination for BridgeTransactionsPagination {}
+
+/// Query string form of `BridgeTransactionsPagination`. Values are kept as
+/// strings so a malformed one is reported by name instead of failing the
+/// whole extractor.
+#[derive(Deserialize, Clone, Debug)]
+
+pub struct BridgeTransactionsPaginationQuery {
+    pub items_count: Option<String>,
+
+    pub chain_id: Option<String>,
+
+    pub nonce: Option<String>,
+}
+
+impl BridgeTransactionsPaginationQuery {
+    pub fn parse(self) -> Result<BridgeTransactionsPagination, AppError> {
+        Ok(BridgeTransactionsPagination {
+            items_count: parse_param("items_count", self.items_count)?,
+            chain_id: parse_param("chain_id", self.chain_id)?,
+            nonce: parse_param("nonce", self.nonce)?,
+        })
+    }
+}
+
+/// Cursor for `/changes`, the last source transaction and flow ids returned
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ChangesPagination {
+    pub items_count: Option<u64>,
+    pub source_id: Option<i64>,
+    pub flow_id: Option<i64>,
+}
+
+impl Pagination for ChangesPagination {}
+
+/// Query string form of `ChangesPagination`
+#[derive(Deserialize, Clone, Debug)]
+pub struct ChangesPaginationQuery {
+    pub items_count: Option<String>,
+    pub source_id: Option<String>,
+    pub flow_id: Option<String>,
+}
+
+impl ChangesPaginationQuery {
+    pub fn parse(self) -> Result<ChangesPagination, AppError> {
+        Ok(ChangesPagination {
+            items_count: parse_param("items_count", self.items_count)?,
+            source_id: parse_param::<u64>("source_id", self.source_id)?.map(|id| id as i64),
+            flow_id: parse_param::<u64>("flow_id", self.flow_id)?.map(|id| id as i64),
+        })
+    }
+}
+
+/// Cursor for `/uniswap/pools`, the swap count and pair of the last pool
+/// returned
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct UniswapPoolsPagination {
+    pub items_count: Option<u64>,
+    pub swap_count: Option<u64>,
+    pub pair: Option<String>,
+}
+
+impl Pagination for UniswapPoolsPagination {}
+
+/// Query string form of `UniswapPoolsPagination`, with the optional `token`
+/// filter
+#[derive(Deserialize, Clone, Debug)]
+pub struct UniswapPoolsPaginationQuery {
+    pub items_count: Option<String>,
+    pub swap_count: Option<String>,
+    pub pair: Option<String>,
+    /// Address of the token on either side of the pair
+    pub token: Option<String>,
+}
+
+impl UniswapPoolsPaginationQuery {
+    pub fn parse(self) -> Result<UniswapPoolsPagination, AppError> {
+        Ok(UniswapPoolsPagination {
+            items_count: parse_param("items_count", self.items_count)?,
+            swap_count: parse_param("swap_count", self.swap_count)?,
+            pair: self.pair,
+        })
+    }
+}
+
+/// Parses an optional non-negative integer query parameter
+fn parse_param<T: FromStr>(name: &str, value: Option<String>) -> Result<Option<T>, AppError> {
+    match value {
+        None => Ok(None),
+        Some(raw) => raw.trim().parse().map(Some).map_err(|_| {
+            AppError::BadRequest(format!(
+                "Query parameter '{}' must be a non-negative integer, got '{}'",
+                name, raw
+            ))
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(
+        items_count: Option<&str>,
+        chain_id: Option<&str>,
+        nonce: Option<&str>,
+    ) -> BridgeTransactionsPaginationQuery {
+        BridgeTransactionsPaginationQuery {
+            items_count: items_count.map(str::to_string),
+            chain_id: chain_id.map(str::to_string),
+            nonce: nonce.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn cursor_parameters_are_parsed() {
+        let pagination = query(Some("20"), Some(" 1 "), Some("42")).parse().unwrap();
+
+        assert_eq!(pagination.items_count, Some(20));
+        assert_eq!(pagination.chain_id, Some(1));
+        assert_eq!(pagination.nonce, Some(42));
+    }
+
+    #[test]
+    fn missing_parameters_start_from_the_first_page() {
+        let pagination = query(None, None, None).parse().unwrap();
+
+        assert_eq!(pagination.items_count, None);
+        assert_eq!(pagination.chain_id, None);
+        assert_eq!(pagination.nonce, None);
+    }
+
+    #[test]
+    fn malformed_parameter_is_named() {
+        for (bad, name) in [
+            (query(None, None, Some("-1")), "nonce"),
+            (query(None, Some("0x1"), None), "chain_id"),
+            (query(Some("ten"), None, None), "items_count"),
+        ] {
+            match bad.parse() {
+                Err(AppError::BadRequest(message)) => assert!(message.contains(name), "{message}"),
+                other => panic!("expected a bad request for {name}, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn items_count_is_clamped() {
+        assert_eq!(items_count(None), DEFAULT_PER_PAGE);
+        assert_eq!(items_count(Some(0)), 1);
+        assert_eq!(items_count(Some(MAX_PER_PAGE + 1)), MAX_PER_PAGE);
+    }
+}
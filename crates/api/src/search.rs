@@ -4,8 +4,9 @@ use crate::{
 };
 use axum::extract::{Query, State};
 use chrono::{DateTime, Utc};
-use database::entities::{
-    sea_orm_active_enums::TransactionTypeEnum, source_transactions, transaction_flows,
+use database::{
+    bridge::SearchMatch,
+    entities::{sea_orm_active_enums::TransactionTypeEnum, source_transactions, transaction_flows},
 };
 use eyre::{eyre, Result};
 use serde::Deserialize;
@@ -51,7 +52,7 @@ pub async fn quick_search(
 
     let suggestions = search_results
         .iter()
-        .map(|(source, dest)| map_to_suggestion(source, dest))
+        .map(|(source, dest, search_match)| map_to_suggestion(source, dest, *search_match))
         .collect::<Result<Vec<_>, _>>()
         .map_err(|suggestion_err| {
             error!(error = %suggestion_err, "Mapping suggestion error during quick search");
@@ -72,7 +73,10 @@ fn map_to_suggestion(
     source: &source_transactions::Model,
 
     dest: &transaction_flows::Model,
+    search_match: SearchMatch,
 ) -> Result<TransactionSuggestion> {
+    let match_kind = search_match.as_str().to_string();
+
     let to_address = source.twine_address.clone();
 
     let timestamp_utc = DateTime::<Utc>::from_naive_utc_and_offset(
@@ -94,6 +98,7 @@ fn map_to_suggestion(
                 "/tx/{}",
                 source.transaction_hash.clone().unwrap_or_default()
             ),
+            match_kind,
         }),
 
         TransactionTypeEnum::Withdraw => Ok(TransactionSuggestion {
@@ -109,6 +114,7 @@ fn map_to_suggestion(
                 "/tx/{}",
                 source.transaction_hash.clone().unwrap_or_default()
             ),
+            match_kind,
         }),
 
         TransactionTypeEnum::ForcedWithdraw => Ok(TransactionSuggestion {
@@ -124,6 +130,7 @@ fn map_to_suggestion(
                 "/tx/{}",
                 source.transaction_hash.clone().unwrap_or_default()
             ),
+            match_kind,
         }),
     }
 }
@@ -0,0 +1,323 @@
+use crate::{
+    error::AppError,
+    pagination::{items_count, PlaceholderPagination},
+    types::{
+        BridgeStatsResponse, ChainReconciliationResponse, LargestTransactionResponse,
+        LargestTransactionsQuery, ReconciliationQuery, ReconciliationResponse,
+    },
+    ApiResponse, ApiResult, AppState,
+};
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+};
+use chrono::{DateTime, Utc};
+use database::{
+    bridge::BridgeStats, client::DbClient, entities::sea_orm_active_enums::TransactionTypeEnum,
+};
+use sea_orm::DbErr;
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+use tracing::{debug, info, instrument};
+
+struct CachedStats {
+    computed_at: Instant,
+    stats: BridgeStatsResponse,
+}
+
+/// Bridge stats cached for `ttl`. Recomputing holds the lock, so requests
+/// arriving meanwhile wait for that result instead of querying the DB again.
+pub struct StatsCache {
+    ttl: Duration,
+    cached: Mutex<Option<CachedStats>>,
+}
+
+impl StatsCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Cached stats, recomputed when older than the TTL
+    pub async fn get(&self, db_client: &DbClient) -> Result<BridgeStatsResponse, AppError> {
+        self.get_with(|| db_client.fetch_bridge_stats()).await
+    }
+
+    /// Stats computed after this call started. Refreshes that queue up behind
+    /// one already in flight reuse its result.
+    pub async fn refresh(&self, db_client: &DbClient) -> Result<BridgeStatsResponse, AppError> {
+        self.refresh_with(|| db_client.fetch_bridge_stats()).await
+    }
+
+    async fn get_with<F, Fut>(&self, fetch: F) -> Result<BridgeStatsResponse, AppError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<BridgeStats, DbErr>>,
+    {
+        let mut cached = self.cached.lock().await;
+        if let Some(entry) = cached.as_ref() {
+            if entry.computed_at.elapsed() < self.ttl {
+                return Ok(entry.stats.clone());
+            }
+        }
+
+        let entry = Self::compute(fetch).await?;
+        let stats = entry.stats.clone();
+        *cached = Some(entry);
+        Ok(stats)
+    }
+
+    async fn refresh_with<F, Fut>(&self, fetch: F) -> Result<BridgeStatsResponse, AppError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<BridgeStats, DbErr>>,
+    {
+        let requested_at = Instant::now();
+        let mut cached = self.cached.lock().await;
+        if let Some(entry) = cached.as_ref() {
+            if entry.computed_at >= requested_at {
+                debug!("Reusing stats computed by a concurrent refresh");
+                return Ok(entry.stats.clone());
+            }
+        }
+
+        let entry = Self::compute(fetch).await?;
+        let stats = entry.stats.clone();
+        *cached = Some(entry);
+        Ok(stats)
+    }
+
+    async fn compute<F, Fut>(fetch: F) -> Result<CachedStats, AppError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<BridgeStats, DbErr>>,
+    {
+        let stats = fetch().await?;
+        Ok(CachedStats {
+            computed_at: Instant::now(),
+            stats: BridgeStatsResponse {
+                total_deposits: stats.total_deposits,
+                total_withdraws: stats.total_withdraws,
+                total_forced_withdraws: stats.total_forced_withdraws,
+                pending_deposits: stats.pending_deposits,
+                computed_at: Utc::now(),
+            },
+        })
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn get_stats(
+    State(state): State<AppState>,
+) -> ApiResult<BridgeStatsResponse, PlaceholderPagination> {
+    let stats = state.stats.get(&state.db_client).await?;
+    Ok(ApiResponse {
+        success: true,
+        items: stats,
+        next_page_params: None,
+    })
+}
+
+#[instrument(skip_all)]
+pub async fn refresh_stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<BridgeStatsResponse, PlaceholderPagination> {
+    let expected = state
+        .stats_refresh_token
+        .as_deref()
+        .ok_or_else(|| AppError::Unauthorized("Stats refresh is disabled".to_string()))?;
+
+    let provided = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    if provided != Some(expected) {
+        return Err(AppError::Unauthorized(
+            "Missing or invalid refresh token".to_string(),
+        ));
+    }
+
+    info!("Refreshing bridge stats on request");
+    let stats = state.stats.refresh(&state.db_client).await?;
+    Ok(ApiResponse {
+        success: true,
+        items: stats,
+        next_page_params: None,
+    })
+}
+
+/// Deposits started per L1 in a window against those handled on Twine, the
+/// last day by default
+#[instrument(skip(state), fields(query = ?query))]
+pub async fn get_reconciliation(
+    State(state): State<AppState>,
+    Query(query): Query<ReconciliationQuery>,
+) -> ApiResult<ReconciliationResponse, PlaceholderPagination> {
+    let to = parse_timestamp("to", query.to)?.unwrap_or_else(Utc::now);
+    let from = parse_timestamp("from", query.from)?.unwrap_or(to - chrono::Duration::days(1));
+    if from >= to {
+        return Err(AppError::BadRequest(format!(
+            "'from' ({}) must be before 'to' ({})",
+            from, to
+        )));
+    }
+
+    let report = state
+        .db_client
+        .reconciliation_report(from.fixed_offset(), to.fixed_offset())
+        .await?;
+
+    let chains = report
+        .iter()
+        .map(|chain| ChainReconciliationResponse {
+            chain_id: chain.chain_id,
+            deposits: chain.deposits,
+            handled: chain.handled,
+            delta: chain.delta(),
+        })
+        .collect();
+
+    Ok(ApiResponse {
+        success: true,
+        items: ReconciliationResponse { from, to, chains },
+        next_page_params: None,
+    })
+}
+
+/// Bridge transactions of one type moving the largest amounts in a window,
+/// the last day by default
+#[instrument(skip(state), fields(query = ?query))]
+pub async fn get_largest(
+    State(state): State<AppState>,
+    Query(query): Query<LargestTransactionsQuery>,
+) -> ApiResult<Vec<LargestTransactionResponse>, PlaceholderPagination> {
+    let tx_type = match query
+        .transaction_type
+        .as_deref()
+        .map(|value| value.trim().to_lowercase())
+        .as_deref()
+    {
+        None | Some("deposit") => TransactionTypeEnum::Deposit,
+        Some("withdraw") => TransactionTypeEnum::Withdraw,
+        Some("forced_withdraw") => TransactionTypeEnum::ForcedWithdraw,
+        Some(other) => {
+            return Err(AppError::BadRequest(format!(
+                "Unknown transaction_type '{}', expected deposit, withdraw or forced_withdraw",
+                other
+            )))
+        }
+    };
+    let to = parse_timestamp("to", query.to)?.unwrap_or_else(Utc::now);
+    let from = parse_timestamp("from", query.from)?.unwrap_or(to - chrono::Duration::days(1));
+    if from >= to {
+        return Err(AppError::BadRequest(format!(
+            "'from' ({}) must be before 'to' ({})",
+            from, to
+        )));
+    }
+
+    let items = state
+        .db_client
+        .top_transactions_by_amount(
+            tx_type,
+            from.fixed_offset(),
+            to.fixed_offset(),
+            items_count(query.limit),
+        )
+        .await?
+        .into_iter()
+        .map(|tx| LargestTransactionResponse {
+            chain_id: tx.chain_id,
+            nonce: tx.nonce,
+            transaction_type: format!("{:?}", tx.transaction_type),
+            source_tx_hash: tx.transaction_hash,
+            l1_address: tx.l1_address,
+            twine_address: tx.twine_address,
+            l1_token: tx.l1_token,
+            l2_token: tx.l2_token,
+            amount: tx.amount.to_string(),
+            timestamp: tx.timestamp,
+        })
+        .collect();
+
+    Ok(ApiResponse {
+        success: true,
+        items,
+        next_page_params: None,
+    })
+}
+
+fn parse_timestamp(name: &str, value: Option<String>) -> Result<Option<DateTime<Utc>>, AppError> {
+    value
+        .map(|raw| {
+            DateTime::parse_from_rfc3339(raw.trim())
+                .map(|ts| ts.with_timezone(&Utc))
+                .map_err(|_| {
+                    AppError::BadRequest(format!(
+                        "Query parameter '{}' must be an RFC 3339 timestamp, got '{}'",
+                        name, raw
+                    ))
+                })
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Stats with `total_deposits`, counting the queries and taking a while
+    /// like the real aggregation
+    async fn fetch(queries: &AtomicUsize, total_deposits: u64) -> Result<BridgeStats, DbErr> {
+        queries.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        Ok(BridgeStats {
+            total_deposits,
+            total_withdraws: 0,
+            total_forced_withdraws: 0,
+            pending_deposits: 0,
+        })
+    }
+
+    #[tokio::test]
+    async fn forced_refresh_returns_updated_numbers() {
+        let cache = StatsCache::new(Duration::from_secs(3600));
+        let queries = AtomicUsize::new(0);
+
+        let first = cache.get_with(|| fetch(&queries, 1)).await.unwrap();
+        let cached = cache.get_with(|| fetch(&queries, 2)).await.unwrap();
+        let refreshed = cache.refresh_with(|| fetch(&queries, 3)).await.unwrap();
+        let after = cache.get_with(|| fetch(&queries, 4)).await.unwrap();
+
+        assert_eq!(first.total_deposits, 1);
+        assert_eq!(cached.total_deposits, 1);
+        assert_eq!(refreshed.total_deposits, 3);
+        assert_eq!(after.total_deposits, 3);
+        assert_eq!(queries.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn concurrent_refreshes_coalesce_into_one_query() {
+        let cache = StatsCache::new(Duration::from_secs(3600));
+        let queries = AtomicUsize::new(0);
+
+        let (a, b, c) = tokio::join!(
+            cache.refresh_with(|| fetch(&queries, 1)),
+            cache.refresh_with(|| fetch(&queries, 2)),
+            cache.refresh_with(|| fetch(&queries, 3)),
+        );
+
+        assert_eq!(queries.load(Ordering::SeqCst), 1);
+        for stats in [a, b, c] {
+            assert_eq!(stats.unwrap().total_deposits, 1);
+        }
+    }
+}
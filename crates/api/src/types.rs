@@ -1,10 +1,66 @@
 use chrono::{DateTime, Utc};
+use common::chains::ChainEntry;
+use database::bridge::BridgeEventFilter;
 use sea_orm::prelude::DateTimeWithTimeZone;
 use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// Serializes `bytea` columns as `0x`-prefixed hex strings instead of byte arrays.
+pub mod hex_bytes {
+    use serde::Serializer;
+
+    pub fn encode(bytes: &[u8]) -> String {
+        format!("0x{}", hex::encode(bytes))
+    }
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode(bytes))
+    }
+
+    pub mod option {
+        use serde::Serializer;
+
+        pub fn serialize<S: Serializer>(
+            bytes: &Option<Vec<u8>>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match bytes {
+                Some(bytes) => super::serialize(bytes, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+    }
+}
+
+/// Whether a source height is an EVM block number or a Solana slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HeightType {
+    Block,
+    Slot,
+}
+
+impl HeightType {
+    /// Heights of the configured Solana chain are slots, every other chain
+    /// (and one missing from `chains`) counts EVM blocks.
+    pub fn for_chain(chain_id: i64, chains: &[ChainEntry]) -> Self {
+        match chains
+            .iter()
+            .find(|chain| chain.chain_id as i64 == chain_id)
+        {
+            Some(chain) if chain.name.eq_ignore_ascii_case("Solana") => HeightType::Slot,
+            _ => HeightType::Block,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct BridgeTransactionsResponse {
     pub source_tx_hash: String,
     pub source_block_height: Option<i64>,
+    pub height: Option<i64>,
+    pub height_type: HeightType,
     pub nonce: i64,
     pub chain_id: i64,
 
@@ -15,6 +71,9 @@ pub struct BridgeTransactionsResponse {
     pub l1_execute_hash: Option<String>,
     pub l1_execute_block_height: Option<i64>,
     pub l1_executed_at: Option<DateTimeWithTimeZone>,
+    pub l1_is_executed: bool,
+    /// Forced withdrawals need both the Twine handle and the L1 execute
+    pub is_completed: bool,
 
     pub status: Option<i16>,
     pub l1_token: Option<String>,
@@ -22,6 +81,10 @@ pub struct BridgeTransactionsResponse {
     pub from: String,
     pub to_twine_address: Option<String>,
     pub amount: Option<String>,
+    #[serde(serialize_with = "hex_bytes::option::serialize")]
+    pub message: Option<Vec<u8>>,
+    #[serde(serialize_with = "hex_bytes::option::serialize")]
+    pub transaction_output: Option<Vec<u8>>,
     pub created_at: DateTimeWithTimeZone,
 }
 
@@ -36,6 +99,9 @@ pub struct TransactionSuggestion {
     pub timestamp: DateTime<Utc>,
     pub r#type: String,
     pub url: String,
+    /// `exact_hash` when the query is one of the transaction's hashes,
+    /// `partial` when it matched a fragment of a searched column
+    pub match_kind: String,
 }
 
 #[derive(Deserialize, Debug)]
@@ -75,10 +141,172 @@ pub struct L2WithdrawExecuteHashResponse {
     pub found: bool,
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceTransactionChange {
+    pub id: i64,
+    pub chain_id: i64,
+    pub nonce: i64,
+    pub transaction_type: String,
+    pub transaction_hash: Option<String>,
+    pub block_number: i64,
+    pub l1_address: String,
+    pub twine_address: String,
+    pub l1_token: String,
+    pub l2_token: String,
+    pub amount: String,
+    pub timestamp: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionFlowChange {
+    pub id: i64,
+    pub chain_id: i64,
+    pub nonce: i64,
+    pub handle_tx_hash: Option<String>,
+    pub handled_at: Option<DateTimeWithTimeZone>,
+    pub execute_tx_hash: Option<String>,
+    pub executed_at: Option<DateTimeWithTimeZone>,
+    pub is_completed: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangesResponse {
+    pub source_transactions: Vec<SourceTransactionChange>,
+    pub transaction_flows: Vec<TransactionFlowChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionResponse {
+    pub version: String,
+    pub git_sha: String,
+    pub latest_migration: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WithdrawalTimelineResponse {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub transaction_type: String,
+    pub source_tx_hash: Option<String>,
+    pub submitted_at: Option<DateTimeWithTimeZone>,
+    pub handle_tx_hash: Option<String>,
+    pub handled_at: Option<DateTimeWithTimeZone>,
+    pub execute_tx_hash: Option<String>,
+    pub executed_at: Option<DateTimeWithTimeZone>,
+    pub is_completed: bool,
+}
+
+/// Breaks in the nonce sequence of a chain, hinting at dropped events or at
+/// another chain sharing its chain id
+#[derive(Debug, Clone, Serialize)]
+pub struct NonceAnomaliesResponse {
+    pub chain_id: u64,
+    pub gaps: Vec<NonceGapResponse>,
+    pub out_of_order: Vec<OutOfOrderNonceResponse>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NonceGapResponse {
+    pub after_nonce: i64,
+    pub before_nonce: i64,
+    pub missing: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutOfOrderNonceResponse {
+    pub nonce: i64,
+    pub block_number: i64,
+    pub previous_nonce: i64,
+    pub previous_block_number: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChainStatusResponse {
+    pub chain_id: i64,
+    pub last_synced_block: i64,
+    /// Latest head the chain's indexer saw, null until it has polled once
+    pub current_head: Option<i64>,
+    pub lag: Option<u64>,
+}
+
+/// How far a chain's indexer is between its start height and the head
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncProgressResponse {
+    pub chain: String,
+    pub chain_id: u64,
+    /// Null until the chain has checkpointed once
+    pub synced_height: Option<i64>,
+    /// Null until the chain's indexer has polled the head once
+    pub head: Option<i64>,
+    /// Share of the heights from the start height to the head indexed so
+    /// far, null without a head
+    pub percent: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DepositResponse {
+    pub source_tx_hash: String,
+    pub chain_id: i64,
+    pub destination_chain_id: Option<i64>,
+    pub nonce: i64,
+    pub block_number: i64,
+    pub submitted_at: Option<DateTimeWithTimeZone>,
+    pub from: String,
+    pub to_twine_address: String,
+    pub l1_token: String,
+    pub l2_token: String,
+    pub amount: String,
+    /// One of `Pending`, `Handled`, `Failed` or `Refunded`
+    pub status: String,
+    pub l2_handle_tx_hash: Option<String>,
+    pub l2_handled_at: Option<DateTimeWithTimeZone>,
+    pub l1_refund_tx_hash: Option<String>,
+    pub is_completed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenResponse {
+    pub l1_token: String,
+    pub l2_token: String,
+    /// Symbol of the L2 token, null when its metadata has not been fetched
+    pub symbol: Option<String>,
+    pub first_seen: Option<DateTimeWithTimeZone>,
+    pub last_seen: Option<DateTimeWithTimeZone>,
+    pub transfers: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchDaReferenceResponse {
+    pub celestia_height: i64,
+    pub namespace: String,
+    pub commitment: String,
+}
+
+/// Where a batch was posted on Celestia, enough to fetch the blob back and
+/// check it is available
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchDaResponse {
+    pub batch_number: u64,
+    pub celestia_height: i64,
+    pub namespace: String,
+    pub commitment: String,
+    /// When the DA indexer linked the blob to the batch
+    pub linked_at: DateTimeWithTimeZone,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchDetailResponse {
+    pub batch_number: u64,
+    /// Celestia blob the batch was posted in, null until the DA indexer has seen it
+    pub da_reference: Option<BatchDaReferenceResponse>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct UserDepositsResponse {
     pub l1_tx_hash: String,
     pub l1_block_height: Option<i64>,
+    pub height: Option<i64>,
+    pub height_type: HeightType,
     pub nonce: i64,
     pub chain_id: i64,
 
@@ -89,6 +317,9 @@ pub struct UserDepositsResponse {
     pub l1_execute_hash: Option<String>,
     pub l1_execute_block_height: Option<i64>,
     pub l1_executed_at: Option<DateTimeWithTimeZone>,
+    pub l1_is_executed: bool,
+    /// Forced withdrawals need both the Twine handle and the L1 execute
+    pub is_completed: bool,
 
     pub status: Option<i16>,
     pub l1_token: Option<String>,
@@ -96,12 +327,83 @@ pub struct UserDepositsResponse {
     pub from: String,
     pub to_twine_address: Option<String>,
     pub amount: Option<String>,
+    #[serde(serialize_with = "hex_bytes::option::serialize")]
+    pub message: Option<Vec<u8>>,
+    #[serde(serialize_with = "hex_bytes::option::serialize")]
+    pub transaction_output: Option<Vec<u8>>,
     pub created_at: DateTimeWithTimeZone,
 
     // Additional fields for user deposits
     pub is_handled: bool,
     pub is_executed: bool,
-    pub is_completed: bool,
+}
+
+/// One entry of an address's bridge history. Deposit-shaped fields are reused
+/// for withdrawals, where `from` is the L1 side of the transfer.
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressHistoryResponse {
+    /// Deposit, Withdraw or ForcedWithdraw
+    pub transaction_type: String,
+    #[serde(flatten)]
+    pub transaction: UserDepositsResponse,
+}
+
+/// Deposits not handled within this window are reported as overdue
+pub const DEFAULT_DEPOSIT_SLA_SECS: u64 = 60 * 60;
+
+/// Filters of `/l1_deposits`. The source chain is `source_chain_id` because
+/// `chain_id` is already the cursor's.
+#[derive(Deserialize, Clone, Debug)]
+pub struct L1DepositsFilterQuery {
+    pub source_chain_id: Option<String>,
+    /// L1 or L2 address of the deposited token
+    pub token: Option<String>,
+}
+
+impl L1DepositsFilterQuery {
+    pub fn parse(self) -> Result<BridgeEventFilter, AppError> {
+        let chain_id = self
+            .source_chain_id
+            .map(|value| {
+                value.parse::<i64>().map_err(|_| {
+                    AppError::BadRequest(format!(
+                        "Invalid source_chain_id '{}', expected an integer",
+                        value
+                    ))
+                })
+            })
+            .transpose()?;
+        let token = self.token.filter(|token| !token.trim().is_empty());
+
+        Ok(BridgeEventFilter { chain_id, token })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UniswapPoolResponse {
+    pub pair: String,
+    pub token0: String,
+    pub token0_symbol: Option<String>,
+    pub token1: String,
+    pub token1_symbol: Option<String>,
+    pub creation_tx_hash: String,
+    pub creation_block: i64,
+    pub swap_count: i64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OverdueDepositsQuery {
+    pub sla_secs: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BridgeStatsResponse {
+    pub total_deposits: u64,
+    pub total_withdraws: u64,
+    pub total_forced_withdraws: u64,
+    pub pending_deposits: u64,
+    pub computed_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -130,3 +432,117 @@ pub struct UserSwapEventsResponse {
     pub to: String,
     pub pair_address: String,
 }
+
+#[derive(Deserialize, Debug)]
+pub struct ReconciliationQuery {
+    /// RFC 3339 start of the window, a day before `to` when unset
+    pub from: Option<String>,
+    /// RFC 3339 end of the window, now when unset
+    pub to: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LargestTransactionsQuery {
+    /// `deposit`, `withdraw` or `forced_withdraw`, deposits when unset
+    pub transaction_type: Option<String>,
+    /// RFC 3339 start of the window, a day before `to` when unset
+    pub from: Option<String>,
+    /// RFC 3339 end of the window, now when unset
+    pub to: Option<String>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LargestTransactionResponse {
+    pub chain_id: i64,
+    pub nonce: i64,
+    pub transaction_type: String,
+    pub source_tx_hash: Option<String>,
+    pub l1_address: String,
+    pub twine_address: String,
+    pub l1_token: String,
+    pub l2_token: String,
+    pub amount: String,
+    pub timestamp: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChainReconciliationResponse {
+    pub chain_id: i64,
+    pub deposits: u64,
+    pub handled: u64,
+    pub delta: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReconciliationResponse {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub chains: Vec<ChainReconciliationResponse>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn chain(name: &str, chain_id: u64) -> ChainEntry {
+        ChainEntry {
+            name: name.to_string(),
+            chain_id,
+            config: serde_json::from_value(json!({
+                "http_rpc_url": "http://localhost:8545",
+                "chain_id": chain_id,
+                "start_block": 0,
+                "block_sync_batch_size": 100,
+                "block_time_ms": 1000,
+            }))
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn height_type_follows_the_chain_not_the_hash() {
+        let chains = [chain("Ethereum", 1), chain("Solana", 900)];
+
+        assert_eq!(HeightType::for_chain(1, &chains), HeightType::Block);
+        assert_eq!(HeightType::for_chain(900, &chains), HeightType::Slot);
+    }
+
+    #[test]
+    fn unknown_chain_counts_blocks() {
+        assert_eq!(HeightType::for_chain(42, &[]), HeightType::Block);
+    }
+
+    fn filter(source_chain_id: Option<&str>, token: Option<&str>) -> L1DepositsFilterQuery {
+        L1DepositsFilterQuery {
+            source_chain_id: source_chain_id.map(str::to_string),
+            token: token.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn deposit_filter_parses_chain_and_token() {
+        assert_eq!(
+            filter(Some("8453"), Some("0xToken")).parse().unwrap(),
+            BridgeEventFilter {
+                chain_id: Some(8453),
+                token: Some("0xToken".to_string()),
+            }
+        );
+        assert_eq!(
+            filter(None, Some(" ")).parse().unwrap(),
+            BridgeEventFilter::default()
+        );
+    }
+
+    #[test]
+    fn non_numeric_source_chain_is_a_bad_request() {
+        use axum::{http::StatusCode, response::IntoResponse};
+
+        let err = filter(Some("base"), None).parse().unwrap_err();
+
+        assert!(matches!(err, AppError::BadRequest(_)), "{err}");
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+}
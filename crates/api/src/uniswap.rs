@@ -0,0 +1,70 @@
+use crate::{
+    error::AppError,
+    pagination::{items_count, UniswapPoolsPagination, UniswapPoolsPaginationQuery},
+    types::UniswapPoolResponse,
+    ApiResponse, ApiResult, AppState,
+};
+use axum::extract::{Query, State};
+use database::uniswap::PoolCursor;
+use tracing::{info, instrument};
+
+/// Uniswap pools with the most swaps first, optionally only those trading
+/// `token`
+#[instrument(skip(state), fields(query = ?query))]
+pub async fn get_pools(
+    State(state): State<AppState>,
+    Query(query): Query<UniswapPoolsPaginationQuery>,
+) -> ApiResult<Vec<UniswapPoolResponse>, UniswapPoolsPagination> {
+    let token = query.token.clone().filter(|token| !token.trim().is_empty());
+    let pagination = query.parse()?;
+    let items_count = items_count(pagination.items_count);
+    let cursor = match (pagination.swap_count, pagination.pair) {
+        (Some(swap_count), Some(pair)) => Some(PoolCursor {
+            swap_count: swap_count as i64,
+            pair,
+        }),
+        (None, None) => None,
+        _ => {
+            return Err(AppError::BadRequest(
+                "swap_count and pair must be given together".to_string(),
+            ))
+        }
+    };
+
+    let pools = state
+        .db_client
+        .fetch_pools_paginated(token.as_deref(), items_count, cursor)
+        .await?;
+
+    let next_page_params = if pools.len() == items_count as usize {
+        pools.last().map(|pool| UniswapPoolsPagination {
+            items_count: Some(items_count),
+            swap_count: Some(pool.swap_count as u64),
+            pair: Some(pool.pair.clone()),
+        })
+    } else {
+        None
+    };
+
+    let items: Vec<UniswapPoolResponse> = pools
+        .into_iter()
+        .map(|pool| UniswapPoolResponse {
+            pair: pool.pair,
+            token0: pool.token0,
+            token0_symbol: pool.token0_symbol,
+            token1: pool.token1,
+            token1_symbol: pool.token1_symbol,
+            creation_tx_hash: pool.tx_hash,
+            creation_block: pool.block_number,
+            swap_count: pool.swap_count,
+        })
+        .collect();
+
+    info!(count = items.len(), "Fetched uniswap pools");
+
+    Ok(ApiResponse {
+        success: true,
+        items,
+        next_page_params,
+    })
+}
@@ -0,0 +1,163 @@
+use std::collections::{HashMap, HashSet};
+
+use eyre::{eyre, Result};
+
+use crate::config::{ChainConfig, IndexerConfig, INDEXER_CHAINS};
+
+/// A chain the indexer runs, under its canonical name
+#[derive(Debug, Clone)]
+pub struct ChainEntry {
+    pub name: String,
+    pub chain_id: u64,
+    pub config: ChainConfig,
+}
+
+/// Maps canonical chain names to chain ids and their config. A name can never
+/// appear twice, and chain ids and RPC URLs must be distinct unless shared
+/// endpoints are explicitly allowed.
+#[derive(Debug, Clone, Default)]
+pub struct ChainRegistry {
+    chains: Vec<ChainEntry>,
+}
+
+impl ChainRegistry {
+    pub fn new(chains: Vec<ChainEntry>, allow_shared_endpoints: bool) -> Result<Self> {
+        let mut names = HashSet::new();
+        let mut chain_ids: HashMap<u64, &str> = HashMap::new();
+        let mut rpc_urls: HashMap<String, &str> = HashMap::new();
+
+        for chain in &chains {
+            if !names.insert(chain.name.to_lowercase()) {
+                return Err(eyre!("Chain '{}' is configured more than once", chain.name));
+            }
+            if allow_shared_endpoints {
+                continue;
+            }
+
+            if let Some(other) = chain_ids.insert(chain.chain_id, &chain.name) {
+                return Err(eyre!(
+                    "Chains '{}' and '{}' are both configured with chain id {}",
+                    other,
+                    chain.name,
+                    chain.chain_id
+                ));
+            }
+            let rpc_url = chain
+                .config
+                .http_rpc_url
+                .trim_end_matches('/')
+                .to_lowercase();
+            if let Some(other) = rpc_urls.insert(rpc_url, &chain.name) {
+                return Err(eyre!(
+                    "Chains '{}' and '{}' are both configured with RPC URL {}",
+                    other,
+                    chain.name,
+                    chain.config.http_rpc_url
+                ));
+            }
+        }
+
+        Ok(Self { chains })
+    }
+
+    /// Registry of every chain in the indexer config, in `INDEXER_CHAINS` order
+    pub fn from_config(cfg: &IndexerConfig) -> Result<Self> {
+        let configs = [
+            &cfg.twine.common,
+            &cfg.l1s.ethereum.common,
+            &cfg.l1s.arbitrum.common,
+            &cfg.l1s.base.common,
+            &cfg.l1s.solana.common,
+        ];
+
+        let chains = INDEXER_CHAINS
+            .iter()
+            .zip(configs)
+            .map(|(name, config)| ChainEntry {
+                name: name.to_string(),
+                chain_id: config.chain_id,
+                config: config.clone(),
+            })
+            .collect();
+
+        Self::new(chains, cfg.allow_shared_chain_endpoints)
+    }
+
+    /// Looks a chain up by name, ignoring case
+    pub fn by_name(&self, name: &str) -> Option<&ChainEntry> {
+        self.chains
+            .iter()
+            .find(|chain| chain.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn by_chain_id(&self, chain_id: u64) -> Option<&ChainEntry> {
+        self.chains.iter().find(|chain| chain.chain_id == chain_id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ChainEntry> {
+        self.chains.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::tests::indexer_config;
+
+    fn entries(cfg: &IndexerConfig) -> Vec<ChainEntry> {
+        ChainRegistry::from_config(cfg)
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn registry_looks_chains_up_by_name_and_id() {
+        let registry = ChainRegistry::from_config(&indexer_config("")).unwrap();
+
+        assert_eq!(
+            registry.by_name("solana").map(|chain| chain.chain_id),
+            Some(103)
+        );
+        assert_eq!(
+            registry.by_chain_id(1).map(|chain| chain.name.as_str()),
+            Some("Ethereum")
+        );
+        assert!(registry.by_name("Polygon").is_none());
+    }
+
+    #[test]
+    fn duplicate_name_is_rejected() {
+        let mut chains = entries(&indexer_config(""));
+        let mut twin = chains[1].clone();
+        twin.name = "ETHEREUM".to_string();
+        twin.chain_id = 5;
+        twin.config.http_rpc_url = "http://localhost:8545/5".to_string();
+        chains.push(twin);
+
+        let error = ChainRegistry::new(chains, true).unwrap_err();
+        assert!(error.to_string().contains("more than once"), "{error}");
+    }
+
+    #[test]
+    fn shared_chain_id_is_rejected_unless_allowed() {
+        let mut chains = entries(&indexer_config(""));
+        chains[2].chain_id = chains[1].chain_id;
+
+        let error = ChainRegistry::new(chains.clone(), false).unwrap_err();
+        assert!(error.to_string().contains("chain id 1"), "{error}");
+        assert!(ChainRegistry::new(chains, true).is_ok());
+    }
+
+    #[test]
+    fn shared_rpc_url_is_rejected_unless_allowed() {
+        let mut chains = entries(&indexer_config(""));
+        chains[2].config.http_rpc_url =
+            format!("{}/", chains[1].config.http_rpc_url.to_uppercase());
+
+        let error = ChainRegistry::new(chains.clone(), false).unwrap_err();
+        assert!(error.to_string().contains("RPC URL"), "{error}");
+        assert!(ChainRegistry::new(chains, true).is_ok());
+    }
+}
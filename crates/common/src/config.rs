@@ -1,7 +1,13 @@
 use config::{Config, File};
 use dotenv::dotenv;
 use eyre::{eyre, Result};
-use serde::{de::DeserializeOwned, Deserialize};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use database::{entities::sea_orm_active_enums::TransactionTypeEnum, DbOperations};
+
+use crate::indexer::RetryPolicy;
 
 fn config_from_env() -> Result<AppConfig> {
     dotenv().ok();
@@ -32,12 +38,36 @@ pub struct AppConfig {
 #[derive(Deserialize, Debug, Clone)]
 pub struct DatabaseConfig {
     pub url: String,
+    /// Statements running longer than this are cancelled by Postgres
+    #[serde(default)]
+    pub statement_timeout_ms: Option<u64>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct ApiConfig {
     pub database: DatabaseConfig,
     pub port: u16,
+    /// Responses smaller than this many bytes are sent uncompressed
+    #[serde(default = "default_compression_min_size")]
+    pub compression_min_size: u16,
+    /// How long /indexer/stats serves cached numbers before recomputing
+    #[serde(default = "default_stats_cache_ttl_secs")]
+    pub stats_cache_ttl_secs: u64,
+    /// Bearer token for POST /indexer/stats/refresh, refresh is disabled when unset
+    #[serde(default)]
+    pub stats_refresh_token: Option<String>,
+    /// /indexer/status answers 503 when a chain lags its head by more than
+    /// this many blocks, it always answers 200 when unset
+    #[serde(default)]
+    pub max_sync_lag_blocks: Option<u64>,
+}
+
+fn default_compression_min_size() -> u16 {
+    1024
+}
+
+fn default_stats_cache_ttl_secs() -> u64 {
+    60
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -47,23 +77,254 @@ pub struct ChainConfig {
     pub start_block: u64,
     pub block_sync_batch_size: u64,
     pub block_time_ms: u64,
+    /// Typical time between blocks (slots on Solana) that lag thresholds are
+    /// derived from, the known value for `chain_id` or `block_time_ms` when unset
+    #[serde(default)]
+    pub expected_block_time_ms: Option<u64>,
+    /// Blocks a log must be buried under before it is indexed
+    #[serde(default)]
+    pub confirmation_blocks: u64,
+    /// Delay before a range is handled again after a finalize was deferred
+    /// because its commit is not confirmed on L1 yet
+    #[serde(default = "default_pending_finalize_retry_interval_ms")]
+    pub pending_finalize_retry_interval_ms: u64,
+    /// Attempts before a finalize whose commit never got its L1 confirmations
+    /// is dead-lettered, 0 to keep retrying
+    #[serde(default)]
+    pub max_pending_finalize_attempts: u32,
+    /// Blocks (slots on Solana) behind the checkpoint indexed again on
+    /// startup, to heal ranges a crash left partially written
+    #[serde(default)]
+    pub startup_reprocess_window: u64,
+    /// Attempts at a failing RPC call before giving up, `MAX_RETRIES` when unset
+    #[serde(default)]
+    pub max_retries: Option<i32>,
+    /// Delay before the first retry of a failed RPC call, `RETRY_DELAY` when unset
+    #[serde(default)]
+    pub retry_delay_ms: Option<u64>,
+    /// Factor the retry delay grows by after each failure, fixed when unset
+    #[serde(default)]
+    pub retry_backoff_multiplier: Option<f64>,
+    /// Longest the retry delay may grow to, `MAX_RETRY_DELAY` when unset
+    #[serde(default)]
+    pub retry_max_delay_ms: Option<u64>,
+    /// Checks decoded bridge events must pass, failing events are dead-lettered
+    #[serde(default)]
+    pub invariants: EventInvariants,
+    /// Share of the last `dead_letter_window` logs that may be dead-lettered
+    /// before the chain pauses, 0 disables the check
+    #[serde(default)]
+    pub max_dead_letter_ratio: f64,
+    /// Parsed or dead-lettered logs the dead-letter ratio is measured over
+    #[serde(default = "default_dead_letter_window")]
+    pub dead_letter_window: usize,
+}
+
+/// Invariants of decoded bridge events beyond what the database constraints
+/// enforce, each off unless enabled
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct EventInvariants {
+    /// Deposits must move a non-zero amount
+    pub positive_deposit_amount: bool,
+    /// The sending address must not be the zero address
+    pub nonzero_sender: bool,
+}
+
+impl EventInvariants {
+    /// Names the first invariant `operation` breaks, `None` when it passes
+    /// them all or carries no bridge transaction
+    pub fn violation(&self, operation: &DbOperations) -> Option<String> {
+        let DbOperations::BridgeSourceTransaction(model) = operation else {
+            return None;
+        };
+        let transaction_type = set_value(&model.transaction_type)?;
+
+        if self.positive_deposit_amount && transaction_type == TransactionTypeEnum::Deposit {
+            if let Some(amount) = set_value(&model.amount) {
+                if amount.is_zero() {
+                    return Some("positive_deposit_amount: deposit of amount 0".to_string());
+                }
+            }
+        }
+
+        if self.nonzero_sender {
+            // Withdrawals are sent from Twine, everything else from the L1
+            let sender = match transaction_type {
+                TransactionTypeEnum::Withdraw => &model.twine_address,
+                _ => &model.l1_address,
+            };
+            if let Some(sender) = set_value(sender) {
+                if is_zero_address(&sender) {
+                    return Some(format!("nonzero_sender: sent from zero address {}", sender));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+fn set_value<V: Into<sea_orm::Value> + Clone>(value: &sea_orm::ActiveValue<V>) -> Option<V> {
+    match value {
+        sea_orm::ActiveValue::Set(v) | sea_orm::ActiveValue::Unchanged(v) => Some(v.clone()),
+        sea_orm::ActiveValue::NotSet => None,
+    }
+}
+
+/// The EVM zero address or the Solana all-zero public key
+fn is_zero_address(address: &str) -> bool {
+    let address = address.trim();
+    match address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+    {
+        Some(hex) => !hex.is_empty() && hex.chars().all(|c| c == '0'),
+        None => !address.is_empty() && address.chars().all(|c| c == '1'),
+    }
+}
+
+fn default_pending_finalize_retry_interval_ms() -> u64 {
+    5_000
+}
+
+fn default_dead_letter_window() -> usize {
+    100
+}
+
+/// Block times of the public chains the indexer runs against, mainnets and
+/// their testnets, Solana keyed by its conventional cluster ids
+fn known_block_time_ms(chain_id: u64) -> Option<u64> {
+    match chain_id {
+        // Ethereum, Sepolia, Holesky
+        1 | 11155111 | 17000 => Some(12_000),
+        // Arbitrum One, Arbitrum Sepolia
+        42161 | 421614 => Some(250),
+        // Base, Base Sepolia
+        8453 | 84532 => Some(2_000),
+        // Solana mainnet-beta, testnet, devnet
+        101..=103 => Some(400),
+        _ => None,
+    }
+}
+
+impl ChainConfig {
+    /// Block time lag thresholds are derived from, see `expected_block_time_ms`
+    pub fn expected_block_time_ms(&self) -> u64 {
+        self.expected_block_time_ms
+            .or_else(|| known_block_time_ms(self.chain_id))
+            .unwrap_or(self.block_time_ms)
+    }
+
+    /// Blocks produced in `lag`, at least one so a chain is never required
+    /// to be exactly at its head
+    pub fn lag_threshold_blocks(&self, lag: Duration) -> u64 {
+        let block_time_ms = self.expected_block_time_ms().max(1);
+        (lag.as_millis() as u64).div_ceil(block_time_ms).max(1)
+    }
+
+    /// Retry policy for this chain's RPC calls, the shared defaults filling in
+    /// whatever is not configured
+    pub fn retry_policy(&self) -> RetryPolicy {
+        let defaults = RetryPolicy::default();
+        RetryPolicy {
+            max_retries: self.max_retries.unwrap_or(defaults.max_retries),
+            delay: self
+                .retry_delay_ms
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.delay),
+            backoff_multiplier: self
+                .retry_backoff_multiplier
+                .unwrap_or(defaults.backoff_multiplier),
+            max_delay: self
+                .retry_max_delay_ms
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.max_delay),
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct EvmConfig {
     pub common: ChainConfig,
     pub l1_message_handler_address: String,
-    pub l1_erc20_gateway_address: String,
+    /// Token gateway contracts whose logs are indexed, a single address is
+    /// accepted too
+    #[serde(alias = "l1_erc20_gateway_address", deserialize_with = "one_or_many")]
+    pub l1_erc20_gateway_addresses: Vec<String>,
     pub eth_twine_chain_address: String,
     pub chain: String,
+    /// Recent blocks whose hashes are tracked to detect reorgs, 0 disables
+    /// reorg detection
+    #[serde(default)]
+    pub reorg_depth: u64,
+    /// How long the polled block number may stay the same before the RPC is
+    /// treated as having silently stopped serving data, 0 disables the check
+    #[serde(default = "default_no_data_timeout_ms")]
+    pub no_data_timeout_ms: u64,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct SvmConfig {
     pub common: ChainConfig,
-    pub tokens_gateway_program_address: String,
+    /// Token gateway programs, a single address is accepted too
+    #[serde(
+        alias = "tokens_gateway_program_address",
+        deserialize_with = "one_or_many"
+    )]
+    pub tokens_gateway_program_addresses: Vec<String>,
     pub twine_chain_program_address: String,
     pub chain: String,
+    /// Event names emitted by the programs mapped to the event they are
+    /// parsed as, for events renamed in a program upgrade
+    #[serde(default)]
+    pub event_aliases: HashMap<String, String>,
+    /// Slot windows of a historical range fetched concurrently, 1 fetches
+    /// them one after another
+    #[serde(default = "default_historical_window_concurrency")]
+    pub historical_window_concurrency: usize,
+    /// Commitment every RPC call is made at, `confirmed` indexes sooner but
+    /// may see slots that are later rolled back
+    #[serde(default)]
+    pub commitment: SolanaCommitment,
+    /// How long the polled slot may stay the same before the RPC is treated
+    /// as having silently stopped serving data, 0 disables the check
+    #[serde(default = "default_no_data_timeout_ms")]
+    pub no_data_timeout_ms: u64,
+}
+
+/// Solana commitment levels, anything else fails the config load
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SolanaCommitment {
+    Processed,
+    Confirmed,
+    #[default]
+    Finalized,
+}
+
+/// Reads a list of strings, or a single string as a one element list so
+/// configs written before a field took several values keep loading
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+fn default_historical_window_concurrency() -> usize {
+    1
+}
+
+fn default_no_data_timeout_ms() -> u64 {
+    60_000
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -80,6 +341,21 @@ pub struct TwineConfig {
     pub common: ChainConfig,
     pub l2_twine_messenger_address: String,
     pub uniswap_factory_address: String,
+    /// Twine blocks fetched concurrently when collecting a batch's blocks
+    #[serde(default = "default_block_fetch_concurrency")]
+    pub block_fetch_concurrency: usize,
+    /// Names of the Twine events to index, e.g. `SentMessage`, every event
+    /// when empty. Leaving out `PairCreated` and `Swap` skips Uniswap entirely
+    #[serde(default)]
+    pub event_filter: Vec<String>,
+    /// How long the polled block number may stay the same before the RPC is
+    /// treated as having silently stopped serving data, 0 disables the check
+    #[serde(default = "default_no_data_timeout_ms")]
+    pub no_data_timeout_ms: u64,
+}
+
+fn default_block_fetch_concurrency() -> usize {
+    8
 }
 #[derive(Deserialize, Debug, Clone)]
 pub struct L1sConfig {
@@ -94,6 +370,81 @@ pub struct IndexerSettings {
     pub max_log_batch_size: u64,
     pub max_log_batch_time: u64,
     pub max_concurrency_for_log_process: u64,
+    /// Number of database writer tasks operations are sharded across
+    #[serde(default = "default_db_writer_workers")]
+    pub db_writer_workers: usize,
+    /// Pending writes each writer task can hold before the reader waits
+    #[serde(default = "default_db_writer_queue_capacity")]
+    pub db_writer_queue_capacity: usize,
+    /// Delay between spawning each chain indexer at startup
+    #[serde(default)]
+    pub startup_stagger_ms: u64,
+    /// Maximum number of chains backfilling at once, 0 for no limit
+    #[serde(default)]
+    pub max_concurrent_historical_syncs: usize,
+    /// Port to serve Prometheus metrics and the health probes on, disabled when unset
+    #[serde(default)]
+    pub metrics_port: Option<u16>,
+    /// Heights a chain may have to index again to restore the batches
+    /// blockscout lost before startup warns, the check is skipped when unset
+    #[serde(default)]
+    pub sync_divergence_tolerance_blocks: Option<u64>,
+    /// Rewind the chain's `last_synced` to before the first missing batch when
+    /// the check fails
+    #[serde(default)]
+    pub rewind_on_sync_divergence: bool,
+    /// Blocks between catchup progress logs, 0 to disable them
+    #[serde(default = "default_progress_log_interval_blocks")]
+    pub progress_log_interval_blocks: u64,
+    /// How long shutdown waits for queued database writes to be persisted
+    #[serde(default = "default_shutdown_drain_timeout_ms")]
+    pub shutdown_drain_timeout_ms: u64,
+    /// Times a stopped chain indexer is restarted before the process fails
+    #[serde(default = "default_max_indexer_restarts")]
+    pub max_indexer_restarts: u32,
+    /// Delay before the first restart of a stopped indexer, doubled each time
+    #[serde(default = "default_indexer_restart_backoff_ms")]
+    pub indexer_restart_backoff_ms: u64,
+    /// Also print every operation written as a JSON line on stdout, logs
+    /// then go to stderr so the output can be piped
+    #[serde(default)]
+    pub emit_json_to_stdout: bool,
+    /// Logs written between `last_synced` updates, 0 updates it after every
+    /// batch unless `checkpoint_interval_ms` is set
+    #[serde(default)]
+    pub checkpoint_every_events: u64,
+    /// Longest a written height waits before `last_synced` is updated, 0 for
+    /// no time limit
+    #[serde(default)]
+    pub checkpoint_interval_ms: u64,
+    /// How far behind its head, in time, a chain may be and still count as
+    /// live for readiness, one sync batch when unset
+    #[serde(default)]
+    pub max_live_lag_ms: Option<u64>,
+}
+
+fn default_db_writer_workers() -> usize {
+    4
+}
+
+fn default_db_writer_queue_capacity() -> usize {
+    16
+}
+
+fn default_progress_log_interval_blocks() -> u64 {
+    10_000
+}
+
+fn default_shutdown_drain_timeout_ms() -> u64 {
+    30_000
+}
+
+fn default_max_indexer_restarts() -> u32 {
+    5
+}
+
+fn default_indexer_restart_backoff_ms() -> u64 {
+    5_000
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -103,6 +454,75 @@ pub struct IndexerConfig {
     pub settings: IndexerSettings,
     pub l1s: L1sConfig,
     pub twine: TwineConfig,
+    /// Chains to spawn indexers for, all of them when unset
+    #[serde(default = "default_enabled_chains")]
+    pub enabled_chains: Vec<String>,
+    /// Let chains share a chain id or RPC URL, e.g. several L1 sections
+    /// pointed at the same testnet
+    #[serde(default)]
+    pub allow_shared_chain_endpoints: bool,
+}
+
+/// Chains the indexer binary knows how to run
+pub const INDEXER_CHAINS: [&str; 5] = ["Twine", "Ethereum", "Arbitrum", "Base", "Solana"];
+
+fn default_enabled_chains() -> Vec<String> {
+    INDEXER_CHAINS
+        .iter()
+        .map(|chain| chain.to_string())
+        .collect()
+}
+
+impl IndexerConfig {
+    /// Errors when `enabled_chains` names a chain the indexer does not know
+    pub fn validate_enabled_chains(&self) -> Result<()> {
+        for chain in &self.enabled_chains {
+            if !INDEXER_CHAINS
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(chain))
+            {
+                return Err(eyre!(
+                    "Unknown chain '{}' in enabled_chains, expected one of {:?}",
+                    chain,
+                    INDEXER_CHAINS
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Every chain section with its name, whether enabled or not
+    pub fn chain_configs(&self) -> [(&'static str, &ChainConfig); 5] {
+        [
+            ("Twine", &self.twine.common),
+            ("Ethereum", &self.l1s.ethereum.common),
+            ("Arbitrum", &self.l1s.arbitrum.common),
+            ("Base", &self.l1s.base.common),
+            ("Solana", &self.l1s.solana.common),
+        ]
+    }
+
+    /// Errors when an enabled chain ends up with a zero block time
+    pub fn validate_block_times(&self) -> Result<()> {
+        for (chain, config) in self.chain_configs() {
+            if !self.is_chain_enabled(chain) {
+                continue;
+            }
+            if config.expected_block_time_ms() == 0 {
+                return Err(eyre!(
+                    "{} expected_block_time_ms must be positive, got 0",
+                    chain
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_chain_enabled(&self, chain: &str) -> bool {
+        self.enabled_chains
+            .iter()
+            .any(|enabled| enabled.eq_ignore_ascii_case(chain))
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -136,3 +556,80 @@ impl LoadFromEnv for DaIndexerConfig {
             .ok_or_else(|| eyre!("Configuration for the 'indexer' service is missing."))
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use config::FileFormat;
+
+    fn chain_yaml(chain_id: u64) -> String {
+        format!(
+            r#"{{ http_rpc_url: "http://localhost:8545/{chain_id}", chain_id: {chain_id}, start_block: 0, block_sync_batch_size: 100, block_time_ms: 1000 }}"#
+        )
+    }
+
+    /// Indexer config with one distinct chain per section, `extra` is appended
+    /// to the top level
+    pub(crate) fn indexer_config(extra: &str) -> IndexerConfig {
+        let evm = |chain_id| {
+            format!(
+                r#"{{ common: {}, chain: evm, l1_message_handler_address: "0x1", l1_erc20_gateway_addresses: ["0x2"], eth_twine_chain_address: "0x3" }}"#,
+                chain_yaml(chain_id)
+            )
+        };
+        let yaml = format!(
+            r#"
+database: {{ url: "postgres://localhost/indexer" }}
+blockscout: {{ url: "postgres://localhost/blockscout" }}
+settings: {{ max_log_batch_size: 1000, max_log_batch_time: 12, max_concurrency_for_log_process: 10 }}
+twine: {{ common: {twine}, l2_twine_messenger_address: "0x4", uniswap_factory_address: "0x5" }}
+l1s:
+  ethereum: {ethereum}
+  arbitrum: {arbitrum}
+  base: {base}
+  solana: {{ common: {solana}, chain: svm, tokens_gateway_program_addresses: ["Gateway"], twine_chain_program_address: "TwineChain" }}
+{extra}
+"#,
+            twine = chain_yaml(1337),
+            ethereum = evm(1),
+            arbitrum = evm(42161),
+            base = evm(8453),
+            solana = chain_yaml(103),
+        );
+
+        Config::builder()
+            .add_source(File::from_str(&yaml, FileFormat::Yaml))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap()
+    }
+
+    #[test]
+    fn every_chain_is_enabled_by_default() {
+        let cfg = indexer_config("");
+
+        for chain in INDEXER_CHAINS {
+            assert!(cfg.is_chain_enabled(chain), "{chain} is disabled");
+        }
+        assert!(cfg.validate_enabled_chains().is_ok());
+    }
+
+    #[test]
+    fn only_listed_chains_are_enabled() {
+        let cfg = indexer_config(r#"enabled_chains: ["twine", "Solana"]"#);
+
+        assert!(cfg.is_chain_enabled("Twine"));
+        assert!(cfg.is_chain_enabled("solana"));
+        assert!(!cfg.is_chain_enabled("Ethereum"));
+        assert!(!cfg.is_chain_enabled("Base"));
+    }
+
+    #[test]
+    fn unknown_enabled_chain_is_rejected() {
+        let cfg = indexer_config(r#"enabled_chains: ["Twine", "Polygon"]"#);
+
+        let error = cfg.validate_enabled_chains().unwrap_err();
+        assert!(error.to_string().contains("Polygon"), "{error}");
+    }
+}
@@ -2,3 +2,74 @@ use std::time::Duration;
 
 pub const MAX_RETRIES: i32 = 20;
 pub const RETRY_DELAY: Duration = Duration::from_millis(5000);
+/// Longest a backoff may grow to unless configured otherwise
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// How often and how patiently a failed RPC call is retried
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: i32,
+    pub delay: Duration,
+    /// Factor the delay grows by after each failed attempt, 1 keeps it fixed
+    pub backoff_multiplier: f64,
+    /// Cap on the delay however many attempts have failed
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: MAX_RETRIES,
+            delay: RETRY_DELAY,
+            backoff_multiplier: 1.0,
+            max_delay: MAX_RETRY_DELAY,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay to wait after the `attempt`-th consecutive failure, counting from 1
+    pub fn delay_for(&self, attempt: i32) -> Duration {
+        let factor = self
+            .backoff_multiplier
+            .max(1.0)
+            .powi(attempt.saturating_sub(1).max(0));
+        // A long failure streak overflows `Duration`, which is past the cap
+        Duration::try_from_secs_f64(self.delay.as_secs_f64() * factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(backoff_multiplier: f64) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 5,
+            delay: Duration::from_secs(1),
+            backoff_multiplier,
+            max_delay: Duration::from_secs(10),
+        }
+    }
+
+    #[test]
+    fn delay_grows_by_the_multiplier() {
+        let retry = policy(2.0);
+        assert_eq!(retry.delay_for(1), Duration::from_secs(1));
+        assert_eq!(retry.delay_for(2), Duration::from_secs(2));
+        assert_eq!(retry.delay_for(3), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn delay_is_capped() {
+        let retry = policy(2.0);
+        assert_eq!(retry.delay_for(5), Duration::from_secs(10));
+        assert_eq!(retry.delay_for(i32::MAX), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn multiplier_below_one_keeps_the_delay_fixed() {
+        assert_eq!(policy(0.5).delay_for(4), Duration::from_secs(1));
+    }
+}
@@ -1,4 +1,5 @@
 //- Only a comment
 pub mod blockscout_entities;
+pub mod chains;
 pub mod config;
 pub mod indexer;
@@ -5,6 +5,7 @@ use eyre::Result;
 use crate::types::BlockData;
 
 pub struct CelestiaBlob {
+    pub twine_block_number: u64,
     pub twine_block_hash: String,
     pub commitment_hash: String,
     pub height: u64,
@@ -20,11 +21,10 @@ pub fn parse_blob(height: u64, blob: &Blob) -> Result<Vec<CelestiaBlob>> {
     let mut blobs: Vec<CelestiaBlob> = Vec::new();
 
     for block in blocks {
-        let _ = block.number;
-
         //TODO: Find block hash from the number
 
         let blob = CelestiaBlob {
+            twine_block_number: block.number,
             commitment_hash: commitment_hash.clone(),
             twine_block_hash: commitment_hash.clone(),
             height,
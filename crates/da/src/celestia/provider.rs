@@ -1,6 +1,9 @@
-use std::sync::{
-    Arc,
-    atomic::{AtomicBool, AtomicU64},
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64},
+    },
 };
 
 use celestia_rpc::{BlobClient, Client, HeaderClient};
@@ -96,6 +99,10 @@ impl CelestiaProvider {
                         }
                     };
 
+                if let Err(e) = self.link_batches(&parsed_blobs).await {
+                    tracing::error!("Failed to link batches at height {}: {:?}", height, e);
+                }
+
                 for blb in parsed_blobs {
                     println!("Found blob: {}", blb.height.to_string());
                     println!("Commitment {}", blb.commitment_hash);
@@ -105,6 +112,39 @@ impl CelestiaProvider {
         }
     }
 
+    /// Links every batch whose blocks were posted in a blob to that blob
+    async fn link_batches(&self, blobs: &[CelestiaBlob]) -> Result<()> {
+        let mut block_ranges: HashMap<&str, (u64, u64, &CelestiaBlob)> = HashMap::new();
+        for blob in blobs {
+            block_ranges
+                .entry(blob.commitment_hash.as_str())
+                .and_modify(|(first, last, _)| {
+                    *first = (*first).min(blob.twine_block_number);
+                    *last = (*last).max(blob.twine_block_number);
+                })
+                .or_insert((blob.twine_block_number, blob.twine_block_number, blob));
+        }
+
+        for (commitment, (first, last, blob)) in block_ranges {
+            let batches = self.db.find_batches_covering(first, last).await?;
+            if batches.is_empty() {
+                continue;
+            }
+
+            tracing::info!(
+                "Blob {} at height {} carries batches {:?}",
+                commitment,
+                blob.height,
+                batches
+            );
+            self.db
+                .upsert_batch_da_references(&batches, blob.height, &blob.namespace, commitment)
+                .await?;
+        }
+
+        Ok(())
+    }
+
     pub async fn subscribe_headers(&self) -> Result<()> {
         let mut header_sub = self
             .client
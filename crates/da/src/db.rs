@@ -1,6 +1,12 @@
-use database::entities::{celestia_blobs, last_synced};
+use database::{
+    blockscout_entities::twine_transaction_batch,
+    entities::{batch_da_references, celestia_blobs, last_synced},
+};
 use eyre::Result;
-use sea_orm::{DatabaseConnection, EntityTrait, sea_query::OnConflict};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, sea_query::OnConflict,
+};
 use tracing::error;
 
 pub enum DbModel {
@@ -9,12 +15,22 @@ pub enum DbModel {
 
 pub struct DbClient {
     db: DatabaseConnection,
+    blockscout: Option<DatabaseConnection>,
 }
 
 impl DbClient {
     pub fn new(db: &DatabaseConnection) -> Self {
         let db_client = db.clone();
-        Self { db: db_client }
+        Self {
+            db: db_client,
+            blockscout: None,
+        }
+    }
+
+    /// Blockscout connection used to find the batches a blob carries
+    pub fn with_blockscout(mut self, blockscout: &DatabaseConnection) -> Self {
+        self.blockscout = Some(blockscout.clone());
+        self
     }
 
     pub async fn insert_celestia_blobs(&self, model: DbModel) -> Result<()> {
@@ -73,4 +89,80 @@ impl DbClient {
             .map(|record| record.block_number)
             .unwrap_or(start_block as i64))
     }
+
+    /// Numbers of the Twine batches containing any block in
+    /// `first_block..=last_block`
+    pub async fn find_batches_covering(
+        &self,
+        first_block: u64,
+        last_block: u64,
+    ) -> Result<Vec<i64>> {
+        let blockscout = self
+            .blockscout
+            .as_ref()
+            .ok_or_else(|| eyre::eyre!("Blockscout database connection is not available"))?;
+
+        let batches = twine_transaction_batch::Entity::find()
+            .select_only()
+            .column(twine_transaction_batch::Column::Number)
+            .filter(twine_transaction_batch::Column::StartBlock.lte(last_block as i64))
+            .filter(twine_transaction_batch::Column::EndBlock.gte(first_block as i64))
+            .order_by_asc(twine_transaction_batch::Column::Number)
+            .into_tuple::<i64>()
+            .all(blockscout)
+            .await?;
+        Ok(batches)
+    }
+
+    /// Records that `batch_number` was posted in the blob with `commitment`
+    /// under `namespace` at Celestia `height`
+    pub async fn link_blob_to_batch(
+        &self,
+        batch_number: i64,
+        namespace: &str,
+        commitment: &str,
+        height: u64,
+    ) -> Result<()> {
+        self.upsert_batch_da_references(&[batch_number], height, namespace, commitment)
+            .await
+    }
+
+    /// Records the Celestia blob each batch was posted in. A batch keeps the
+    /// first blob it was linked to when it is reposted.
+    pub async fn upsert_batch_da_references(
+        &self,
+        batch_numbers: &[i64],
+        height: u64,
+        namespace: &str,
+        commitment: &str,
+    ) -> Result<()> {
+        if batch_numbers.is_empty() {
+            return Ok(());
+        }
+
+        let models = batch_numbers
+            .iter()
+            .map(|batch_number| batch_da_references::ActiveModel {
+                batch_number: Set(*batch_number),
+                celestia_height: Set(height as i64),
+                namespace: Set(namespace.to_string()),
+                commitment: Set(commitment.to_string()),
+                ..Default::default()
+            });
+
+        batch_da_references::Entity::insert_many(models)
+            .on_conflict(
+                OnConflict::column(batch_da_references::Column::BatchNumber)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(&self.db)
+            .await
+            .map_err(|e| {
+                error!("Failed to insert batch DA references: {:?}", e);
+                eyre::eyre!("Failed to insert batch DA references: {:?}", e)
+            })?;
+
+        Ok(())
+    }
 }
@@ -1,11 +1,18 @@
-use crate::blockscout_entities::{twine_transaction_batch, twine_transaction_batch_detail};
+use crate::blockscout_entities::{
+    blocks, transactions, twine_transaction_batch, twine_transaction_batch_detail,
+};
 use crate::client::DbClient;
+use crate::entities::{batch_da_references, batch_l1_blocks, pending_finalizations};
+use crate::{BatchFinalization, CommittedBatch};
 use eyre::{Context, Result};
-use sea_orm::sea_query::OnConflict;
+use sea_orm::prelude::Decimal;
+use sea_orm::sea_query::{Expr, OnConflict, Query, SimpleExpr};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseTransaction, EntityTrait, QueryFilter, TransactionTrait,
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, Condition, DatabaseTransaction, DbErr,
+    EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, TransactionTrait, Value,
 };
-use tracing::error;
+use std::collections::{HashMap, HashSet};
+use tracing::{debug, error, warn};
 
 /// Generic utility for processing database operations in batches to avoid PostgreSQL parameter limits
 async fn process_in_batches<T, F, Fut>(items: Vec<T>, batch_size: usize, operation: F) -> Result<()>
@@ -20,7 +27,80 @@ where
     Ok(())
 }
 
+/// Current UTC time the way blockscout stores it, in columns without a time zone
+fn blockscout_now() -> SimpleExpr {
+    Expr::cust("(NOW() AT TIME ZONE 'utc')")
+}
+
+/// How many of a batch's L2 blocks and transactions are tagged with it in blockscout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchCompleteness {
+    pub batch_number: i64,
+    pub start_block: i64,
+    pub end_block: i64,
+    /// Blocks in the batch range
+    pub expected_blocks: u64,
+    pub stored_blocks: u64,
+    /// Transactions blockscout has in the batch range
+    pub expected_transactions: u64,
+    pub stored_transactions: u64,
+}
+
+impl BatchCompleteness {
+    pub fn is_complete(&self) -> bool {
+        self.stored_blocks == self.expected_blocks
+            && self.stored_transactions == self.expected_transactions
+    }
+}
+
 impl DbClient {
+    /// Batches the primary recorded as committed on `chain_id` at or below
+    /// `up_to_block` that blockscout has no detail row of for the chain, with
+    /// the block each was committed in, in commit order
+    pub async fn get_batches_missing_in_blockscout(
+        &self,
+        chain_id: i64,
+        up_to_block: i64,
+    ) -> Result<Vec<(i64, i64)>> {
+        let blockscout = self.blockscout.as_ref().ok_or_else(|| {
+            error!("Blockscout database connection is not available");
+            eyre::eyre!("Blockscout database connection is not available")
+        })?;
+
+        let committed: Vec<(i64, i64)> = batch_l1_blocks::Entity::find()
+            .filter(batch_l1_blocks::Column::ChainId.eq(chain_id))
+            .filter(batch_l1_blocks::Column::CommitBlockNumber.lte(up_to_block))
+            .order_by_asc(batch_l1_blocks::Column::CommitBlockNumber)
+            .all(&self.primary)
+            .await?
+            .into_iter()
+            .filter_map(|row| Some((row.batch_number, row.commit_block_number?)))
+            .collect();
+
+        // Bounded so the batch numbers stay within the parameter limit
+        const CHUNK_SIZE: usize = 5000;
+        let mut stored = HashSet::new();
+        for chunk in committed.chunks(CHUNK_SIZE) {
+            let numbers: Vec<i64> = chunk.iter().map(|(number, _)| *number).collect();
+            stored.extend(
+                twine_transaction_batch_detail::Entity::find()
+                    .filter(
+                        twine_transaction_batch_detail::Column::ChainId.eq(Decimal::from(chain_id)),
+                    )
+                    .filter(twine_transaction_batch_detail::Column::BatchNumber.is_in(numbers))
+                    .all(blockscout)
+                    .await?
+                    .into_iter()
+                    .map(|detail| detail.batch_number),
+            );
+        }
+
+        Ok(committed
+            .into_iter()
+            .filter(|(number, _)| !stored.contains(number))
+            .collect())
+    }
+
     pub async fn get_batch_by_id(
         &self,
         batch_number: i64,
@@ -38,6 +118,16 @@ impl DbClient {
         Ok(batch)
     }
 
+    /// Celestia blob the batch was posted in, once the DA indexer has linked it
+    pub async fn get_batch_da_reference(
+        &self,
+        batch_number: i64,
+    ) -> Result<Option<batch_da_references::Model>, DbErr> {
+        batch_da_references::Entity::find_by_id(batch_number)
+            .one(&self.primary)
+            .await
+    }
+
     pub async fn get_batch_details(
         &self,
         batch_number: i64,
@@ -68,17 +158,12 @@ impl DbClient {
         })?;
 
         let txn = blockscout.begin().await?;
-        let batch_number = batch_model.number.clone().unwrap();
-
-        // Check if batch already exists
-        let exists = self.get_batch_by_id(batch_number).await?.is_some();
 
-        if !exists {
-            tracing::info!("Inserting new batch {}", batch_number);
-            self.insert_twine_transaction_batch(batch_model, &txn)
-                .await
-                .context("Failed to insert twine transaction batch")?;
-        }
+        // The EVM and Solana indexers may race on the same batch, the conflict
+        // on the batch number keeps whichever row landed first
+        self.insert_twine_transaction_batch(batch_model, &txn)
+            .await
+            .context("Failed to insert twine transaction batch")?;
 
         // Insert batch details
         self.insert_twine_transaction_batch_detail(batch_details_model, &txn)
@@ -107,6 +192,285 @@ impl DbClient {
         Ok(())
     }
 
+    /// Buffers a finalization whose batch has no detail row yet, so it is
+    /// applied by [`Self::reconcile_pending_finalizations`] once the commit
+    /// lands instead of being lost
+    pub async fn enqueue_pending_finalization(
+        &self,
+        finalization: &BatchFinalization,
+        txn: &DatabaseTransaction,
+    ) -> Result<()> {
+        let model = pending_finalizations::ActiveModel {
+            chain_id: Set(finalization.chain_id),
+            batch_number: Set(finalization.batch_number),
+            finalize_hash: Set(finalization.finalize_hash.clone()),
+            block_number: Set(finalization.block_number),
+            ..Default::default()
+        };
+        pending_finalizations::Entity::insert(model)
+            .on_conflict(
+                OnConflict::columns([
+                    pending_finalizations::Column::ChainId,
+                    pending_finalizations::Column::BatchNumber,
+                ])
+                .update_columns([
+                    pending_finalizations::Column::FinalizeHash,
+                    pending_finalizations::Column::BlockNumber,
+                ])
+                .to_owned(),
+            )
+            .exec(txn)
+            .await
+            .context("Failed to enqueue pending finalization")?;
+
+        warn!(
+            chain_id = finalization.chain_id,
+            batch_number = finalization.batch_number,
+            "Batch finalized before its commit was indexed, buffering the finalization"
+        );
+        Ok(())
+    }
+
+    /// Records the finalize hash on the detail row the batch was committed
+    /// with on the same chain, returning false when it has not been yet
+    async fn apply_finalization(
+        &self,
+        finalization: &BatchFinalization,
+        txn: &DatabaseTransaction,
+    ) -> Result<bool> {
+        let result = twine_transaction_batch_detail::Entity::update_many()
+            .col_expr(
+                twine_transaction_batch_detail::Column::FinalizeTransactionHash,
+                Expr::value(finalization.finalize_hash.clone()),
+            )
+            .col_expr(
+                twine_transaction_batch_detail::Column::FinalizedAt,
+                blockscout_now(),
+            )
+            .col_expr(
+                twine_transaction_batch_detail::Column::UpdatedAt,
+                blockscout_now(),
+            )
+            .filter(
+                twine_transaction_batch_detail::Column::BatchNumber.eq(finalization.batch_number),
+            )
+            .filter(
+                twine_transaction_batch_detail::Column::ChainId
+                    .eq(Decimal::from(finalization.chain_id)),
+            )
+            .exec(txn)
+            .await
+            .context("Failed to apply batch finalization")?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Applies `finalizations` in the blockscout `txn`, returning the applied
+    /// ones and those whose batch is not committed yet
+    pub async fn apply_finalizations(
+        &self,
+        finalizations: Vec<BatchFinalization>,
+        txn: &DatabaseTransaction,
+    ) -> Result<(Vec<BatchFinalization>, Vec<BatchFinalization>)> {
+        let mut applied = Vec::new();
+        let mut uncommitted = Vec::new();
+        for finalization in finalizations {
+            if self.apply_finalization(&finalization, txn).await? {
+                applied.push(finalization);
+            } else {
+                uncommitted.push(finalization);
+            }
+        }
+        Ok((applied, uncommitted))
+    }
+
+    /// Applies buffered finalizations of the `committed` batches, whose detail
+    /// rows were just written in the blockscout `txn`. Pending rows are read
+    /// and the applied ones deleted in the primary `pending_txn`, which must
+    /// commit after `txn`. Returns the applied finalizations.
+    pub async fn reconcile_pending_finalizations(
+        &self,
+        committed: &[CommittedBatch],
+        txn: &DatabaseTransaction,
+        pending_txn: &DatabaseTransaction,
+    ) -> Result<Vec<BatchFinalization>> {
+        if committed.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let condition = committed.iter().fold(Condition::any(), |condition, batch| {
+            condition.add(
+                Condition::all()
+                    .add(pending_finalizations::Column::ChainId.eq(batch.chain_id))
+                    .add(pending_finalizations::Column::BatchNumber.eq(batch.batch_number)),
+            )
+        });
+        let pending = pending_finalizations::Entity::find()
+            .filter(condition)
+            .order_by_asc(pending_finalizations::Column::Id)
+            .all(pending_txn)
+            .await
+            .context("Failed to fetch pending finalizations")?;
+
+        let mut applied = Vec::new();
+        let mut applied_ids = Vec::new();
+        for row in pending {
+            let finalization = BatchFinalization {
+                chain_id: row.chain_id,
+                batch_number: row.batch_number,
+                finalize_hash: row.finalize_hash,
+                block_number: row.block_number,
+            };
+            if self.apply_finalization(&finalization, txn).await? {
+                debug!(
+                    chain_id = finalization.chain_id,
+                    batch_number = finalization.batch_number,
+                    "Applied buffered finalization"
+                );
+                applied.push(finalization);
+                applied_ids.push(row.id);
+            }
+        }
+
+        if !applied_ids.is_empty() {
+            pending_finalizations::Entity::delete_many()
+                .filter(pending_finalizations::Column::Id.is_in(applied_ids))
+                .exec(pending_txn)
+                .await
+                .context("Failed to clear pending finalizations")?;
+        }
+        Ok(applied)
+    }
+
+    /// Deletes the detail rows `chain_id` committed `batch_numbers` with in the
+    /// blockscout `txn`, and the batches no other chain has committed.
+    /// Blockscout's foreign keys untag their L2 blocks and transactions.
+    pub async fn uncommit_batches(
+        &self,
+        chain_id: i64,
+        batch_numbers: &[i64],
+        txn: &DatabaseTransaction,
+    ) -> Result<()> {
+        if batch_numbers.is_empty() {
+            return Ok(());
+        }
+
+        twine_transaction_batch_detail::Entity::delete_many()
+            .filter(
+                twine_transaction_batch_detail::Column::BatchNumber
+                    .is_in(batch_numbers.iter().copied()),
+            )
+            .filter(twine_transaction_batch_detail::Column::ChainId.eq(Decimal::from(chain_id)))
+            .exec(txn)
+            .await
+            .context("Failed to delete orphaned batch details")?;
+
+        twine_transaction_batch::Entity::delete_many()
+            .filter(twine_transaction_batch::Column::Number.is_in(batch_numbers.iter().copied()))
+            .filter(
+                twine_transaction_batch::Column::Number.not_in_subquery(
+                    Query::select()
+                        .column(twine_transaction_batch_detail::Column::BatchNumber)
+                        .from(twine_transaction_batch_detail::Entity)
+                        .to_owned(),
+                ),
+            )
+            .exec(txn)
+            .await
+            .context("Failed to delete orphaned batches")?;
+        Ok(())
+    }
+
+    /// Clears the finalize hash `chain_id` recorded on `batch_numbers` in the
+    /// blockscout `txn`
+    pub async fn unfinalize_batches(
+        &self,
+        chain_id: i64,
+        batch_numbers: &[i64],
+        txn: &DatabaseTransaction,
+    ) -> Result<()> {
+        if batch_numbers.is_empty() {
+            return Ok(());
+        }
+
+        twine_transaction_batch_detail::Entity::update_many()
+            .col_expr(
+                twine_transaction_batch_detail::Column::FinalizeTransactionHash,
+                Expr::value(Value::String(None)),
+            )
+            .col_expr(
+                twine_transaction_batch_detail::Column::FinalizedAt,
+                Expr::value(Value::ChronoDateTime(None)),
+            )
+            .col_expr(
+                twine_transaction_batch_detail::Column::UpdatedAt,
+                blockscout_now(),
+            )
+            .filter(
+                twine_transaction_batch_detail::Column::BatchNumber
+                    .is_in(batch_numbers.iter().copied()),
+            )
+            .filter(twine_transaction_batch_detail::Column::ChainId.eq(Decimal::from(chain_id)))
+            .exec(txn)
+            .await
+            .context("Failed to clear orphaned batch finalizations")?;
+        Ok(())
+    }
+
+    /// Remembers the L1 blocks `commits` and `finalizations` were logged at in
+    /// the primary `txn`, so a reorg of those blocks can undo their rows
+    pub async fn record_batch_l1_blocks(
+        &self,
+        commits: &[(CommittedBatch, i64)],
+        finalizations: &[BatchFinalization],
+        txn: &DatabaseTransaction,
+    ) -> Result<()> {
+        for (batch, block_number) in commits {
+            let model = batch_l1_blocks::ActiveModel {
+                chain_id: Set(batch.chain_id),
+                batch_number: Set(batch.batch_number),
+                commit_block_number: Set(Some(*block_number)),
+                ..Default::default()
+            };
+            batch_l1_blocks::Entity::insert(model)
+                .on_conflict(
+                    OnConflict::columns([
+                        batch_l1_blocks::Column::ChainId,
+                        batch_l1_blocks::Column::BatchNumber,
+                    ])
+                    .update_column(batch_l1_blocks::Column::CommitBlockNumber)
+                    .to_owned(),
+                )
+                .exec_without_returning(txn)
+                .await
+                .context("Failed to record batch commit block")?;
+        }
+
+        for finalization in finalizations {
+            let Some(block_number) = finalization.block_number else {
+                continue;
+            };
+            let model = batch_l1_blocks::ActiveModel {
+                chain_id: Set(finalization.chain_id),
+                batch_number: Set(finalization.batch_number),
+                finalize_block_number: Set(Some(block_number)),
+                ..Default::default()
+            };
+            batch_l1_blocks::Entity::insert(model)
+                .on_conflict(
+                    OnConflict::columns([
+                        batch_l1_blocks::Column::ChainId,
+                        batch_l1_blocks::Column::BatchNumber,
+                    ])
+                    .update_column(batch_l1_blocks::Column::FinalizeBlockNumber)
+                    .to_owned(),
+                )
+                .exec_without_returning(txn)
+                .await
+                .context("Failed to record batch finalize block")?;
+        }
+        Ok(())
+    }
+
     pub async fn insert_twine_transaction_batch(
         &self,
         model: twine_transaction_batch::ActiveModel,
@@ -118,7 +482,7 @@ impl DbClient {
                     .do_nothing()
                     .to_owned(),
             )
-            .exec_with_returning(txn)
+            .exec_without_returning(txn)
             .await
             .map_err(|e| {
                 error!("Failed to insert batch: {:?}", e);
@@ -136,24 +500,99 @@ impl DbClient {
         // Process in batches to avoid PostgreSQL parameter limit (max ~65k parameters)
         const BATCH_SIZE: usize = 5000;
 
-        process_in_batches(models, BATCH_SIZE, |chunk| async {
-            twine_transaction_batch::Entity::insert_many(chunk)
-                .on_conflict(
-                    OnConflict::columns([twine_transaction_batch::Column::Number])
-                        .do_nothing()
-                        .to_owned(),
-                )
-                .exec_with_returning_many(txn)
-                .await
-                .map_err(|e| {
-                    error!("Failed to insert batch: {:?}", e);
-                    eyre::eyre!("Failed to insert batch: {:?}", e)
-                })?;
-            Ok(())
+        // A flush can hold the same batch from more than one chain, the first one wins
+        let mut seen = HashSet::new();
+        let mut unique = Vec::with_capacity(models.len());
+        for model in models {
+            let number = crate::active_value(&model.number)
+                .ok_or_else(|| eyre::eyre!("Twine transaction batch without a number"))?;
+            if seen.insert(number) {
+                unique.push(model);
+            }
+        }
+
+        process_in_batches(unique, BATCH_SIZE, |chunk| async {
+            let inserted: HashSet<i64> =
+                twine_transaction_batch::Entity::insert_many(chunk.clone())
+                    .on_conflict(
+                        OnConflict::columns([twine_transaction_batch::Column::Number])
+                            .do_nothing()
+                            .to_owned(),
+                    )
+                    .exec_with_returning_many(txn)
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to insert batch: {:?}", e);
+                        eyre::eyre!("Failed to insert batch: {:?}", e)
+                    })?
+                    .into_iter()
+                    .map(|batch| batch.number)
+                    .collect();
+
+            let conflicting: Vec<_> = chunk
+                .into_iter()
+                .filter(|model| {
+                    crate::active_value(&model.number)
+                        .is_some_and(|number| !inserted.contains(&number))
+                })
+                .collect();
+            self.log_batch_conflicts(conflicting, txn).await
         })
         .await
     }
 
+    /// Logs the batches an insert skipped. Re-processing a batch that is
+    /// already stored as it is now is expected and only logged at debug, a
+    /// stored batch with other blocks, or none at all, is worth a warning.
+    async fn log_batch_conflicts(
+        &self,
+        conflicting: Vec<twine_transaction_batch::ActiveModel>,
+        txn: &DatabaseTransaction,
+    ) -> Result<()> {
+        if conflicting.is_empty() {
+            return Ok(());
+        }
+
+        let numbers: Vec<i64> = conflicting
+            .iter()
+            .filter_map(|model| crate::active_value(&model.number))
+            .collect();
+        let stored: HashMap<i64, twine_transaction_batch::Model> =
+            twine_transaction_batch::Entity::find()
+                .filter(twine_transaction_batch::Column::Number.is_in(numbers))
+                .all(txn)
+                .await?
+                .into_iter()
+                .map(|batch| (batch.number, batch))
+                .collect();
+
+        for model in conflicting {
+            let (Some(number), Some(start_block), Some(end_block)) = (
+                crate::active_value(&model.number),
+                crate::active_value(&model.start_block),
+                crate::active_value(&model.end_block),
+            ) else {
+                continue;
+            };
+
+            match stored.get(&number) {
+                Some(batch) if batch.start_block == start_block && batch.end_block == end_block => {
+                    debug!("Batch {} already stored, keeping it", number);
+                }
+                Some(batch) => warn!(
+                    "Batch {} already stored with blocks {} to {}, ignoring blocks {} to {}",
+                    number, batch.start_block, batch.end_block, start_block, end_block
+                ),
+                None => warn!(
+                    "Insert of batch {} conflicted but no stored batch has that number",
+                    number
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
     pub async fn insert_twine_transaction_batch_detail(
         &self,
         model: twine_transaction_batch_detail::ActiveModel,
@@ -169,6 +608,10 @@ impl DbClient {
                     twine_transaction_batch_detail::Column::FinalizedAt,
                     twine_transaction_batch_detail::Column::FinalizeTransactionHash,
                 ])
+                .value(
+                    twine_transaction_batch_detail::Column::UpdatedAt,
+                    blockscout_now(),
+                )
                 .to_owned(),
             )
             .exec_with_returning_many(txn)
@@ -199,6 +642,10 @@ impl DbClient {
                         twine_transaction_batch_detail::Column::FinalizedAt,
                         twine_transaction_batch_detail::Column::FinalizeTransactionHash,
                     ])
+                    .value(
+                        twine_transaction_batch_detail::Column::UpdatedAt,
+                        blockscout_now(),
+                    )
                     .to_owned(),
                 )
                 .exec(txn)
@@ -211,4 +658,223 @@ impl DbClient {
         })
         .await
     }
+
+    /// Expected vs stored L2 blocks and transactions of a batch, `None` when
+    /// the batch is not in blockscout
+    pub async fn batch_l2_completeness(
+        &self,
+        batch_number: i64,
+    ) -> Result<Option<BatchCompleteness>> {
+        let blockscout = self.blockscout.as_ref().ok_or_else(|| {
+            error!("Blockscout database connection is not available");
+            eyre::eyre!("Blockscout database connection is not available")
+        })?;
+
+        let Some(batch) = self.get_batch_by_id(batch_number).await? else {
+            return Ok(None);
+        };
+        let (start_block, end_block) = (batch.start_block as i64, batch.end_block as i64);
+
+        let stored_blocks = blocks::Entity::find()
+            .filter(blocks::Column::BatchNumber.eq(batch_number))
+            .count(blockscout)
+            .await?;
+
+        let expected_transactions = transactions::Entity::find()
+            .filter(transactions::Column::BlockNumber.gte(start_block))
+            .filter(transactions::Column::BlockNumber.lte(end_block))
+            .count(blockscout)
+            .await?;
+
+        let stored_transactions = transactions::Entity::find()
+            .filter(transactions::Column::BatchNumber.eq(batch_number))
+            .count(blockscout)
+            .await?;
+
+        Ok(Some(BatchCompleteness {
+            batch_number,
+            start_block,
+            end_block,
+            expected_blocks: (end_block - start_block + 1).max(0) as u64,
+            stored_blocks,
+            expected_transactions,
+            stored_transactions,
+        }))
+    }
+
+    /// Tags every block and transaction blockscout has in `start_block..=end_block`
+    /// with `batch_number`, filling the gaps left when the batch was committed
+    /// before blockscout had indexed all of its blocks
+    pub async fn repair_batch_l2(
+        &self,
+        batch_number: i64,
+        start_block: u64,
+        end_block: u64,
+    ) -> Result<()> {
+        let blockscout = self.blockscout.as_ref().ok_or_else(|| {
+            error!("Blockscout database connection is not available");
+            eyre::eyre!("Blockscout database connection is not available")
+        })?;
+
+        let txn = blockscout.begin().await?;
+        self.tag_batch_l2(batch_number, start_block, end_block, &txn)
+            .await?;
+        txn.commit().await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db;
+    use sea_orm::{ConnectionTrait, prelude::DateTime};
+
+    fn batch(number: i64, start_block: i64) -> twine_transaction_batch::ActiveModel {
+        twine_transaction_batch::ActiveModel {
+            number: Set(number),
+            start_block: Set(start_block),
+            end_block: Set(start_block + 9),
+            timestamp: Set(DateTime::default()),
+            root_hash: Set(vec![0; 32]),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn concurrent_inserts_of_a_batch_keep_one_row() {
+        let db = test_db::client(true).await;
+        let blockscout = db.blockscout.as_ref().unwrap();
+
+        let insert = |start_block| {
+            let db = &db;
+            async move {
+                let txn = blockscout.begin().await?;
+                db.bulk_insert_twine_transaction_batch(
+                    vec![batch(7, start_block), batch(7, start_block)],
+                    &txn,
+                )
+                .await?;
+                txn.commit().await?;
+                eyre::Ok(())
+            }
+        };
+        let (first, second) = tokio::join!(insert(100), insert(200));
+        first.unwrap();
+        second.unwrap();
+
+        let stored = twine_transaction_batch::Entity::find()
+            .all(blockscout)
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].number, 7);
+        assert!([100, 200].contains(&stored[0].start_block));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn batch_without_a_number_is_an_error() {
+        let db = test_db::client(true).await;
+        let txn = db.blockscout.as_ref().unwrap().begin().await.unwrap();
+
+        let without_number = twine_transaction_batch::ActiveModel {
+            number: sea_orm::ActiveValue::NotSet,
+            ..batch(7, 100)
+        };
+        let result = db
+            .bulk_insert_twine_transaction_batch(vec![batch(8, 100), without_number], &txn)
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn finalization_bumps_the_detail_updated_at() {
+        let db = test_db::client(true).await;
+        let txn = db.blockscout.as_ref().unwrap().begin().await.unwrap();
+        db.bulk_insert_twine_transaction_batch(vec![batch(7, 100)], &txn)
+            .await
+            .unwrap();
+        let stale = DateTime::default();
+        twine_transaction_batch_detail::Entity::insert(
+            twine_transaction_batch_detail::ActiveModel {
+                batch_number: Set(7),
+                l1_transaction_count: Set(0),
+                l2_transaction_count: Set(0),
+                l1_gas_price: Set(Decimal::ZERO),
+                l2_fair_gas_price: Set(Decimal::ZERO),
+                chain_id: Set(Decimal::from(1)),
+                inserted_at: Set(stale),
+                updated_at: Set(stale),
+                ..Default::default()
+            },
+        )
+        .exec(&txn)
+        .await
+        .unwrap();
+
+        let finalization = BatchFinalization {
+            chain_id: 1,
+            batch_number: 7,
+            finalize_hash: "0xfinalize".to_string(),
+            block_number: Some(110),
+        };
+        assert!(db.apply_finalization(&finalization, &txn).await.unwrap());
+
+        let detail = twine_transaction_batch_detail::Entity::find()
+            .one(&txn)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(detail.inserted_at, stale);
+        assert!(detail.updated_at > stale);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn repair_tags_the_blocks_and_transactions_a_batch_missed() {
+        let db = test_db::client(true).await;
+        let blockscout = db.blockscout.as_ref().unwrap();
+        let txn = blockscout.begin().await.unwrap();
+        db.bulk_insert_twine_transaction_batch(vec![batch(7, 100)], &txn)
+            .await
+            .unwrap();
+        txn.commit().await.unwrap();
+        // Blockscout has 5 of the batch's 10 blocks, one transaction in each,
+        // and only the first 3 were tagged when the batch was committed
+        blockscout
+            .execute_unprepared(
+                r#"
+                INSERT INTO blocks (consensus, gas_limit, gas_used, hash, miner_hash, nonce,
+                    number, parent_hash, timestamp, batch_number)
+                SELECT true, 0, 0, int8send(n::bigint), '\x00', '\x00', n,
+                    int8send(n::bigint - 1), NOW(), CASE WHEN n <= 102 THEN 7 END
+                FROM generate_series(100, 104) AS n;
+                INSERT INTO transactions (gas, hash, index, input, nonce, r, s, v, value,
+                    block_hash, block_number, from_address_hash, batch_number)
+                SELECT 0, int8send(n::bigint + 1000), 0, '\x', 0, 0, 0, 0, 0,
+                    int8send(n::bigint), n, '\x00', CASE WHEN n <= 102 THEN 7 END
+                FROM generate_series(100, 104) AS n;
+                "#,
+            )
+            .await
+            .unwrap();
+
+        let before = db.batch_l2_completeness(7).await.unwrap().unwrap();
+        assert_eq!(before.expected_blocks, 10);
+        assert_eq!(before.stored_blocks, 3);
+        assert_eq!(before.expected_transactions, 5);
+        assert_eq!(before.stored_transactions, 3);
+
+        db.repair_batch_l2(7, 100, 109).await.unwrap();
+
+        let after = db.batch_l2_completeness(7).await.unwrap().unwrap();
+        assert_eq!(after.stored_blocks, 5);
+        assert_eq!(after.stored_transactions, 5);
+        assert!(db.batch_l2_completeness(8).await.unwrap().is_none());
+    }
 }
@@ -0,0 +1,373 @@
+use crate::client::DbClient;
+use crate::entities::{
+    batch_l1_blocks, block_hashes, pending_finalizations, source_transactions, transaction_flows,
+    unparsed_logs,
+};
+use sea_orm::{
+    ActiveValue::Set,
+    ColumnTrait, Condition, DbErr, EntityTrait, QueryFilter, QueryOrder, TransactionTrait, Value,
+    sea_query::{Expr, OnConflict},
+};
+use tracing::warn;
+
+impl DbClient {
+    /// Stores the hashes of indexed blocks and forgets those below `keep_from`,
+    /// which are too deep to be reorged
+    pub async fn upsert_block_hashes(
+        &self,
+        chain_id: i64,
+        hashes: Vec<(i64, String)>,
+        keep_from: i64,
+    ) -> Result<(), DbErr> {
+        let txn = self.primary.begin().await?;
+
+        if !hashes.is_empty() {
+            let models =
+                hashes
+                    .into_iter()
+                    .map(|(block_number, block_hash)| block_hashes::ActiveModel {
+                        chain_id: Set(chain_id),
+                        block_number: Set(block_number),
+                        block_hash: Set(block_hash),
+                    });
+
+            block_hashes::Entity::insert_many(models)
+                .on_conflict(
+                    OnConflict::columns([
+                        block_hashes::Column::ChainId,
+                        block_hashes::Column::BlockNumber,
+                    ])
+                    .update_column(block_hashes::Column::BlockHash)
+                    .to_owned(),
+                )
+                .exec_without_returning(&txn)
+                .await?;
+        }
+
+        block_hashes::Entity::delete_many()
+            .filter(block_hashes::Column::ChainId.eq(chain_id))
+            .filter(block_hashes::Column::BlockNumber.lt(keep_from))
+            .exec(&txn)
+            .await?;
+
+        txn.commit().await
+    }
+
+    /// Tracked block hashes of `chain_id`, highest block first
+    pub async fn fetch_block_hashes(
+        &self,
+        chain_id: i64,
+    ) -> Result<Vec<block_hashes::Model>, DbErr> {
+        block_hashes::Entity::find()
+            .filter(block_hashes::Column::ChainId.eq(chain_id))
+            .order_by_desc(block_hashes::Column::BlockNumber)
+            .all(&self.primary)
+            .await
+    }
+
+    /// Undoes what `chain_id` indexed at or above `from_block` after a reorg
+    /// orphaned those blocks: the bridge transactions it sourced, the execute
+    /// legs it recorded on bridge flows, the batches it committed or finalized
+    /// in blockscout, its buffered finalizations, its dead-lettered logs and
+    /// the block hashes tracked for it. Returns the number of bridge
+    /// transactions removed.
+    pub async fn rollback_chain_from(&self, chain_id: i64, from_block: i64) -> eyre::Result<u64> {
+        let txn = self.primary.begin().await?;
+
+        let removed = source_transactions::Entity::delete_many()
+            .filter(source_transactions::Column::ChainId.eq(chain_id))
+            .filter(source_transactions::Column::BlockNumber.gte(from_block))
+            .exec(&txn)
+            .await?
+            .rows_affected;
+
+        // The update_is_completed trigger recomputes completion from is_executed
+        transaction_flows::Entity::update_many()
+            .col_expr(
+                transaction_flows::Column::ExecuteTxHash,
+                Expr::value(Value::String(None)),
+            )
+            .col_expr(
+                transaction_flows::Column::ExecuteBlockNumber,
+                Expr::value(Value::BigInt(None)),
+            )
+            .col_expr(
+                transaction_flows::Column::ExecutedAt,
+                Expr::value(Value::ChronoDateTimeWithTimeZone(None)),
+            )
+            .col_expr(transaction_flows::Column::IsExecuted, Expr::value(false))
+            .col_expr(
+                transaction_flows::Column::RefundAmount,
+                Expr::value(Value::Decimal(None)),
+            )
+            .col_expr(
+                transaction_flows::Column::RefundToken,
+                Expr::value(Value::String(None)),
+            )
+            .filter(transaction_flows::Column::ChainId.eq(chain_id))
+            .filter(transaction_flows::Column::ExecuteBlockNumber.gte(from_block))
+            .exec(&txn)
+            .await?;
+
+        let orphaned_batches = batch_l1_blocks::Entity::find()
+            .filter(batch_l1_blocks::Column::ChainId.eq(chain_id))
+            .filter(
+                Condition::any()
+                    .add(batch_l1_blocks::Column::CommitBlockNumber.gte(from_block))
+                    .add(batch_l1_blocks::Column::FinalizeBlockNumber.gte(from_block)),
+            )
+            .all(&txn)
+            .await?;
+        let (uncommitted, unfinalized): (Vec<_>, Vec<_>) =
+            orphaned_batches.into_iter().partition(|batch| {
+                batch
+                    .commit_block_number
+                    .is_some_and(|block_number| block_number >= from_block)
+            });
+        let uncommitted: Vec<i64> = uncommitted.iter().map(|batch| batch.batch_number).collect();
+        let unfinalized: Vec<i64> = unfinalized.iter().map(|batch| batch.batch_number).collect();
+
+        if !uncommitted.is_empty() || !unfinalized.is_empty() {
+            match &self.blockscout {
+                // Committed before the primary so a failure there leaves the
+                // batch blocks recorded and the rollback can be run again
+                Some(blockscout) => {
+                    let blockscout_txn = blockscout.begin().await?;
+                    self.uncommit_batches(chain_id, &uncommitted, &blockscout_txn)
+                        .await?;
+                    self.unfinalize_batches(chain_id, &unfinalized, &blockscout_txn)
+                        .await?;
+                    blockscout_txn.commit().await?;
+                }
+                None => warn!(
+                    "Chain {} rolled back batches {:?} but no blockscout connection is available",
+                    chain_id, uncommitted
+                ),
+            }
+
+            batch_l1_blocks::Entity::delete_many()
+                .filter(batch_l1_blocks::Column::ChainId.eq(chain_id))
+                .filter(batch_l1_blocks::Column::CommitBlockNumber.gte(from_block))
+                .exec(&txn)
+                .await?;
+            batch_l1_blocks::Entity::update_many()
+                .col_expr(
+                    batch_l1_blocks::Column::FinalizeBlockNumber,
+                    Expr::value(Value::BigInt(None)),
+                )
+                .filter(batch_l1_blocks::Column::ChainId.eq(chain_id))
+                .filter(batch_l1_blocks::Column::FinalizeBlockNumber.gte(from_block))
+                .exec(&txn)
+                .await?;
+        }
+
+        pending_finalizations::Entity::delete_many()
+            .filter(pending_finalizations::Column::ChainId.eq(chain_id))
+            .filter(pending_finalizations::Column::BlockNumber.gte(from_block))
+            .exec(&txn)
+            .await?;
+
+        unparsed_logs::Entity::delete_many()
+            .filter(unparsed_logs::Column::ChainId.eq(chain_id))
+            .filter(unparsed_logs::Column::BlockNumber.gte(from_block))
+            .exec(&txn)
+            .await?;
+
+        block_hashes::Entity::delete_many()
+            .filter(block_hashes::Column::ChainId.eq(chain_id))
+            .filter(block_hashes::Column::BlockNumber.gte(from_block))
+            .exec(&txn)
+            .await?;
+
+        txn.commit().await?;
+
+        warn!(
+            "Rolled back chain {} from block {}, removed {} bridge transactions and {} batch commits",
+            chain_id,
+            from_block,
+            removed,
+            uncommitted.len()
+        );
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DbOperations;
+    use crate::blockscout_entities::{twine_transaction_batch, twine_transaction_batch_detail};
+    use crate::entities::sea_orm_active_enums::TransactionTypeEnum;
+    use crate::test_db;
+    use sea_orm::prelude::{DateTime, Decimal};
+
+    const CHAIN: i64 = 11155111;
+
+    fn source(nonce: i64, block_number: i64) -> DbOperations {
+        DbOperations::BridgeSourceTransaction(source_transactions::ActiveModel {
+            chain_id: Set(CHAIN),
+            nonce: Set(nonce),
+            transaction_type: Set(TransactionTypeEnum::ForcedWithdraw),
+            block_number: Set(block_number),
+            l1_token: Set("0xl1".to_string()),
+            l2_token: Set("0xl2".to_string()),
+            l1_address: Set("0xfrom".to_string()),
+            twine_address: Set("0xto".to_string()),
+            amount: Set(Decimal::ONE),
+            ..Default::default()
+        })
+    }
+
+    fn execute(nonce: i64, block_number: i64) -> DbOperations {
+        DbOperations::BridgeDestinationTransactions(transaction_flows::ActiveModel {
+            chain_id: Set(CHAIN),
+            nonce: Set(nonce),
+            execute_tx_hash: Set(Some(format!("0xexecute{}", block_number))),
+            execute_block_number: Set(Some(block_number)),
+            is_executed: Set(Some(true)),
+            ..Default::default()
+        })
+    }
+
+    fn commit(batch_number: i64, l1_block_number: i64) -> DbOperations {
+        DbOperations::CommitBatch {
+            batch: twine_transaction_batch::ActiveModel {
+                number: Set(batch_number),
+                start_block: Set(batch_number * 10),
+                end_block: Set(batch_number * 10 + 9),
+                timestamp: Set(DateTime::default()),
+                root_hash: Set(vec![0; 32]),
+                ..Default::default()
+            },
+            details: twine_transaction_batch_detail::ActiveModel {
+                batch_number: Set(batch_number),
+                l1_transaction_count: Set(0),
+                l2_transaction_count: Set(0),
+                l1_gas_price: Set(Decimal::ZERO),
+                l2_fair_gas_price: Set(Decimal::ZERO),
+                chain_id: Set(Decimal::from(CHAIN)),
+                ..Default::default()
+            },
+            l2_blocks: None,
+            l1_block_number,
+        }
+    }
+
+    fn finalize(batch_number: i64, l1_block_number: i64) -> DbOperations {
+        DbOperations::FinalizeBatch {
+            finalize_hash: format!("0xfinalize{}", batch_number),
+            batch_number,
+            chain_id: CHAIN,
+            l1_block_number,
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn rollback_undoes_everything_logged_in_orphaned_blocks() {
+        let client = test_db::client(true).await;
+        let blockscout = client.blockscout.clone().unwrap();
+        client
+            .process_bulk_l1_database_operations(vec![vec![
+                source(1, 90),
+                source(2, 110),
+                execute(1, 95),
+                execute(2, 115),
+                commit(5, 95),
+                commit(6, 105),
+                finalize(5, 106),
+                // Not committed yet, buffered
+                finalize(9, 108),
+            ]])
+            .await
+            .unwrap();
+        client
+            .upsert_block_hashes(CHAIN, vec![(95, "0xa".into()), (105, "0xb".into())], 0)
+            .await
+            .unwrap();
+
+        let removed = client.rollback_chain_from(CHAIN, 100).await.unwrap();
+        assert_eq!(removed, 1);
+
+        let sources: Vec<i64> = source_transactions::Entity::find()
+            .all(&client.primary)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|tx| tx.nonce)
+            .collect();
+        assert_eq!(sources, vec![1]);
+
+        let flows = transaction_flows::Entity::find()
+            .order_by_asc(transaction_flows::Column::Nonce)
+            .all(&client.primary)
+            .await
+            .unwrap();
+        assert_eq!(flows[0].execute_block_number, Some(95));
+        assert_eq!(flows[0].is_executed, Some(true));
+        assert_eq!(flows[1].execute_block_number, None);
+        assert_eq!(flows[1].execute_tx_hash, None);
+        assert_eq!(flows[1].is_executed, Some(false));
+
+        let batches: Vec<i64> = twine_transaction_batch::Entity::find()
+            .all(&blockscout)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|batch| batch.number)
+            .collect();
+        assert_eq!(batches, vec![5]);
+        let details = twine_transaction_batch_detail::Entity::find()
+            .all(&blockscout)
+            .await
+            .unwrap();
+        assert_eq!(details.len(), 1);
+        assert_eq!(details[0].batch_number, 5);
+        assert_eq!(details[0].finalize_transaction_hash, None);
+        assert_eq!(details[0].finalized_at, None);
+
+        let pending = pending_finalizations::Entity::find()
+            .all(&client.primary)
+            .await
+            .unwrap();
+        assert!(pending.is_empty());
+
+        let recorded = batch_l1_blocks::Entity::find()
+            .all(&client.primary)
+            .await
+            .unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].batch_number, 5);
+        assert_eq!(recorded[0].commit_block_number, Some(95));
+        assert_eq!(recorded[0].finalize_block_number, None);
+
+        let hashes: Vec<i64> = client
+            .fetch_block_hashes(CHAIN)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|hash| hash.block_number)
+            .collect();
+        assert_eq!(hashes, vec![95]);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn rollback_leaves_other_chains_alone() {
+        let client = test_db::client(false).await;
+        client
+            .process_bulk_l1_database_operations(vec![vec![source(1, 110), execute(1, 115)]])
+            .await
+            .unwrap();
+
+        let removed = client.rollback_chain_from(CHAIN + 1, 100).await.unwrap();
+
+        assert_eq!(removed, 0);
+        let flow = transaction_flows::Entity::find()
+            .one(&client.primary)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(flow.execute_block_number, Some(115));
+    }
+}
@@ -1,5 +1,6 @@
 use sea_orm::{
-    ColumnTrait, DatabaseTransaction, EntityTrait, QueryFilter as _, sea_query::OnConflict,
+    ActiveValue::Set, ColumnTrait, DatabaseTransaction, EntityTrait, IntoActiveModel,
+    PaginatorTrait, QueryFilter as _, sea_query::OnConflict,
 };
 use tracing::error;
 
@@ -8,6 +9,10 @@ use crate::{
     client::DbClient,
 };
 
+/// Blocks whose rows are loaded and re-tagged at a time when a batch is
+/// committed, so a batch spanning thousands of blocks is never held in memory
+const L2_TAG_WINDOW_BLOCKS: u64 = 500;
+
 /// Generic utility for processing database operations in batches to avoid PostgreSQL parameter limits
 async fn process_in_batches<T, F, Fut>(
     items: Vec<T>,
@@ -61,6 +66,22 @@ impl DbClient {
         Ok(block)
     }
 
+    /// Number of blocks blockscout has in `start_block..=end_block`
+    pub async fn count_blocks(&self, start_block: u64, end_block: u64) -> eyre::Result<u64> {
+        let blockscout = self.blockscout.as_ref().ok_or_else(|| {
+            error!("Blockscout database connection is not available");
+            eyre::eyre!("Blockscout database connection is not available")
+        })?;
+
+        let count = blocks::Entity::find()
+            .filter(blocks::Column::Number.gte(start_block as i64))
+            .filter(blocks::Column::Number.lte(end_block as i64))
+            .count(blockscout)
+            .await?;
+
+        Ok(count)
+    }
+
     pub async fn get_transactions(
         &self,
         start_block: u64,
@@ -147,4 +168,56 @@ impl DbClient {
         })
         .await
     }
+
+    /// Tags every block and transaction in `start_block..=end_block` with
+    /// `batch_number` inside `txn`, loading and upserting one window of
+    /// blocks at a time
+    pub async fn tag_batch_l2(
+        &self,
+        batch_number: i64,
+        start_block: u64,
+        end_block: u64,
+        txn: &DatabaseTransaction,
+    ) -> eyre::Result<()> {
+        let mut window_start = start_block;
+        while window_start <= end_block {
+            let window_end = (window_start + L2_TAG_WINDOW_BLOCKS - 1).min(end_block);
+
+            let l2_blocks: Vec<blocks::ActiveModel> = blocks::Entity::find()
+                .filter(blocks::Column::Number.gte(window_start as i64))
+                .filter(blocks::Column::Number.lte(window_end as i64))
+                .all(txn)
+                .await?
+                .into_iter()
+                .map(|model| {
+                    let mut am = model.into_active_model();
+                    am.batch_number = Set(Some(batch_number));
+                    am
+                })
+                .collect();
+            if !l2_blocks.is_empty() {
+                self.bulk_update_blocks(l2_blocks, txn).await?;
+            }
+
+            let l2_txs: Vec<transactions::ActiveModel> = transactions::Entity::find()
+                .filter(transactions::Column::BlockNumber.gte(window_start as i64))
+                .filter(transactions::Column::BlockNumber.lte(window_end as i64))
+                .all(txn)
+                .await?
+                .into_iter()
+                .map(|model| {
+                    let mut am = model.into_active_model();
+                    am.batch_number = Set(Some(batch_number));
+                    am
+                })
+                .collect();
+            if !l2_txs.is_empty() {
+                self.bulk_update_transactions(l2_txs, txn).await?;
+            }
+
+            window_start = window_end + 1;
+        }
+
+        Ok(())
+    }
 }
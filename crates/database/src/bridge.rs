@@ -1,19 +1,375 @@
-use sea_orm::sea_query::Expr;
+use sea_orm::prelude::{DateTimeWithTimeZone, Decimal};
+use sea_orm::sea_query::{Expr, Func, SimpleExpr};
 use sea_orm::{
-    ColumnTrait, Condition, DatabaseTransaction, DbErr, EntityTrait, QueryFilter, QueryOrder,
-    QuerySelect, sea_query::OnConflict,
+    ColumnTrait, Condition, ConnectionTrait, DatabaseTransaction, DbBackend, DbErr, EntityTrait,
+    IdenStatic, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Select, Statement,
+    TransactionTrait, sea_query::OnConflict,
 };
-use std::collections::HashMap;
-use tracing::{debug, error, instrument, warn};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tracing::{debug, error, info, instrument, warn};
 
 use crate::client::DbClient;
-use crate::entities::{source_transactions, transaction_flows};
+use crate::entities::{
+    sea_orm_active_enums::{TokenStandardEnum, TransactionTypeEnum},
+    source_transactions, transaction_flows,
+};
+use crate::{DbOperations, OrderingKey};
+
+/// Re-evaluates `is_completed` with the rules of the `update_is_completed`
+/// trigger, touching only rows whose stored value differs
+const RECOMPUTE_IS_COMPLETED_SQL: &str = r#"
+    UPDATE transaction_flows tf
+    SET is_completed = computed.is_completed
+    FROM (
+        SELECT f.id,
+            CASE
+                WHEN st.transaction_type = 'Deposit'::transaction_type_enum THEN f.is_handled OR f.is_executed
+                WHEN st.transaction_type = 'Withdraw'::transaction_type_enum THEN f.is_handled
+                WHEN st.transaction_type = 'ForcedWithdraw'::transaction_type_enum THEN f.is_handled AND f.is_executed
+                ELSE FALSE
+            END AS is_completed
+        FROM transaction_flows f
+        LEFT JOIN source_transactions st ON st.chain_id = f.chain_id AND st.nonce = f.nonce
+    ) computed
+    WHERE tf.id = computed.id
+      AND tf.is_completed IS DISTINCT FROM computed.is_completed
+"#;
+
+/// Anomalies of each kind reported by `detect_nonce_anomalies`, so a chain
+/// with a badly broken sequence does not return every row
+const MAX_NONCE_ANOMALIES: i64 = 1000;
+
+/// Length in bytes of an ed25519 signature, which identifies a Solana transaction
+const SOLANA_SIGNATURE_LEN: usize = 64;
+
+/// Checks that `signature` is a base58 encoded Solana transaction signature
+pub fn validate_solana_signature(signature: &str) -> eyre::Result<()> {
+    let decoded = bs58::decode(signature)
+        .into_vec()
+        .map_err(|e| eyre::eyre!("Signature '{}' is not valid base58: {}", signature, e))?;
+
+    if decoded.len() != SOLANA_SIGNATURE_LEN {
+        return Err(eyre::eyre!(
+            "Signature '{}' decodes to {} bytes, expected {}",
+            signature,
+            decoded.len(),
+            SOLANA_SIGNATURE_LEN
+        ));
+    }
+
+    Ok(())
+}
+
+/// Token addresses the Solana programs use for native SOL: the system program and the wrapped SOL mint
+const SOLANA_NATIVE_TOKENS: [&str; 2] = [
+    "11111111111111111111111111111111",
+    "So11111111111111111111111111111111111111112",
+];
+
+/// Classifies the L1 side of a bridge transfer from its token address, for
+/// events that don't say which kind of token moved. EVM gateways use the zero
+/// address for ETH, anything else is an ERC20.
+pub fn token_standard_for_l1_token(l1_token: &str) -> TokenStandardEnum {
+    match l1_token.strip_prefix("0x") {
+        Some(address) if address.chars().all(|c| c == '0') => TokenStandardEnum::Native,
+        Some(_) => TokenStandardEnum::Erc20,
+        None if SOLANA_NATIVE_TOKENS.contains(&l1_token) => TokenStandardEnum::Native,
+        None => TokenStandardEnum::Spl,
+    }
+}
+
+/// Classifies a Solana bridge transfer from the instruction that emitted it,
+/// e.g. `NativeTokenDeposit` or `SplTokensDeposit`. `None` for an instruction
+/// that names neither.
+pub fn token_standard_for_solana_instruction(instruction: &str) -> Option<TokenStandardEnum> {
+    if instruction.starts_with("Native") {
+        Some(TokenStandardEnum::Native)
+    } else if instruction.starts_with("Spl") {
+        Some(TokenStandardEnum::Spl)
+    } else {
+        None
+    }
+}
+
+/// Outcome of handling an L1 message on Twine, stored in `transaction_flows.handle_status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandleStatus {
+    Failed,
+    Success,
+}
+
+impl HandleStatus {
+    /// Maps the status reported by `L1TransactionsHandled`, `None` for a value it never emits
+    pub fn from_event_status(status: u64) -> Option<Self> {
+        match status {
+            0 => Some(HandleStatus::Failed),
+            1 => Some(HandleStatus::Success),
+            _ => None,
+        }
+    }
+
+    pub fn as_i16(self) -> i16 {
+        match self {
+            HandleStatus::Failed => 0,
+            HandleStatus::Success => 1,
+        }
+    }
+}
+
+/// Message bytes carried by a deposit, empty messages are stored as NULL
+pub fn deposit_message(message: &[u8]) -> Option<Vec<u8>> {
+    (!message.is_empty()).then(|| message.to_vec())
+}
+
+/// Digits `source_transactions.amount`, a decimal(78,0), can hold
+const AMOUNT_MAX_DIGITS: usize = 78;
+
+/// Parses a bridged amount, which must be a non-negative integer that fits
+/// `decimal(78,0)`. The error describes why the amount was rejected.
+pub fn parse_amount(raw: &str) -> Result<Decimal, String> {
+    if raw.is_empty() || !raw.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("amount '{}' is not a non-negative integer", raw));
+    }
+    if raw.len() > AMOUNT_MAX_DIGITS {
+        return Err(format!(
+            "amount '{}' has {} digits, more than the {} decimal(78,0) holds",
+            raw,
+            raw.len(),
+            AMOUNT_MAX_DIGITS
+        ));
+    }
+    raw.parse::<Decimal>()
+        .map_err(|e| format!("amount '{}' could not be parsed: {}", raw, e))
+}
+
+/// A token pair seen on the bridge, with the symbol of its L2 token when the
+/// uniswap indexer has fetched it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BridgedToken {
+    pub l1_token: String,
+    pub l2_token: String,
+    pub symbol: Option<String>,
+    pub first_seen: Option<DateTimeWithTimeZone>,
+    pub last_seen: Option<DateTimeWithTimeZone>,
+    pub transfers: u64,
+}
+
+/// Deposits started on one chain in a time window against how many of them
+/// were handled on Twine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainReconciliation {
+    pub chain_id: i64,
+    pub deposits: u64,
+    pub handled: u64,
+}
+
+impl ChainReconciliation {
+    /// Deposits not handled on Twine yet
+    pub fn delta(&self) -> u64 {
+        self.deposits.saturating_sub(self.handled)
+    }
+}
+
+/// A break in a chain's nonce sequence. Source transactions are unique on
+/// `(chain_id, nonce)`, so a second chain sharing the chain id never shows up
+/// as a duplicate row, only through the patterns below.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NonceAnomaly {
+    /// Nonces between `after` and `before` are missing, dropped events or
+    /// rows another chain's events took
+    Gap { after: i64, before: i64 },
+    /// `nonce` was submitted in a later block than `previous_nonce` but is
+    /// lower, as happens when two chains' sequences interleave
+    OutOfOrder {
+        nonce: i64,
+        block_number: i64,
+        previous_nonce: i64,
+        previous_block_number: i64,
+    },
+}
+
+impl NonceAnomaly {
+    /// Nonces missing in a gap, 0 for other anomalies
+    pub fn missing(&self) -> u64 {
+        match self {
+            NonceAnomaly::Gap { after, before } => (before - after - 1).max(0) as u64,
+            NonceAnomaly::OutOfOrder { .. } => 0,
+        }
+    }
+}
+
+/// Bridge-wide totals
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BridgeStats {
+    pub total_deposits: u64,
+    pub total_withdraws: u64,
+    pub total_forced_withdraws: u64,
+    /// Deposits that have not been handled on Twine yet
+    pub pending_deposits: u64,
+}
+
+/// Stages of a single withdrawal. Stages that have not been indexed yet are `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithdrawalTimeline {
+    pub transaction_type: TransactionTypeEnum,
+    pub source_tx_hash: Option<String>,
+    pub submitted_at: Option<DateTimeWithTimeZone>,
+    pub handle_tx_hash: Option<String>,
+    pub handled_at: Option<DateTimeWithTimeZone>,
+    pub execute_tx_hash: Option<String>,
+    pub executed_at: Option<DateTimeWithTimeZone>,
+    pub is_completed: bool,
+}
+
+/// Trims an address and lowercases EVM hex addresses so checksummed and
+/// lowercase forms compare equal. Solana base58 addresses are case sensitive
+/// and kept as is.
+pub fn normalize_address(address: &str) -> String {
+    let address = address.trim();
+    match address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+    {
+        Some(hex) => format!("0x{}", hex.to_lowercase()),
+        None => address.to_string(),
+    }
+}
+
+/// Trims a transaction hash and lowercases EVM hex hashes, adding the `0x`
+/// prefix when it is missing. Solana signatures are kept as is.
+pub fn normalize_tx_hash(hash: &str) -> String {
+    let hash = hash.trim();
+    let hex = hash
+        .strip_prefix("0x")
+        .or_else(|| hash.strip_prefix("0X"))
+        .unwrap_or(hash);
+    if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        format!("0x{}", hex.to_lowercase())
+    } else {
+        hash.to_string()
+    }
+}
+
+/// Whether a normalized hash is a complete EVM transaction hash or Solana
+/// signature rather than a fragment of one
+pub fn is_full_tx_hash(hash: &str) -> bool {
+    let is_evm = hash.len() == 66
+        && hash.starts_with("0x")
+        && hash[2..].chars().all(|c| c.is_ascii_hexdigit());
+    let is_solana = (86..=88).contains(&hash.len())
+        && hash
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() && !matches!(c, '0' | 'O' | 'I' | 'l'));
+    is_evm || is_solana
+}
+
+/// How a quick search result matched the query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMatch {
+    /// One of the result's transaction hashes is the query
+    ExactHash,
+    /// The query is a fragment of one of the searched columns
+    Partial,
+}
+
+impl SearchMatch {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchMatch::ExactHash => "exact_hash",
+            SearchMatch::Partial => "partial",
+        }
+    }
+}
+
+/// Source transaction a page of bridge transactions starts after, the last
+/// one of the previous page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BridgeCursor {
+    pub chain_id: i64,
+    pub nonce: i64,
+}
+
+impl BridgeCursor {
+    /// Cursor from the `chain_id` and `nonce` query parameters, `None` unless both are set
+    pub fn from_parts(chain_id: Option<i64>, nonce: Option<i64>) -> Option<Self> {
+        Some(Self {
+            chain_id: chain_id?,
+            nonce: nonce?,
+        })
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct FetchBridgeTransactionsParams {
     pub items_count: u64,
-    pub cursor_chain_id: Option<i64>,
-    pub cursor_nonce: Option<i64>,
+    pub cursor: Option<BridgeCursor>,
+}
+
+/// Rows after the cursor row, stamped `timestamp`, in the (timestamp,
+/// chain_id, nonce, id) descending order used for pagination. (chain_id,
+/// nonce) is unique, so the id tie-breaker never has to be compared here, it
+/// only pins the order rows are returned in.
+fn after_bridge_cursor(timestamp: Option<DateTimeWithTimeZone>, cursor: BridgeCursor) -> Condition {
+    let BridgeCursor { chain_id, nonce } = cursor;
+    Condition::any()
+        .add(source_transactions::Column::Timestamp.lt(timestamp))
+        .add(
+            Condition::all()
+                .add(source_transactions::Column::Timestamp.eq(timestamp))
+                .add(source_transactions::Column::ChainId.lt(chain_id)),
+        )
+        .add(
+            Condition::all()
+                .add(source_transactions::Column::Timestamp.eq(timestamp))
+                .add(source_transactions::Column::ChainId.eq(chain_id))
+                .add(source_transactions::Column::Nonce.lt(nonce)),
+        )
+}
+
+/// Narrows a bridge transaction listing, an unset field matches everything
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BridgeEventFilter {
+    /// Chain the transaction was sent from
+    pub chain_id: Option<i64>,
+    /// Token on either side of the bridge
+    pub token: Option<String>,
+}
+
+/// Error of a paginated bridge query
+#[derive(Debug)]
+pub enum BridgeQueryError {
+    /// The cursor does not point at an existing source transaction
+    CursorNotFound(BridgeCursor),
+    Database(DbErr),
+}
+
+impl From<DbErr> for BridgeQueryError {
+    fn from(err: DbErr) -> Self {
+        BridgeQueryError::Database(err)
+    }
+}
+
+impl std::error::Error for BridgeQueryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BridgeQueryError::CursorNotFound(_) => None,
+            BridgeQueryError::Database(err) => Some(err),
+        }
+    }
+}
+
+impl std::fmt::Display for BridgeQueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BridgeQueryError::CursorNotFound(cursor) => write!(
+                f,
+                "No bridge transaction with chain_id {} and nonce {} to page from",
+                cursor.chain_id, cursor.nonce
+            ),
+            BridgeQueryError::Database(err) => write!(f, "Database error: {}", err),
+        }
+    }
 }
 
 impl DbClient {
@@ -33,7 +389,13 @@ impl DbClient {
                     source_transactions::Column::ChainId,
                     source_transactions::Column::Nonce,
                 ])
-                .do_nothing()
+                // Rows indexed before deposits carried a destination get it on re-index
+                .value(
+                    source_transactions::Column::DestinationChainId,
+                    Expr::cust(
+                        "COALESCE(source_transactions.destination_chain_id, EXCLUDED.destination_chain_id)",
+                    ),
+                )
                 .to_owned(),
             )
             .exec_with_returning_many(txn)
@@ -63,12 +425,23 @@ impl DbClient {
                     transaction_flows::Column::ChainId,
                     transaction_flows::Column::Nonce,
                 ])
-                .update_columns([
-                    transaction_flows::Column::ExecuteTxHash,
-                    transaction_flows::Column::ExecuteBlockNumber,
-                    transaction_flows::Column::IsExecuted,
-                    transaction_flows::Column::ExecutedAt,
+                // An execute seen at a lower block than the stored one comes from
+                // a reorged branch, don't let it replace the confirmed execute
+                .values([
+                    later_execute_wins(transaction_flows::Column::ExecuteTxHash),
+                    later_execute_wins(transaction_flows::Column::ExecuteBlockNumber),
+                    later_execute_wins(transaction_flows::Column::IsExecuted),
+                    later_execute_wins(transaction_flows::Column::ExecutedAt),
                 ])
+                // Only refunds carry these, keep them when other flow updates land later
+                .value(
+                    transaction_flows::Column::RefundAmount,
+                    Expr::cust("COALESCE(EXCLUDED.refund_amount, transaction_flows.refund_amount)"),
+                )
+                .value(
+                    transaction_flows::Column::RefundToken,
+                    Expr::cust("COALESCE(EXCLUDED.refund_token, transaction_flows.refund_token)"),
+                )
                 .to_owned(),
             )
             .exec_with_returning_many(txn)
@@ -83,53 +456,87 @@ impl DbClient {
         Ok(())
     }
 
+    /// Restricts `query` to rows after the cursor in `params`, in the
+    /// (timestamp, chain_id, nonce, id) descending order used for pagination.
+    /// Fails with `CursorNotFound` when the cursor row does not exist.
+    async fn _apply_bridge_cursor(
+        &self,
+        query: Select<source_transactions::Entity>,
+        params: &FetchBridgeTransactionsParams,
+    ) -> Result<Select<source_transactions::Entity>, BridgeQueryError> {
+        let Some(cursor) = params.cursor else {
+            return Ok(query);
+        };
+        let BridgeCursor { chain_id, nonce } = cursor;
+
+        let reference_tx = source_transactions::Entity::find()
+            .filter(
+                Condition::all()
+                    .add(source_transactions::Column::ChainId.eq(chain_id))
+                    .add(source_transactions::Column::Nonce.eq(nonce)),
+            )
+            .one(&self.primary)
+            .await?;
+
+        let Some(tx) = reference_tx else {
+            warn!(cursor = ?cursor, "Client provided a cursor that does not exist.");
+            return Err(BridgeQueryError::CursorNotFound(cursor));
+        };
+
+        Ok(query.filter(after_bridge_cursor(tx.timestamp, cursor)))
+    }
+
     #[instrument(skip(self), fields(event_type = ?event_type, params = ?params))]
     async fn _fetch_paginated_bridge_events(
         &self,
         event_type: String,
         params: &FetchBridgeTransactionsParams,
-    ) -> Result<Vec<(source_transactions::Model, transaction_flows::Model)>, DbErr> {
+    ) -> Result<Vec<(source_transactions::Model, transaction_flows::Model)>, BridgeQueryError> {
+        self._fetch_filtered_bridge_events(event_type, params, &BridgeEventFilter::default())
+            .await
+    }
+
+    /// `_fetch_paginated_bridge_events` restricted to the rows matching
+    /// `filter`. The cursor condition is applied on top, so paging through a
+    /// filtered listing stays in the same keyset order.
+    async fn _fetch_filtered_bridge_events(
+        &self,
+        event_type: String,
+        params: &FetchBridgeTransactionsParams,
+        filter: &BridgeEventFilter,
+    ) -> Result<Vec<(source_transactions::Model, transaction_flows::Model)>, BridgeQueryError> {
         let mut query_builder = source_transactions::Entity::find()
             .filter(source_transactions::Column::TransactionType.eq(event_type));
 
-        if let (Some(chain_id), Some(nonce)) = (params.cursor_chain_id, params.cursor_nonce) {
-            let reference_tx = source_transactions::Entity::find()
-                .filter(
-                    Condition::all()
-                        .add(source_transactions::Column::ChainId.eq(chain_id))
-                        .add(source_transactions::Column::Nonce.eq(nonce)),
-                )
-                .one(&self.primary)
-                .await?;
-
-            if let Some(tx) = reference_tx {
-                let timestamp = tx.timestamp;
-                query_builder = query_builder.filter(
-                    Condition::any()
-                        .add(source_transactions::Column::Timestamp.lt(timestamp))
-                        .add(
-                            Condition::all()
-                                .add(source_transactions::Column::Timestamp.eq(timestamp))
-                                .add(source_transactions::Column::ChainId.lt(chain_id)),
-                        )
-                        .add(
-                            Condition::all()
-                                .add(source_transactions::Column::Timestamp.eq(timestamp))
-                                .add(source_transactions::Column::ChainId.eq(chain_id))
-                                .add(source_transactions::Column::Nonce.lt(nonce)),
-                        ),
-                );
-            } else {
-                warn!(cursor = ?params, "Client provided a cursor that does not exist.");
-                return Ok(Vec::new());
-            }
+        if let Some(chain_id) = filter.chain_id {
+            query_builder = query_builder.filter(source_transactions::Column::ChainId.eq(chain_id));
+        }
+        if let Some(token) = &filter.token {
+            let token = normalize_address(token);
+            // Stored EVM token addresses may be checksummed
+            let matches_token = |column: source_transactions::Column| {
+                let column = Expr::col((source_transactions::Entity, column));
+                if token.starts_with("0x") {
+                    Expr::expr(Func::lower(column)).eq(token.clone())
+                } else {
+                    column.eq(token.clone())
+                }
+            };
+            query_builder = query_builder.filter(
+                Condition::any()
+                    .add(matches_token(source_transactions::Column::L1Token))
+                    .add(matches_token(source_transactions::Column::L2Token)),
+            );
         }
 
+        let query_builder = self._apply_bridge_cursor(query_builder, params).await?;
+
         // First get the source transactions
         let source_transactions = query_builder
             .order_by_desc(source_transactions::Column::Timestamp)
             .order_by_desc(source_transactions::Column::ChainId)
             .order_by_desc(source_transactions::Column::Nonce)
+            .order_by_desc(source_transactions::Column::Id)
             .limit(params.items_count)
             .all(&self.primary)
             .await?;
@@ -178,16 +585,27 @@ impl DbClient {
     pub async fn fetch_l1_deposits_paginated(
         &self,
         params: FetchBridgeTransactionsParams,
-    ) -> Result<Vec<(source_transactions::Model, transaction_flows::Model)>, DbErr> {
+    ) -> Result<Vec<(source_transactions::Model, transaction_flows::Model)>, BridgeQueryError> {
         self._fetch_paginated_bridge_events("Deposit".to_string(), &params)
             .await
     }
 
+    /// L1 deposits from the chain and of the token in `filter`
+    #[instrument(skip(self), fields(params = ?params, filter = ?filter))]
+    pub async fn fetch_l1_deposits_filtered(
+        &self,
+        params: FetchBridgeTransactionsParams,
+        filter: BridgeEventFilter,
+    ) -> Result<Vec<(source_transactions::Model, transaction_flows::Model)>, BridgeQueryError> {
+        self._fetch_filtered_bridge_events("Deposit".to_string(), &params, &filter)
+            .await
+    }
+
     #[instrument(skip(self), fields(params = ?params))]
     pub async fn fetch_l2_withdraws_paginated(
         &self,
         params: FetchBridgeTransactionsParams,
-    ) -> Result<Vec<(source_transactions::Model, transaction_flows::Model)>, DbErr> {
+    ) -> Result<Vec<(source_transactions::Model, transaction_flows::Model)>, BridgeQueryError> {
         self._fetch_paginated_bridge_events("Withdraw".to_string(), &params)
             .await
     }
@@ -196,17 +614,43 @@ impl DbClient {
     pub async fn fetch_l1_forced_withdraws_paginated(
         &self,
         params: FetchBridgeTransactionsParams,
-    ) -> Result<Vec<(source_transactions::Model, transaction_flows::Model)>, DbErr> {
+    ) -> Result<Vec<(source_transactions::Model, transaction_flows::Model)>, BridgeQueryError> {
         self._fetch_paginated_bridge_events("ForcedWithdraw".to_string(), &params)
             .await
     }
 
+    /// Bridge transactions matching `q`. A full transaction hash is first
+    /// looked up exactly, and only falls back to the fuzzy search when no
+    /// transaction has it.
     #[instrument(skip(self), fields(q = q, limit = limit))]
     pub async fn quick_search_transactions(
         &self,
         q: &str,
         limit: u64,
-    ) -> Result<Vec<(source_transactions::Model, transaction_flows::Model)>, DbErr> {
+    ) -> Result<
+        Vec<(
+            source_transactions::Model,
+            transaction_flows::Model,
+            SearchMatch,
+        )>,
+        DbErr,
+    > {
+        let hash = normalize_tx_hash(q);
+        if is_full_tx_hash(&hash) {
+            let exact = self.find_transactions_by_hash(&hash, limit).await?;
+            if !exact.is_empty() {
+                debug!(
+                    search_query = q,
+                    found_count = exact.len(),
+                    "Quick search matched an exact hash"
+                );
+                return Ok(exact
+                    .into_iter()
+                    .map(|(source, flow)| (source, flow, SearchMatch::ExactHash))
+                    .collect());
+            }
+        }
+
         let text_pattern = format!("%{}%", q);
         let mut condition = Condition::any();
 
@@ -263,6 +707,63 @@ impl DbClient {
             .all(&self.primary)
             .await?;
 
+        let results: Vec<_> = self
+            .with_flows(source_transactions)
+            .await?
+            .into_iter()
+            .map(|(source, flow)| (source, flow, SearchMatch::Partial))
+            .collect();
+
+        debug!(
+            search_query = q,
+            found_count = results.len(),
+            "Quick search completed"
+        );
+        Ok(results)
+    }
+
+    /// Bridge transactions started, handled or executed in the transaction
+    /// `hash`
+    async fn find_transactions_by_hash(
+        &self,
+        hash: &str,
+        limit: u64,
+    ) -> Result<Vec<(source_transactions::Model, transaction_flows::Model)>, DbErr> {
+        let flows = transaction_flows::Entity::find()
+            .filter(
+                Condition::any()
+                    .add(transaction_flows::Column::HandleTxHash.eq(hash))
+                    .add(transaction_flows::Column::ExecuteTxHash.eq(hash)),
+            )
+            .limit(limit)
+            .all(&self.primary)
+            .await?;
+
+        let mut condition =
+            Condition::any().add(source_transactions::Column::TransactionHash.eq(hash));
+        for flow in &flows {
+            condition = condition.add(
+                Condition::all()
+                    .add(source_transactions::Column::ChainId.eq(flow.chain_id))
+                    .add(source_transactions::Column::Nonce.eq(flow.nonce)),
+            );
+        }
+
+        let source_transactions = source_transactions::Entity::find()
+            .filter(condition)
+            .order_by_desc(source_transactions::Column::Timestamp)
+            .limit(limit)
+            .all(&self.primary)
+            .await?;
+
+        self.with_flows(source_transactions).await
+    }
+
+    /// Pairs source transactions with their flows, dropping those that have none
+    async fn with_flows(
+        &self,
+        source_transactions: Vec<source_transactions::Model>,
+    ) -> Result<Vec<(source_transactions::Model, transaction_flows::Model)>, DbErr> {
         if source_transactions.is_empty() {
             return Ok(Vec::new());
         }
@@ -295,12 +796,6 @@ impl DbClient {
                 results.push((source_tx, flow.clone()));
             }
         }
-
-        debug!(
-            search_query = q,
-            found_count = results.len(),
-            "Quick search completed"
-        );
         Ok(results)
     }
 
@@ -348,6 +843,29 @@ impl DbClient {
         Ok(None)
     }
 
+    /// Whether a Solana transaction has already been stored, either as the
+    /// source of a bridge transaction or as the execution of a flow
+    #[instrument(skip(self), fields(signature = signature))]
+    pub async fn is_signature_processed(&self, signature: &str) -> eyre::Result<bool> {
+        validate_solana_signature(signature)?;
+
+        let source_tx = source_transactions::Entity::find()
+            .filter(source_transactions::Column::TransactionHash.eq(signature))
+            .one(&self.primary)
+            .await?;
+
+        if source_tx.is_some() {
+            return Ok(true);
+        }
+
+        let flow = transaction_flows::Entity::find()
+            .filter(transaction_flows::Column::ExecuteTxHash.eq(signature))
+            .one(&self.primary)
+            .await?;
+
+        Ok(flow.is_some())
+    }
+
     #[instrument(skip(self), fields(request_count = l1_transactions.len()))]
     pub async fn batch_find_l2_transactions_by_l1_transactions(
         &self,
@@ -445,18 +963,28 @@ impl DbClient {
     }
 
     #[instrument(skip(self), fields(user_address = %user_address))]
+    /// A page of the deposits made by `user_address`, newest first, with the
+    /// flow of each deposit if there is one
     pub async fn fetch_user_deposits(
         &self,
         user_address: &str,
-    ) -> Result<Vec<(source_transactions::Model, Option<transaction_flows::Model>)>, DbErr> {
-        // First get all deposit transactions for the user
-        let source_transactions = source_transactions::Entity::find()
-            .filter(
-                Condition::all()
-                    .add(source_transactions::Column::TransactionType.eq("Deposit"))
-                    .add(source_transactions::Column::L1Address.eq(user_address)),
-            )
+        params: FetchBridgeTransactionsParams,
+    ) -> Result<Vec<(source_transactions::Model, Option<transaction_flows::Model>)>, BridgeQueryError>
+    {
+        let query_builder = source_transactions::Entity::find().filter(
+            Condition::all()
+                .add(source_transactions::Column::TransactionType.eq("Deposit"))
+                .add(source_transactions::Column::L1Address.eq(user_address)),
+        );
+
+        let query_builder = self._apply_bridge_cursor(query_builder, &params).await?;
+
+        let source_transactions = query_builder
             .order_by_desc(source_transactions::Column::Timestamp)
+            .order_by_desc(source_transactions::Column::ChainId)
+            .order_by_desc(source_transactions::Column::Nonce)
+            .order_by_desc(source_transactions::Column::Id)
+            .limit(params.items_count)
             .all(&self.primary)
             .await?;
 
@@ -504,50 +1032,1380 @@ impl DbClient {
         Ok(results)
     }
 
-    #[instrument(skip(self), fields(l2_tx_hash = l2_tx_hash, destination_chain_id = destination_chain_id))]
-    pub async fn find_execute_tx_hash_for_l2_withdraw(
+    /// Every bridge transaction of any type where `address` is either the L1
+    /// or the Twine side, newest first, with its flow if there is one
+    #[instrument(skip(self), fields(address = %address, params = ?params))]
+    pub async fn fetch_address_history(
         &self,
-        l2_tx_hash: &str,
-        destination_chain_id: i64,
-    ) -> Result<Option<transaction_flows::Model>, DbErr> {
-        // First find the source transaction (L2 withdraw)
-        let source_tx: Option<source_transactions::Model> = source_transactions::Entity::find()
-            .filter(
-                Condition::all()
-                    .add(source_transactions::Column::TransactionHash.eq(l2_tx_hash))
-                    .add(source_transactions::Column::TransactionType.eq("Withdraw"))
-                    .add(source_transactions::Column::DestinationChainId.eq(destination_chain_id)),
-            )
-            .one(&self.primary)
-            .await?;
+        address: &str,
+        params: FetchBridgeTransactionsParams,
+    ) -> Result<Vec<(source_transactions::Model, Option<transaction_flows::Model>)>, BridgeQueryError>
+    {
+        let address = normalize_address(address);
+        // Stored EVM addresses may be checksummed, so compare them lowercased
+        let is_evm = address.starts_with("0x");
+        let matches_address = |column: source_transactions::Column| {
+            let column = Expr::col((source_transactions::Entity, column));
+            if is_evm {
+                Expr::expr(Func::lower(column)).eq(address.clone())
+            } else {
+                column.eq(address.clone())
+            }
+        };
 
-        if let Some(source_model) = source_tx {
-            // Find the execute transaction in transaction_flows using destination_chain_id and nonce
-            let execute_tx = transaction_flows::Entity::find()
-                .filter(
-                    Condition::all()
-                        .add(transaction_flows::Column::ChainId.eq(destination_chain_id))
-                        .add(transaction_flows::Column::Nonce.eq(source_model.nonce)),
-                )
-                .one(&self.primary)
-                .await?;
+        let query_builder = source_transactions::Entity::find().filter(
+            Condition::any()
+                .add(matches_address(source_transactions::Column::L1Address))
+                .add(matches_address(source_transactions::Column::TwineAddress)),
+        );
 
-            if let Some(execute_model) = execute_tx {
-                debug!(
-                    l2_tx_hash = l2_tx_hash,
-                    destination_chain_id = destination_chain_id,
-                    execute_tx_hash = execute_model.execute_tx_hash,
-                    "Found execute transaction for L2 withdraw"
-                );
-                return Ok(Some(execute_model));
-            }
+        let query_builder = self._apply_bridge_cursor(query_builder, &params).await?;
+
+        let source_transactions = query_builder
+            .order_by_desc(source_transactions::Column::Timestamp)
+            .order_by_desc(source_transactions::Column::ChainId)
+            .order_by_desc(source_transactions::Column::Nonce)
+            .order_by_desc(source_transactions::Column::Id)
+            .limit(params.items_count)
+            .all(&self.primary)
+            .await?;
+
+        if source_transactions.is_empty() {
+            return Ok(Vec::new());
         }
 
-        debug!(
-            l2_tx_hash = l2_tx_hash,
-            destination_chain_id = destination_chain_id,
-            "No execute transaction found for L2 withdraw"
-        );
-        Ok(None)
+        // Build a condition to get all matching transaction flows in one query
+        let mut flow_condition = Condition::any();
+        for source_tx in &source_transactions {
+            flow_condition = flow_condition.add(
+                Condition::all()
+                    .add(transaction_flows::Column::ChainId.eq(source_tx.chain_id))
+                    .add(transaction_flows::Column::Nonce.eq(source_tx.nonce)),
+            );
+        }
+
+        let transaction_flows = transaction_flows::Entity::find()
+            .filter(flow_condition)
+            .all(&self.primary)
+            .await?;
+
+        let mut flow_map = HashMap::new();
+        for flow in transaction_flows {
+            flow_map.insert((flow.chain_id, flow.nonce), flow);
+        }
+
+        let results: Vec<_> = source_transactions
+            .into_iter()
+            .map(|source_tx| {
+                let flow = flow_map
+                    .get(&(source_tx.chain_id, source_tx.nonce))
+                    .cloned();
+                (source_tx, flow)
+            })
+            .collect();
+
+        debug!(count = results.len(), "Fetched address history");
+
+        Ok(results)
+    }
+
+    /// Deposits older than `sla` that have not been handled on Twine yet, oldest first
+    #[instrument(skip(self), fields(sla_secs = sla.as_secs(), limit = limit))]
+    pub async fn fetch_overdue_deposits(
+        &self,
+        sla: Duration,
+        limit: u64,
+    ) -> Result<Vec<(source_transactions::Model, Option<transaction_flows::Model>)>, DbErr> {
+        let source_transactions = source_transactions::Entity::find()
+            .filter(
+                Condition::all()
+                    .add(source_transactions::Column::TransactionType.eq("Deposit"))
+                    .add(Expr::cust_with_values(
+                        "source_transactions.timestamp < NOW() - make_interval(secs => $1)",
+                        [sla.as_secs_f64()],
+                    ))
+                    .add(Expr::cust(
+                        "NOT EXISTS (
+                            SELECT 1 FROM transaction_flows tf
+                            WHERE tf.chain_id = source_transactions.chain_id
+                              AND tf.nonce = source_transactions.nonce
+                              AND tf.is_handled = TRUE
+                        )",
+                    )),
+            )
+            .order_by_asc(source_transactions::Column::Timestamp)
+            .limit(limit)
+            .all(&self.primary)
+            .await?;
+
+        if source_transactions.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Build a condition to get all matching transaction flows in one query
+        let mut flow_condition = Condition::any();
+        for source_tx in &source_transactions {
+            flow_condition = flow_condition.add(
+                Condition::all()
+                    .add(transaction_flows::Column::ChainId.eq(source_tx.chain_id))
+                    .add(transaction_flows::Column::Nonce.eq(source_tx.nonce)),
+            );
+        }
+
+        let transaction_flows = transaction_flows::Entity::find()
+            .filter(flow_condition)
+            .all(&self.primary)
+            .await?;
+
+        // Create a map for quick lookup
+        let mut flow_map = std::collections::HashMap::new();
+        for flow in transaction_flows {
+            flow_map.insert((flow.chain_id, flow.nonce), flow);
+        }
+
+        // A flow may exist without being handled (e.g. a refund seen first)
+        let mut results = Vec::new();
+        for source_tx in source_transactions {
+            let flow = flow_map
+                .get(&(source_tx.chain_id, source_tx.nonce))
+                .cloned();
+            results.push((source_tx, flow));
+        }
+
+        debug!(overdue_count = results.len(), "Fetched overdue deposits");
+
+        Ok(results)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn fetch_bridge_stats(&self) -> Result<BridgeStats, DbErr> {
+        let count_of = |transaction_type: &'static str| {
+            source_transactions::Entity::find()
+                .filter(source_transactions::Column::TransactionType.eq(transaction_type))
+                .count(&self.primary)
+        };
+
+        let total_deposits = count_of("Deposit").await?;
+        let total_withdraws = count_of("Withdraw").await?;
+        let total_forced_withdraws = count_of("ForcedWithdraw").await?;
+
+        let pending_deposits = source_transactions::Entity::find()
+            .filter(
+                Condition::all()
+                    .add(source_transactions::Column::TransactionType.eq("Deposit"))
+                    .add(Expr::cust(
+                        "NOT EXISTS (
+                            SELECT 1 FROM transaction_flows tf
+                            WHERE tf.chain_id = source_transactions.chain_id
+                              AND tf.nonce = source_transactions.nonce
+                              AND tf.is_handled = TRUE
+                        )",
+                    )),
+            )
+            .count(&self.primary)
+            .await?;
+
+        Ok(BridgeStats {
+            total_deposits,
+            total_withdraws,
+            total_forced_withdraws,
+            pending_deposits,
+        })
+    }
+
+    /// One page of the bridge transactions `chain_id` submitted in `from..to`
+    /// with their flows, in nonce order. Pass the last nonce of a page as
+    /// `after_nonce` to get the next one.
+    #[instrument(skip(self))]
+    pub async fn fetch_bridge_export_page(
+        &self,
+        chain_id: i64,
+        from: DateTimeWithTimeZone,
+        to: DateTimeWithTimeZone,
+        after_nonce: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<(source_transactions::Model, Option<transaction_flows::Model>)>, DbErr> {
+        let mut condition = Condition::all()
+            .add(source_transactions::Column::ChainId.eq(chain_id))
+            .add(source_transactions::Column::Timestamp.gte(from))
+            .add(source_transactions::Column::Timestamp.lt(to));
+        if let Some(nonce) = after_nonce {
+            condition = condition.add(source_transactions::Column::Nonce.gt(nonce));
+        }
+
+        let source_transactions = source_transactions::Entity::find()
+            .filter(condition)
+            .order_by_asc(source_transactions::Column::Nonce)
+            .limit(limit)
+            .all(&self.primary)
+            .await?;
+
+        if source_transactions.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let nonces: Vec<i64> = source_transactions.iter().map(|tx| tx.nonce).collect();
+        let mut flows: HashMap<i64, transaction_flows::Model> = transaction_flows::Entity::find()
+            .filter(
+                Condition::all()
+                    .add(transaction_flows::Column::ChainId.eq(chain_id))
+                    .add(transaction_flows::Column::Nonce.is_in(nonces)),
+            )
+            .all(&self.primary)
+            .await?
+            .into_iter()
+            .map(|flow| (flow.nonce, flow))
+            .collect();
+
+        Ok(source_transactions
+            .into_iter()
+            .map(|tx| {
+                let flow = flows.remove(&tx.nonce);
+                (tx, flow)
+            })
+            .collect())
+    }
+
+    /// Per source chain, deposits submitted in `from..to` and how many of them
+    /// were handled on Twine
+    #[instrument(skip(self))]
+    pub async fn reconciliation_report(
+        &self,
+        from: DateTimeWithTimeZone,
+        to: DateTimeWithTimeZone,
+    ) -> Result<Vec<ChainReconciliation>, DbErr> {
+        let rows = self
+            .primary
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT st.chain_id,
+                    COUNT(*) AS deposits,
+                    COUNT(tf.id) FILTER (WHERE tf.is_handled) AS handled
+                FROM source_transactions st
+                LEFT JOIN transaction_flows tf ON tf.chain_id = st.chain_id AND tf.nonce = st.nonce
+                WHERE st.transaction_type = 'Deposit'::transaction_type_enum
+                  AND st.timestamp >= $1
+                  AND st.timestamp < $2
+                GROUP BY st.chain_id
+                ORDER BY st.chain_id
+                "#,
+                [from.into(), to.into()],
+            ))
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(ChainReconciliation {
+                    chain_id: row.try_get("", "chain_id")?,
+                    deposits: row.try_get::<i64>("", "deposits")? as u64,
+                    handled: row.try_get::<i64>("", "handled")? as u64,
+                })
+            })
+            .collect()
+    }
+
+    /// Gaps and out of order nonces in the source transactions of `chain_id`,
+    /// signs of dropped events or of another chain sharing the chain id.
+    /// Nonces below the lowest one indexed are not reported as missing.
+    #[instrument(skip(self), fields(chain_id = chain_id))]
+    pub async fn detect_nonce_anomalies(&self, chain_id: i64) -> Result<Vec<NonceAnomaly>, DbErr> {
+        let gaps = self
+            .primary
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT previous_nonce, nonce
+                FROM (
+                    SELECT nonce, LAG(nonce) OVER (ORDER BY nonce) AS previous_nonce
+                    FROM source_transactions
+                    WHERE chain_id = $1
+                ) ordered
+                WHERE nonce - previous_nonce > 1
+                ORDER BY nonce
+                LIMIT $2
+                "#,
+                [chain_id.into(), MAX_NONCE_ANOMALIES.into()],
+            ))
+            .await?;
+
+        // Within a block nonces are ascending by the sort itself, so only a
+        // lower nonce in a later block is flagged
+        let out_of_order = self
+            .primary
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT nonce, block_number, previous_nonce, previous_block_number
+                FROM (
+                    SELECT nonce,
+                        block_number,
+                        LAG(nonce) OVER (ORDER BY block_number, nonce) AS previous_nonce,
+                        LAG(block_number) OVER (ORDER BY block_number, nonce) AS previous_block_number
+                    FROM source_transactions
+                    WHERE chain_id = $1
+                ) ordered
+                WHERE nonce < previous_nonce
+                ORDER BY block_number, nonce
+                LIMIT $2
+                "#,
+                [chain_id.into(), MAX_NONCE_ANOMALIES.into()],
+            ))
+            .await?;
+
+        let mut anomalies = Vec::with_capacity(gaps.len() + out_of_order.len());
+        for row in &gaps {
+            anomalies.push(NonceAnomaly::Gap {
+                after: row.try_get("", "previous_nonce")?,
+                before: row.try_get("", "nonce")?,
+            });
+        }
+        for row in &out_of_order {
+            anomalies.push(NonceAnomaly::OutOfOrder {
+                nonce: row.try_get("", "nonce")?,
+                block_number: row.try_get("", "block_number")?,
+                previous_nonce: row.try_get("", "previous_nonce")?,
+                previous_block_number: row.try_get("", "previous_block_number")?,
+            });
+        }
+
+        if !anomalies.is_empty() {
+            warn!(
+                chain_id,
+                gaps = gaps.len(),
+                out_of_order = out_of_order.len(),
+                "Nonce anomalies found"
+            );
+        }
+        Ok(anomalies)
+    }
+
+    /// The `n` bridge transactions of `tx_type` submitted in `from..to` that
+    /// moved the largest amounts, largest first. `amount` is a NUMERIC column,
+    /// so amounts compare by value rather than by digits.
+    #[instrument(skip(self))]
+    pub async fn top_transactions_by_amount(
+        &self,
+        tx_type: TransactionTypeEnum,
+        from: DateTimeWithTimeZone,
+        to: DateTimeWithTimeZone,
+        n: u64,
+    ) -> Result<Vec<source_transactions::Model>, DbErr> {
+        source_transactions::Entity::find()
+            .filter(source_transactions::Column::TransactionType.eq(tx_type))
+            .filter(source_transactions::Column::Timestamp.gte(from))
+            .filter(source_transactions::Column::Timestamp.lt(to))
+            .order_by_desc(source_transactions::Column::Amount)
+            .order_by_desc(source_transactions::Column::Id)
+            .limit(n)
+            .all(&self.primary)
+            .await
+    }
+
+    /// Distinct `(l1_token, l2_token)` pairs bridged so far, most recently
+    /// used first
+    #[instrument(skip(self))]
+    pub async fn distinct_tokens(&self) -> Result<Vec<BridgedToken>, DbErr> {
+        let rows = self
+            .primary
+            .query_all(Statement::from_string(
+                DbBackend::Postgres,
+                r#"
+                SELECT st.l1_token,
+                    st.l2_token,
+                    MIN(st.timestamp) AS first_seen,
+                    MAX(st.timestamp) AS last_seen,
+                    COUNT(*) AS transfers,
+                    MAX(ut.symbol) AS symbol
+                FROM source_transactions st
+                LEFT JOIN uniswap_tokens ut ON LOWER(ut.address) = LOWER(st.l2_token)
+                GROUP BY st.l1_token, st.l2_token
+                ORDER BY last_seen DESC NULLS LAST, st.l1_token, st.l2_token
+                "#,
+            ))
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(BridgedToken {
+                    l1_token: row.try_get("", "l1_token")?,
+                    l2_token: row.try_get("", "l2_token")?,
+                    symbol: row.try_get("", "symbol")?,
+                    first_seen: row.try_get("", "first_seen")?,
+                    last_seen: row.try_get("", "last_seen")?,
+                    transfers: row.try_get::<i64>("", "transfers")? as u64,
+                })
+            })
+            .collect()
+    }
+
+    /// Deposits from `chain_id` that have no transaction_flows row at all
+    #[instrument(skip(self), fields(chain_id = chain_id))]
+    pub async fn find_deposits_missing_flows(
+        &self,
+        chain_id: i64,
+    ) -> Result<Vec<source_transactions::Model>, DbErr> {
+        source_transactions::Entity::find()
+            .filter(
+                Condition::all()
+                    .add(source_transactions::Column::TransactionType.eq("Deposit"))
+                    .add(source_transactions::Column::ChainId.eq(chain_id))
+                    .add(Expr::cust(
+                        "NOT EXISTS (
+                            SELECT 1 FROM transaction_flows tf
+                            WHERE tf.chain_id = source_transactions.chain_id
+                              AND tf.nonce = source_transactions.nonce
+                        )",
+                    )),
+            )
+            .order_by_asc(source_transactions::Column::Nonce)
+            .all(&self.primary)
+            .await
+    }
+
+    /// Inserts the flows from `candidates` that belong to deposits of
+    /// `chain_id` which are missing their flow. `candidates` are usually the
+    /// operations produced by re-indexing a range of L2 blocks. Returns the
+    /// number of flows inserted.
+    #[instrument(skip(self, candidates), fields(chain_id = chain_id, candidate_count = candidates.len()))]
+    pub async fn reconcile_missing_flows(
+        &self,
+        chain_id: i64,
+        candidates: Vec<DbOperations>,
+    ) -> eyre::Result<usize> {
+        let missing: HashSet<(i64, i64)> = self
+            .find_deposits_missing_flows(chain_id)
+            .await?
+            .into_iter()
+            .map(|deposit| (deposit.chain_id, deposit.nonce))
+            .collect();
+
+        if missing.is_empty() {
+            info!("No deposits are missing their transaction flow");
+            return Ok(0);
+        }
+
+        let recovered: Vec<transaction_flows::ActiveModel> = candidates
+            .into_iter()
+            .filter_map(|operation| match operation.ordering_key() {
+                OrderingKey::Bridge { chain_id, nonce } if missing.contains(&(chain_id, nonce)) => {
+                    match operation {
+                        DbOperations::BridgeDestinationTransactions(model) => Some(model),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+            .collect();
+
+        info!(
+            missing = missing.len(),
+            recovered = recovered.len(),
+            "Reconciling missing transaction flows"
+        );
+
+        if recovered.is_empty() {
+            return Ok(0);
+        }
+
+        let count = recovered.len();
+        let txn = self.primary.begin().await?;
+        self.bulk_insert_destination_transactions(recovered, &txn)
+            .await?;
+        txn.commit().await?;
+
+        Ok(count)
+    }
+
+    #[instrument(skip(self), fields(l2_tx_hash = l2_tx_hash, destination_chain_id = destination_chain_id))]
+    pub async fn find_execute_tx_hash_for_l2_withdraw(
+        &self,
+        l2_tx_hash: &str,
+        destination_chain_id: i64,
+    ) -> Result<Option<transaction_flows::Model>, DbErr> {
+        // First find the source transaction (L2 withdraw)
+        let source_tx: Option<source_transactions::Model> = source_transactions::Entity::find()
+            .filter(
+                Condition::all()
+                    .add(source_transactions::Column::TransactionHash.eq(l2_tx_hash))
+                    .add(source_transactions::Column::TransactionType.eq("Withdraw"))
+                    .add(source_transactions::Column::DestinationChainId.eq(destination_chain_id)),
+            )
+            .one(&self.primary)
+            .await?;
+
+        if let Some(source_model) = source_tx {
+            // Find the execute transaction in transaction_flows using destination_chain_id and nonce
+            let execute_tx = transaction_flows::Entity::find()
+                .filter(
+                    Condition::all()
+                        .add(transaction_flows::Column::ChainId.eq(destination_chain_id))
+                        .add(transaction_flows::Column::Nonce.eq(source_model.nonce)),
+                )
+                .one(&self.primary)
+                .await?;
+
+            if let Some(execute_model) = execute_tx {
+                debug!(
+                    l2_tx_hash = l2_tx_hash,
+                    destination_chain_id = destination_chain_id,
+                    execute_tx_hash = execute_model.execute_tx_hash,
+                    "Found execute transaction for L2 withdraw"
+                );
+                return Ok(Some(execute_model));
+            }
+        }
+
+        debug!(
+            l2_tx_hash = l2_tx_hash,
+            destination_chain_id = destination_chain_id,
+            "No execute transaction found for L2 withdraw"
+        );
+        Ok(None)
+    }
+
+    /// Fixes `is_completed` on flows written while the trigger was missing or
+    /// disabled. Returns the number of rows changed.
+    #[instrument(skip(self))]
+    pub async fn recompute_is_completed(&self) -> Result<u64, DbErr> {
+        let result = self
+            .primary
+            .execute(Statement::from_string(
+                DbBackend::Postgres,
+                RECOMPUTE_IS_COMPLETED_SQL,
+            ))
+            .await?;
+
+        info!(
+            rows = result.rows_affected(),
+            "Recomputed transaction_flows.is_completed"
+        );
+        Ok(result.rows_affected())
+    }
+
+    /// Deposit submitted in the L1 transaction `tx_hash`, with its flow once
+    /// Twine has handled it
+    #[instrument(skip(self))]
+    pub async fn find_deposit_by_tx_hash(
+        &self,
+        tx_hash: &str,
+    ) -> Result<Option<(source_transactions::Model, Option<transaction_flows::Model>)>, DbErr> {
+        let Some(source_tx) = source_transactions::Entity::find()
+            .filter(
+                Condition::all()
+                    .add(source_transactions::Column::TransactionHash.eq(tx_hash))
+                    .add(
+                        source_transactions::Column::TransactionType
+                            .eq(TransactionTypeEnum::Deposit),
+                    ),
+            )
+            .one(&self.primary)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let flow = transaction_flows::Entity::find()
+            .filter(
+                Condition::all()
+                    .add(transaction_flows::Column::ChainId.eq(source_tx.chain_id))
+                    .add(transaction_flows::Column::Nonce.eq(source_tx.nonce)),
+            )
+            .one(&self.primary)
+            .await?;
+
+        Ok(Some((source_tx, flow)))
+    }
+
+    /// Timeline of a withdraw or forced withdraw, `None` when no such withdrawal exists
+    #[instrument(skip(self))]
+    pub async fn withdrawal_timeline(
+        &self,
+        chain_id: i64,
+        nonce: i64,
+    ) -> Result<Option<WithdrawalTimeline>, DbErr> {
+        let Some(source_tx) = source_transactions::Entity::find()
+            .filter(
+                Condition::all()
+                    .add(source_transactions::Column::ChainId.eq(chain_id))
+                    .add(source_transactions::Column::Nonce.eq(nonce))
+                    .add(source_transactions::Column::TransactionType.is_in([
+                        TransactionTypeEnum::Withdraw,
+                        TransactionTypeEnum::ForcedWithdraw,
+                    ])),
+            )
+            .one(&self.primary)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let flow = transaction_flows::Entity::find()
+            .filter(
+                Condition::all()
+                    .add(transaction_flows::Column::ChainId.eq(chain_id))
+                    .add(transaction_flows::Column::Nonce.eq(nonce)),
+            )
+            .one(&self.primary)
+            .await?;
+
+        let is_completed = is_flow_completed(&source_tx.transaction_type, flow.as_ref());
+
+        Ok(Some(WithdrawalTimeline {
+            transaction_type: source_tx.transaction_type,
+            source_tx_hash: source_tx.transaction_hash,
+            submitted_at: source_tx.timestamp,
+            handle_tx_hash: flow.as_ref().and_then(|flow| flow.handle_tx_hash.clone()),
+            handled_at: flow.as_ref().and_then(|flow| flow.handled_at),
+            execute_tx_hash: flow.as_ref().and_then(|flow| flow.execute_tx_hash.clone()),
+            executed_at: flow.as_ref().and_then(|flow| flow.executed_at),
+            is_completed,
+        }))
+    }
+}
+
+/// Conflict update for an execute column of `transaction_flows` that takes
+/// the incoming value only when no execute is stored yet or the incoming
+/// one is at a higher or equal block
+fn later_execute_wins(
+    column: transaction_flows::Column,
+) -> (transaction_flows::Column, SimpleExpr) {
+    let name = column.as_str();
+    (
+        column,
+        Expr::cust(format!(
+            "CASE WHEN transaction_flows.execute_block_number IS NULL \
+                OR EXCLUDED.execute_block_number >= transaction_flows.execute_block_number \
+             THEN EXCLUDED.{name} ELSE transaction_flows.{name} END"
+        )),
+    )
+}
+
+/// Where a deposit stands on Twine: `Pending` until handled, then `Handled`,
+/// or `Failed` until the failed deposit is refunded on L1 and `Refunded` after
+pub fn deposit_flow_status(flow: Option<&transaction_flows::Model>) -> &'static str {
+    let Some(flow) = flow else {
+        return "Pending";
+    };
+    match flow.handle_status {
+        Some(status) if status == HandleStatus::Success.as_i16() => "Handled",
+        Some(status) if status == HandleStatus::Failed.as_i16() => {
+            if flow.is_executed.unwrap_or(false) {
+                "Refunded"
+            } else {
+                "Failed"
+            }
+        }
+        _ => "Pending",
+    }
+}
+
+/// Whether a bridge transaction has finished. Forced withdrawals are only
+/// complete once executed on L1, everything else once handled on Twine.
+pub fn is_flow_completed(
+    transaction_type: &TransactionTypeEnum,
+    flow: Option<&transaction_flows::Model>,
+) -> bool {
+    match (transaction_type, flow) {
+        (_, None) => false,
+        (TransactionTypeEnum::ForcedWithdraw, Some(flow)) => {
+            flow.handled_at.is_some() && flow.executed_at.is_some()
+        }
+        (_, Some(flow)) => flow.handled_at.is_some(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{ActiveValue::Set, QueryTrait, sqlx::types::chrono};
+
+    #[test]
+    fn solana_instructions_map_to_their_token_standard() {
+        assert_eq!(
+            token_standard_for_solana_instruction("NativeTokenDeposit"),
+            Some(TokenStandardEnum::Native)
+        );
+        assert_eq!(
+            token_standard_for_solana_instruction("SplTokensDeposit"),
+            Some(TokenStandardEnum::Spl)
+        );
+        assert_eq!(token_standard_for_solana_instruction("CommitBatch"), None);
+    }
+
+    #[test]
+    fn cursor_needs_both_chain_and_nonce() {
+        assert_eq!(
+            BridgeCursor::from_parts(Some(1), Some(7)),
+            Some(BridgeCursor {
+                chain_id: 1,
+                nonce: 7
+            })
+        );
+        assert_eq!(BridgeCursor::from_parts(Some(1), None), None);
+        assert_eq!(BridgeCursor::from_parts(None, Some(7)), None);
+    }
+
+    #[test]
+    fn page_resumes_strictly_after_the_cursor_row() {
+        let timestamp = "2025-10-16T12:00:00+00:00".parse().unwrap();
+        let cursor = BridgeCursor {
+            chain_id: 1,
+            nonce: 7,
+        };
+        let sql = source_transactions::Entity::find()
+            .filter(after_bridge_cursor(Some(timestamp), cursor))
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        // Older rows, then same-instant rows of lower chains, then lower nonces
+        // of the cursor's chain, never the cursor row itself
+        for condition in [
+            r#""source_transactions"."timestamp" < '2025-10-16 12:00:00 +00:00'"#,
+            r#""source_transactions"."chain_id" < 1"#,
+            r#""source_transactions"."chain_id" = 1 AND "source_transactions"."nonce" < 7"#,
+        ] {
+            assert!(sql.contains(condition), "{sql}");
+        }
+        assert!(!sql.contains(r#""nonce" <= 7"#), "{sql}");
+    }
+
+    #[test]
+    fn amount_accepts_non_negative_integers() {
+        assert_eq!(parse_amount("0"), Ok(Decimal::ZERO));
+        assert_eq!(parse_amount("1000"), Ok(Decimal::from(1000)));
+        assert_eq!(parse_amount("007"), Ok(Decimal::from(7)));
+    }
+
+    #[test]
+    fn amount_rejects_anything_but_digits() {
+        for raw in ["", "-1", "1.5", "0x10", " 1", "1e18"] {
+            let reason = parse_amount(raw).unwrap_err();
+            assert!(reason.contains("not a non-negative integer"), "{reason}");
+        }
+    }
+
+    #[test]
+    fn amount_rejects_more_digits_than_the_column_holds() {
+        let reason = parse_amount(&"9".repeat(AMOUNT_MAX_DIGITS + 1)).unwrap_err();
+        assert!(reason.contains("79 digits"), "{reason}");
+    }
+
+    #[test]
+    fn amount_beyond_decimal_range_is_rejected_not_truncated() {
+        assert!(parse_amount(&"9".repeat(40)).is_err());
+    }
+
+    #[test]
+    fn evm_tokens_map_to_their_token_standard() {
+        assert_eq!(
+            token_standard_for_l1_token("0x0000000000000000000000000000000000000000"),
+            TokenStandardEnum::Native
+        );
+        assert_eq!(
+            token_standard_for_l1_token("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"),
+            TokenStandardEnum::Erc20
+        );
+    }
+
+    fn solana_signature(byte: u8) -> String {
+        bs58::encode([byte; SOLANA_SIGNATURE_LEN]).into_string()
+    }
+
+    #[test]
+    fn solana_signature_is_64_bytes_of_base58() {
+        assert!(validate_solana_signature(&solana_signature(7)).is_ok());
+        // 0 is not in the base58 alphabet
+        assert!(validate_solana_signature("0xdeadbeef").is_err());
+        assert!(validate_solana_signature(&bs58::encode([7; 32]).into_string()).is_err());
+        assert!(validate_solana_signature("").is_err());
+    }
+
+    fn execute(nonce: i64, block_number: i64) -> transaction_flows::ActiveModel {
+        transaction_flows::ActiveModel {
+            chain_id: Set(1),
+            nonce: Set(nonce),
+            execute_tx_hash: Set(Some(format!("0xexecute{}", block_number))),
+            execute_block_number: Set(Some(block_number)),
+            is_executed: Set(Some(true)),
+            ..Default::default()
+        }
+    }
+
+    async fn write_executes(client: &DbClient, models: Vec<transaction_flows::ActiveModel>) {
+        let txn = client.primary.begin().await.unwrap();
+        client
+            .bulk_insert_destination_transactions(models, &txn)
+            .await
+            .unwrap();
+        txn.commit().await.unwrap();
+    }
+
+    async fn stored_execute(client: &DbClient) -> (Option<i64>, Option<String>) {
+        let flow = transaction_flows::Entity::find()
+            .one(&client.primary)
+            .await
+            .unwrap()
+            .unwrap();
+        (flow.execute_block_number, flow.execute_tx_hash)
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn lower_block_execute_does_not_replace_a_higher_one() {
+        let client = crate::test_db::client(false).await;
+        write_executes(&client, vec![execute(7, 120)]).await;
+
+        write_executes(&client, vec![execute(7, 110)]).await;
+
+        assert_eq!(
+            stored_execute(&client).await,
+            (Some(120), Some("0xexecute120".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn execute_on_the_canonical_branch_lands_after_a_rollback() {
+        let client = crate::test_db::client(false).await;
+        write_executes(&client, vec![execute(7, 120)]).await;
+
+        client.rollback_chain_from(1, 115).await.unwrap();
+        write_executes(&client, vec![execute(7, 116)]).await;
+
+        assert_eq!(
+            stored_execute(&client).await,
+            (Some(116), Some("0xexecute116".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn update_bumps_updated_at() {
+        let client = crate::test_db::client(false).await;
+        write_executes(&client, vec![execute(7, 120)]).await;
+        let before = transaction_flows::Entity::find()
+            .one(&client.primary)
+            .await
+            .unwrap()
+            .unwrap()
+            .updated_at;
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        // Leaves updated_at out, as a code path that forgets it would
+        transaction_flows::Entity::update_many()
+            .col_expr(transaction_flows::Column::IsExecuted, Expr::value(false))
+            .exec(&client.primary)
+            .await
+            .unwrap();
+
+        let after = transaction_flows::Entity::find()
+            .one(&client.primary)
+            .await
+            .unwrap()
+            .unwrap()
+            .updated_at;
+        assert!(after.is_some());
+        assert!(after > before, "{before:?} -> {after:?}");
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn flow_lookup_is_answered_from_the_covering_index() {
+        let client = crate::test_db::client(false).await;
+        client
+            .primary
+            .execute_unprepared(
+                "INSERT INTO transaction_flows (chain_id, nonce, is_handled, is_executed)
+                 SELECT chain_id, nonce, true, nonce % 2 = 0
+                 FROM generate_series(1, 3) AS chain_id, generate_series(1, 5000) AS nonce",
+            )
+            .await
+            .unwrap();
+        // Outside a transaction, so the visibility map allows index-only scans
+        client
+            .primary
+            .execute_unprepared("VACUUM ANALYZE transaction_flows")
+            .await
+            .unwrap();
+
+        let plan: Vec<String> = client
+            .primary
+            .query_all(Statement::from_string(
+                DbBackend::Postgres,
+                "EXPLAIN SELECT is_handled, is_executed, is_completed, execute_block_number
+                 FROM transaction_flows WHERE chain_id = 2 AND nonce = 2500",
+            ))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| row.try_get::<String>("", "QUERY PLAN").unwrap())
+            .collect();
+        let plan = plan.join("\n");
+
+        assert!(
+            plan.contains("Index Only Scan using idx_transaction_flows_chain_nonce_covering"),
+            "{plan}"
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn signature_is_processed_once_its_execute_is_stored() {
+        let client = crate::test_db::client(false).await;
+        write_executes(
+            &client,
+            vec![transaction_flows::ActiveModel {
+                execute_tx_hash: Set(Some(solana_signature(1))),
+                ..execute(7, 120)
+            }],
+        )
+        .await;
+
+        assert!(
+            client
+                .is_signature_processed(&solana_signature(1))
+                .await
+                .unwrap()
+        );
+        assert!(
+            !client
+                .is_signature_processed(&solana_signature(2))
+                .await
+                .unwrap()
+        );
+        assert!(client.is_signature_processed("0xexecute120").await.is_err());
+    }
+
+    /// A source transaction on chain 1 sent `age` ago
+    fn source(
+        nonce: i64,
+        transaction_type: TransactionTypeEnum,
+        age: Duration,
+    ) -> source_transactions::ActiveModel {
+        let sent_at = chrono::Utc::now() - chrono::Duration::from_std(age).unwrap();
+        source_transactions::ActiveModel {
+            chain_id: Set(1),
+            nonce: Set(nonce),
+            transaction_type: Set(transaction_type),
+            block_number: Set(100 + nonce),
+            l1_token: Set("0xl1".to_string()),
+            l2_token: Set("0xl2".to_string()),
+            l1_address: Set("0xfrom".to_string()),
+            twine_address: Set("0xto".to_string()),
+            amount: Set(Decimal::ONE),
+            transaction_hash: Set(Some(format!("0xsource{}", nonce))),
+            timestamp: Set(Some(sent_at.fixed_offset())),
+            ..Default::default()
+        }
+    }
+
+    fn deposit(nonce: i64, age: Duration) -> source_transactions::ActiveModel {
+        source(nonce, TransactionTypeEnum::Deposit, age)
+    }
+
+    async fn write_sources(client: &DbClient, models: Vec<source_transactions::ActiveModel>) {
+        let txn = client.primary.begin().await.unwrap();
+        client
+            .bulk_insert_source_transactions(models, &txn)
+            .await
+            .unwrap();
+        txn.commit().await.unwrap();
+    }
+
+    /// The L2 handle of the source transaction `nonce` on chain 1
+    fn handle(nonce: i64) -> transaction_flows::ActiveModel {
+        transaction_flows::ActiveModel {
+            chain_id: Set(1),
+            nonce: Set(nonce),
+            handle_tx_hash: Set(Some(format!("0xhandle{}", nonce))),
+            handle_block_number: Set(Some(200 + nonce)),
+            handled_at: Set(Some(chrono::Utc::now().fixed_offset())),
+            is_handled: Set(Some(true)),
+            ..Default::default()
+        }
+    }
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn only_old_unhandled_deposits_are_overdue() {
+        let client = crate::test_db::client(false).await;
+        write_sources(
+            &client,
+            vec![
+                deposit(1, 2 * HOUR),
+                deposit(2, Duration::from_secs(60)),
+                deposit(3, 2 * HOUR),
+            ],
+        )
+        .await;
+        write_executes(&client, vec![handle(3)]).await;
+
+        let overdue = client.fetch_overdue_deposits(HOUR, 10).await.unwrap();
+
+        let nonces: Vec<i64> = overdue.iter().map(|(source, _)| source.nonce).collect();
+        assert_eq!(nonces, vec![1]);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn reconciliation_creates_only_the_dropped_flows() {
+        let client = crate::test_db::client(false).await;
+        write_sources(
+            &client,
+            vec![deposit(1, HOUR), deposit(2, HOUR), deposit(3, HOUR)],
+        )
+        .await;
+        write_executes(&client, vec![handle(1)]).await;
+        // Re-indexing the L2 found the handles of 1 and 2, the one of 2 had
+        // been dropped. 9 has no deposit.
+        let candidates = [1, 2, 9]
+            .map(|nonce| DbOperations::BridgeDestinationTransactions(handle(nonce)))
+            .to_vec();
+
+        let inserted = client.reconcile_missing_flows(1, candidates).await.unwrap();
+
+        assert_eq!(inserted, 1);
+        let missing: Vec<i64> = client
+            .find_deposits_missing_flows(1)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|deposit| deposit.nonce)
+            .collect();
+        assert_eq!(missing, vec![3]);
+        let flows = transaction_flows::Entity::find()
+            .order_by_asc(transaction_flows::Column::Nonce)
+            .all(&client.primary)
+            .await
+            .unwrap();
+        let handled: Vec<(i64, Option<String>)> = flows
+            .into_iter()
+            .map(|flow| (flow.nonce, flow.handle_tx_hash))
+            .collect();
+        assert_eq!(
+            handled,
+            vec![
+                (1, Some("0xhandle1".to_string())),
+                (2, Some("0xhandle2".to_string())),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn withdrawal_timeline_follows_each_withdrawal_to_completion() {
+        let client = crate::test_db::client(false).await;
+        write_sources(
+            &client,
+            vec![
+                source(1, TransactionTypeEnum::Withdraw, HOUR),
+                source(2, TransactionTypeEnum::ForcedWithdraw, HOUR),
+                source(3, TransactionTypeEnum::ForcedWithdraw, HOUR),
+                deposit(4, HOUR),
+            ],
+        )
+        .await;
+        write_executes(&client, vec![handle(1), handle(2), handle(3)]).await;
+        write_executes(
+            &client,
+            vec![transaction_flows::ActiveModel {
+                executed_at: Set(Some(chrono::Utc::now().fixed_offset())),
+                ..execute(2, 300)
+            }],
+        )
+        .await;
+
+        let withdraw = client.withdrawal_timeline(1, 1).await.unwrap().unwrap();
+        assert_eq!(withdraw.transaction_type, TransactionTypeEnum::Withdraw);
+        assert_eq!(withdraw.source_tx_hash.as_deref(), Some("0xsource1"));
+        assert_eq!(withdraw.handle_tx_hash.as_deref(), Some("0xhandle1"));
+        assert!(withdraw.handled_at.is_some());
+        assert_eq!(withdraw.execute_tx_hash, None);
+        assert!(withdraw.is_completed);
+
+        let forced = client.withdrawal_timeline(1, 2).await.unwrap().unwrap();
+        assert_eq!(forced.transaction_type, TransactionTypeEnum::ForcedWithdraw);
+        assert_eq!(forced.handle_tx_hash.as_deref(), Some("0xhandle2"));
+        assert_eq!(forced.execute_tx_hash.as_deref(), Some("0xexecute300"));
+        assert!(forced.executed_at.is_some());
+        assert!(forced.is_completed);
+
+        // Forced withdrawals also need the L1 execute
+        let unexecuted = client.withdrawal_timeline(1, 3).await.unwrap().unwrap();
+        assert!(!unexecuted.is_completed);
+
+        assert!(client.withdrawal_timeline(1, 4).await.unwrap().is_none());
+        assert!(client.withdrawal_timeline(1, 5).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn recompute_fixes_flows_written_before_their_source() {
+        let client = crate::test_db::client(false).await;
+        write_sources(
+            &client,
+            vec![source(2, TransactionTypeEnum::Withdraw, HOUR)],
+        )
+        .await;
+        // The trigger finds no source for 1 yet and leaves it incomplete
+        write_executes(&client, vec![handle(1), handle(2)]).await;
+        write_sources(&client, vec![deposit(1, HOUR)]).await;
+
+        assert_eq!(client.recompute_is_completed().await.unwrap(), 1);
+
+        let completed: Vec<(i64, Option<bool>)> = transaction_flows::Entity::find()
+            .order_by_asc(transaction_flows::Column::Nonce)
+            .all(&client.primary)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|flow| (flow.nonce, flow.is_completed))
+            .collect();
+        assert_eq!(completed, vec![(1, Some(true)), (2, Some(true))]);
+        assert_eq!(client.recompute_is_completed().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn reconciliation_reports_unhandled_deposits_in_the_window() {
+        let client = crate::test_db::client(false).await;
+        write_sources(
+            &client,
+            vec![
+                deposit(1, 2 * HOUR),
+                deposit(2, 2 * HOUR),
+                deposit(3, 2 * HOUR),
+                deposit(4, 30 * HOUR),
+                source(5, TransactionTypeEnum::Withdraw, 2 * HOUR),
+            ],
+        )
+        .await;
+        write_executes(&client, vec![handle(1), handle(4), handle(5)]).await;
+
+        let now = chrono::Utc::now();
+        let report = client
+            .reconciliation_report(
+                (now - chrono::Duration::days(1)).fixed_offset(),
+                now.fixed_offset(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            report,
+            vec![ChainReconciliation {
+                chain_id: 1,
+                deposits: 3,
+                handled: 1,
+            }]
+        );
+        assert_eq!(report[0].delta(), 2);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn each_token_pair_is_listed_once_most_recent_first() {
+        let client = crate::test_db::client(false).await;
+        let other_pair =
+            |model: source_transactions::ActiveModel| source_transactions::ActiveModel {
+                l1_token: Set("0xa1".to_string()),
+                // Checksummed, unlike the uniswap token below
+                l2_token: Set("0xA2".to_string()),
+                ..model
+            };
+        write_sources(
+            &client,
+            vec![
+                deposit(1, 3 * HOUR),
+                deposit(2, HOUR),
+                other_pair(source(3, TransactionTypeEnum::Withdraw, 2 * HOUR)),
+            ],
+        )
+        .await;
+        let now = chrono::Utc::now().fixed_offset();
+        crate::entities::uniswap_tokens::Entity::insert(
+            crate::entities::uniswap_tokens::ActiveModel {
+                address: Set("0xa2".to_string()),
+                symbol: Set(Some("TKN".to_string())),
+                created_at: Set(now),
+                updated_at: Set(now),
+                ..Default::default()
+            },
+        )
+        .exec(&client.primary)
+        .await
+        .unwrap();
+
+        let tokens = client.distinct_tokens().await.unwrap();
+
+        let pairs: Vec<(&str, &str, Option<&str>, u64)> = tokens
+            .iter()
+            .map(|token| {
+                (
+                    token.l1_token.as_str(),
+                    token.l2_token.as_str(),
+                    token.symbol.as_deref(),
+                    token.transfers,
+                )
+            })
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![("0xl1", "0xl2", None, 2), ("0xa1", "0xA2", Some("TKN"), 1)]
+        );
+        assert!(tokens[0].first_seen < tokens[0].last_seen);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn deposits_are_filtered_by_chain_and_token() {
+        let client = crate::test_db::client(false).await;
+        let on_chain = |chain_id: i64, model: source_transactions::ActiveModel| {
+            source_transactions::ActiveModel {
+                chain_id: Set(chain_id),
+                ..model
+            }
+        };
+        let with_token =
+            |model: source_transactions::ActiveModel| source_transactions::ActiveModel {
+                l1_token: Set("0xA1".to_string()),
+                l2_token: Set("0xA2".to_string()),
+                ..model
+            };
+        write_sources(
+            &client,
+            vec![
+                deposit(1, 3 * HOUR),
+                deposit(2, 2 * HOUR),
+                with_token(deposit(3, HOUR)),
+                on_chain(8453, deposit(4, HOUR)),
+            ],
+        )
+        .await;
+        let flows = [(1, 1), (1, 2), (1, 3), (8453, 4)]
+            .map(|(chain_id, nonce)| transaction_flows::ActiveModel {
+                chain_id: Set(chain_id),
+                ..handle(nonce)
+            })
+            .to_vec();
+        write_executes(&client, flows).await;
+        let deposits = |chain_id: Option<i64>, token: Option<&str>| {
+            let client = &client;
+            let filter = BridgeEventFilter {
+                chain_id,
+                token: token.map(str::to_string),
+            };
+            async move {
+                client
+                    .fetch_l1_deposits_filtered(
+                        FetchBridgeTransactionsParams {
+                            items_count: 10,
+                            cursor: None,
+                        },
+                        filter,
+                    )
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|(source, _)| (source.chain_id, source.nonce))
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(deposits(Some(8453), None).await, vec![(8453, 4)]);
+        // Matches the checksummed address stored for 3
+        assert_eq!(deposits(None, Some("0xa1")).await, vec![(1, 3)]);
+        assert_eq!(deposits(Some(1), Some("0xl2")).await, vec![(1, 2), (1, 1)]);
+        assert!(deposits(Some(8453), Some("0xa1")).await.is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn largest_amounts_compare_by_value_not_digits() {
+        let client = crate::test_db::client(false).await;
+        let moving = |amount: i64, model: source_transactions::ActiveModel| {
+            source_transactions::ActiveModel {
+                amount: Set(Decimal::from(amount)),
+                ..model
+            }
+        };
+        write_sources(
+            &client,
+            vec![
+                moving(9, deposit(1, HOUR)),
+                moving(100, deposit(2, HOUR)),
+                moving(10, deposit(3, HOUR)),
+                // Larger, but too old or not a deposit
+                moving(1000, deposit(4, 30 * HOUR)),
+                moving(1000, source(5, TransactionTypeEnum::Withdraw, HOUR)),
+            ],
+        )
+        .await;
+
+        let now = chrono::Utc::now();
+        let top = client
+            .top_transactions_by_amount(
+                TransactionTypeEnum::Deposit,
+                (now - chrono::Duration::days(1)).fixed_offset(),
+                now.fixed_offset(),
+                2,
+            )
+            .await
+            .unwrap();
+
+        let amounts: Vec<Decimal> = top.into_iter().map(|tx| tx.amount).collect();
+        assert_eq!(amounts, vec![Decimal::from(100), Decimal::from(10)]);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn nonce_gaps_and_interleaved_sequences_are_reported() {
+        let client = crate::test_db::client(false).await;
+        let in_block = |block_number: i64, model: source_transactions::ActiveModel| {
+            source_transactions::ActiveModel {
+                block_number: Set(block_number),
+                ..model
+            }
+        };
+        write_sources(
+            &client,
+            vec![
+                // Indexing started at 3, so 1 and 2 are not missing
+                deposit(3, HOUR),
+                deposit(4, HOUR),
+                deposit(7, HOUR),
+                // Another chain's sequence landing on this chain id
+                in_block(200, deposit(5, HOUR)),
+                source_transactions::ActiveModel {
+                    chain_id: Set(2),
+                    ..deposit(20, HOUR)
+                },
+            ],
+        )
+        .await;
+        // A colliding 7 from the other chain leaves the stored row as is
+        write_sources(&client, vec![in_block(300, deposit(7, HOUR))]).await;
+
+        let anomalies = client.detect_nonce_anomalies(1).await.unwrap();
+
+        assert_eq!(
+            anomalies,
+            vec![
+                NonceAnomaly::Gap {
+                    after: 5,
+                    before: 7
+                },
+                NonceAnomaly::OutOfOrder {
+                    nonce: 5,
+                    block_number: 200,
+                    previous_nonce: 7,
+                    previous_block_number: 107,
+                },
+            ]
+        );
+        assert!(client.detect_nonce_anomalies(2).await.unwrap().is_empty());
     }
 }
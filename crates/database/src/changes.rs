@@ -0,0 +1,141 @@
+use sea_orm::{ColumnTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use tracing::{debug, instrument};
+
+use crate::client::DbClient;
+use crate::entities::{source_transactions, transaction_flows};
+
+/// Position of an incremental consumer in the append-only ids of
+/// `source_transactions` and `transaction_flows`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChangeCursor {
+    pub source_id: i64,
+    pub flow_id: i64,
+}
+
+/// Rows inserted after a cursor, together with the cursor to resume from
+#[derive(Debug, Clone)]
+pub struct ChangeSet {
+    pub source_transactions: Vec<source_transactions::Model>,
+    pub transaction_flows: Vec<transaction_flows::Model>,
+    pub cursor: ChangeCursor,
+}
+
+impl DbClient {
+    /// Up to `limit` rows of each table inserted after `cursor`, in insertion
+    /// order. Unlike timestamps, ids never move backwards when a chain is
+    /// backfilled, so a consumer resuming from the returned cursor sees every
+    /// row once.
+    #[instrument(skip(self))]
+    pub async fn fetch_changes_since(
+        &self,
+        cursor: ChangeCursor,
+        limit: u64,
+    ) -> Result<ChangeSet, DbErr> {
+        let source_transactions = source_transactions::Entity::find()
+            .filter(source_transactions::Column::Id.gt(cursor.source_id))
+            .order_by_asc(source_transactions::Column::Id)
+            .limit(limit)
+            .all(&self.primary)
+            .await?;
+
+        let transaction_flows = transaction_flows::Entity::find()
+            .filter(transaction_flows::Column::Id.gt(cursor.flow_id))
+            .order_by_asc(transaction_flows::Column::Id)
+            .limit(limit)
+            .all(&self.primary)
+            .await?;
+
+        let cursor = ChangeCursor {
+            source_id: source_transactions
+                .last()
+                .map_or(cursor.source_id, |tx| tx.id),
+            flow_id: transaction_flows
+                .last()
+                .map_or(cursor.flow_id, |flow| flow.id),
+        };
+
+        debug!(
+            source_count = source_transactions.len(),
+            flow_count = transaction_flows.len(),
+            "Fetched changes"
+        );
+
+        Ok(ChangeSet {
+            source_transactions,
+            transaction_flows,
+            cursor,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DbOperations, test_db};
+    use sea_orm::{ActiveValue::Set, prelude::Decimal, sqlx::types::chrono};
+
+    /// A deposit on chain 1 sent at `timestamp`
+    fn deposit(nonce: i64, timestamp: &str) -> DbOperations {
+        let timestamp: chrono::DateTime<chrono::FixedOffset> = timestamp.parse().unwrap();
+        DbOperations::BridgeSourceTransaction(source_transactions::ActiveModel {
+            chain_id: Set(1),
+            nonce: Set(nonce),
+            transaction_type: Set(
+                crate::entities::sea_orm_active_enums::TransactionTypeEnum::Deposit,
+            ),
+            block_number: Set(100 + nonce),
+            l1_token: Set("0xl1".to_string()),
+            l2_token: Set("0xl2".to_string()),
+            l1_address: Set("0xfrom".to_string()),
+            twine_address: Set("0xto".to_string()),
+            amount: Set(Decimal::ONE),
+            timestamp: Set(Some(timestamp)),
+            ..Default::default()
+        })
+    }
+
+    fn nonces(changes: &ChangeSet) -> Vec<i64> {
+        changes
+            .source_transactions
+            .iter()
+            .map(|tx| tx.nonce)
+            .collect()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn row_inserted_between_calls_is_consumed_once() {
+        let client = test_db::client(false).await;
+        client
+            .process_bulk_l1_database_operations(vec![vec![
+                deposit(1, "2025-10-16T12:00:00+00:00"),
+                deposit(2, "2025-10-16T13:00:00+00:00"),
+            ]])
+            .await
+            .unwrap();
+
+        let first = client
+            .fetch_changes_since(ChangeCursor::default(), 10)
+            .await
+            .unwrap();
+        assert_eq!(nonces(&first), vec![1, 2]);
+
+        // Backfilled, so older than everything consumed so far
+        client
+            .process_bulk_l1_database_operations(vec![vec![deposit(
+                3,
+                "2025-10-16T11:00:00+00:00",
+            )]])
+            .await
+            .unwrap();
+
+        let second = client.fetch_changes_since(first.cursor, 10).await.unwrap();
+        assert_eq!(nonces(&second), vec![3]);
+        assert!(second.cursor.source_id > first.cursor.source_id);
+        assert_eq!(second.cursor.flow_id, first.cursor.flow_id);
+
+        let third = client.fetch_changes_since(second.cursor, 10).await.unwrap();
+        assert!(third.source_transactions.is_empty());
+        assert_eq!(third.cursor, second.cursor);
+    }
+}
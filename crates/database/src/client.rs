@@ -1,10 +1,41 @@
-use crate::{DbOperations, entities::last_synced};
+use crate::{
+    BatchFinalization, CommittedBatch, DbOperations,
+    entities::{last_synced, sea_orm_active_enums::HeightKindEnum},
+};
 use sea_orm::{
-    ActiveValue::Set, DatabaseConnection, EntityTrait, TransactionTrait, sea_query::OnConflict,
+    ActiveValue::Set,
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, Statement, TransactionTrait,
+    sea_query::{Expr, OnConflict},
 };
 
 use tracing::{error, info};
 
+/// Batches the primary recorded as committed on a chain that blockscout no
+/// longer has, e.g. after a blockscout-only outage or restore
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyncDivergence {
+    pub chain_id: i64,
+    pub height_kind: HeightKindEnum,
+    pub last_synced: i64,
+    /// Missing batch numbers, in the order they were committed
+    pub missing_batches: Vec<i64>,
+    /// Height the first missing batch was committed at
+    pub first_missing_block: i64,
+}
+
+impl SyncDivergence {
+    /// Heights that have to be indexed again to restore the missing batches
+    pub fn blocks(&self) -> u64 {
+        (self.last_synced - self.first_missing_block + 1).max(0) as u64
+    }
+
+    /// The height up to which blockscout holds every batch the primary recorded
+    pub fn safe_height(&self) -> i64 {
+        self.first_missing_block - 1
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct DbClient {
     pub primary: DatabaseConnection,
@@ -19,27 +50,117 @@ impl DbClient {
         }
     }
 
-    pub async fn get_last_synced_height(
+    /// Last synced height of `chain_id`, or `None` if it has never synced.
+    /// Fails if the stored height was recorded as a different kind, so a
+    /// Solana slot can't be read back as an EVM block or vice versa. Rows
+    /// written before the kind was recorded are accepted as `kind`.
+    pub async fn get_last_synced(
         &self,
         chain_id: i64,
-        start_block: u64,
-    ) -> eyre::Result<i64> {
+        kind: HeightKindEnum,
+    ) -> eyre::Result<Option<i64>> {
         let res = last_synced::Entity::find_by_id(chain_id)
             .one(&self.primary)
             .await?;
-        Ok(res.map(|r| r.block_number).unwrap_or(start_block as i64))
+
+        match res {
+            Some(row) => match row.height_kind {
+                Some(stored) if stored != kind => Err(eyre::eyre!(
+                    "last_synced for chain {} is a {:?} height, not a {:?} height",
+                    chain_id,
+                    stored,
+                    kind
+                )),
+                _ => Ok(Some(row.block_number)),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Compares the batches the primary recorded as committed on `chain_id`
+    /// up to its `last_synced` against those in blockscout. Batches committed
+    /// later are not expected there yet, so normal lag is never reported.
+    /// Returns `None` when the chain never synced or nothing is missing.
+    pub async fn check_sync_divergence(
+        &self,
+        chain_id: i64,
+    ) -> eyre::Result<Option<SyncDivergence>> {
+        let Some(last_synced) = last_synced::Entity::find_by_id(chain_id)
+            .one(&self.primary)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let missing = self
+            .get_batches_missing_in_blockscout(chain_id, last_synced.block_number)
+            .await?;
+        let Some(first_missing_block) = missing.first().map(|(_, block_number)| *block_number)
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(SyncDivergence {
+            chain_id,
+            height_kind: last_synced.height_kind.unwrap_or(HeightKindEnum::Block),
+            last_synced: last_synced.block_number,
+            missing_batches: missing.into_iter().map(|(number, _)| number).collect(),
+            first_missing_block,
+        }))
     }
 
-    pub async fn upsert_last_synced(&self, chain_id: i64, block_number: i64) -> eyre::Result<()> {
+    /// Name of the most recently applied migration, `None` before any ran
+    pub async fn latest_migration(&self) -> Result<Option<String>, DbErr> {
+        let row = self
+            .primary
+            .query_one(Statement::from_string(
+                DbBackend::Postgres,
+                "SELECT version FROM seaql_migrations ORDER BY version DESC LIMIT 1",
+            ))
+            .await?;
+
+        row.map(|row| row.try_get::<String>("", "version"))
+            .transpose()
+    }
+
+    /// Every chain's `last_synced` row, ordered by chain id
+    pub async fn get_all_last_synced(&self) -> Result<Vec<last_synced::Model>, DbErr> {
+        last_synced::Entity::find()
+            .order_by_asc(last_synced::Column::ChainId)
+            .all(&self.primary)
+            .await
+    }
+
+    /// Records the latest head seen on `chain_id`. Does nothing until the
+    /// chain has checkpointed once and has a `last_synced` row.
+    pub async fn update_chain_head(&self, chain_id: i64, chain_head: i64) -> Result<(), DbErr> {
+        last_synced::Entity::update_many()
+            .col_expr(last_synced::Column::ChainHead, Expr::value(chain_head))
+            .filter(last_synced::Column::ChainId.eq(chain_id))
+            .exec(&self.primary)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn upsert_last_synced(
+        &self,
+        chain_id: i64,
+        kind: HeightKindEnum,
+        block_number: i64,
+    ) -> eyre::Result<()> {
         let model: last_synced::ActiveModel = last_synced::ActiveModel {
             chain_id: Set(chain_id),
             block_number: Set(block_number),
+            height_kind: Set(Some(kind)),
             ..Default::default()
         };
         last_synced::Entity::insert(model)
             .on_conflict(
                 OnConflict::column(last_synced::Column::ChainId)
-                    .update_column(last_synced::Column::BlockNumber)
+                    .update_columns([
+                        last_synced::Column::BlockNumber,
+                        last_synced::Column::HeightKind,
+                    ])
                     .to_owned(),
             )
             .exec(&self.primary)
@@ -62,14 +183,18 @@ impl DbClient {
         // Blockscout-related tables
         let mut batches = Vec::new();
         let mut batch_details = Vec::new();
-        let mut l2_blocks = Vec::new();
-        let mut l2_txns = Vec::new();
+        let mut l2_ranges = Vec::new();
+        let mut commit_blocks = Vec::new();
         let mut update_details = Vec::new();
 
         let mut uniswap_swaps = Vec::new();
         let mut uniswap_pools = Vec::new();
         let mut uniswap_tokens = Vec::new();
 
+        let mut unparsed_logs = Vec::new();
+        let mut svm_cursors = Vec::new();
+        let mut processed_signatures = Vec::new();
+
         for data_item in ops {
             for op in data_item {
                 match op {
@@ -82,19 +207,31 @@ impl DbClient {
                     DbOperations::CommitBatch {
                         batch,
                         details,
-                        blocks,
-                        transactions,
+                        l2_blocks,
+                        l1_block_number,
                     } => {
+                        if let (Some(range), Some(number)) =
+                            (l2_blocks, crate::active_value(&batch.number))
+                        {
+                            l2_ranges.push((number, range));
+                        }
+                        if let Some(committed) = CommittedBatch::from_details(&details) {
+                            commit_blocks.push((committed, l1_block_number));
+                        }
                         batches.push(batch);
                         batch_details.push(details);
-                        l2_blocks.extend(blocks);
-                        l2_txns.extend(transactions);
                     }
                     DbOperations::FinalizeBatch {
                         finalize_hash,
                         batch_number,
                         chain_id,
-                    } => update_details.push((batch_number, finalize_hash, chain_id)),
+                        l1_block_number,
+                    } => update_details.push(BatchFinalization {
+                        chain_id,
+                        batch_number,
+                        finalize_hash,
+                        block_number: Some(l1_block_number),
+                    }),
 
                     DbOperations::UniswapSwap { swap } => {
                         uniswap_swaps.push(swap);
@@ -103,6 +240,15 @@ impl DbClient {
                         uniswap_pools.push(pool);
                         uniswap_tokens.extend(tokens);
                     }
+                    DbOperations::UnparsedLog(model) => {
+                        unparsed_logs.push(model);
+                    }
+                    DbOperations::SvmCursor(model) => {
+                        svm_cursors.push(model);
+                    }
+                    DbOperations::ProcessedSignature(model) => {
+                        processed_signatures.push(model);
+                    }
                 }
             }
         }
@@ -133,10 +279,25 @@ impl DbClient {
                 .await?;
         }
 
-        primary_txn.commit().await?;
+        if !unparsed_logs.is_empty() {
+            self.bulk_insert_unparsed_logs(unparsed_logs, &primary_txn)
+                .await?;
+        }
+
+        if !svm_cursors.is_empty() {
+            self.bulk_upsert_svm_cursors(svm_cursors, &primary_txn)
+                .await?;
+        }
+
+        if !processed_signatures.is_empty() {
+            self.bulk_insert_processed_signatures(processed_signatures, &primary_txn)
+                .await?;
+        }
 
         // Blockscout database operations (only if blockscout connection exists)
         if let Some(blockscout) = &self.blockscout {
+            let committed: Vec<CommittedBatch> =
+                commit_blocks.iter().map(|(batch, _)| *batch).collect();
             let blockscout_txn = blockscout.begin().await?;
             if !batches.is_empty() {
                 self.bulk_insert_twine_transaction_batch(batches, &blockscout_txn)
@@ -146,24 +307,136 @@ impl DbClient {
                 self.bulk_insert_twine_transaction_batch_detail(batch_details, &blockscout_txn)
                     .await?;
             }
-            if !l2_blocks.is_empty() {
-                self.bulk_update_blocks(l2_blocks, &blockscout_txn).await?;
+            for (batch_number, range) in l2_ranges {
+                self.tag_batch_l2(batch_number, *range.start(), *range.end(), &blockscout_txn)
+                    .await?;
             }
-            if !l2_txns.is_empty() {
-                self.bulk_update_transactions(l2_txns, &blockscout_txn)
+            let mut finalized = self
+                .reconcile_pending_finalizations(&committed, &blockscout_txn, &primary_txn)
+                .await?;
+            let (applied, uncommitted) = self
+                .apply_finalizations(update_details, &blockscout_txn)
+                .await?;
+            finalized.extend(applied);
+            for finalization in &uncommitted {
+                self.enqueue_pending_finalization(finalization, &primary_txn)
                     .await?;
             }
+            self.record_batch_l1_blocks(&commit_blocks, &finalized, &primary_txn)
+                .await?;
+            // Pending rows are cleared and enqueued in the primary transaction,
+            // committed below. If that fails the range is re-processed and
+            // applying a finalization again is harmless.
             blockscout_txn.commit().await?;
         } else if !batches.is_empty()
             || !batch_details.is_empty()
-            || !l2_blocks.is_empty()
-            || !l2_txns.is_empty()
+            || !l2_ranges.is_empty()
             || !update_details.is_empty()
         {
             tracing::warn!("Blockscout operations requested but no blockscout connection provided");
         }
 
+        // Committed last, so a processed signature mark never lands without
+        // the blockscout rows of its event
+        primary_txn.commit().await?;
+
         info!("All data successfully processed and saved.");
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockscout_entities::{twine_transaction_batch, twine_transaction_batch_detail};
+    use crate::test_db;
+    use sea_orm::prelude::{DateTime, Decimal};
+
+    const CHAIN: i64 = 11155111;
+
+    fn commit(batch_number: i64, l1_block_number: i64) -> DbOperations {
+        DbOperations::CommitBatch {
+            batch: twine_transaction_batch::ActiveModel {
+                number: Set(batch_number),
+                start_block: Set(batch_number * 10),
+                end_block: Set(batch_number * 10 + 9),
+                timestamp: Set(DateTime::default()),
+                root_hash: Set(vec![0; 32]),
+                ..Default::default()
+            },
+            details: twine_transaction_batch_detail::ActiveModel {
+                batch_number: Set(batch_number),
+                l1_transaction_count: Set(0),
+                l2_transaction_count: Set(0),
+                l1_gas_price: Set(Decimal::ZERO),
+                l2_fair_gas_price: Set(Decimal::ZERO),
+                chain_id: Set(Decimal::from(CHAIN)),
+                ..Default::default()
+            },
+            l2_blocks: None,
+            l1_block_number,
+        }
+    }
+
+    async fn synced_with_batches() -> DbClient {
+        let client = test_db::client(true).await;
+        client
+            .process_bulk_l1_database_operations(vec![vec![
+                commit(1, 10),
+                commit(2, 20),
+                commit(3, 30),
+            ]])
+            .await
+            .unwrap();
+        client
+            .upsert_last_synced(CHAIN, HeightKindEnum::Block, 25)
+            .await
+            .unwrap();
+        client
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn batches_in_blockscout_are_not_a_divergence() {
+        let client = synced_with_batches().await;
+
+        // The Twine chain is far ahead of the last batch, which is normal lag
+        client
+            .upsert_last_synced(1337, HeightKindEnum::Block, 10_000)
+            .await
+            .unwrap();
+
+        assert_eq!(client.check_sync_divergence(CHAIN).await.unwrap(), None);
+        assert_eq!(client.check_sync_divergence(1337).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn batch_lost_by_blockscout_is_a_divergence() {
+        let client = synced_with_batches().await;
+        let blockscout = client.blockscout.clone().unwrap();
+        twine_transaction_batch::Entity::delete_by_id(2)
+            .exec(&blockscout)
+            .await
+            .unwrap();
+        // Committed after last_synced, so not expected in blockscout yet
+        twine_transaction_batch::Entity::delete_by_id(3)
+            .exec(&blockscout)
+            .await
+            .unwrap();
+
+        let divergence = client.check_sync_divergence(CHAIN).await.unwrap().unwrap();
+        assert_eq!(
+            divergence,
+            SyncDivergence {
+                chain_id: CHAIN,
+                height_kind: HeightKindEnum::Block,
+                last_synced: 25,
+                missing_batches: vec![2],
+                first_missing_block: 20,
+            }
+        );
+        assert_eq!(divergence.blocks(), 6);
+        assert_eq!(divergence.safe_height(), 19);
+    }
+}
@@ -1,9 +1,35 @@
 use eyre::Result;
-use sea_orm::{ConnectOptions, Database, DatabaseConnection, DbErr};
+use sea_orm::{ConnectOptions, Database, DatabaseConnection, DbErr, RuntimeErr, sqlx};
 
-pub async fn connect(database_url: &str) -> Result<DatabaseConnection, DbErr> {
+/// SQLSTATE Postgres reports when a statement is cancelled
+const QUERY_CANCELED: &str = "57014";
+
+/// Connects to `database_url`. With `statement_timeout_ms` set, Postgres
+/// cancels any statement on these connections that runs longer.
+pub async fn connect(
+    database_url: &str,
+    statement_timeout_ms: Option<u64>,
+) -> Result<DatabaseConnection, DbErr> {
     let mut opt = ConnectOptions::new(database_url.to_owned());
     opt.sqlx_logging(false); // Disable SQLx log
 
+    if let Some(timeout_ms) = statement_timeout_ms {
+        opt.map_sqlx_postgres_opts(move |pg_opts| {
+            pg_opts.options([("statement_timeout", timeout_ms.to_string())])
+        });
+    }
+
     Database::connect(opt).await
 }
+
+/// Returns true when Postgres cancelled the statement, which is how a
+/// `statement_timeout` surfaces
+pub fn is_statement_timeout(err: &DbErr) -> bool {
+    match err {
+        DbErr::Query(RuntimeErr::SqlxError(sqlx::Error::Database(db_err)))
+        | DbErr::Exec(RuntimeErr::SqlxError(sqlx::Error::Database(db_err))) => {
+            db_err.code().as_deref() == Some(QUERY_CANCELED)
+        }
+        _ => false,
+    }
+}
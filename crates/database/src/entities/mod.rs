@@ -2,11 +2,18 @@
 
 pub mod prelude;
 
+pub mod batch_da_references;
+pub mod batch_l1_blocks;
+pub mod block_hashes;
 pub mod celestia_blobs;
 pub mod last_synced;
+pub mod pending_finalizations;
+pub mod processed_signatures;
 pub mod sea_orm_active_enums;
 pub mod source_transactions;
+pub mod svm_cursor;
 pub mod transaction_flows;
 pub mod uniswap_pools;
 pub mod uniswap_swaps;
 pub mod uniswap_tokens;
+pub mod unparsed_logs;
@@ -1,9 +1,16 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.7
 
+pub use super::batch_da_references::Entity as BatchDaReferences;
+pub use super::batch_l1_blocks::Entity as BatchL1Blocks;
+pub use super::block_hashes::Entity as BlockHashes;
 pub use super::celestia_blobs::Entity as CelestiaBlobs;
 pub use super::last_synced::Entity as LastSynced;
+pub use super::pending_finalizations::Entity as PendingFinalizations;
+pub use super::processed_signatures::Entity as ProcessedSignatures;
 pub use super::source_transactions::Entity as SourceTransactions;
+pub use super::svm_cursor::Entity as SvmCursor;
 pub use super::transaction_flows::Entity as TransactionFlows;
 pub use super::uniswap_pools::Entity as UniswapPools;
 pub use super::uniswap_swaps::Entity as UniswapSwaps;
 pub use super::uniswap_tokens::Entity as UniswapTokens;
+pub use super::unparsed_logs::Entity as UnparsedLogs;
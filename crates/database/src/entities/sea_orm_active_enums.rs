@@ -2,6 +2,30 @@
 
 use sea_orm::entity::prelude::*;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "height_kind_enum")]
+pub enum HeightKindEnum {
+    #[sea_orm(string_value = "Block")]
+    Block,
+    #[sea_orm(string_value = "Slot")]
+    Slot,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "token_standard_enum"
+)]
+pub enum TokenStandardEnum {
+    #[sea_orm(string_value = "Erc20")]
+    Erc20,
+    #[sea_orm(string_value = "Native")]
+    Native,
+    #[sea_orm(string_value = "Spl")]
+    Spl,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
 #[sea_orm(
     rs_type = "String",
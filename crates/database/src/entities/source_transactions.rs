@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.7
 
+use super::sea_orm_active_enums::TokenStandardEnum;
 use super::sea_orm_active_enums::TransactionTypeEnum;
 use sea_orm::entity::prelude::*;
 
@@ -25,6 +26,7 @@ pub struct Model {
     pub timestamp: Option<DateTimeWithTimeZone>,
     pub created_at: Option<DateTimeWithTimeZone>,
     pub updated_at: Option<DateTimeWithTimeZone>,
+    pub token_standard: Option<TokenStandardEnum>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
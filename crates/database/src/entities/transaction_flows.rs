@@ -23,6 +23,9 @@ pub struct Model {
     pub is_completed: Option<bool>,
     pub created_at: Option<DateTimeWithTimeZone>,
     pub updated_at: Option<DateTimeWithTimeZone>,
+    #[sea_orm(column_type = "Decimal(Some((78, 0)))", nullable)]
+    pub refund_amount: Option<Decimal>,
+    pub refund_token: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
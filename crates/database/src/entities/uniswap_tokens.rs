@@ -9,7 +9,7 @@ pub struct Model {
     pub address: String,
     pub name: Option<String>,
     pub symbol: Option<String>,
-    pub decimals: i32,
+    pub decimals: Option<i32>,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
 }
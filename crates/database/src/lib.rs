@@ -1,19 +1,32 @@
-use blockscout_entities::{
-    blocks, transactions, twine_transaction_batch, twine_transaction_batch_detail,
+use std::ops::RangeInclusive;
+
+use blockscout_entities::{twine_transaction_batch, twine_transaction_batch_detail};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, DbErr, EntityTrait, IdenStatic, Iterable, JsonValue,
+    RuntimeErr, sea_query::sea_value_to_json_value,
 };
+use serde_json::json;
 
 use crate::entities::{
-    source_transactions, transaction_flows, uniswap_pools, uniswap_swaps, uniswap_tokens,
+    processed_signatures, source_transactions, svm_cursor, transaction_flows, uniswap_pools,
+    uniswap_swaps, uniswap_tokens, unparsed_logs,
 };
 
 mod batches;
+mod block_hashes;
 mod blockscout;
 pub mod blockscout_entities;
 pub mod bridge;
+pub mod changes;
 pub mod client;
 pub mod connect;
 pub mod entities;
+mod processed_signatures;
+mod svm_cursor;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_db;
 pub mod uniswap;
+mod unparsed;
 
 #[derive(Debug, Clone)]
 pub enum DbOperations {
@@ -23,13 +36,18 @@ pub enum DbOperations {
     CommitBatch {
         batch: twine_transaction_batch::ActiveModel,
         details: twine_transaction_batch_detail::ActiveModel,
-        blocks: Vec<blocks::ActiveModel>,
-        transactions: Vec<transactions::ActiveModel>,
+        /// L2 blocks whose blocks and transactions are tagged with the batch
+        /// number as it is written, `None` when an earlier commit tagged them
+        l2_blocks: Option<RangeInclusive<u64>>,
+        /// L1 block or slot the commit was logged at
+        l1_block_number: i64,
     },
     FinalizeBatch {
         finalize_hash: String,
         batch_number: i64,
         chain_id: i64,
+        /// L1 block or slot the finalize was logged at
+        l1_block_number: i64,
     },
     UniswapSwap {
         swap: uniswap_swaps::ActiveModel,
@@ -38,4 +56,371 @@ pub enum DbOperations {
         pool: uniswap_pools::ActiveModel,
         tokens: Vec<uniswap_tokens::ActiveModel>,
     },
+    /// A log that was seen on chain but could not be parsed, kept for manual handling
+    UnparsedLog(unparsed_logs::ActiveModel),
+    /// Advances the resume cursor of a Solana program
+    SvmCursor(svm_cursor::ActiveModel),
+    /// Records a Solana transaction whose events were indexed
+    ProcessedSignature(processed_signatures::ActiveModel),
+}
+
+/// A Twine batch finalized on the L1 `chain_id` by `finalize_hash`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchFinalization {
+    pub chain_id: i64,
+    pub batch_number: i64,
+    pub finalize_hash: String,
+    /// L1 block the finalize was logged at, unknown for finalizations
+    /// buffered before it was recorded
+    pub block_number: Option<i64>,
+}
+
+/// A Twine batch committed on the L1 `chain_id`. The L1s commit the same
+/// batch numbers, so a batch is only identified together with its chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommittedBatch {
+    pub chain_id: i64,
+    pub batch_number: i64,
+}
+
+impl CommittedBatch {
+    /// The batch a detail row commits, `None` when its chain id or batch
+    /// number is unset or the chain id does not fit an `i64`
+    pub fn from_details(details: &twine_transaction_batch_detail::ActiveModel) -> Option<Self> {
+        Some(Self {
+            chain_id: i64::try_from(active_value(&details.chain_id)?).ok()?,
+            batch_number: active_value(&details.batch_number)?,
+        })
+    }
+}
+
+/// Key used to keep related operations in order when they are applied
+/// concurrently. Operations sharing a key must be written by the same worker.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum OrderingKey {
+    Bridge { chain_id: i64, nonce: i64 },
+    Batch(i64),
+    Pair(String),
+    Program(String),
+    Unordered,
+}
+
+impl DbOperations {
+    /// Short name of the operation, used for logging and dry-run summaries
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DbOperations::BridgeSourceTransaction(_) => "BridgeSourceTransaction",
+            DbOperations::BridgeDestinationTransactions(_) => "BridgeDestinationTransactions",
+            DbOperations::CommitBatch { .. } => "CommitBatch",
+            DbOperations::FinalizeBatch { .. } => "FinalizeBatch",
+            DbOperations::UniswapSwap { .. } => "UniswapSwap",
+            DbOperations::UniswapPool { .. } => "UniswapPool",
+            DbOperations::UnparsedLog(_) => "UnparsedLog",
+            DbOperations::SvmCursor(_) => "SvmCursor",
+            DbOperations::ProcessedSignature(_) => "ProcessedSignature",
+        }
+    }
+
+    /// Describes a value the database CHECK constraints would reject, so the
+    /// log can be dead-lettered instead of failing the whole write
+    pub fn constraint_violation(&self) -> Option<String> {
+        match self {
+            DbOperations::BridgeSourceTransaction(model) => {
+                if let Some(nonce) = active_value(&model.nonce).filter(|nonce| *nonce < 0) {
+                    return Some(format!("negative nonce {}", nonce));
+                }
+                active_value(&model.amount)
+                    .filter(|amount| amount.is_sign_negative() && !amount.is_zero())
+                    .map(|amount| format!("negative amount {}", amount))
+            }
+            DbOperations::BridgeDestinationTransactions(model) => active_value(&model.nonce)
+                .filter(|nonce| *nonce < 0)
+                .map(|nonce| format!("negative nonce {}", nonce)),
+            _ => None,
+        }
+    }
+
+    /// Dead-letter recording this operation as rejected for `reason`, with
+    /// the operation as its payload. `None` for operations on tables without
+    /// CHECK constraints.
+    pub fn into_dead_letter(self, reason: String) -> Option<DbOperations> {
+        let (chain_id, block_number, transaction_hash) = match &self {
+            DbOperations::BridgeSourceTransaction(model) => (
+                active_value(&model.chain_id)?,
+                active_value(&model.block_number).unwrap_or_default(),
+                active_value(&model.transaction_hash).flatten(),
+            ),
+            DbOperations::BridgeDestinationTransactions(model) => (
+                active_value(&model.chain_id)?,
+                active_value(&model.execute_block_number)
+                    .flatten()
+                    .or(active_value(&model.handle_block_number).flatten())
+                    .unwrap_or_default(),
+                active_value(&model.execute_tx_hash)
+                    .flatten()
+                    .or(active_value(&model.handle_tx_hash).flatten()),
+            ),
+            _ => return None,
+        };
+
+        Some(DbOperations::UnparsedLog(unparsed_logs::ActiveModel {
+            chain_id: Set(chain_id),
+            block_number: Set(block_number),
+            transaction_hash: Set(transaction_hash.unwrap_or_default()),
+            event_type: Set(Some(self.kind().to_string())),
+            reason: Set(reason),
+            payload: Set(Some(self.to_json().to_string())),
+            ..Default::default()
+        }))
+    }
+
+    /// The operation as JSON, with the fields it sets keyed by column name
+    pub fn to_json(&self) -> JsonValue {
+        let data = match self {
+            DbOperations::BridgeSourceTransaction(model) => active_model_json(model),
+            DbOperations::BridgeDestinationTransactions(model) => active_model_json(model),
+            DbOperations::CommitBatch {
+                batch,
+                details,
+                l2_blocks,
+                l1_block_number,
+            } => json!({
+                "batch": active_model_json(batch),
+                "details": active_model_json(details),
+                "l2_blocks": l2_blocks.as_ref().map(|blocks| [*blocks.start(), *blocks.end()]),
+                "l1_block_number": l1_block_number,
+            }),
+            DbOperations::FinalizeBatch {
+                finalize_hash,
+                batch_number,
+                chain_id,
+                l1_block_number,
+            } => json!({
+                "finalize_hash": finalize_hash,
+                "batch_number": batch_number,
+                "chain_id": chain_id,
+                "l1_block_number": l1_block_number,
+            }),
+            DbOperations::UniswapSwap { swap } => active_model_json(swap),
+            DbOperations::UniswapPool { pool, tokens } => json!({
+                "pool": active_model_json(pool),
+                "tokens": tokens.iter().map(active_model_json).collect::<Vec<_>>(),
+            }),
+            DbOperations::UnparsedLog(model) => active_model_json(model),
+            DbOperations::SvmCursor(model) => active_model_json(model),
+            DbOperations::ProcessedSignature(model) => active_model_json(model),
+        };
+
+        json!({ "kind": self.kind(), "data": data })
+    }
+
+    pub fn ordering_key(&self) -> OrderingKey {
+        match self {
+            DbOperations::BridgeSourceTransaction(model) => {
+                match (active_value(&model.chain_id), active_value(&model.nonce)) {
+                    (Some(chain_id), Some(nonce)) => OrderingKey::Bridge { chain_id, nonce },
+                    _ => OrderingKey::Unordered,
+                }
+            }
+            DbOperations::BridgeDestinationTransactions(model) => {
+                match (active_value(&model.chain_id), active_value(&model.nonce)) {
+                    (Some(chain_id), Some(nonce)) => OrderingKey::Bridge { chain_id, nonce },
+                    _ => OrderingKey::Unordered,
+                }
+            }
+            DbOperations::CommitBatch { batch, .. } => active_value(&batch.number)
+                .map(OrderingKey::Batch)
+                .unwrap_or(OrderingKey::Unordered),
+            DbOperations::FinalizeBatch { batch_number, .. } => OrderingKey::Batch(*batch_number),
+            DbOperations::UniswapSwap { swap } => active_value(&swap.pair)
+                .map(OrderingKey::Pair)
+                .unwrap_or(OrderingKey::Unordered),
+            DbOperations::UniswapPool { pool, .. } => active_value(&pool.pair)
+                .map(OrderingKey::Pair)
+                .unwrap_or(OrderingKey::Unordered),
+            DbOperations::UnparsedLog(_) => OrderingKey::Unordered,
+            // Not sharded by the writer pool, which upserts cursors with `last_synced`
+            DbOperations::SvmCursor(cursor) => active_value(&cursor.program_id)
+                .map(OrderingKey::Program)
+                .unwrap_or(OrderingKey::Unordered),
+            // Not sharded by the writer pool either, which marks signatures
+            // with `last_synced` once their rows are written
+            DbOperations::ProcessedSignature(processed) => active_value(&processed.program_id)
+                .map(OrderingKey::Program)
+                .unwrap_or(OrderingKey::Unordered),
+        }
+    }
+}
+
+fn active_model_json<A: ActiveModelTrait>(model: &A) -> JsonValue {
+    let mut fields = serde_json::Map::new();
+    for column in <A::Entity as EntityTrait>::Column::iter() {
+        if let sea_orm::ActiveValue::Set(value) | sea_orm::ActiveValue::Unchanged(value) =
+            model.get(column)
+        {
+            fields.insert(column.as_str().to_string(), sea_value_to_json_value(&value));
+        }
+    }
+    JsonValue::Object(fields)
+}
+
+/// SQLSTATE Postgres fails a row violating a CHECK constraint with
+const CHECK_VIOLATION: &str = "23514";
+
+/// Whether `error` is Postgres rejecting a row for a CHECK constraint. Most
+/// writes wrap the database error in a message of their own, so its text is
+/// checked too.
+pub fn is_check_violation(error: &eyre::Report) -> bool {
+    let coded = error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<DbErr>())
+        .any(|err| match err {
+            DbErr::Exec(RuntimeErr::SqlxError(sea_orm::sqlx::Error::Database(db)))
+            | DbErr::Query(RuntimeErr::SqlxError(sea_orm::sqlx::Error::Database(db))) => {
+                db.code().as_deref() == Some(CHECK_VIOLATION)
+            }
+            _ => false,
+        });
+    coded || format!("{:?}", error).contains("violates check constraint")
+}
+
+fn active_value<V>(value: &sea_orm::ActiveValue<V>) -> Option<V>
+where
+    V: Into<sea_orm::Value> + Clone,
+{
+    match value {
+        sea_orm::ActiveValue::Set(v) | sea_orm::ActiveValue::Unchanged(v) => Some(v.clone()),
+        sea_orm::ActiveValue::NotSet => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{ActiveValue::Set, prelude::Decimal};
+
+    fn details(
+        chain_id: Option<i64>,
+        batch_number: Option<i64>,
+    ) -> twine_transaction_batch_detail::ActiveModel {
+        let mut model = twine_transaction_batch_detail::ActiveModel::default();
+        if let Some(chain_id) = chain_id {
+            model.chain_id = Set(Decimal::from(chain_id));
+        }
+        if let Some(batch_number) = batch_number {
+            model.batch_number = Set(batch_number);
+        }
+        model
+    }
+
+    #[test]
+    fn committed_batch_keeps_the_chain_of_its_detail_row() {
+        assert_eq!(
+            CommittedBatch::from_details(&details(Some(11155111), Some(42))),
+            Some(CommittedBatch {
+                chain_id: 11155111,
+                batch_number: 42,
+            })
+        );
+        // The same batch number committed on another L1 is a different batch
+        assert_ne!(
+            CommittedBatch::from_details(&details(Some(103), Some(42))),
+            CommittedBatch::from_details(&details(Some(11155111), Some(42)))
+        );
+    }
+
+    #[test]
+    fn committed_batch_needs_chain_and_batch_number() {
+        assert_eq!(CommittedBatch::from_details(&details(None, Some(42))), None);
+        assert_eq!(CommittedBatch::from_details(&details(Some(1), None)), None);
+    }
+
+    #[test]
+    fn committed_batch_rejects_chain_ids_beyond_i64() {
+        let mut model = details(None, Some(42));
+        model.chain_id = Set(Decimal::from(i64::MAX) + Decimal::ONE);
+        assert_eq!(CommittedBatch::from_details(&model), None);
+    }
+
+    fn negative_source() -> DbOperations {
+        DbOperations::BridgeSourceTransaction(source_transactions::ActiveModel {
+            chain_id: Set(1),
+            nonce: Set(7),
+            transaction_type: Set(entities::sea_orm_active_enums::TransactionTypeEnum::Deposit),
+            block_number: Set(100),
+            l1_token: Set("0xl1".to_string()),
+            l2_token: Set("0xl2".to_string()),
+            l1_address: Set("0xfrom".to_string()),
+            twine_address: Set("0xto".to_string()),
+            amount: Set(Decimal::NEGATIVE_ONE),
+            transaction_hash: Set(Some("0xdeposit".to_string())),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn rejected_source_transaction_is_dead_lettered_with_its_fields() {
+        let Some(DbOperations::UnparsedLog(dead_letter)) =
+            negative_source().into_dead_letter("negative amount".to_string())
+        else {
+            panic!("expected a dead-letter");
+        };
+
+        assert_eq!(dead_letter.chain_id, Set(1));
+        assert_eq!(dead_letter.block_number, Set(100));
+        assert_eq!(dead_letter.transaction_hash, Set("0xdeposit".to_string()));
+        assert_eq!(
+            dead_letter.event_type,
+            Set(Some("BridgeSourceTransaction".to_string()))
+        );
+        let payload = active_value(&dead_letter.payload).flatten().unwrap();
+        assert!(payload.contains("\"amount\""), "{payload}");
+    }
+
+    #[test]
+    fn operations_without_checks_have_no_dead_letter() {
+        let cursor = DbOperations::SvmCursor(svm_cursor::ActiveModel::default());
+        assert!(cursor.into_dead_letter("reason".to_string()).is_none());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn negative_values_are_rejected_by_the_checks() {
+        let client = test_db::client(false).await;
+
+        for (table, row) in [
+            (
+                "source_transactions",
+                "(chain_id, nonce, transaction_type, block_number, l1_token, l2_token, \
+                 l1_address, twine_address, amount) \
+                 VALUES (1, -1, 'Deposit', 1, 'a', 'b', 'c', 'd', 1)",
+            ),
+            (
+                "source_transactions",
+                "(chain_id, nonce, transaction_type, block_number, l1_token, l2_token, \
+                 l1_address, twine_address, amount) \
+                 VALUES (1, 1, 'Deposit', 1, 'a', 'b', 'c', 'd', -1)",
+            ),
+            ("transaction_flows", "(chain_id, nonce) VALUES (1, -1)"),
+        ] {
+            let error = client
+                .primary
+                .execute_unprepared(&format!("INSERT INTO {table} {row}"))
+                .await
+                .unwrap_err();
+            assert!(is_check_violation(&error.into()), "{table} {row}");
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn other_write_errors_are_not_check_violations() {
+        let client = test_db::client(false).await;
+        let error = client
+            .primary
+            .execute_unprepared("INSERT INTO transaction_flows (chain_id) VALUES (1)")
+            .await
+            .unwrap_err();
+        assert!(!is_check_violation(&error.into()));
+    }
 }
@@ -0,0 +1,148 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::client::DbClient;
+use crate::entities::processed_signatures;
+use eyre::Result;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, DatabaseTransaction, DbErr, EntityTrait, QueryFilter,
+    QuerySelect, TransactionTrait, sea_query::OnConflict,
+};
+use tracing::error;
+
+impl DbClient {
+    /// Whether [`Self::mark_signature_processed`] recorded `signature`. Unlike
+    /// `is_signature_processed`, which looks for the bridge rows a transaction
+    /// produced, this also knows about transactions without events.
+    pub async fn is_signature_marked_processed(&self, signature: &str) -> Result<bool, DbErr> {
+        let processed = processed_signatures::Entity::find_by_id(signature.to_string())
+            .one(&self.primary)
+            .await?;
+        Ok(processed.is_some())
+    }
+
+    /// Records that every event of the `program_id` transaction `signature`
+    /// has been written, so it is not fetched again
+    pub async fn mark_signature_processed(
+        &self,
+        signature: &str,
+        program_id: &str,
+        slot: i64,
+    ) -> Result<()> {
+        self.mark_signatures_processed(vec![processed_signatures::ActiveModel {
+            signature: Set(signature.to_string()),
+            program_id: Set(program_id.to_string()),
+            slot: Set(slot),
+            ..Default::default()
+        }])
+        .await
+    }
+
+    /// [`Self::mark_signature_processed`] for several transactions at once
+    pub async fn mark_signatures_processed(
+        &self,
+        models: Vec<processed_signatures::ActiveModel>,
+    ) -> Result<()> {
+        let txn = self.primary.begin().await?;
+        self.bulk_insert_processed_signatures(models, &txn).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// The subset of `signatures` that was already processed, looked up in a
+    /// single query
+    pub async fn find_processed_signatures(
+        &self,
+        signatures: &[String],
+    ) -> Result<HashSet<String>, DbErr> {
+        if signatures.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        let processed: Vec<String> = processed_signatures::Entity::find()
+            .select_only()
+            .column(processed_signatures::Column::Signature)
+            .filter(processed_signatures::Column::Signature.is_in(signatures.iter().cloned()))
+            .into_tuple()
+            .all(&self.primary)
+            .await?;
+        Ok(processed.into_iter().collect())
+    }
+
+    /// Records the transactions whose events are written in `txn`. A
+    /// transaction emitting several events appears once.
+    pub async fn bulk_insert_processed_signatures(
+        &self,
+        models: Vec<processed_signatures::ActiveModel>,
+        txn: &DatabaseTransaction,
+    ) -> Result<()> {
+        let unique: HashMap<String, processed_signatures::ActiveModel> = models
+            .into_iter()
+            .filter_map(|model| match &model.signature {
+                Set(signature) => Some((signature.clone(), model)),
+                _ => None,
+            })
+            .collect();
+
+        if unique.is_empty() {
+            return Ok(());
+        }
+
+        processed_signatures::Entity::insert_many(unique.into_values())
+            .on_conflict(
+                OnConflict::column(processed_signatures::Column::Signature)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(txn)
+            .await
+            .map_err(|e| {
+                error!("Failed to insert processed signatures: {:?}", e);
+                eyre::eyre!("Failed to insert processed signatures: {:?}", e)
+            })?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_db;
+
+    const SIGNATURE: &str =
+        "5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnbJLgp8uirBgmQpjKhoR4tjF3ZpRzrFmBV6UjKdiSZkQUW";
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn marked_signature_is_found_once() {
+        let client = test_db::client(false).await;
+        assert!(
+            !client
+                .is_signature_marked_processed(SIGNATURE)
+                .await
+                .unwrap()
+        );
+
+        client
+            .mark_signature_processed(SIGNATURE, "program", 100)
+            .await
+            .unwrap();
+        // Marking again is a no-op
+        client
+            .mark_signature_processed(SIGNATURE, "program", 100)
+            .await
+            .unwrap();
+
+        assert!(
+            client
+                .is_signature_marked_processed(SIGNATURE)
+                .await
+                .unwrap()
+        );
+        let found = client
+            .find_processed_signatures(&[SIGNATURE.to_string(), "other".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(found.contains(SIGNATURE));
+    }
+}
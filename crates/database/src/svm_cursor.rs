@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use crate::client::DbClient;
+use crate::entities::svm_cursor;
+use eyre::Result;
+use sea_orm::{
+    ActiveValue::Set, DatabaseTransaction, EntityTrait, TransactionTrait, sea_query::Expr,
+    sea_query::OnConflict,
+};
+use tracing::error;
+
+impl DbClient {
+    pub async fn get_svm_cursor(&self, program_id: &str) -> Result<Option<svm_cursor::Model>> {
+        let cursor = svm_cursor::Entity::find_by_id(program_id.to_string())
+            .one(&self.primary)
+            .await?;
+        Ok(cursor)
+    }
+
+    /// Upserts cursors in their own transaction, for callers that advance them
+    /// only after the rows below them were written
+    pub async fn upsert_svm_cursors(&self, models: Vec<svm_cursor::ActiveModel>) -> Result<()> {
+        let txn = self.primary.begin().await?;
+        self.bulk_upsert_svm_cursors(models, &txn).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// Upserts the cursor of each program, keeping only the newest slot when a
+    /// program appears more than once. A cursor never moves backwards.
+    pub async fn bulk_upsert_svm_cursors(
+        &self,
+        models: Vec<svm_cursor::ActiveModel>,
+        txn: &DatabaseTransaction,
+    ) -> Result<()> {
+        let mut newest: HashMap<String, svm_cursor::ActiveModel> = HashMap::new();
+        for model in models {
+            let (Set(program_id), Set(slot)) = (model.program_id.clone(), model.slot.clone())
+            else {
+                continue;
+            };
+            let is_newer = match newest.get(&program_id).map(|current| &current.slot) {
+                Some(Set(current)) => slot >= *current,
+                _ => true,
+            };
+            if is_newer {
+                newest.insert(program_id, model);
+            }
+        }
+
+        if newest.is_empty() {
+            return Ok(());
+        }
+
+        svm_cursor::Entity::insert_many(newest.into_values())
+            .on_conflict(
+                OnConflict::column(svm_cursor::Column::ProgramId)
+                    .update_columns([
+                        svm_cursor::Column::Signature,
+                        svm_cursor::Column::Slot,
+                        svm_cursor::Column::UpdatedAt,
+                    ])
+                    .action_and_where(
+                        Expr::col((svm_cursor::Entity, svm_cursor::Column::Slot))
+                            .lte(Expr::cust("EXCLUDED.slot")),
+                    )
+                    .to_owned(),
+            )
+            .exec_without_returning(txn)
+            .await
+            .map_err(|e| {
+                error!("Failed to upsert svm cursors: {:?}", e);
+                eyre::eyre!("Failed to upsert svm cursors: {:?}", e)
+            })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+//! Throwaway Postgres schemas for tests that need a real database. Those tests
+//! are ignored by default, run them against a scratch server with
+//! `TEST_DATABASE_URL=postgres://... cargo test -- --ignored`
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ConnectOptions, ConnectionTrait, Database, DatabaseConnection};
+
+use crate::client::DbClient;
+
+static SCHEMAS: AtomicU32 = AtomicU32::new(0);
+
+/// Connects to a new, empty schema of `TEST_DATABASE_URL`
+pub async fn empty_schema() -> DatabaseConnection {
+    let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let schema = format!(
+        "test_{}_{}_{}",
+        started,
+        std::process::id(),
+        SCHEMAS.fetch_add(1, Ordering::Relaxed)
+    );
+
+    let admin = Database::connect(&url)
+        .await
+        .expect("failed to connect to TEST_DATABASE_URL");
+    admin
+        .execute_unprepared(&format!("CREATE SCHEMA {schema}"))
+        .await
+        .expect("failed to create the test schema");
+
+    let mut options = ConnectOptions::new(url);
+    options.sqlx_logging(false).set_schema_search_path(schema);
+    Database::connect(options)
+        .await
+        .expect("failed to connect to the test schema")
+}
+
+/// A primary database with every migration applied
+pub async fn primary() -> DatabaseConnection {
+    let db = empty_schema().await;
+    Migrator::up(&db, None)
+        .await
+        .expect("failed to run the migrations");
+    db
+}
+
+/// A blockscout database holding the Twine batch tables the indexer writes
+/// and the blocks and transactions it tags, as blockscout creates them
+pub async fn blockscout() -> DatabaseConnection {
+    let db = empty_schema().await;
+    db.execute_unprepared(
+        r#"
+        CREATE TABLE twine_transaction_batch (
+            number BIGINT PRIMARY KEY,
+            start_block BIGINT NOT NULL,
+            end_block BIGINT NOT NULL,
+            timestamp TIMESTAMP NOT NULL,
+            root_hash BYTEA NOT NULL,
+            inserted_at TIMESTAMP NOT NULL DEFAULT (NOW() AT TIME ZONE 'utc'),
+            updated_at TIMESTAMP NOT NULL DEFAULT (NOW() AT TIME ZONE 'utc')
+        );
+        CREATE TABLE twine_transaction_batch_detail (
+            id SERIAL PRIMARY KEY,
+            batch_number BIGINT NOT NULL REFERENCES twine_transaction_batch (number)
+                ON UPDATE CASCADE ON DELETE CASCADE,
+            l1_transaction_count INTEGER NOT NULL,
+            l2_transaction_count INTEGER NOT NULL,
+            l1_gas_price NUMERIC(100) NOT NULL,
+            l2_fair_gas_price NUMERIC(100) NOT NULL,
+            chain_id NUMERIC(100) NOT NULL,
+            finalize_transaction_hash VARCHAR,
+            finalized_at TIMESTAMP,
+            inserted_at TIMESTAMP NOT NULL DEFAULT (NOW() AT TIME ZONE 'utc'),
+            updated_at TIMESTAMP NOT NULL DEFAULT (NOW() AT TIME ZONE 'utc'),
+            UNIQUE (batch_number, chain_id)
+        );
+        CREATE TABLE blocks (
+            consensus BOOLEAN NOT NULL,
+            difficulty NUMERIC(50),
+            gas_limit NUMERIC(100) NOT NULL,
+            gas_used NUMERIC(100) NOT NULL,
+            hash BYTEA PRIMARY KEY,
+            miner_hash BYTEA NOT NULL,
+            nonce BYTEA NOT NULL,
+            number BIGINT NOT NULL UNIQUE,
+            parent_hash BYTEA NOT NULL UNIQUE,
+            size INTEGER,
+            timestamp TIMESTAMP NOT NULL,
+            total_difficulty NUMERIC(50),
+            inserted_at TIMESTAMP NOT NULL DEFAULT (NOW() AT TIME ZONE 'utc'),
+            updated_at TIMESTAMP NOT NULL DEFAULT (NOW() AT TIME ZONE 'utc'),
+            refetch_needed BOOLEAN DEFAULT false,
+            base_fee_per_gas NUMERIC(100),
+            is_empty BOOLEAN,
+            batch_number BIGINT
+        );
+        CREATE TABLE transactions (
+            cumulative_gas_used NUMERIC(100),
+            error VARCHAR,
+            gas NUMERIC(100) NOT NULL,
+            gas_price NUMERIC(100),
+            gas_used NUMERIC(100),
+            hash BYTEA PRIMARY KEY,
+            index INTEGER,
+            input BYTEA NOT NULL,
+            nonce INTEGER NOT NULL,
+            r NUMERIC(100) NOT NULL,
+            s NUMERIC(100) NOT NULL,
+            status INTEGER,
+            v NUMERIC(100) NOT NULL,
+            value NUMERIC(100) NOT NULL,
+            inserted_at TIMESTAMP NOT NULL DEFAULT (NOW() AT TIME ZONE 'utc'),
+            updated_at TIMESTAMP NOT NULL DEFAULT (NOW() AT TIME ZONE 'utc'),
+            block_hash BYTEA REFERENCES blocks (hash),
+            block_number INTEGER,
+            from_address_hash BYTEA NOT NULL,
+            to_address_hash BYTEA,
+            created_contract_address_hash BYTEA,
+            created_contract_code_indexed_at TIMESTAMP,
+            earliest_processing_start TIMESTAMP,
+            old_block_hash BYTEA,
+            revert_reason TEXT,
+            max_priority_fee_per_gas NUMERIC(100),
+            max_fee_per_gas NUMERIC(100),
+            type INTEGER,
+            has_error_in_internal_transactions BOOLEAN,
+            block_timestamp TIMESTAMP,
+            block_consensus BOOLEAN DEFAULT true,
+            batch_number BIGINT,
+            UNIQUE (block_hash, index)
+        );
+        "#,
+    )
+    .await
+    .expect("failed to create the blockscout tables");
+    db
+}
+
+/// A client over a migrated primary database, with a blockscout one when
+/// `with_blockscout` is set
+pub async fn client(with_blockscout: bool) -> DbClient {
+    let blockscout = if with_blockscout {
+        Some(blockscout().await)
+    } else {
+        None
+    };
+    DbClient::new(primary().await, blockscout)
+}
@@ -2,8 +2,8 @@ use crate::client::DbClient;
 use crate::entities::{uniswap_pools, uniswap_swaps, uniswap_tokens};
 use eyre::{Context, Result};
 use sea_orm::{
-    ColumnTrait, DatabaseTransaction, EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect,
-    RelationTrait, sea_query::OnConflict,
+    ColumnTrait, ConnectionTrait, DatabaseTransaction, DbBackend, DbErr, EntityTrait, JoinType,
+    QueryFilter, QueryOrder, QuerySelect, RelationTrait, Statement, sea_query::OnConflict,
 };
 use tracing::error;
 
@@ -152,6 +152,66 @@ impl DbClient {
         Ok(pairs.into_iter().map(|pair| pair.pair).collect())
     }
 
+    /// Pools ordered by how many swaps they have seen, most first, optionally
+    /// only those with `token` on either side. `cursor` is the swap count and
+    /// pair of the last pool of the previous page.
+    pub async fn fetch_pools_paginated(
+        &self,
+        token: Option<&str>,
+        limit: u64,
+        cursor: Option<PoolCursor>,
+    ) -> Result<Vec<PoolSummary>, DbErr> {
+        let (cursor_count, cursor_pair) = match cursor {
+            Some(cursor) => (Some(cursor.swap_count), Some(cursor.pair)),
+            None => (None, None),
+        };
+
+        let rows = self
+            .primary
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"
+                SELECT p.pair, p.token0, p.token1,
+                    t0.symbol AS token0_symbol,
+                    t1.symbol AS token1_symbol,
+                    p.tx_hash, p.block_number,
+                    COALESCE(s.swap_count, 0) AS swap_count
+                FROM uniswap_pools p
+                LEFT JOIN (
+                    SELECT pair, COUNT(*) AS swap_count FROM uniswap_swaps GROUP BY pair
+                ) s ON s.pair = p.pair
+                LEFT JOIN uniswap_tokens t0 ON t0.address = p.token0
+                LEFT JOIN uniswap_tokens t1 ON t1.address = p.token1
+                WHERE ($1::text IS NULL OR LOWER(p.token0) = LOWER($1) OR LOWER(p.token1) = LOWER($1))
+                  AND ($2::bigint IS NULL OR (COALESCE(s.swap_count, 0), p.pair) < ($2, $3::text))
+                ORDER BY swap_count DESC, p.pair DESC
+                LIMIT $4
+                "#,
+                [
+                    token.map(str::to_string).into(),
+                    cursor_count.into(),
+                    cursor_pair.into(),
+                    (limit as i64).into(),
+                ],
+            ))
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(PoolSummary {
+                    pair: row.try_get("", "pair")?,
+                    token0: row.try_get("", "token0")?,
+                    token1: row.try_get("", "token1")?,
+                    token0_symbol: row.try_get("", "token0_symbol")?,
+                    token1_symbol: row.try_get("", "token1_symbol")?,
+                    tx_hash: row.try_get("", "tx_hash")?,
+                    block_number: row.try_get("", "block_number")?,
+                    swap_count: row.try_get("", "swap_count")?,
+                })
+            })
+            .collect()
+    }
+
     /// Get all swap events for a given user address with joined token information
     /// Uses a single query with proper JOINs for efficiency
     pub async fn get_user_swap_events(&self, user_address: &str) -> Result<Vec<UserSwapEvent>> {
@@ -230,12 +290,12 @@ impl DbClient {
                 // Token0 (L1 token)
                 l1_token: token0.and_then(|t| t.name.clone()).unwrap_or_default(),
                 l1_token_address: raw.token0_address,
-                l1_token_decimals: token0.map(|t| t.decimals).unwrap_or(18),
+                l1_token_decimals: token0.and_then(|t| t.decimals).unwrap_or(18),
 
                 // Token1 (L2 token)
                 l2_token: token1.and_then(|t| t.name.clone()).unwrap_or_default(),
                 l2_token_address: raw.token1_address,
-                l2_token_decimals: token1.map(|t| t.decimals).unwrap_or(18),
+                l2_token_decimals: token1.and_then(|t| t.decimals).unwrap_or(18),
 
                 // Amounts
                 l1_token_amount_in: raw.amount0_in,
@@ -256,6 +316,26 @@ impl DbClient {
     }
 }
 
+/// Position after the last pool of a `fetch_pools_paginated` page
+#[derive(Debug, Clone)]
+pub struct PoolCursor {
+    pub swap_count: i64,
+    pub pair: String,
+}
+
+/// A pool with its tokens' symbols and the number of swaps indexed for it
+#[derive(Debug, Clone)]
+pub struct PoolSummary {
+    pub pair: String,
+    pub token0: String,
+    pub token1: String,
+    pub token0_symbol: Option<String>,
+    pub token1_symbol: Option<String>,
+    pub tx_hash: String,
+    pub block_number: i64,
+    pub swap_count: i64,
+}
+
 /// Raw result struct for the joined query
 #[derive(Debug, sea_orm::FromQueryResult)]
 struct UserSwapEventRaw {
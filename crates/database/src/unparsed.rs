@@ -0,0 +1,74 @@
+use crate::client::DbClient;
+use crate::entities::unparsed_logs;
+use eyre::Result;
+use sea_orm::{
+    ColumnTrait, DatabaseTransaction, DbErr, EntityTrait, Order, QueryFilter, QueryOrder,
+    sea_query::{NullOrdering, OnConflict},
+};
+use tracing::{error, warn};
+
+impl DbClient {
+    /// Bulk insert logs that could not be parsed into the unparsed_logs dead-letter table
+    pub async fn bulk_insert_unparsed_logs(
+        &self,
+        models: Vec<unparsed_logs::ActiveModel>,
+        txn: &DatabaseTransaction,
+    ) -> Result<()> {
+        if models.is_empty() {
+            return Ok(());
+        }
+
+        warn!("Storing {} unparsed logs for manual handling", models.len());
+
+        // A log dead-lettered again, e.g. after a restart, keeps its first row
+        unparsed_logs::Entity::insert_many(models)
+            .on_conflict(
+                OnConflict::columns([
+                    unparsed_logs::Column::ChainId,
+                    unparsed_logs::Column::TransactionHash,
+                    unparsed_logs::Column::LogIndex,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .exec_without_returning(txn)
+            .await
+            .map_err(|e| {
+                error!("Failed to insert unparsed logs: {:?}", e);
+                eyre::eyre!("Failed to insert unparsed logs: {:?}", e)
+            })?;
+
+        Ok(())
+    }
+
+    /// Dead-lettered logs of `chain_id` in the order they were emitted, so a
+    /// replay handles a commit before the finalize that depends on it
+    pub async fn fetch_unparsed_logs_for_replay(
+        &self,
+        chain_id: i64,
+    ) -> Result<Vec<unparsed_logs::Model>, DbErr> {
+        unparsed_logs::Entity::find()
+            .filter(unparsed_logs::Column::ChainId.eq(chain_id))
+            .order_by_asc(unparsed_logs::Column::BlockNumber)
+            .order_by_with_nulls(
+                unparsed_logs::Column::LogIndex,
+                Order::Asc,
+                NullOrdering::Last,
+            )
+            .order_by_asc(unparsed_logs::Column::Id)
+            .all(&self.primary)
+            .await
+    }
+
+    pub async fn delete_unparsed_logs(&self, ids: Vec<i64>) -> Result<u64, DbErr> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let result = unparsed_logs::Entity::delete_many()
+            .filter(unparsed_logs::Column::Id.is_in(ids))
+            .exec(&self.primary)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
@@ -0,0 +1,92 @@
+use alloy_primitives::Address;
+use common::config::{EvmConfig, IndexerConfig, TwineConfig};
+use eyre::{eyre, Result, WrapErr};
+use tracing::warn;
+
+/// Parses the contract address configured as `field`. Any case is accepted,
+/// so lowercased and checksummed addresses select the same logs. A mixed-case
+/// address whose checksum does not match is most likely a typo and is warned
+/// about. Fails on malformed and zero addresses.
+pub fn parse_address(field: &str, value: &str) -> Result<Address> {
+    let value = value.trim();
+    let address = value
+        .parse::<Address>()
+        .map_err(|e| eyre!("Invalid {} '{}': {}", field, value, e))?;
+    if address.is_zero() {
+        return Err(eyre!("{} is the zero address", field));
+    }
+
+    let hex = value.trim_start_matches("0x");
+    let mixed_case =
+        hex.chars().any(|c| c.is_ascii_lowercase()) && hex.chars().any(|c| c.is_ascii_uppercase());
+    if mixed_case && Address::parse_checksummed(value, None).is_err() {
+        warn!(
+            "{} '{}' has an invalid checksum, using {}",
+            field,
+            value,
+            address.to_checksum(None)
+        );
+    }
+    Ok(address)
+}
+
+/// Gateway, Twine chain and message handler addresses of an L1 config, each
+/// gateway once. Logs of all of them are fetched with a single filter.
+pub fn evm_contract_addresses(config: &EvmConfig) -> Result<Vec<Address>> {
+    if config.l1_erc20_gateway_addresses.is_empty() {
+        return Err(eyre!("l1_erc20_gateway_addresses is empty"));
+    }
+
+    let mut addresses = Vec::new();
+    for value in &config.l1_erc20_gateway_addresses {
+        let address = parse_address("l1_erc20_gateway_addresses", value)?;
+        if !addresses.contains(&address) {
+            addresses.push(address);
+        }
+    }
+    addresses.push(parse_address(
+        "eth_twine_chain_address",
+        &config.eth_twine_chain_address,
+    )?);
+    addresses.push(parse_address(
+        "l1_message_handler_address",
+        &config.l1_message_handler_address,
+    )?);
+    Ok(addresses)
+}
+
+/// Contracts the Twine indexer watches besides the Uniswap pairs it discovers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TwineContracts {
+    pub l2_twine_messenger: Address,
+    pub uniswap_factory: Address,
+}
+
+impl TwineContracts {
+    pub fn from_config(config: &TwineConfig) -> Result<Self> {
+        Ok(Self {
+            l2_twine_messenger: parse_address(
+                "l2_twine_messenger_address",
+                &config.l2_twine_messenger_address,
+            )?,
+            uniswap_factory: parse_address(
+                "uniswap_factory_address",
+                &config.uniswap_factory_address,
+            )?,
+        })
+    }
+}
+
+/// Checks every EVM contract address of the indexer config, so a bad one
+/// stops the indexer at startup instead of silently matching no logs
+pub fn validate_config(cfg: &IndexerConfig) -> Result<()> {
+    TwineContracts::from_config(&cfg.twine).wrap_err("twine")?;
+    for (chain, config) in [
+        ("ethereum", &cfg.l1s.ethereum),
+        ("arbitrum", &cfg.l1s.arbitrum),
+        ("base", &cfg.l1s.base),
+    ] {
+        evm_contract_addresses(config).wrap_err_with(|| format!("l1s.{}", chain))?;
+    }
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use std::env;
+use std::{env, time::Duration};
 
 use super::EVMChain;
 use alloy_primitives::{Address, Log};
@@ -7,7 +7,7 @@ use alloy_rpc_types::Filter;
 use chrono::{DateTime, Utc};
 use common::{
     blockscout_entities::{twine_batch_l2_blocks, twine_batch_l2_transactions},
-    indexer::{MAX_RETRIES, RETRY_DELAY},
+    indexer::RetryPolicy,
 };
 use eyre::{Report, Result};
 use futures_util::Stream;
@@ -15,10 +15,14 @@ use sea_orm::ActiveValue::Set;
 use tokio::time::sleep;
 use tracing::{debug, error, info, instrument};
 
-pub async fn with_retry<F, Fut, T>(mut operation: F) -> Result<T, eyre::Report>
+/// Runs `operation` until it succeeds or `retry.max_retries` attempts have
+/// failed. Waits a random delay up to the policy's backoff between attempts
+/// ("full jitter"), so chains that lose their RPC together do not reconnect in
+/// lockstep.
+pub async fn with_retry<F, Fut, T, E>(retry: RetryPolicy, mut operation: F) -> Result<T, E>
 where
     F: FnMut() -> Fut,
-    Fut: std::future::Future<Output = Result<T, eyre::Report>>,
+    Fut: std::future::Future<Output = Result<T, E>>,
 {
     let mut attempt = 0;
 
@@ -27,33 +31,37 @@ where
             Ok(result) => return Ok(result),
             Err(e) => {
                 attempt += 1;
-                if attempt >= MAX_RETRIES {
+                if attempt >= retry.max_retries {
                     return Err(e);
                 }
-                tracing::warn!(attempt, "Operation failed, retrying in {:?}", RETRY_DELAY);
-                sleep(RETRY_DELAY).await;
+                let delay = jittered(retry.delay_for(attempt));
+                tracing::warn!(attempt, "Operation failed, retrying in {:?}", delay);
+                sleep(delay).await;
             }
         }
     }
 }
 
+/// Picks a delay uniformly between zero and `ceiling`
+pub fn jittered(ceiling: Duration) -> Duration {
+    let ceiling_ms = ceiling.as_millis().min(u64::MAX as u128) as u64;
+    Duration::from_millis(rand::random_range(0..=ceiling_ms))
+}
+
 #[instrument(skip_all, fields(CHAIN = %chain))]
 pub async fn subscribe_stream(
     provider: &dyn Provider,
-    contract_addresses: &[String],
+    contract_addresses: &[Address],
+    events: &[&str],
     chain: EVMChain,
+    retry: RetryPolicy,
 ) -> Result<impl Stream<Item = alloy_rpc_types::Log>> {
-    let events = chain.get_event_signatures();
-    let addresses = contract_addresses
-        .iter()
-        .map(|addr| addr.parse::<Address>())
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| eyre::eyre!("Invalid address format: {}", e))?;
-
-    let filter = Filter::new().address(addresses).events(events);
+    let filter = Filter::new()
+        .address(contract_addresses.to_vec())
+        .events(events);
     info!("Creating log subscription");
 
-    let subscription = with_retry(|| async {
+    let subscription = with_retry(retry, || async {
         provider
             .subscribe_logs(&filter)
             .await
@@ -69,10 +77,11 @@ pub async fn poll_missing_logs(
     provider: &dyn Provider,
     last_synced: u64,
     max_blocks_per_request: u64,
-    contract_addresses: &[String],
+    contract_addresses: &[Address],
     chain: EVMChain,
+    retry: RetryPolicy,
 ) -> Result<Vec<alloy_rpc_types::Log>> {
-    let current_block = with_retry(|| async {
+    let current_block = with_retry(retry, || async {
         provider
             .get_block_number()
             .await
@@ -93,11 +102,6 @@ pub async fn poll_missing_logs(
     );
 
     let events = chain.get_event_signatures();
-    let addresses = contract_addresses
-        .iter()
-        .map(|addr| addr.parse::<Address>())
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| eyre::eyre!("Invalid address format: {}", e))?;
 
     let mut all_logs = Vec::new();
     let mut start_block = last_synced + 1;
@@ -107,11 +111,12 @@ pub async fn poll_missing_logs(
         let filter = Filter::new()
             .select(start_block..=end_block)
             .events(events)
-            .address(addresses.clone());
+            .address(contract_addresses.to_vec());
 
-        let logs =
-            with_retry(|| async { provider.get_logs(&filter).await.map_err(eyre::Report::from) })
-                .await?;
+        let logs = with_retry(retry, || async {
+            provider.get_logs(&filter).await.map_err(eyre::Report::from)
+        })
+        .await?;
 
         if !logs.is_empty() {
             debug!(
@@ -125,6 +130,10 @@ pub async fn poll_missing_logs(
         start_block = end_block + 1;
     }
 
+    // Each range is ordered by the node, but nothing guarantees the order
+    // across range boundaries. Commits must be handled before their finalizes.
+    sort_logs_by_position(&mut all_logs);
+
     info!(
         "Completed log sync: {} logs fetched across {} blocks",
         all_logs.len(),
@@ -134,9 +143,24 @@ pub async fn poll_missing_logs(
     Ok(all_logs)
 }
 
+/// Orders logs by `(block_number, log_index)`. Pending logs without a
+/// position sort last.
+pub fn sort_logs_by_position(logs: &mut [alloy_rpc_types::Log]) {
+    logs.sort_by_key(|log| {
+        (
+            log.block_number.unwrap_or(u64::MAX),
+            log.log_index.unwrap_or(u64::MAX),
+        )
+    });
+}
+
 #[instrument(skip_all, fields(CHAIN = %chain))]
-pub async fn create_ws_provider(ws_rpc_url: String, chain: EVMChain) -> Result<impl Provider> {
-    let provider = with_retry(|| async {
+pub async fn create_ws_provider(
+    ws_rpc_url: String,
+    chain: EVMChain,
+    retry: RetryPolicy,
+) -> Result<impl Provider> {
+    let provider = with_retry(retry, || async {
         ProviderBuilder::new()
             .connect_ws(WsConnect::new(&ws_rpc_url))
             .await
@@ -149,16 +173,45 @@ pub async fn create_ws_provider(ws_rpc_url: String, chain: EVMChain) -> Result<i
 }
 
 #[instrument(skip_all, fields(CHAIN = %chain))]
-pub async fn create_http_provider(http_rpc_url: String, chain: EVMChain) -> Result<impl Provider> {
+pub async fn create_http_provider(
+    http_rpc_url: String,
+    chain: EVMChain,
+    retry: RetryPolicy,
+) -> Result<impl Provider> {
     let parsed_url = http_rpc_url
         .parse()
         .map_err(|e| eyre::eyre!("Invalid HTTP URL: {}", e))?;
 
     let provider = ProviderBuilder::new().connect_http(parsed_url);
 
-    let chain_id =
-        with_retry(|| async { provider.get_chain_id().await.map_err(eyre::Report::from) }).await?;
+    let chain_id = with_retry(retry, || async {
+        provider.get_chain_id().await.map_err(eyre::Report::from)
+    })
+    .await?;
 
     info!(chain_id, "HTTP connection verified");
     Ok(provider)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jittered_delay_stays_within_the_ceiling() {
+        let ceiling = Duration::from_millis(250);
+        for _ in 0..1000 {
+            assert!(jittered(ceiling) <= ceiling);
+        }
+    }
+
+    #[test]
+    fn jittered_zero_ceiling_is_zero() {
+        assert_eq!(jittered(Duration::ZERO), Duration::ZERO);
+    }
+
+    #[test]
+    fn jittered_huge_ceiling_does_not_overflow() {
+        assert!(jittered(Duration::MAX) <= Duration::MAX);
+    }
+}
@@ -23,6 +23,17 @@ pub enum ParserError {
     NumberOverflow {
         value: u64,
     },
+    InvalidBlockRange {
+        batch_number: u64,
+    },
+    CommitNotConfirmed {
+        batch_number: u64,
+        commit_block: u64,
+        confirmed_block: u64,
+    },
+    InvalidHandleStatus {
+        status: u64,
+    },
     SkipLog,
 }
 
@@ -60,6 +71,23 @@ impl std::fmt::Display for ParserError {
                 "Generated batch number {} exceeds i32 maximum value",
                 value
             ),
+            ParserError::InvalidBlockRange { batch_number } => write!(
+                f,
+                "Invalid block range for batch {}: the batch has no blocks",
+                batch_number
+            ),
+            ParserError::CommitNotConfirmed {
+                batch_number,
+                commit_block,
+                confirmed_block,
+            } => write!(
+                f,
+                "Batch {} was committed at L1 block {} which is past the confirmed block {}",
+                batch_number, commit_block, confirmed_block
+            ),
+            ParserError::InvalidHandleStatus { status } => {
+                write!(f, "Unknown handle status {} in L1TransactionsHandled", status)
+            }
             ParserError::SkipLog => write!(f, "Missing event in log")
         }
     }
@@ -1,6 +1,12 @@
-use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc};
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
 
-use alloy_primitives::B256;
+use alloy_primitives::{Address, B256};
 use alloy_rpc_types::Log;
 use alloy_sol_types::{SolEvent as _, SolType};
 use async_trait::async_trait;
@@ -10,14 +16,15 @@ use database::{
     blockscout_entities::{
         blocks, transactions, twine_transaction_batch, twine_transaction_batch_detail,
     },
+    bridge::{deposit_message, token_standard_for_l1_token},
     client::DbClient,
     entities::{source_transactions, transaction_flows},
-    DbOperations,
+    DbOperations, OrderingKey,
 };
 use eyre::Result;
 use generic_indexer::handler::ChainEventHandler;
 use num_traits::FromPrimitive;
-use sea_orm::{prelude::Decimal, sqlx::types::uuid::timestamp, ActiveValue::Set, IntoActiveModel};
+use sea_orm::{prelude::Decimal, sqlx::types::uuid::timestamp, ActiveValue::Set};
 use tracing::{debug, error, info, instrument, warn};
 use twine_evm_contracts::{
     l1_message_handler::{L1MessageHandler, TwineTypes},
@@ -25,9 +32,10 @@ use twine_evm_contracts::{
 };
 
 use crate::{
+    addresses::evm_contract_addresses,
     error::ParserError,
     ethereum::parser::get_event_name_from_signature_hash,
-    handler::{EvmEventHandler, LogContext},
+    handler::{batch_block_range, unparsed_log, EvmEventHandler, LogContext},
     provider::EvmProvider,
     EVMChain,
 };
@@ -40,12 +48,81 @@ pub struct EthereumEventHandler {
     chain_id: u64,
     config: EvmConfig,
     twine_provider: Arc<EvmProvider>,
+    /// Provider of this L1, used to check a commit has its confirmations
+    l1_provider: Arc<EvmProvider>,
+    /// Times each batch's finalize was deferred waiting for L1 confirmations
+    pending_finalize_attempts: Arc<Mutex<HashMap<u64, u32>>>,
+    /// Contracts logs are fetched from, replaced in place by
+    /// [`Self::reload_contract_addresses`] so clones see the new set
+    contract_addresses: Arc<RwLock<Vec<Address>>>,
 }
 
 #[async_trait]
 impl ChainEventHandler for EthereumEventHandler {
     type LogType = Log;
 
+    fn event_type(&self, log: &Log) -> String {
+        log.topic0()
+            .map(get_event_name_from_signature_hash)
+            .unwrap_or_else(|| "Unknown Event".to_string())
+    }
+
+    fn dead_letter(&self, log: &Log, reason: String) -> Option<DbOperations> {
+        Some(unparsed_log(
+            self.chain_id,
+            log,
+            &self.event_type(log),
+            reason,
+        ))
+    }
+
+    fn retry_delay(&self, error: &eyre::Report) -> Option<Duration> {
+        match error.downcast_ref::<ParserError>() {
+            Some(ParserError::CommitNotConfirmed { .. }) => Some(Duration::from_millis(
+                self.config.common.pending_finalize_retry_interval_ms,
+            )),
+            _ => None,
+        }
+    }
+
+    fn ordering_key(&self, log: &Log) -> OrderingKey {
+        let chain_id = self.chain_id as i64;
+        let bridge = |nonce: Option<i64>| match nonce {
+            Some(nonce) => OrderingKey::Bridge { chain_id, nonce },
+            None => OrderingKey::Unordered,
+        };
+
+        match log.topic0().copied() {
+            Some(L1MessageHandler::MessageTransaction::SIGNATURE_HASH) => bridge(
+                log.log_decode::<L1MessageHandler::MessageTransaction>()
+                    .ok()
+                    .and_then(|decoded| decoded.inner.data.nonce.try_into().ok()),
+            ),
+            Some(TwineChain::L2WithdrawExecuted::SIGNATURE_HASH) => bridge(
+                log.log_decode::<TwineChain::L2WithdrawExecuted>()
+                    .ok()
+                    .and_then(|decoded| decoded.inner.data.nonce.try_into().ok()),
+            ),
+            Some(TwineChain::RefundSuccessful::SIGNATURE_HASH) => bridge(
+                log.log_decode::<TwineChain::RefundSuccessful>()
+                    .ok()
+                    .and_then(|decoded| decoded.inner.data.nonce.try_into().ok()),
+            ),
+            Some(TwineChain::ForcedWithdrawalSuccessful::SIGNATURE_HASH) => bridge(
+                log.log_decode::<TwineChain::ForcedWithdrawalSuccessful>()
+                    .ok()
+                    .and_then(|decoded| decoded.inner.data.nonce.try_into().ok()),
+            ),
+            Some(TwineChain::FinalizedBatch::SIGNATURE_HASH) => log
+                .log_decode::<TwineChain::FinalizedBatch>()
+                .ok()
+                .and_then(|decoded| decoded.inner.data.batchNumber.try_into().ok())
+                .map(OrderingKey::Batch)
+                .unwrap_or(OrderingKey::Unordered),
+            _ => OrderingKey::Unordered,
+        }
+    }
+
     #[instrument(skip_all, fields(CHAIN = "Ethereum"))]
     async fn handle_event(&self, log: Log) -> eyre::Result<Vec<DbOperations>> {
         let sig = log.topic0().ok_or(ParserError::UnknownEvent {
@@ -83,8 +160,39 @@ impl ChainEventHandler for EthereumEventHandler {
             }
 
             TwineChain::FinalizedBatch::SIGNATURE_HASH => {
-                let operation = self.handle_commit_batch(log).await?;
-                operations.push(operation);
+                match self.handle_commit_batch(log.clone()).await {
+                    Ok(operation) => operations.push(operation),
+                    Err(e) => match e.downcast_ref::<ParserError>() {
+                        Some(err @ ParserError::InvalidBlockRange { .. }) => {
+                            error!("Dead-lettering FinalizedBatch: {}", err);
+                            operations.push(unparsed_log(
+                                self.chain_id,
+                                &log,
+                                "FinalizedBatch",
+                                err.to_string(),
+                            ));
+                        }
+                        Some(err @ ParserError::CommitNotConfirmed { batch_number, .. }) => {
+                            let Some(attempts) = self.give_up_pending_finalize(*batch_number)
+                            else {
+                                warn!("Deferring FinalizedBatch: {}", err);
+                                return Err(e);
+                            };
+
+                            error!(
+                                "Dead-lettering FinalizedBatch after {} attempts: {}",
+                                attempts, err
+                            );
+                            operations.push(unparsed_log(
+                                self.chain_id,
+                                &log,
+                                "FinalizedBatch",
+                                format!("{} after {} attempts", err, attempts),
+                            ));
+                        }
+                        _ => return Err(e),
+                    },
+                }
             }
             other => {
                 error!("Unknown event to handle")
@@ -102,20 +210,7 @@ impl ChainEventHandler for EthereumEventHandler {
 #[async_trait]
 impl EvmEventHandler for EthereumEventHandler {
     async fn relevant_addresses(&self) -> Vec<alloy_primitives::Address> {
-        let addresss = [
-            self.config.l1_erc20_gateway_address.clone(),
-            self.config.eth_twine_chain_address.clone(),
-            self.config.l1_message_handler_address.clone(),
-        ];
-
-        let contract_addresss = addresss
-            .iter()
-            .map(|addr| addr.parse::<alloy_primitives::Address>())
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| eyre::eyre!("Invalid address format: {}", e))
-            .unwrap();
-
-        contract_addresss
+        self.contract_addresses.read().unwrap().clone()
     }
 
     fn relevant_topics(&self) -> Vec<&'static str> {
@@ -128,13 +223,65 @@ impl EthereumEventHandler {
         db_client: Arc<DbClient>,
         config: EvmConfig,
         twine_provider: Arc<EvmProvider>,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        let contract_addresses = evm_contract_addresses(&config)?;
+        let l1_provider = Arc::new(
+            EvmProvider::new(&config.common.http_rpc_url, config.common.chain_id)
+                .with_confirmation_blocks(config.common.confirmation_blocks),
+        );
+        Ok(Self {
             db_client,
             chain_id: config.common.chain_id,
             config,
             twine_provider,
+            l1_provider,
+            pending_finalize_attempts: Arc::new(Mutex::new(HashMap::new())),
+            contract_addresses: Arc::new(RwLock::new(contract_addresses)),
+        })
+    }
+
+    /// Swaps in the contract addresses of `config`, e.g. after a gateway is
+    /// redeployed. Nothing changes when an address is invalid or `config` is
+    /// for another chain. Returns whether the set changed.
+    pub fn reload_contract_addresses(&self, config: &EvmConfig) -> Result<bool> {
+        if config.common.chain_id != self.chain_id {
+            return Err(eyre::eyre!(
+                "Reloaded config is for chain {}, handler indexes chain {}",
+                config.common.chain_id,
+                self.chain_id
+            ));
+        }
+
+        let updated = evm_contract_addresses(config)?;
+        let mut current = self.contract_addresses.write().unwrap();
+        if *current == updated {
+            return Ok(false);
         }
+
+        info!(
+            chain_id = self.chain_id,
+            "Contract addresses reloaded: {:?} -> {:?}", *current, updated
+        );
+        *current = updated;
+        Ok(true)
+    }
+
+    /// Counts another deferred attempt at finalizing `batch_number`. Once
+    /// `max_pending_finalize_attempts` is reached the count is cleared and
+    /// returned, and the finalize should be dead-lettered instead of retried.
+    fn give_up_pending_finalize(&self, batch_number: u64) -> Option<u32> {
+        let max_attempts = self.config.common.max_pending_finalize_attempts;
+        let mut pending = self.pending_finalize_attempts.lock().unwrap();
+        let attempts = pending.entry(batch_number).or_insert(0);
+        *attempts += 1;
+        debug!(
+            "FinalizedBatch {} deferred {} times",
+            batch_number, *attempts
+        );
+        if max_attempts == 0 || *attempts < max_attempts {
+            return None;
+        }
+        pending.remove(&batch_number)
     }
 
     async fn handle_l1_message_transaction(&self, log: Log) -> Result<DbOperations> {
@@ -173,9 +320,11 @@ impl EthereumEventHandler {
             twine_address: Set(format!("{:?}", data.twineAddress)),
             l2_token: Set(format!("{:?}", data.l2Token)),
             l1_token: Set(format!("{:?}", data.l1Token)),
+            token_standard: Set(Some(token_standard_for_l1_token(&data.l1Token.to_string()))),
             transaction_hash: Set(Some(decoded.tx_hash_str.clone())),
             timestamp: Set(Some(decoded.timestamp.fixed_offset())),
             amount: Set(data.amount.to_string().parse::<Decimal>().unwrap()),
+            message: Set(deposit_message(&data.data)),
             transaction_type: Set(
                 database::entities::sea_orm_active_enums::TransactionTypeEnum::Deposit,
             ),
@@ -206,6 +355,7 @@ impl EthereumEventHandler {
             twine_address: Set(format!("{:?}", data.twineAddress)),
             l2_token: Set(format!("{:?}", data.l2Token)),
             l1_token: Set(format!("{:?}", data.l1Token)),
+            token_standard: Set(Some(token_standard_for_l1_token(&data.l1Token.to_string()))),
             transaction_hash: Set(Some(decoded.tx_hash_str.clone())),
             timestamp: Set(Some(decoded.timestamp.fixed_offset())),
             amount: Set(data.amount.to_string().parse::<Decimal>().unwrap()),
@@ -300,14 +450,23 @@ impl EthereumEventHandler {
         let data = decoded.data;
 
         let batch_number = data.batchNumber;
+
+        // Defer reconciling until the commit can no longer be reorged away on
+        // L1, the error makes the indexer retry this range later
+        let confirmed_block = self.l1_provider.get_confirmed_block_number().await?;
+        ensure_commit_confirmed(batch_number, decoded.block_number as u64, confirmed_block)?;
+        self.pending_finalize_attempts
+            .lock()
+            .unwrap()
+            .remove(&batch_number);
+
         let blocks = self
             .twine_provider
             .get_blocks_in_batch(batch_number)
             .await?;
 
         // Extract start and end block numbers from the blocks vector
-        let start_block = blocks.iter().min().copied().unwrap_or(0);
-        let end_block = blocks.iter().max().copied().unwrap_or(0);
+        let (start_block, end_block) = batch_block_range(batch_number, &blocks)?;
 
         let root_hash = format!("{:?}", data.batchHash);
 
@@ -336,70 +495,154 @@ impl EthereumEventHandler {
             ..Default::default()
         };
 
-        let mut l2_blocks = Vec::new();
-        let mut l2_txs = Vec::new();
-
         //1. Check if batch already exists
-        match self.db_client.get_batch_by_id(batch_number as i64).await? {
-            Some(existing_batch) => {
+        let l2_blocks = match self.db_client.get_batch_by_id(batch_number as i64).await? {
+            Some(_) => {
                 //If the batch already exists, it means it already has its corresponding transactions and blocks as welll.
                 // So we just need to put twine commit information in the table
 
                 debug!("Batch already exists so don't need to fetch blocks and transactions");
+                None
             }
             None => {
-                // info!("First time encoutering this batch, so need to fecth blocks and transactions from twine");
-
-                let blocks = self.db_client.get_blocks(start_block, end_block).await?;
-
+                // Blocks and transactions are tagged a window at a time as the
+                // batch is written, here we only make sure blockscout has them all
+                let stored_blocks = self.db_client.count_blocks(start_block, end_block).await?;
                 let batch_length = end_block - start_block + 1;
 
-                if blocks.len() != batch_length as usize {
+                if stored_blocks != batch_length {
                     error!(
                         "Fetched blocks length {:?} mismatched with batch length {:?}",
-                        blocks.len(),
-                        batch_length
+                        stored_blocks, batch_length
                     );
 
                     return Err(eyre::eyre!(
                         "Fetched blocks length {:?} mismatched with batch length {:?}",
-                        blocks.len(),
+                        stored_blocks,
                         batch_length
                     ));
-                } else {
-                    let transactions = self
-                        .db_client
-                        .get_transactions(start_block, end_block)
-                        .await?;
-
-                    l2_blocks = blocks
-                        .into_iter()
-                        .map(|model| {
-                            let mut am = model.into_active_model();
-                            am.batch_number = Set(Some(batch_number as i64));
-                            am
-                        })
-                        .collect();
-
-                    l2_txs = transactions
-                        .into_iter()
-                        .map(|model| {
-                            let mut am = model.into_active_model();
-                            am.batch_number = Set(Some(batch_number as i64));
-                            am
-                        })
-                        .collect();
                 }
+                Some(start_block..=end_block)
             }
-        }
+        };
 
         let operation = DbOperations::CommitBatch {
             batch: batch_model,
             details: detail_model,
-            blocks: l2_blocks,
-            transactions: l2_txs,
+            l2_blocks,
+            l1_block_number: decoded.block_number as i64,
         };
 
         Ok(operation)
     }
 }
+
+/// Fails with [`ParserError::CommitNotConfirmed`] while the commit logged at
+/// `commit_block` is past the L1 `confirmed_block`
+fn ensure_commit_confirmed(
+    batch_number: u64,
+    commit_block: u64,
+    confirmed_block: u64,
+) -> std::result::Result<(), ParserError> {
+    if commit_block > confirmed_block {
+        return Err(ParserError::CommitNotConfirmed {
+            batch_number,
+            commit_block,
+            confirmed_block,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::DatabaseConnection;
+    use serde_json::json;
+
+    use super::*;
+
+    fn handler(max_pending_finalize_attempts: u32) -> EthereumEventHandler {
+        let config: EvmConfig = serde_json::from_value(json!({
+            "common": {
+                "http_rpc_url": "http://localhost:8545",
+                "chain_id": 11155111,
+                "start_block": 0,
+                "block_sync_batch_size": 100,
+                "block_time_ms": 1000,
+                "pending_finalize_retry_interval_ms": 250,
+                "max_pending_finalize_attempts": max_pending_finalize_attempts,
+            },
+            "l1_message_handler_address": "0x1000000000000000000000000000000000000001",
+            "l1_erc20_gateway_addresses": ["0x2000000000000000000000000000000000000002"],
+            "eth_twine_chain_address": "0x3000000000000000000000000000000000000003",
+            "chain": "ethereum",
+        }))
+        .unwrap();
+        EthereumEventHandler::new(
+            Arc::new(DbClient::new(DatabaseConnection::Disconnected, None)),
+            config,
+            Arc::new(EvmProvider::new("http://localhost:8545", 1337)),
+        )
+        .unwrap()
+    }
+
+    fn not_confirmed(batch_number: u64) -> eyre::Report {
+        ParserError::CommitNotConfirmed {
+            batch_number,
+            commit_block: 101,
+            confirmed_block: 100,
+        }
+        .into()
+    }
+
+    #[test]
+    fn unconfirmed_finalize_is_retried_at_the_configured_interval() {
+        let handler = handler(3);
+
+        assert_eq!(
+            handler.retry_delay(&not_confirmed(7)),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(handler.retry_delay(&eyre::eyre!("rpc down")), None);
+    }
+
+    #[test]
+    fn finalize_is_dead_lettered_after_max_attempts() {
+        let handler = handler(3);
+
+        assert_eq!(handler.give_up_pending_finalize(7), None);
+        assert_eq!(handler.give_up_pending_finalize(7), None);
+        // Other batches count their own attempts
+        assert_eq!(handler.give_up_pending_finalize(8), None);
+        assert_eq!(handler.give_up_pending_finalize(7), Some(3));
+        // Dead-lettering starts the count over
+        assert_eq!(handler.give_up_pending_finalize(7), None);
+    }
+
+    #[test]
+    fn finalize_is_retried_forever_without_a_max() {
+        let handler = handler(0);
+
+        for _ in 0..10 {
+            assert_eq!(handler.give_up_pending_finalize(7), None);
+        }
+    }
+
+    #[test]
+    fn finalize_waits_for_commit_confirmations() {
+        assert!(matches!(
+            ensure_commit_confirmed(7, 101, 100),
+            Err(ParserError::CommitNotConfirmed {
+                batch_number: 7,
+                commit_block: 101,
+                confirmed_block: 100,
+            })
+        ));
+    }
+
+    #[test]
+    fn confirmed_commit_is_reconciled() {
+        assert!(ensure_commit_confirmed(7, 100, 100).is_ok());
+        assert!(ensure_commit_confirmed(7, 90, 100).is_ok());
+    }
+}
@@ -9,7 +9,6 @@ use crate::handler::EvmEventHandler;
 use alloy_sol_types::SolEvent as _;
 use async_trait::async_trait;
 use common::config::EvmConfig;
-use common::indexer::{MAX_RETRIES, RETRY_DELAY};
 use database::client::DbClient;
 use database::entities::last_synced;
 use eyre::{Report, Result};
@@ -2,10 +2,11 @@ use alloy_primitives::Address;
 use alloy_rpc_types::Log;
 use alloy_sol_types::SolEvent;
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeDelta, Utc};
 use common::config::ChainConfig;
-use database::{client::DbClient, DbOperations};
+use database::{client::DbClient, entities::unparsed_logs, DbOperations};
 use eyre::Result;
+use sea_orm::ActiveValue::Set;
 use std::{collections::HashMap, future::Future, pin::Pin};
 use tracing::{debug, info};
 
@@ -24,6 +25,48 @@ pub struct LogContext<T> {
     pub data: T,
 }
 
+/// How far ahead of the local clock a block timestamp may be before it is
+/// treated as bogus
+const MAX_BLOCK_TIMESTAMP_DRIFT_SECS: i64 = 3600;
+
+/// Block timestamp as a `DateTime`, `None` for zero or far-future values that
+/// can't be a real block time
+pub fn valid_block_timestamp(timestamp: u64) -> Option<DateTime<Utc>> {
+    if timestamp == 0 {
+        return None;
+    }
+
+    let timestamp = DateTime::<Utc>::from_timestamp(i64::try_from(timestamp).ok()?, 0)?;
+    let latest_plausible = Utc::now() + TimeDelta::seconds(MAX_BLOCK_TIMESTAMP_DRIFT_SECS);
+    (timestamp <= latest_plausible).then_some(timestamp)
+}
+
+/// Start and end L2 block of a batch, rejecting a batch without blocks
+/// instead of producing a degenerate 0..0 range.
+pub fn batch_block_range(batch_number: u64, blocks: &[u64]) -> Result<(u64, u64), ParserError> {
+    match (blocks.iter().min(), blocks.iter().max()) {
+        (Some(&start_block), Some(&end_block)) => Ok((start_block, end_block)),
+        _ => Err(ParserError::InvalidBlockRange { batch_number }),
+    }
+}
+
+/// Builds a dead-letter operation for a log that cannot be indexed
+pub fn unparsed_log(chain_id: u64, log: &Log, event_type: &str, reason: String) -> DbOperations {
+    DbOperations::UnparsedLog(unparsed_logs::ActiveModel {
+        chain_id: Set(chain_id as i64),
+        block_number: Set(log.block_number.unwrap_or_default() as i64),
+        transaction_hash: Set(log
+            .transaction_hash
+            .map(|hash| hash.to_string())
+            .unwrap_or_default()),
+        log_index: Set(log.log_index.map(|index| index as i64)),
+        event_type: Set(Some(event_type.to_string())),
+        reason: Set(reason),
+        payload: Set(Some(format!("{:?}", log.inner))),
+        ..Default::default()
+    })
+}
+
 #[async_trait]
 pub trait EvmEventHandler: Send + Sync + Clone + 'static {
     async fn relevant_addresses(&self) -> Vec<Address>;
@@ -45,14 +88,18 @@ pub trait EvmEventHandler: Send + Sync + Clone + 'static {
             .map(|n| n as i64)
             .ok_or(ParserError::MissingBlockNumber)?;
 
+        // Logs are fetched with their timestamps already checked and replaced
+        // by the block's own when invalid, see `EvmProvider::fill_block_timestamps`.
+        // Only a block the RPC could not return is left without one.
         let timestamp = log
             .block_timestamp
-            .and_then(|ts| DateTime::<Utc>::from_timestamp(ts as i64, 0))
+            .and_then(valid_block_timestamp)
             .unwrap_or_else(|| {
-                // tracing::warn!(
-                //     "Missing or invalid block timestamp in {}. Using now.",
-                //     event_name
-                // );
+                tracing::warn!(
+                    "No block timestamp for {} in block {}, using now",
+                    event_name,
+                    block_number
+                );
                 Utc::now()
             });
 
@@ -71,3 +118,22 @@ pub trait EvmEventHandler: Send + Sync + Clone + 'static {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_without_blocks_is_rejected() {
+        assert!(matches!(
+            batch_block_range(3, &[]),
+            Err(ParserError::InvalidBlockRange { batch_number: 3 })
+        ));
+    }
+
+    #[test]
+    fn batch_range_spans_its_lowest_and_highest_block() {
+        assert_eq!(batch_block_range(3, &[12, 10, 11]).unwrap(), (10, 12));
+        assert_eq!(batch_block_range(3, &[7]).unwrap(), (7, 7));
+    }
+}
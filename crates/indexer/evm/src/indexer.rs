@@ -3,7 +3,7 @@ use std::{
     pin::Pin,
     sync::{
         atomic::{AtomicU64, Ordering},
-        Arc,
+        Arc, RwLock,
     },
     time::Duration,
 };
@@ -15,23 +15,32 @@ use common::config::{ChainConfig, EvmConfig, IndexerSettings};
 use database::{client::DbClient, entities::last_synced, DbOperations};
 use eyre::{eyre, Error};
 use futures_util::{Stream, StreamExt};
-use generic_indexer::{handler::ChainEventHandler, indexer::ChainIndexer, state::IndexerState};
+use generic_indexer::{
+    handler::ChainEventHandler, indexer::ChainIndexer, metrics, state::IndexerState,
+    watchdog::HeadWatchdog,
+};
 use tokio::{
     spawn,
     sync::{watch, Semaphore},
     task::JoinSet,
     time::Instant,
 };
-use tracing::{error, info, instrument};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, instrument, warn};
 
-use crate::{handler::EvmEventHandler, provider::EvmProvider};
+use crate::{handler::EvmEventHandler, provider::EvmProvider, reorg::detect_reorg};
 
 pub struct EvmIndexer<H: EvmEventHandler + ChainEventHandler<LogType = Log>> {
-    provider: EvmProvider,
+    /// Shared with the indexer's clones, so a reconnect is seen by all of them
+    provider: Arc<RwLock<EvmProvider>>,
     handler: H,
     config: ChainConfig,
     db_client: Arc<DbClient>,
     settings: IndexerSettings,
+    backfill_limiter: Option<Arc<Semaphore>>,
+    reorg_depth: u64,
+    shutdown: Option<CancellationToken>,
+    head_watchdog: Arc<HeadWatchdog>,
 }
 
 #[async_trait]
@@ -50,33 +59,87 @@ impl<H: EvmEventHandler + ChainEventHandler<LogType = Log>> ChainIndexer for Evm
         self.settings.clone()
     }
 
+    fn get_backfill_limiter(&self) -> Option<Arc<Semaphore>> {
+        self.backfill_limiter.clone()
+    }
+
+    fn get_shutdown_token(&self) -> Option<CancellationToken> {
+        self.shutdown.clone()
+    }
+
     async fn get_initial_state(&self) -> eyre::Result<u64> {
         let last_synced = self
             .get_db_client()
-            .get_last_synced_height(self.handler.chain_id() as i64, self.config.start_block)
-            .await
+            .get_last_synced(self.handler.chain_id() as i64, self.handler.height_kind())
+            .await?
             .unwrap_or(self.config.start_block as i64);
         Ok(last_synced as u64)
     }
 
     async fn get_current_chain_height(&self) -> eyre::Result<u64> {
-        self.provider.get_block_number().await
+        let head = self.provider().get_block_number().await?;
+        if let Err(err) = self.head_watchdog.observe(self.config.chain_id, head) {
+            self.reconnect(head);
+            return Err(err);
+        }
+        Ok(head)
     }
 
     async fn get_historical_logs(&self, from: u64, to: u64) -> eyre::Result<Vec<Log>> {
-        self.provider
+        let provider = self.provider();
+        let mut logs = provider
             .get_logs(
                 &self.handler.relevant_addresses().await,
                 &self.handler.relevant_topics(),
                 from,
                 to,
             )
-            .await
+            .await?;
+        provider.fill_block_timestamps(&mut logs).await?;
+        Ok(logs)
     }
 
     fn get_block_number_from_log(&self, log: &Log) -> Option<u64> {
         log.block_number
     }
+
+    async fn detect_reorg(&self) -> eyre::Result<Option<u64>> {
+        if self.reorg_depth == 0 {
+            return Ok(None);
+        }
+
+        let tracked = self
+            .db_client
+            .fetch_block_hashes(self.handler.chain_id() as i64)
+            .await?;
+        detect_reorg(&self.provider(), &tracked).await
+    }
+
+    /// Tracks the hashes of the blocks logs were found in and of the range
+    /// end, for the blocks within `reorg_depth` of it
+    async fn record_block_hashes(&self, logs: &[Log], to: u64) -> eyre::Result<()> {
+        if self.reorg_depth == 0 {
+            return Ok(());
+        }
+
+        let keep_from = to.saturating_sub(self.reorg_depth - 1);
+        let mut hashes: Vec<(i64, String)> = logs
+            .iter()
+            .filter_map(|log| Some((log.block_number?, log.block_hash?)))
+            .filter(|(block_number, _)| *block_number >= keep_from)
+            .map(|(block_number, block_hash)| (block_number as i64, block_hash.to_string()))
+            .collect();
+        if let Some(block_hash) = self.provider().get_block_hash(to).await? {
+            hashes.push((to as i64, block_hash));
+        }
+        hashes.sort();
+        hashes.dedup_by_key(|(block_number, _)| *block_number);
+
+        self.db_client
+            .upsert_block_hashes(self.handler.chain_id() as i64, hashes, keep_from as i64)
+            .await?;
+        Ok(())
+    }
 }
 
 impl<H: EvmEventHandler + ChainEventHandler<LogType = Log>> EvmIndexer<H> {
@@ -85,13 +148,57 @@ impl<H: EvmEventHandler + ChainEventHandler<LogType = Log>> EvmIndexer<H> {
         let provider = EvmProvider::new(&config.http_rpc_url, config.chain_id);
 
         Self {
-            provider,
+            provider: Arc::new(RwLock::new(provider)),
             handler,
             config,
             db_client,
             settings,
+            backfill_limiter: None,
+            reorg_depth: 0,
+            shutdown: None,
+            head_watchdog: Arc::new(HeadWatchdog::new(Duration::ZERO)),
         }
     }
+
+    pub fn with_backfill_limiter(mut self, limiter: Option<Arc<Semaphore>>) -> Self {
+        self.backfill_limiter = limiter;
+        self
+    }
+
+    fn provider(&self) -> EvmProvider {
+        self.provider.read().unwrap().clone()
+    }
+
+    /// Replaces the provider with one over a new connection after the head
+    /// stopped advancing at `head`
+    fn reconnect(&self, head: u64) {
+        let mut provider = self.provider.write().unwrap();
+        *provider = provider.reconnected();
+        metrics::rpc_reconnect(self.config.chain_id);
+        warn!(
+            chain_id = self.config.chain_id,
+            head,
+            stall_count = self.head_watchdog.stall_count(),
+            "Reconnecting RPC provider after no data timeout"
+        );
+    }
+
+    pub fn with_reorg_depth(mut self, reorg_depth: u64) -> Self {
+        self.reorg_depth = reorg_depth;
+        self
+    }
+
+    /// Fails the head poll and reconnects the provider once the block number
+    /// hasn't advanced for `timeout`, zero disables the check
+    pub fn with_no_data_timeout(mut self, timeout: Duration) -> Self {
+        self.head_watchdog = Arc::new(HeadWatchdog::new(timeout));
+        self
+    }
+
+    pub fn with_shutdown(mut self, shutdown: CancellationToken) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
 }
 
 impl<H: EvmEventHandler + ChainEventHandler<LogType = Log>> Clone for EvmIndexer<H> {
@@ -102,6 +209,10 @@ impl<H: EvmEventHandler + ChainEventHandler<LogType = Log>> Clone for EvmIndexer
             config: self.config.clone(),
             db_client: self.db_client.clone(),
             settings: self.settings.clone(),
+            backfill_limiter: self.backfill_limiter.clone(),
+            reorg_depth: self.reorg_depth,
+            shutdown: self.shutdown.clone(),
+            head_watchdog: self.head_watchdog.clone(),
         }
     }
 }
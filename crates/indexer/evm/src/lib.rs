@@ -1,9 +1,11 @@
+pub mod addresses;
 mod common;
-mod error;
+pub mod error;
 pub mod ethereum;
 pub mod handler;
 pub mod indexer;
 pub mod provider;
+pub mod reorg;
 pub mod twine;
 
 use common::{create_http_provider, create_ws_provider, poll_missing_logs, subscribe_stream};
@@ -3,9 +3,16 @@ use alloy_provider::{Provider, ProviderBuilder};
 use alloy_rpc_types::TransactionRequest;
 use alloy_rpc_types::{Block, Filter, Log, Transaction};
 use alloy_sol_types::{sol, SolCall};
-use std::sync::Arc;
+use futures_util::{stream, StreamExt};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+use tracing::{debug, warn};
 use twine_rpc::client::BatchClient;
 
+use crate::handler::valid_block_timestamp;
+
 // ERC-20 contract interface
 sol! {
     contract ERC20 {
@@ -17,9 +24,54 @@ sol! {
 
 #[derive(Debug, Clone)]
 pub struct TokenInfo {
-    pub name: String,
-    pub symbol: String,
-    pub decimals: u8,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub decimals: Option<u8>,
+}
+
+/// Timestamps of recently fetched blocks, evicting the least recently used
+struct BlockTimestampCache {
+    capacity: usize,
+    timestamps: HashMap<u64, u64>,
+    /// Block numbers from least to most recently used
+    recency: VecDeque<u64>,
+}
+
+impl BlockTimestampCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            timestamps: HashMap::with_capacity(capacity),
+            recency: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    fn get(&mut self, block_number: u64) -> Option<u64> {
+        let timestamp = *self.timestamps.get(&block_number)?;
+        self.touch(block_number);
+        Some(timestamp)
+    }
+
+    fn insert(&mut self, block_number: u64, timestamp: u64) {
+        if self.timestamps.insert(block_number, timestamp).is_some() {
+            self.touch(block_number);
+            return;
+        }
+
+        self.recency.push_back(block_number);
+        if self.recency.len() > self.capacity {
+            if let Some(evicted) = self.recency.pop_front() {
+                self.timestamps.remove(&evicted);
+            }
+        }
+    }
+
+    fn touch(&mut self, block_number: u64) {
+        if let Some(position) = self.recency.iter().position(|n| *n == block_number) {
+            self.recency.remove(position);
+        }
+        self.recency.push_back(block_number);
+    }
 }
 
 #[derive(Clone)]
@@ -27,8 +79,17 @@ pub struct EvmProvider {
     http: Arc<dyn Provider + Send + Sync>,
     chain_id: u64,
     http_url: String,
+    confirmation_blocks: u64,
+    block_fetch_concurrency: usize,
+    block_timestamps: Arc<Mutex<BlockTimestampCache>>,
 }
 
+/// Blocks fetched at once when a range is requested
+const DEFAULT_BLOCK_FETCH_CONCURRENCY: usize = 8;
+
+/// Block timestamps kept for logs fetched without one
+const BLOCK_TIMESTAMP_CACHE_CAPACITY: usize = 1024;
+
 impl EvmProvider {
     pub fn new(http_url: &str, chain_id: u64) -> Self {
         let http = ProviderBuilder::new().connect_http(http_url.parse().expect("Invalid Http URL"));
@@ -37,6 +98,33 @@ impl EvmProvider {
             http: Arc::new(http),
             http_url: http_url.to_string(),
             chain_id,
+            confirmation_blocks: 0,
+            block_fetch_concurrency: DEFAULT_BLOCK_FETCH_CONCURRENCY,
+            block_timestamps: Arc::new(Mutex::new(BlockTimestampCache::new(
+                BLOCK_TIMESTAMP_CACHE_CAPACITY,
+            ))),
+        }
+    }
+
+    /// A provider answering RPC calls with the responses queued on `asserter`
+    #[cfg(test)]
+    pub(crate) fn mocked(asserter: &alloy_transport::mock::Asserter) -> Self {
+        Self {
+            http: Arc::new(ProviderBuilder::new().connect_mocked_client(asserter.clone())),
+            ..Self::new("http://localhost:8545", 1)
+        }
+    }
+
+    /// A copy of this provider over a new HTTP connection, for when the
+    /// current one silently stopped serving data. Settings and cached block
+    /// timestamps are kept.
+    pub fn reconnected(&self) -> Self {
+        let http =
+            ProviderBuilder::new().connect_http(self.http_url.parse().expect("Invalid Http URL"));
+
+        Self {
+            http: Arc::new(http),
+            ..self.clone()
         }
     }
 
@@ -49,6 +137,16 @@ impl EvmProvider {
         self.chain_id
     }
 
+    pub fn with_confirmation_blocks(mut self, confirmation_blocks: u64) -> Self {
+        self.confirmation_blocks = confirmation_blocks;
+        self
+    }
+
+    pub fn with_block_fetch_concurrency(mut self, block_fetch_concurrency: usize) -> Self {
+        self.block_fetch_concurrency = block_fetch_concurrency;
+        self
+    }
+
     pub async fn get_logs(
         &self,
         addresses: &Vec<Address>,
@@ -69,10 +167,113 @@ impl EvmProvider {
             .map_err(eyre::Report::from)
     }
 
+    /// Replaces missing, zero or far-future `block_timestamp`s with the
+    /// timestamp of the block the log is in. Blocks are fetched once and
+    /// their timestamps cached, as many archive RPCs leave the field unset on
+    /// every log. Logs of blocks the RPC doesn't return are left as they are.
+    pub async fn fill_block_timestamps(&self, logs: &mut [Log]) -> eyre::Result<()> {
+        let mut block_numbers: Vec<u64> = logs
+            .iter()
+            .filter(|log| {
+                log.block_timestamp
+                    .and_then(valid_block_timestamp)
+                    .is_none()
+            })
+            .filter_map(|log| log.block_number)
+            .collect();
+        if block_numbers.is_empty() {
+            return Ok(());
+        }
+
+        // A missing timestamp is normal for many RPCs, a wrong one is not
+        let invalid = logs
+            .iter()
+            .filter(|log| {
+                log.block_timestamp
+                    .is_some_and(|ts| valid_block_timestamp(ts).is_none())
+            })
+            .count();
+        if invalid > 0 {
+            warn!(
+                "{} log(s) on chain {} have an invalid block timestamp, using the block's instead",
+                invalid, self.chain_id
+            );
+        }
+
+        block_numbers.sort_unstable();
+        block_numbers.dedup();
+
+        let mut timestamps = HashMap::new();
+        let mut missing = Vec::new();
+        {
+            let mut cache = self.block_timestamps.lock().unwrap();
+            for block_number in block_numbers {
+                match cache.get(block_number) {
+                    Some(timestamp) => {
+                        timestamps.insert(block_number, timestamp);
+                    }
+                    None => missing.push(block_number),
+                }
+            }
+        }
+
+        if !missing.is_empty() {
+            debug!(
+                "Fetching timestamps of {} block(s) on chain {} for logs without a valid one",
+                missing.len(),
+                self.chain_id
+            );
+        }
+
+        let mut fetched = stream::iter(missing)
+            .map(|block_number| async move {
+                (block_number, self.get_block_by_number(block_number).await)
+            })
+            .buffered(self.block_fetch_concurrency.max(1));
+
+        while let Some((block_number, result)) = fetched.next().await {
+            match result? {
+                Some(block) => {
+                    let timestamp = block.header.timestamp;
+                    self.block_timestamps
+                        .lock()
+                        .unwrap()
+                        .insert(block_number, timestamp);
+                    timestamps.insert(block_number, timestamp);
+                }
+                None => warn!(
+                    "Block {} on chain {} not found, its logs keep their timestamp",
+                    block_number, self.chain_id
+                ),
+            }
+        }
+
+        for log in logs.iter_mut() {
+            if let Some(timestamp) = log.block_number.and_then(|n| timestamps.get(&n)) {
+                log.block_timestamp = Some(*timestamp);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Chain id reported by the RPC node, as opposed to the configured one
+    pub async fn get_rpc_chain_id(&self) -> eyre::Result<u64> {
+        self.http.get_chain_id().await.map_err(Into::into)
+    }
+
     pub async fn get_block_number(&self) -> eyre::Result<u64> {
         self.http.get_block_number().await.map_err(Into::into)
     }
 
+    /// Highest block buried under the configured confirmations
+    pub async fn get_confirmed_block_number(&self) -> eyre::Result<u64> {
+        Ok(self
+            .get_block_number()
+            .await?
+            .saturating_sub(self.confirmation_blocks))
+    }
+
     pub async fn get_block_by_number(&self, block_number: u64) -> eyre::Result<Option<Block>> {
         self.http
             .get_block_by_number(block_number.into())
@@ -80,6 +281,15 @@ impl EvmProvider {
             .map_err(Into::into)
     }
 
+    /// Hash of the canonical block at `block_number`, `None` when the node
+    /// doesn't have it
+    pub async fn get_block_hash(&self, block_number: u64) -> eyre::Result<Option<String>> {
+        Ok(self
+            .get_block_by_number(block_number)
+            .await?
+            .map(|block| block.header.hash.to_string()))
+    }
+
     pub async fn get_blocks_with_transactions(
         &self,
         start_block: u64,
@@ -96,8 +306,16 @@ impl EvmProvider {
         let mut blocks = Vec::with_capacity((end_block - start_block + 1) as usize);
         let mut transactions = Vec::new();
 
-        for block_number in start_block..=end_block {
-            match self.get_block_by_number(block_number).await? {
+        // At most `block_fetch_concurrency` requests in flight, results come
+        // back in block order
+        let mut fetched = stream::iter(start_block..=end_block)
+            .map(|block_number| async move {
+                (block_number, self.get_block_by_number(block_number).await)
+            })
+            .buffered(self.block_fetch_concurrency.max(1));
+
+        while let Some((block_number, result)) = fetched.next().await {
+            match result? {
                 Some(block) => {
                     // Clone transactions from the block and extend our collection
                     transactions.extend(block.clone().transactions.into_transactions());
@@ -116,59 +334,134 @@ impl EvmProvider {
         Ok(blocks.into_iter().map(|block| block).collect())
     }
 
-    /// Get ERC-20 token information (name, symbol, decimals) for a given token address
-    pub async fn get_token_info(&self, token_address: Address) -> eyre::Result<TokenInfo> {
-        // Create contract calls
-        let name_call = ERC20::nameCall {};
-        let symbol_call = ERC20::symbolCall {};
-        let decimals_call = ERC20::decimalsCall {};
+    /// Get ERC-20 token information (name, symbol, decimals) for a given token address.
+    /// A call that reverts or returns something undecodable leaves its field unset,
+    /// so tokens that only partially implement the metadata extension still index
+    pub async fn get_token_info(&self, token_address: Address) -> TokenInfo {
+        TokenInfo {
+            name: self.call_token(token_address, ERC20::nameCall {}).await,
+            symbol: self.call_token(token_address, ERC20::symbolCall {}).await,
+            decimals: self.call_token(token_address, ERC20::decimalsCall {}).await,
+        }
+    }
 
-        // Create transaction requests
-        let name_tx = TransactionRequest::default()
+    async fn call_token<C: SolCall>(&self, token_address: Address, call: C) -> Option<C::Return> {
+        let tx = TransactionRequest::default()
             .to(token_address)
-            .input(name_call.abi_encode().into());
+            .input(call.abi_encode().into());
 
-        let symbol_tx = TransactionRequest::default()
-            .to(token_address)
-            .input(symbol_call.abi_encode().into());
+        let result = match self.http.call(tx).await {
+            Ok(result) => result,
+            Err(e) => {
+                warn!(
+                    "{} call to token {} failed: {}",
+                    C::SIGNATURE,
+                    token_address,
+                    e
+                );
+                return None;
+            }
+        };
 
-        let decimals_tx = TransactionRequest::default()
-            .to(token_address)
-            .input(decimals_call.abi_encode().into());
+        match C::abi_decode_returns(&result) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                warn!(
+                    "Failed to decode {} of token {}: {}",
+                    C::SIGNATURE,
+                    token_address,
+                    e
+                );
+                None
+            }
+        }
+    }
+}
 
-        // Make the calls to the contract
-        let name_result = self
-            .http
-            .call(name_tx)
-            .await
-            .map_err(|e| eyre::eyre!("Failed to get token name: {}", e))?;
+#[cfg(test)]
+mod tests {
+    use alloy_transport::mock::Asserter;
 
-        let symbol_result = self
-            .http
-            .call(symbol_tx)
-            .await
-            .map_err(|e| eyre::eyre!("Failed to get token symbol: {}", e))?;
+    use super::*;
+
+    fn mocked_provider(asserter: &Asserter) -> EvmProvider {
+        EvmProvider::mocked(asserter).with_block_fetch_concurrency(1)
+    }
+
+    fn block(number: u64, timestamp: u64) -> Block {
+        let mut block = Block::default();
+        block.header.inner.number = number;
+        block.header.inner.timestamp = timestamp;
+        block
+    }
+
+    fn log(block_number: u64, block_timestamp: Option<u64>) -> Log {
+        Log {
+            block_number: Some(block_number),
+            block_timestamp,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn cache_evicts_the_least_recently_used_block() {
+        let mut cache = BlockTimestampCache::new(2);
+        cache.insert(1, 100);
+        cache.insert(2, 200);
+        // Block 1 is now the most recently used, so 2 goes first
+        assert_eq!(cache.get(1), Some(100));
+        cache.insert(3, 300);
+
+        assert_eq!(cache.get(2), None);
+        assert_eq!(cache.get(1), Some(100));
+        assert_eq!(cache.get(3), Some(300));
+    }
+
+    #[tokio::test]
+    async fn logs_without_a_timestamp_take_their_blocks() {
+        let asserter = Asserter::new();
+        asserter.push_success(&block(5, 1_700_000_005));
+        asserter.push_success(&block(6, 1_700_000_006));
+        let provider = mocked_provider(&asserter);
+        let mut logs = vec![log(5, None), log(5, Some(0)), log(6, None)];
 
-        let decimals_result = self
-            .http
-            .call(decimals_tx)
+        provider.fill_block_timestamps(&mut logs).await.unwrap();
+
+        let timestamps: Vec<Option<u64>> = logs.iter().map(|log| log.block_timestamp).collect();
+        assert_eq!(
+            timestamps,
+            vec![
+                Some(1_700_000_005),
+                Some(1_700_000_005),
+                Some(1_700_000_006)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn cached_block_timestamps_are_not_fetched_again() {
+        let asserter = Asserter::new();
+        asserter.push_success(&block(5, 1_700_000_005));
+        let provider = mocked_provider(&asserter);
+        provider
+            .fill_block_timestamps(&mut [log(5, None)])
             .await
-            .map_err(|e| eyre::eyre!("Failed to get token decimals: {}", e))?;
+            .unwrap();
 
-        // Decode the results
-        let name_return = ERC20::nameCall::abi_decode_returns(&name_result)
-            .map_err(|e| eyre::eyre!("Failed to decode token name: {}", e))?;
+        // Nothing is queued, a second fetch would fail
+        let mut logs = [log(5, None)];
+        provider.fill_block_timestamps(&mut logs).await.unwrap();
+
+        assert_eq!(logs[0].block_timestamp, Some(1_700_000_005));
+    }
 
-        let symbol_return = ERC20::symbolCall::abi_decode_returns(&symbol_result)
-            .map_err(|e| eyre::eyre!("Failed to decode token symbol: {}", e))?;
+    #[tokio::test]
+    async fn valid_timestamps_are_kept_without_fetching() {
+        let provider = mocked_provider(&Asserter::new());
+        let mut logs = [log(5, Some(1_600_000_000))];
 
-        let decimals_return = ERC20::decimalsCall::abi_decode_returns(&decimals_result)
-            .map_err(|e| eyre::eyre!("Failed to decode token decimals: {}", e))?;
+        provider.fill_block_timestamps(&mut logs).await.unwrap();
 
-        Ok(TokenInfo {
-            name: name_return,
-            symbol: symbol_return,
-            decimals: decimals_return,
-        })
+        assert_eq!(logs[0].block_timestamp, Some(1_600_000_000));
     }
 }
@@ -0,0 +1,116 @@
+use std::future::Future;
+
+use database::entities::block_hashes;
+use eyre::Result;
+use tracing::warn;
+
+use crate::provider::EvmProvider;
+
+/// Compares the tracked block hashes, highest block first, with the canonical
+/// chain and returns the height of the common ancestor when the highest
+/// tracked block has been reorged. A reorg deeper than the tracked blocks
+/// resumes from just below the lowest of them.
+pub async fn detect_reorg(
+    provider: &EvmProvider,
+    tracked: &[block_hashes::Model],
+) -> Result<Option<u64>> {
+    find_common_ancestor(tracked, |block_number| {
+        provider.get_block_hash(block_number)
+    })
+    .await
+}
+
+/// [`detect_reorg`] against the hashes `canonical_hash` returns
+async fn find_common_ancestor<F, Fut>(
+    tracked: &[block_hashes::Model],
+    canonical_hash: F,
+) -> Result<Option<u64>>
+where
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = Result<Option<String>>>,
+{
+    for (depth, tracked_block) in tracked.iter().enumerate() {
+        let block_number = tracked_block.block_number as u64;
+        let canonical = canonical_hash(block_number).await?;
+        if canonical.as_deref() == Some(tracked_block.block_hash.as_str()) {
+            return Ok((depth > 0).then_some(block_number));
+        }
+    }
+
+    let Some(lowest) = tracked.last() else {
+        return Ok(None);
+    };
+    warn!(
+        "Reorg reaches below the lowest tracked block {}, increase reorg_depth",
+        lowest.block_number
+    );
+    Ok(Some((lowest.block_number as u64).saturating_sub(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn tracked(hashes: &[(i64, &str)]) -> Vec<block_hashes::Model> {
+        hashes
+            .iter()
+            .map(|(block_number, block_hash)| block_hashes::Model {
+                chain_id: 1,
+                block_number: *block_number,
+                block_hash: block_hash.to_string(),
+            })
+            .collect()
+    }
+
+    async fn ancestor(tracked: &[block_hashes::Model], canonical: &[(u64, &str)]) -> Option<u64> {
+        let canonical: HashMap<u64, String> = canonical
+            .iter()
+            .map(|(block_number, hash)| (*block_number, hash.to_string()))
+            .collect();
+        find_common_ancestor(tracked, |block_number| {
+            let hash = canonical.get(&block_number).cloned();
+            async move { Ok(hash) }
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn matching_head_is_no_reorg() {
+        let tracked = tracked(&[(102, "0xc"), (101, "0xb"), (100, "0xa")]);
+        let canonical = [(102, "0xc"), (101, "0xb"), (100, "0xa")];
+
+        assert_eq!(ancestor(&tracked, &canonical).await, None);
+    }
+
+    #[tokio::test]
+    async fn reorged_head_resumes_from_the_highest_matching_block() {
+        let tracked = tracked(&[(102, "0xc"), (101, "0xb"), (100, "0xa")]);
+        let canonical = [(102, "0xc2"), (101, "0xb"), (100, "0xa")];
+
+        assert_eq!(ancestor(&tracked, &canonical).await, Some(101));
+    }
+
+    #[tokio::test]
+    async fn missing_canonical_block_counts_as_reorged() {
+        let tracked = tracked(&[(102, "0xc"), (101, "0xb"), (100, "0xa")]);
+        let canonical = [(100, "0xa")];
+
+        assert_eq!(ancestor(&tracked, &canonical).await, Some(100));
+    }
+
+    #[tokio::test]
+    async fn reorg_below_the_tracked_blocks_resumes_under_the_lowest() {
+        let tracked = tracked(&[(102, "0xc"), (101, "0xb"), (100, "0xa")]);
+        let canonical = [(102, "0xc2"), (101, "0xb2"), (100, "0xa2")];
+
+        assert_eq!(ancestor(&tracked, &canonical).await, Some(99));
+    }
+
+    #[tokio::test]
+    async fn nothing_tracked_is_no_reorg() {
+        assert_eq!(ancestor(&[], &[]).await, None);
+    }
+}
@@ -1,18 +1,23 @@
-use std::{future::Future, pin::Pin, sync::Arc};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, RwLock},
+};
 
-use crate::provider::EvmProvider;
-use alloy_primitives::{map::HashMap, Bytes, FixedBytes, B256};
+use crate::provider::{EvmProvider, TokenInfo};
+use alloy_primitives::{map::HashMap, Address, Bytes, FixedBytes, B256};
 use alloy_rpc_types::Log;
 use alloy_sol_types::{SolEvent, SolValue};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use common::config::TwineConfig;
 use database::{
+    bridge::{token_standard_for_l1_token, HandleStatus},
     client::DbClient,
     entities::{
         source_transactions, transaction_flows, uniswap_pools, uniswap_swaps, uniswap_tokens,
     },
-    DbOperations,
+    DbOperations, OrderingKey,
 };
 use eyre::Result;
 use generic_indexer::handler::ChainEventHandler;
@@ -25,12 +30,21 @@ use tracing::{error, info, instrument, warn};
 use twine_evm_contracts::l2_twine_messenger::{L1Txns, L2TwineMessenger};
 
 use crate::{
+    addresses::{parse_address, TwineContracts},
     error::ParserError,
-    handler::{EvmEventHandler, LogContext},
-    twine::{get_event_name_from_signature_hash, PairCreated, Swap},
+    handler::{unparsed_log, EvmEventHandler, LogContext},
+    twine::{filtered_event_signatures, get_event_name_from_signature_hash, PairCreated, Swap},
 };
 
-use super::TWINE_EVENT_SIGNATURES;
+/// Swap amounts are uint256 but stored as NUMERIC through `Decimal`, whose
+/// range is much smaller. An amount past it fails the log instead of being
+/// stored as zero.
+fn swap_amount(value: &impl std::fmt::Display, field: &str) -> Result<Decimal> {
+    let value = value.to_string();
+    value
+        .parse::<Decimal>()
+        .map_err(|e| eyre::eyre!("Swap {} {} does not fit a decimal: {}", field, value, e))
+}
 
 #[derive(Clone)]
 pub struct TwineEventHandler {
@@ -38,12 +52,54 @@ pub struct TwineEventHandler {
     chain_id: u64,
     config: TwineConfig,
     twine_provider: Arc<EvmProvider>,
+    topics: Vec<&'static str>,
+    /// Swapped when the config is reloaded, e.g. after a redeploy
+    contracts: Arc<RwLock<TwineContracts>>,
+    /// Token metadata by address, so tokens shared by many pairs are only
+    /// called once
+    token_info_cache: Arc<Mutex<HashMap<Address, TokenInfo>>>,
 }
 
 #[async_trait]
 impl ChainEventHandler for TwineEventHandler {
     type LogType = Log;
 
+    fn event_type(&self, log: &Log) -> String {
+        log.topic0()
+            .map(get_event_name_from_signature_hash)
+            .unwrap_or_else(|| "Unknown Event".to_string())
+    }
+
+    fn dead_letter(&self, log: &Log, reason: String) -> Option<DbOperations> {
+        Some(unparsed_log(
+            self.chain_id,
+            log,
+            &self.event_type(log),
+            reason,
+        ))
+    }
+
+    fn ordering_key(&self, log: &Log) -> OrderingKey {
+        match log.topic0().copied() {
+            Some(L2TwineMessenger::SentMessage::SIGNATURE_HASH) => log
+                .log_decode::<L2TwineMessenger::SentMessage>()
+                .ok()
+                .and_then(|decoded| decoded.inner.data.nonce.try_into().ok())
+                .map(|nonce| OrderingKey::Bridge {
+                    chain_id: self.chain_id as i64,
+                    nonce,
+                })
+                .unwrap_or(OrderingKey::Unordered),
+            // A pool must be created before its swaps are recorded
+            Some(PairCreated::SIGNATURE_HASH) => log
+                .log_decode::<PairCreated>()
+                .map(|decoded| OrderingKey::Pair(decoded.inner.data.pair.to_string()))
+                .unwrap_or(OrderingKey::Unordered),
+            Some(Swap::SIGNATURE_HASH) => OrderingKey::Pair(log.address().to_string()),
+            _ => OrderingKey::Unordered,
+        }
+    }
+
     fn get_chain_config(&self) -> common::config::ChainConfig {
         self.config.common.clone()
     }
@@ -66,9 +122,24 @@ impl ChainEventHandler for TwineEventHandler {
 
         match *sig {
             L2TwineMessenger::L1TransactionsHandled::SIGNATURE_HASH => {
-                let (deposits, withdraws) = self.handle_l1_transactions_handled(log).await?;
-                operations.extend(deposits);
-                operations.extend(withdraws);
+                match self.handle_l1_transactions_handled(log.clone()).await {
+                    Ok((deposits, withdraws)) => {
+                        operations.extend(deposits);
+                        operations.extend(withdraws);
+                    }
+                    Err(e) => match e.downcast_ref::<ParserError>() {
+                        Some(err @ ParserError::InvalidHandleStatus { .. }) => {
+                            error!("Dead-lettering L1TransactionsHandled: {}", err);
+                            operations.push(unparsed_log(
+                                self.chain_id,
+                                &log,
+                                "L1TransactionsHandled",
+                                err.to_string(),
+                            ));
+                        }
+                        _ => return Err(e),
+                    },
+                }
             }
             L2TwineMessenger::SentMessage::SIGNATURE_HASH => {
                 let operation = self.handle_sent_message(log).await?;
@@ -96,13 +167,73 @@ impl TwineEventHandler {
         db_client: Arc<DbClient>,
         config: TwineConfig,
         twine_provider: Arc<EvmProvider>,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        let topics = filtered_event_signatures(&config.event_filter);
+        for event in &config.event_filter {
+            if !topics
+                .iter()
+                .any(|topic| topic.starts_with(&format!("{}(", event)))
+            {
+                warn!(
+                    "Unknown Twine event '{}' in event_filter, ignoring it",
+                    event
+                );
+            }
+        }
+        info!("Indexing Twine events: {:?}", topics);
+        let contracts = TwineContracts::from_config(&config)?;
+
+        Ok(Self {
             db_client,
             chain_id: config.common.chain_id,
             config,
             twine_provider,
+            topics,
+            contracts: Arc::new(RwLock::new(contracts)),
+            token_info_cache: Arc::new(Mutex::new(HashMap::default())),
+        })
+    }
+
+    /// Swaps in the contract addresses of `config`, e.g. after the messenger
+    /// is redeployed. Nothing changes when an address is invalid or `config`
+    /// is for another chain. Returns whether the addresses changed.
+    pub fn reload_contract_addresses(&self, config: &TwineConfig) -> Result<bool> {
+        if config.common.chain_id != self.chain_id {
+            return Err(eyre::eyre!(
+                "Reloaded config is for chain {}, handler indexes chain {}",
+                config.common.chain_id,
+                self.chain_id
+            ));
+        }
+
+        let updated = TwineContracts::from_config(config)?;
+        let mut current = self.contracts.write().unwrap();
+        if *current == updated {
+            return Ok(false);
+        }
+
+        info!(
+            chain_id = self.chain_id,
+            "Contract addresses reloaded: {:?} -> {:?}", *current, updated
+        );
+        *current = updated;
+        Ok(true)
+    }
+
+    /// Metadata of `token`, read from the token contract the first time the
+    /// address is seen
+    async fn token_info(&self, token: Address) -> TokenInfo {
+        let cached = self.token_info_cache.lock().unwrap().get(&token).cloned();
+        if let Some(token_info) = cached {
+            return token_info;
         }
+
+        let token_info = self.twine_provider.get_token_info(token).await;
+        self.token_info_cache
+            .lock()
+            .unwrap()
+            .insert(token, token_info.clone());
+        token_info
     }
 
     async fn decode_precompile_return(&self, event: Bytes) -> Result<L1Txns, ParserError> {
@@ -125,7 +256,7 @@ impl TwineEventHandler {
         tx_hash: String,
         block_number: i64,
         chain_id: u64,
-        status: u16,
+        status: HandleStatus,
         timestamp: DateTime<Utc>,
     ) -> (Vec<DbOperations>, Vec<DbOperations>) {
         let mut parsed_deposits = Vec::new();
@@ -140,7 +271,7 @@ impl TwineEventHandler {
                     chain_id: Set(chain_id as i64),
                     handle_block_number: Set(Some(block_number)),
                     handle_tx_hash: Set(Some(tx_hash.to_string())),
-                    handle_status: Set(Some(status as i16)),
+                    handle_status: Set(Some(status.as_i16())),
                     handled_at: Set(Some(timestamp.fixed_offset())),
                     is_handled: Set(Some(true)),
                     ..Default::default()
@@ -153,7 +284,7 @@ impl TwineEventHandler {
                     chain_id: Set(chain_id as i64),
                     handle_block_number: Set(Some(block_number)),
                     handle_tx_hash: Set(Some(tx_hash.to_string())),
-                    handle_status: Set(Some(status as i16)),
+                    handle_status: Set(Some(status.as_i16())),
                     handled_at: Set(Some(timestamp.fixed_offset())),
                     is_handled: Set(Some(true)),
                     ..Default::default()
@@ -170,6 +301,9 @@ impl TwineEventHandler {
         log: Log,
     ) -> Result<(Vec<DbOperations>, Vec<DbOperations>)> {
         let decoded = self.extract_log::<L2TwineMessenger::L1TransactionsHandled>(log, "")?;
+        let raw_status = decoded.data.status as u64;
+        let status = HandleStatus::from_event_status(raw_status)
+            .ok_or(ParserError::InvalidHandleStatus { status: raw_status })?;
 
         let pr = self
             .decode_precompile_return(decoded.data.transactionOutput)
@@ -180,7 +314,7 @@ impl TwineEventHandler {
             decoded.tx_hash_str,
             decoded.block_number,
             decoded.data.chainId.to::<u64>(),
-            decoded.data.status as u16,
+            status,
             decoded.timestamp,
         );
 
@@ -199,6 +333,7 @@ impl TwineEventHandler {
             twine_address: Set(format!("{:?}", data.from)),
             l1_address: Set(data.to),
             l2_token: Set(format!("{:?}", data.l2Token)),
+            token_standard: Set(Some(token_standard_for_l1_token(&data.l1Token))),
             l1_token: Set(data.l1Token),
             transaction_hash: Set(Some(decoded.tx_hash_str.clone())),
             timestamp: Set(Some(decoded.timestamp.fixed_offset())),
@@ -224,26 +359,10 @@ impl TwineEventHandler {
             sender: Set(data.sender.to_string()),
             to: Set(data.to.to_string()),
             pair: Set(log.address().to_string()),
-            amount0_in: Set(data
-                .amount0In
-                .to_string()
-                .parse::<Decimal>()
-                .unwrap_or_default()),
-            amount1_in: Set(data
-                .amount1In
-                .to_string()
-                .parse::<Decimal>()
-                .unwrap_or_default()),
-            amount0_out: Set(data
-                .amount0Out
-                .to_string()
-                .parse::<Decimal>()
-                .unwrap_or_default()),
-            amount1_out: Set(data
-                .amount1Out
-                .to_string()
-                .parse::<Decimal>()
-                .unwrap_or_default()),
+            amount0_in: Set(swap_amount(&data.amount0In, "amount0In")?),
+            amount1_in: Set(swap_amount(&data.amount1In, "amount1In")?),
+            amount0_out: Set(swap_amount(&data.amount0Out, "amount0Out")?),
+            amount1_out: Set(swap_amount(&data.amount1Out, "amount1Out")?),
             block_number: Set(decoded.block_number),
             block_time: Set(decoded.timestamp.fixed_offset()),
             created_at: Set(decoded.timestamp.fixed_offset()),
@@ -273,33 +392,24 @@ impl TwineEventHandler {
 
         let mut tokens = Vec::new();
 
-        let is_token0_exists = self
-            .db_client
-            .is_token_exists(&data.token0.to_string())
-            .await?;
-        let is_token1_exists = self
+        // Both tokens are looked up in one query, only unknown ones are read
+        // from their contracts
+        let known = self
             .db_client
-            .is_token_exists(&data.token1.to_string())
+            .get_token_info_for_addresses(vec![data.token0.to_string(), data.token1.to_string()])
             .await?;
 
-        if !is_token0_exists {
-            let token_info = self.twine_provider.get_token_info(data.token0).await?;
-            tokens.push(uniswap_tokens::ActiveModel {
-                address: Set(data.token0.to_string()),
-                name: Set(Some(token_info.name)),
-                symbol: Set(Some(token_info.symbol)),
-                decimals: Set(token_info.decimals as i32),
-                ..Default::default()
-            });
-        }
-
-        if !is_token1_exists {
-            let token_info = self.twine_provider.get_token_info(data.token1).await?;
+        for token in [data.token0, data.token1] {
+            let address = token.to_string();
+            if known.iter().any(|existing| existing.address == address) {
+                continue;
+            }
+            let token_info = self.token_info(token).await;
             tokens.push(uniswap_tokens::ActiveModel {
-                address: Set(data.token1.to_string()),
-                name: Set(Some(token_info.name)),
-                symbol: Set(Some(token_info.symbol)),
-                decimals: Set(token_info.decimals as i32),
+                address: Set(address),
+                name: Set(token_info.name),
+                symbol: Set(token_info.symbol),
+                decimals: Set(token_info.decimals.map(i32::from)),
                 ..Default::default()
             });
         }
@@ -314,32 +424,104 @@ impl TwineEventHandler {
 #[async_trait]
 impl EvmEventHandler for TwineEventHandler {
     fn relevant_topics(&self) -> Vec<&'static str> {
-        TWINE_EVENT_SIGNATURES.to_vec()
+        self.topics.clone()
     }
 
     async fn relevant_addresses(&self) -> Vec<alloy_primitives::Address> {
-        let mut addresss = vec![
-            self.config.l2_twine_messenger_address.clone(),
-            self.config.uniswap_factory_address.clone(),
-        ];
+        let contracts = *self.contracts.read().unwrap();
+        let mut addresss = vec![contracts.l2_twine_messenger];
 
-        let uniswap_pairs = match self.db_client.get_all_pair_addresses().await {
-            Ok(pairs) => pairs,
-            Err(e) => {
-                error!("Failed to get all uniswap pairs from database: {}", e);
-                Vec::new()
+        // Pool and pair contracts are only watched when their events are indexed
+        if self.topics.contains(&PairCreated::SIGNATURE) {
+            addresss.push(contracts.uniswap_factory);
+        }
+        if self.topics.contains(&Swap::SIGNATURE) {
+            let uniswap_pairs = match self.db_client.get_all_pair_addresses().await {
+                Ok(pairs) => pairs,
+                Err(e) => {
+                    error!("Failed to get all uniswap pairs from database: {}", e);
+                    Vec::new()
+                }
+            };
+
+            for pair in uniswap_pairs {
+                match parse_address("uniswap_pools.pair", &pair) {
+                    Ok(address) => addresss.push(address),
+                    Err(e) => error!("Not watching stored pair: {}", e),
+                }
             }
-        };
+        }
+
+        addresss
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_transport::mock::Asserter;
+    use sea_orm::DatabaseConnection;
+    use serde_json::json;
+
+    use super::*;
+
+    fn handler(asserter: &Asserter) -> TwineEventHandler {
+        let config: TwineConfig = serde_json::from_value(json!({
+            "common": {
+                "http_rpc_url": "http://localhost:8545",
+                "chain_id": 1337,
+                "start_block": 0,
+                "block_sync_batch_size": 100,
+                "block_time_ms": 1000,
+            },
+            "l2_twine_messenger_address": "0x1000000000000000000000000000000000000001",
+            "uniswap_factory_address": "0x5000000000000000000000000000000000000001",
+        }))
+        .unwrap();
+        TwineEventHandler::new(
+            Arc::new(DbClient::new(DatabaseConnection::Disconnected, None)),
+            config,
+            Arc::new(EvmProvider::mocked(asserter)),
+        )
+        .unwrap()
+    }
+
+    /// Queues the `name()`, `symbol()` and `decimals()` returns of a token
+    fn push_token(asserter: &Asserter, name: &str, symbol: &str, decimals: u8) {
+        asserter.push_success(&Bytes::from(name.to_string().abi_encode()));
+        asserter.push_success(&Bytes::from(symbol.to_string().abi_encode()));
+        asserter.push_success(&Bytes::from(decimals.abi_encode()));
+    }
+
+    #[tokio::test]
+    async fn token_metadata_is_called_once_per_address() {
+        let asserter = Asserter::new();
+        push_token(&asserter, "Wrapped Ether", "WETH", 18);
+        let handler = handler(&asserter);
+        let token = Address::repeat_byte(0x11);
+
+        let first = handler.token_info(token).await;
+        // Nothing is queued anymore, so a second call would leave the fields unset
+        let second = handler.token_info(token).await;
+
+        for token_info in [first, second] {
+            assert_eq!(token_info.name.as_deref(), Some("Wrapped Ether"));
+            assert_eq!(token_info.symbol.as_deref(), Some("WETH"));
+            assert_eq!(token_info.decimals, Some(18));
+        }
+    }
 
-        addresss.extend(uniswap_pairs);
+    #[tokio::test]
+    async fn failed_metadata_calls_leave_their_fields_unset() {
+        let asserter = Asserter::new();
+        asserter.push_failure_msg("execution reverted");
+        asserter.push_success(&Bytes::from("TKN".to_string().abi_encode()));
+        asserter.push_success(&Bytes::new());
+        let handler = handler(&asserter);
 
-        let contract_addresss = addresss
-            .iter()
-            .map(|addr| addr.parse::<alloy_primitives::Address>())
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| eyre::eyre!("Invalid address format: {}", e))
-            .unwrap();
+        let token_info = handler.token_info(Address::repeat_byte(0x22)).await;
 
-        contract_addresss
+        assert_eq!(token_info.name, None);
+        assert_eq!(token_info.symbol.as_deref(), Some("TKN"));
+        assert_eq!(token_info.decimals, None);
     }
 }
@@ -38,6 +38,19 @@ pub const TWINE_EVENT_SIGNATURES: &[&str] = &[
     Swap::SIGNATURE,
 ];
 
+/// Signatures from `TWINE_EVENT_SIGNATURES` whose event name is listed in
+/// `event_filter`, all of them when the filter is empty
+pub fn filtered_event_signatures(event_filter: &[String]) -> Vec<&'static str> {
+    TWINE_EVENT_SIGNATURES
+        .iter()
+        .copied()
+        .filter(|signature| {
+            let name = signature.split('(').next().unwrap_or(signature);
+            event_filter.is_empty() || event_filter.iter().any(|event| event == name)
+        })
+        .collect()
+}
+
 pub struct TwineIndexer {
     /// WS provider for live subscription.
     ws_provider: Arc<dyn Provider + Send + Sync>,
@@ -0,0 +1,124 @@
+use std::collections::VecDeque;
+
+use eyre::eyre;
+
+use crate::metrics::EventOutcome;
+
+/// Share of a chain's recent logs that were dead-lettered. A contract upgrade
+/// that breaks parsing shows up as a flood of dead-letters, which should stop
+/// the chain rather than be written past.
+pub struct DeadLetterMonitor {
+    max_ratio: f64,
+    window: usize,
+    /// Whether each of the most recent parsed or dead-lettered logs was
+    /// dead-lettered, oldest first
+    recent: VecDeque<bool>,
+    dead_lettered: usize,
+}
+
+impl DeadLetterMonitor {
+    /// Disabled when `max_ratio` is zero or `window` is empty
+    pub fn new(max_ratio: f64, window: usize) -> Self {
+        Self {
+            max_ratio,
+            window,
+            recent: VecDeque::with_capacity(window),
+            dead_lettered: 0,
+        }
+    }
+
+    /// Records the outcomes of a batch of logs. Skipped logs do not count.
+    /// Fails once the window is full and more than `max_ratio` of it was
+    /// dead-lettered.
+    pub fn record(&mut self, outcomes: impl IntoIterator<Item = EventOutcome>) -> eyre::Result<()> {
+        if self.max_ratio <= 0.0 || self.window == 0 {
+            return Ok(());
+        }
+
+        for outcome in outcomes {
+            let dead_lettered = match outcome {
+                EventOutcome::Skipped => continue,
+                EventOutcome::Parsed => false,
+                EventOutcome::DeadLettered => true,
+            };
+            if self.recent.len() == self.window && self.recent.pop_front() == Some(true) {
+                self.dead_lettered -= 1;
+            }
+            self.recent.push_back(dead_lettered);
+            if dead_lettered {
+                self.dead_lettered += 1;
+            }
+        }
+
+        if self.recent.len() < self.window {
+            return Ok(());
+        }
+        let ratio = self.dead_lettered as f64 / self.window as f64;
+        if ratio > self.max_ratio {
+            return Err(eyre!(
+                "{} of the last {} logs were dead-lettered, above the {} limit",
+                self.dead_lettered,
+                self.window,
+                self.max_ratio
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::iter::repeat_n;
+
+    use EventOutcome::{DeadLettered, Parsed, Skipped};
+
+    #[test]
+    fn chain_stops_once_the_window_is_over_the_threshold() {
+        let mut monitor = DeadLetterMonitor::new(0.5, 4);
+
+        // 3 of 3 dead-lettered, but the window is not full yet
+        monitor.record(repeat_n(DeadLettered, 3)).unwrap();
+        assert!(monitor.record([Parsed]).is_err());
+    }
+
+    #[test]
+    fn ratio_at_the_threshold_is_allowed() {
+        let mut monitor = DeadLetterMonitor::new(0.5, 4);
+
+        monitor
+            .record([DeadLettered, Parsed, DeadLettered, Parsed])
+            .unwrap();
+        // Pushes the first dead-letter out of the window
+        monitor.record([Parsed]).unwrap();
+        assert!(monitor.record(repeat_n(DeadLettered, 3)).is_err());
+    }
+
+    #[test]
+    fn old_dead_letters_leave_the_window() {
+        let mut monitor = DeadLetterMonitor::new(0.5, 4);
+
+        monitor.record([Parsed, DeadLettered]).unwrap();
+        monitor.record(repeat_n(Parsed, 10)).unwrap();
+        monitor
+            .record([DeadLettered, DeadLettered, Parsed, Parsed])
+            .unwrap();
+    }
+
+    #[test]
+    fn skipped_logs_do_not_count() {
+        let mut monitor = DeadLetterMonitor::new(0.5, 4);
+
+        monitor.record(repeat_n(Skipped, 10)).unwrap();
+        monitor.record([DeadLettered, DeadLettered]).unwrap();
+        monitor.record(repeat_n(Skipped, 10)).unwrap();
+        assert!(monitor.record([DeadLettered, Parsed]).is_err());
+    }
+
+    #[test]
+    fn zero_ratio_disables_the_monitor() {
+        let mut monitor = DeadLetterMonitor::new(0.0, 4);
+
+        monitor.record(repeat_n(DeadLettered, 10)).unwrap();
+    }
+}
@@ -1,6 +1,8 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
 use common::config::ChainConfig;
-use database::DbOperations;
+use database::{DbOperations, OrderingKey, entities::sea_orm_active_enums::HeightKindEnum};
 
 #[async_trait]
 pub trait ChainEventHandler {
@@ -9,6 +11,34 @@ pub trait ChainEventHandler {
     async fn handle_event(&self, log: Self::LogType) -> eyre::Result<Vec<DbOperations>>;
     fn get_chain_config(&self) -> ChainConfig;
 
+    /// Logs sharing a key are handled one at a time in log order, so that e.g.
+    /// a batch is committed before it is finalized. Unordered logs run freely.
+    fn ordering_key(&self, _log: &Self::LogType) -> OrderingKey {
+        OrderingKey::Unordered
+    }
+
+    /// Name of the event carried by `log`, used to label metrics
+    fn event_type(&self, _log: &Self::LogType) -> String {
+        "unknown".to_string()
+    }
+
+    /// Dead-letter operation recording `log` as unparseable, `None` when the
+    /// chain has no way to store it
+    fn dead_letter(&self, _log: &Self::LogType, _reason: String) -> Option<DbOperations> {
+        None
+    }
+
+    /// How long to wait before retrying a batch that failed on `error`, `None`
+    /// to use the indexer's default delay
+    fn retry_delay(&self, _error: &eyre::Report) -> Option<Duration> {
+        None
+    }
+
+    /// Whether heights on this chain are EVM blocks or Solana slots
+    fn height_kind(&self) -> HeightKindEnum {
+        HeightKindEnum::Block
+    }
+
     fn chain_id(&self) -> u64 {
         let config = self.get_chain_config();
         config.chain_id
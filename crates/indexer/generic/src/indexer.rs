@@ -1,17 +1,26 @@
 use std::{
+    collections::HashMap,
     sync::Arc,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use crate::{handler::ChainEventHandler, state::IndexerState};
+use crate::{
+    deadletter::DeadLetterMonitor,
+    handler::ChainEventHandler,
+    metrics::{self, EventOutcome},
+    progress::SyncProgress,
+    readiness::{self, SyncPhase},
+    sink::{OperationSink, StdoutJsonSink},
+    state::IndexerState,
+    writer::DbWriterPool,
+};
 use async_trait::async_trait;
 use common::config::IndexerSettings;
-use database::client::DbClient;
+use database::{DbOperations, OrderingKey, client::DbClient};
 use eyre::Error;
 use tokio::{sync::Semaphore, task::JoinSet, time::sleep};
-use tracing::{debug, error, info, instrument};
-
-const MAX_RETRIES: i32 = 20;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, instrument, warn};
 
 #[async_trait]
 pub trait ChainIndexer: Send + Sync {
@@ -36,9 +45,49 @@ pub trait ChainIndexer: Send + Sync {
 
     fn get_db_client(&self) -> Arc<DbClient>;
 
+    /// Shared limit on how many chains may backfill at the same time.
+    fn get_backfill_limiter(&self) -> Option<Arc<Semaphore>> {
+        None
+    }
+
+    /// Cancelled when the indexer should stop reading new blocks, persist what
+    /// it has queued and return.
+    fn get_shutdown_token(&self) -> Option<CancellationToken> {
+        None
+    }
+
+    /// Height of the last block the indexer and the chain still agree on when
+    /// blocks that were already indexed have been reorged, `None` otherwise.
+    async fn detect_reorg(&self) -> eyre::Result<Option<u64>> {
+        Ok(None)
+    }
+
+    /// Remembers the blocks of a fetched range so a later reorg of them can be
+    /// detected.
+    async fn record_block_hashes(
+        &self,
+        _logs: &[<Self::EventHandler as ChainEventHandler>::LogType],
+        _to: u64,
+    ) -> eyre::Result<()> {
+        Ok(())
+    }
+
     #[instrument(skip_all, fields(CHAIN = %self.get_event_handler().chain_id()))]
     async fn run(&mut self) -> Result<(), Error> {
-        let initial_height = self.get_initial_state().await?;
+        let checkpoint = self.get_initial_state().await?;
+        let chain_config = self.get_event_handler().get_chain_config();
+
+        // Inserts are idempotent, so going over the window again only fills
+        // in what was missing
+        let initial_height = checkpoint
+            .saturating_sub(chain_config.startup_reprocess_window)
+            .max(chain_config.start_block);
+        if initial_height < checkpoint {
+            info!(
+                "Reprocessing blocks {} to {} behind the checkpoint before resuming",
+                initial_height, checkpoint
+            );
+        }
 
         info!(
             "Initial height for chain: {} is {}",
@@ -49,12 +98,13 @@ pub trait ChainIndexer: Send + Sync {
         let mut indexer_state = IndexerState::new(
             initial_height,
             self.get_event_handler().chain_id(),
-            self.get_event_handler().get_chain_config().block_time_ms,
+            chain_config.block_time_ms,
         );
+        readiness::set_phase(self.get_event_handler().chain_id(), SyncPhase::Historical);
 
         match self.sync_chain(&mut indexer_state).await {
             Ok(()) => {
-                info!("Historical sync completed successfully");
+                info!("Indexer stopped after draining queued writes");
             }
             Err(e) => {
                 error!("Error during historical sync: {:?}", e);
@@ -70,29 +120,120 @@ pub trait ChainIndexer: Send + Sync {
         let chain_config = self.get_event_handler().get_chain_config();
         let block_time_ms = chain_config.block_time_ms;
         let batch_size = chain_config.block_sync_batch_size;
-        loop {
-            let current_chain_height = match self.get_current_chain_height().await {
+        let confirmation_blocks = chain_config.confirmation_blocks;
+        let live_lag_blocks = match self.get_indexer_settings().max_live_lag_ms {
+            Some(lag_ms) => chain_config.lag_threshold_blocks(Duration::from_millis(lag_ms)),
+            None => batch_size,
+        };
+        let retry = chain_config.retry_policy();
+        let writer = DbWriterPool::new(
+            self.get_db_client(),
+            self.get_event_handler().chain_id(),
+            self.get_event_handler().height_kind(),
+            indexer_state.get_last_processed_block(),
+            &self.get_indexer_settings(),
+        );
+        let stdout_sink = self
+            .get_indexer_settings()
+            .emit_json_to_stdout
+            .then(|| StdoutJsonSink::new(self.get_event_handler().chain_id()));
+        let shutdown = self.get_shutdown_token().unwrap_or_default();
+        let mut dead_letters = DeadLetterMonitor::new(
+            chain_config.max_dead_letter_ratio,
+            chain_config.dead_letter_window,
+        );
+        let mut reported_head = None;
+        while !shutdown.is_cancelled() {
+            let chain_head = match self.get_current_chain_height().await {
                 Ok(height) => height,
                 Err(e) => {
+                    metrics::rpc_retry(self.get_event_handler().chain_id());
                     error!("Error while getting current chain height: {:?}", e);
-                    sleep(Duration::from_millis(block_time_ms)).await;
+                    sleep_unless_cancelled(&shutdown, Duration::from_millis(block_time_ms)).await;
                     continue;
                 }
             };
 
+            // Shared through the database so the API can report the lag
+            if reported_head != Some(chain_head) {
+                match self
+                    .get_db_client()
+                    .update_chain_head(
+                        self.get_event_handler().chain_id() as i64,
+                        chain_head as i64,
+                    )
+                    .await
+                {
+                    Ok(()) => reported_head = Some(chain_head),
+                    Err(e) => error!("Failed to record chain head {}: {:?}", chain_head, e),
+                }
+            }
+
+            // Only index up to the height that has enough confirmations
+            let current_chain_height = chain_head.saturating_sub(confirmation_blocks);
+
             let current_indexer_height = indexer_state.get_last_processed_block();
+            let phase =
+                if current_chain_height.saturating_sub(current_indexer_height) > live_lag_blocks {
+                    SyncPhase::Historical
+                } else {
+                    SyncPhase::Live
+                };
+            readiness::set_phase(self.get_event_handler().chain_id(), phase);
+
+            match self.detect_reorg().await {
+                Ok(Some(ancestor)) => {
+                    self.roll_back_to(ancestor, &writer, indexer_state).await?;
+                    continue;
+                }
+                Ok(None) => {}
+                Err(e) => error!("Error while checking for a reorg: {:?}", e),
+            }
+
             if current_indexer_height >= current_chain_height {
                 info!(
                     "Historical sync caught up to block {}. Switching to live or sleeping.",
                     current_indexer_height
                 );
-                sleep(Duration::from_millis(block_time_ms / 2)).await;
+                sleep_unless_cancelled(&shutdown, Duration::from_millis(block_time_ms / 2)).await;
                 continue;
             }
 
+            // Only backfills compete for the shared limit, live indexing never waits on it
+            let _backfill_permit = match self.get_backfill_limiter() {
+                Some(limiter) if current_chain_height - current_indexer_height > batch_size => {
+                    info!(
+                        "Waiting for a historical sync slot, {} blocks behind",
+                        current_chain_height - current_indexer_height
+                    );
+                    tokio::select! {
+                        permit = limiter.acquire_owned() => Some(permit?),
+                        _ = shutdown.cancelled() => break,
+                    }
+                }
+                _ => None,
+            };
+
+            let mut progress = SyncProgress::new(
+                current_indexer_height,
+                current_chain_height,
+                self.get_indexer_settings().progress_log_interval_blocks,
+            );
+
             let mut start_block = current_indexer_height;
-            while start_block <= current_chain_height {
-                let mut reconnect_attempt = 0;
+            let mut reconnect_attempt = 0;
+            while start_block <= current_chain_height && !shutdown.is_cancelled() {
+                if writer.has_failed() {
+                    let resume_from = writer.recover().await?;
+                    error!(
+                        "A queued database write failed, resuming from last persisted block {}",
+                        resume_from
+                    );
+                    indexer_state.update_block(resume_from);
+                    start_block = resume_from;
+                    continue;
+                }
+
                 let batch_end = (start_block + batch_size).min(current_chain_height);
 
                 info!(
@@ -105,6 +246,14 @@ pub trait ChainIndexer: Send + Sync {
 
                 match self.get_historical_logs(start_block, batch_end).await {
                     Ok(logs) => {
+                        reconnect_attempt = 0;
+                        if let Err(e) = self.record_block_hashes(&logs, batch_end).await {
+                            error!(
+                                "Failed to record block hashes up to block {}: {:?}",
+                                batch_end, e
+                            );
+                        }
+
                         if logs.is_empty() {
                             info!(
                                 "No relevant logs found in blocks {} to {}",
@@ -118,46 +267,91 @@ pub trait ChainIndexer: Send + Sync {
                                 batch_end
                             );
 
-                            match self.process_logs(logs, batch_end).await {
-                                Ok(_) => {
-                                    info!(
-                                        "Successfully processed and persisted logs for blocks {} to {}",
-                                        start_block, batch_end
-                                    );
-                                }
+                            let prepared = match self.prepare_logs(logs).await {
+                                Ok(prepared) => prepared,
                                 Err(e) => {
                                     error!(
                                         "Error processing batch for blocks {} to {}: {:?}. Will retry this batch.",
                                         start_block, batch_end, e
                                     );
-                                    // Implement retry logic with backoff, or halt if unrecoverable
-                                    tokio::time::sleep(Duration::from_secs(5)).await;
+                                    let delay = e
+                                        .downcast_ref::<RetryAfter>()
+                                        .map(|retry| retry.0)
+                                        .unwrap_or(BATCH_RETRY_DELAY);
+                                    sleep_unless_cancelled(&shutdown, delay).await;
                                     continue; // Retry the same batch
                                 }
+                            };
+
+                            // Nothing of the batch is written and the checkpoint
+                            // stays put until an operator restarts the indexer
+                            let outcomes = prepared
+                                .iter()
+                                .map(|operations| EventOutcome::from_operations(operations));
+                            if let Err(e) = dead_letters.record(outcomes) {
+                                error!(
+                                    chain_id = self.get_event_handler().chain_id(),
+                                    "CRITICAL: pausing indexing at blocks {} to {}: {:?}",
+                                    start_block,
+                                    batch_end,
+                                    e
+                                );
+                                shutdown.cancelled().await;
+                                break;
                             }
+
+                            let printed = match &stdout_sink {
+                                Some(sink) => sink.write(prepared.clone(), batch_end).await,
+                                None => Ok(()),
+                            };
+                            if let Err(e) = printed {
+                                error!("Failed to print operations to stdout: {:?}", e);
+                            }
+
+                            if let Err(e) = writer.submit(prepared, batch_end).await {
+                                error!(
+                                    "Database write failed while queueing blocks {} to {}: {:?}",
+                                    start_block, batch_end, e
+                                );
+                                let resume_from = writer.recover().await?;
+                                info!("Resuming from last persisted block {}", resume_from);
+                                indexer_state.update_block(resume_from);
+                                start_block = resume_from;
+                                continue;
+                            }
+
+                            debug!(
+                                "Queued logs for blocks {} to {} for persistence",
+                                start_block, batch_end
+                            );
                         }
 
                         indexer_state.update_block(batch_end);
                         start_block = batch_end + 1;
+
+                        if let Some(report) = progress.record(batch_end) {
+                            info!("Catchup progress: {}", report);
+                        }
                     }
 
                     Err(e) => {
-                        if reconnect_attempt > MAX_RETRIES {
-                            panic!(
+                        if reconnect_attempt >= retry.max_retries {
+                            return Err(eyre::eyre!(
                                 "Max retries reached while getting logs from {:?} to {:?}, error: {:?}",
-                                start_block, batch_end, e
-                            );
+                                start_block,
+                                batch_end,
+                                e
+                            ));
                         }
                         reconnect_attempt += 1;
+                        metrics::rpc_retry(self.get_event_handler().chain_id());
 
                         error!(
                             "Error while getting logs from {:?} to {:?}, error: {:?}",
                             start_block, batch_end, e
                         );
 
-                        let delay = Duration::from_secs(5);
-
-                        tokio::time::sleep(delay * 2u32.pow(reconnect_attempt as u32)).await;
+                        sleep_unless_cancelled(&shutdown, retry.delay_for(reconnect_attempt)).await;
                         continue; // Retry the same batch
                     }
                 }
@@ -169,10 +363,78 @@ pub trait ChainIndexer: Send + Sync {
                 block_time_ms,
                 None,
             );
-            tokio::time::sleep(sleep_duration).await;
+            sleep_unless_cancelled(&shutdown, sleep_duration).await;
+        }
 
-            continue;
+        // Nothing new is read past this point, only what is already queued is written
+        let timeout = Duration::from_millis(self.get_indexer_settings().shutdown_drain_timeout_ms);
+        info!("Shutting down, draining queued database writes");
+        let persisted = writer.drain(timeout).await?;
+        info!("Drained queued writes, last persisted block {}", persisted);
+        Ok(())
+    }
+
+    /// Removes what was indexed above `ancestor` once queued writes have
+    /// landed, and resumes indexing from it
+    async fn roll_back_to(
+        &self,
+        ancestor: u64,
+        writer: &DbWriterPool,
+        indexer_state: &mut IndexerState,
+    ) -> Result<(), Error> {
+        let handler = self.get_event_handler();
+        warn!(
+            "Reorg detected, rolling back to common ancestor block {}",
+            ancestor
+        );
+
+        writer.flush().await?;
+        self.get_db_client()
+            .rollback_chain_from(handler.chain_id() as i64, ancestor as i64 + 1)
+            .await?;
+        self.get_db_client()
+            .upsert_last_synced(
+                handler.chain_id() as i64,
+                handler.height_kind(),
+                ancestor as i64,
+            )
+            .await?;
+
+        writer.rewind(ancestor);
+        indexer_state.update_block(ancestor);
+        Ok(())
+    }
+
+    /// Parses the logs in `from..=to` and hands the resulting operations to
+    /// `sink` instead of the database writer. Handlers may still read from the
+    /// database, but nothing is written and `last_synced` is left untouched.
+    #[instrument(skip_all, fields(CHAIN = %self.get_event_handler().chain_id()))]
+    async fn parse_range(&self, from: u64, to: u64, sink: &dyn OperationSink) -> Result<(), Error> {
+        let batch_size = self
+            .get_event_handler()
+            .get_chain_config()
+            .block_sync_batch_size
+            .max(1);
+
+        let mut start_block = from;
+        while start_block <= to {
+            let batch_end = (start_block + batch_size - 1).min(to);
+            let logs = self.get_historical_logs(start_block, batch_end).await?;
+
+            info!(
+                "Parsing {} logs from blocks {} to {}",
+                logs.len(),
+                start_block,
+                batch_end
+            );
+
+            let prepared = self.prepare_logs(logs).await?;
+            sink.write(prepared, batch_end).await?;
+
+            start_block = batch_end + 1;
         }
+
+        Ok(())
     }
 
     /// Calculate sleep duration based on actual block timing
@@ -222,45 +484,108 @@ pub trait ChainIndexer: Send + Sync {
         }
     }
 
-    async fn process_logs(
+    /// Parses logs into database operations without writing anything.
+    ///
+    /// Logs are handled concurrently, except that logs sharing an ordering
+    /// key (the same bridge nonce or batch) are handled one after another in
+    /// log order. Results are returned in log order.
+    async fn prepare_logs(
         &self,
         logs: Vec<<Self::EventHandler as ChainEventHandler>::LogType>,
-        max_seen_height: u64,
-    ) -> eyre::Result<()> {
+    ) -> eyre::Result<Vec<Vec<DbOperations>>> {
         let concurrency_limit =
             self.get_indexer_settings().max_concurrency_for_log_process as usize;
         let semaphore = Arc::new(Semaphore::new(concurrency_limit));
         let handler = self.get_event_handler();
 
+        // Group dependent logs so each group runs serially on one task
+        let mut groups: Vec<Vec<(usize, _)>> = Vec::new();
+        let mut group_by_key: HashMap<OrderingKey, usize> = HashMap::new();
+        for (index, log) in logs.into_iter().enumerate() {
+            match handler.ordering_key(&log) {
+                OrderingKey::Unordered => groups.push(vec![(index, log)]),
+                key => match group_by_key.get(&key) {
+                    Some(&group) => groups[group].push((index, log)),
+                    None => {
+                        group_by_key.insert(key, groups.len());
+                        groups.push(vec![(index, log)]);
+                    }
+                },
+            }
+        }
+
         let mut prepare_tasks = JoinSet::new();
 
-        for log in logs {
+        for group in groups {
             let permit = semaphore.clone().acquire_owned().await?;
             let handler_clone = handler.clone();
 
-            let log_clone = log.clone();
             prepare_tasks.spawn(async move {
-                let result = handler_clone.handle_event(log_clone).await;
+                let chain_id = handler_clone.chain_id();
+                let mut results = Vec::with_capacity(group.len());
+                for (index, log) in group {
+                    let event_type = handler_clone.event_type(&log);
+                    let result =
+                        handler_clone
+                            .handle_event(log.clone())
+                            .await
+                            .and_then(|operations| {
+                                dead_letter_violations(
+                                    &handler_clone,
+                                    &log,
+                                    &event_type,
+                                    operations,
+                                )
+                            });
+
+                    match &result {
+                        Ok(operations) => metrics::event_outcome(
+                            chain_id,
+                            &event_type,
+                            EventOutcome::from_operations(operations),
+                        ),
+                        Err(_) => metrics::parse_failure(chain_id, &event_type),
+                    }
+
+                    // Later logs in the group depend on this one
+                    let failed = result.is_err();
+                    results.push((index, result));
+                    if failed {
+                        break;
+                    }
+                }
                 drop(permit);
 
-                result
+                results
             });
         }
 
-        let mut prepared_event_data_results = Vec::new();
+        let mut prepared_by_index = Vec::new();
         let mut batch_had_errors = false;
+        // Longest delay asked for by the failed events, `None` once one of
+        // them wants the default
+        let mut retry_after = Some(Duration::ZERO);
         while let Some(task_result) = prepare_tasks.join_next().await {
             match task_result {
-                Ok(Ok(task)) => prepared_event_data_results.push(task),
+                Ok(results) => {
+                    for (index, result) in results {
+                        match result {
+                            Ok(task) => prepared_by_index.push((index, task)),
 
-                Ok(Err(parser_error)) => {
-                    // Log individual parser error, decide if it's critical
+                            Err(parser_error) => {
+                                // Log individual parser error, decide if it's critical
 
-                    batch_had_errors = true;
-                    error!(
-                        "Event parsing failed: {:?}. Storing as error.",
-                        parser_error
-                    );
+                                batch_had_errors = true;
+                                retry_after = retry_after
+                                    .zip(handler.retry_delay(&parser_error))
+                                    .map(|(longest, delay)| longest.max(delay));
+                                error!(
+                                    "Event parsing failed: {:?}. Storing as error.",
+                                    parser_error
+                                );
+                            }
+                        }
+                    }
                 }
 
                 Err(join_error) => {
@@ -278,50 +603,439 @@ pub trait ChainIndexer: Send + Sync {
         }
 
         if batch_had_errors {
+            if let Some(delay) = retry_after {
+                return Err(eyre::Report::new(RetryAfter(delay)));
+            }
             return Err(eyre::eyre!(
                 "One or more events failed parsing in the batch. See logs for details."
             ));
         }
 
-        if prepared_event_data_results.is_empty() {
-            debug!("No successfully prepared event data to process ");
-            return Ok(());
-        }
+        prepared_by_index.sort_by_key(|(index, _)| *index);
+        let prepared_event_data_results: Vec<Vec<DbOperations>> = prepared_by_index
+            .into_iter()
+            .map(|(_, operations)| operations)
+            .collect();
 
         debug!(
             "Successfully prepared events: {}",
             prepared_event_data_results.len()
         );
 
-        match self
-            .get_db_client()
-            .process_bulk_l1_database_operations(prepared_event_data_results)
+        Ok(prepared_event_data_results)
+    }
+}
+
+/// Replaces operations the database constraints would reject, or that break
+/// one of the chain's configured event invariants, with a dead-letter for
+/// `log`, so one bad value does not stall the whole chain
+fn dead_letter_violations<H: ChainEventHandler>(
+    handler: &H,
+    log: &H::LogType,
+    event_type: &str,
+    operations: Vec<DbOperations>,
+) -> eyre::Result<Vec<DbOperations>> {
+    let invariants = handler.get_chain_config().invariants;
+    operations
+        .into_iter()
+        .map(|operation| {
+            let violation = operation
+                .constraint_violation()
+                .or_else(|| invariants.violation(&operation));
+            (operation, violation)
+        })
+        .map(|(operation, violation)| match violation {
+            None => Ok(operation),
+            Some(reason) => {
+                error!("Dead-lettering {}: {}", event_type, reason);
+                handler.dead_letter(log, reason.clone()).ok_or_else(|| {
+                    eyre::eyre!(
+                        "{} produced an invalid {}: {}",
+                        event_type,
+                        operation.kind(),
+                        reason
+                    )
+                })
+            }
+        })
+        .collect()
+}
+
+/// Delay before retrying a batch whose events failed to prepare
+const BATCH_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Batch failure whose events all asked to be retried after a given delay
+#[derive(Debug)]
+struct RetryAfter(Duration);
+
+impl std::fmt::Display for RetryAfter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "One or more events deferred in the batch, retrying in {:?}",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for RetryAfter {}
+
+/// Sleeps for `duration`, waking early when `shutdown` is cancelled
+async fn sleep_unless_cancelled(shutdown: &CancellationToken, duration: Duration) {
+    tokio::select! {
+        _ = sleep(duration) => {}
+        _ = shutdown.cancelled() => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::config::ChainConfig;
+    use database::{
+        entities::{sea_orm_active_enums::HeightKindEnum, source_transactions},
+        test_db,
+    };
+    use sea_orm::{ActiveValue::Set, DatabaseConnection, EntityTrait, prelude::Decimal};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const CHAIN: u64 = 11155111;
+
+    #[derive(Clone)]
+    struct NoopHandler;
+
+    #[async_trait]
+    impl ChainEventHandler for NoopHandler {
+        type LogType = u64;
+
+        async fn handle_event(&self, _log: u64) -> eyre::Result<Vec<DbOperations>> {
+            Ok(Vec::new())
+        }
+
+        fn get_chain_config(&self) -> ChainConfig {
+            serde_json::from_value(json!({
+                "http_rpc_url": "http://localhost:8545",
+                "chain_id": CHAIN,
+                "start_block": 0,
+                "block_sync_batch_size": 100,
+                "block_time_ms": 1000,
+            }))
+            .unwrap()
+        }
+    }
+
+    struct TestIndexer {
+        db_client: Arc<DbClient>,
+    }
+
+    #[async_trait]
+    impl ChainIndexer for TestIndexer {
+        type EventHandler = NoopHandler;
+
+        async fn get_initial_state(&self) -> eyre::Result<u64> {
+            Ok(0)
+        }
+
+        async fn get_historical_logs(&self, _from: u64, _to: u64) -> eyre::Result<Vec<u64>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_current_chain_height(&self) -> eyre::Result<u64> {
+            Ok(0)
+        }
+
+        fn get_block_number_from_log(&self, log: &u64) -> Option<u64> {
+            Some(*log)
+        }
+
+        fn get_event_handler(&self) -> NoopHandler {
+            NoopHandler
+        }
+
+        fn get_indexer_settings(&self) -> IndexerSettings {
+            settings()
+        }
+
+        fn get_db_client(&self) -> Arc<DbClient> {
+            self.db_client.clone()
+        }
+    }
+
+    fn settings() -> IndexerSettings {
+        serde_json::from_value(json!({
+            "max_log_batch_size": 100,
+            "max_log_batch_time": 1000,
+            "max_concurrency_for_log_process": 1,
+        }))
+        .unwrap()
+    }
+
+    fn source(nonce: i64, block_number: i64) -> DbOperations {
+        DbOperations::BridgeSourceTransaction(source_transactions::ActiveModel {
+            chain_id: Set(CHAIN as i64),
+            nonce: Set(nonce),
+            transaction_type: Set(
+                database::entities::sea_orm_active_enums::TransactionTypeEnum::Deposit,
+            ),
+            block_number: Set(block_number),
+            l1_token: Set("0xl1".to_string()),
+            l2_token: Set("0xl2".to_string()),
+            l1_address: Set("0xfrom".to_string()),
+            twine_address: Set("0xto".to_string()),
+            amount: Set(Decimal::ONE),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn roll_back_to_resumes_from_the_ancestor() {
+        let db_client = Arc::new(test_db::client(false).await);
+        db_client
+            .process_bulk_l1_database_operations(vec![vec![source(1, 90), source(2, 110)]])
             .await
-        {
-            Ok(_) => {
-                debug!("Succesfully updated the database for a batch of logs");
-                self.get_db_client()
-                    .upsert_last_synced(handler.chain_id() as i64, max_seen_height as i64)
-                    .await?;
+            .unwrap();
+        db_client
+            .upsert_last_synced(CHAIN as i64, HeightKindEnum::Block, 120)
+            .await
+            .unwrap();
+        let indexer = TestIndexer {
+            db_client: db_client.clone(),
+        };
+        let writer = DbWriterPool::new(
+            db_client.clone(),
+            CHAIN,
+            HeightKindEnum::Block,
+            120,
+            &settings(),
+        );
+        let mut indexer_state = IndexerState::new(120, CHAIN, 1000);
+
+        indexer
+            .roll_back_to(100, &writer, &mut indexer_state)
+            .await
+            .unwrap();
+
+        let nonces: Vec<i64> = source_transactions::Entity::find()
+            .all(&db_client.primary)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|tx| tx.nonce)
+            .collect();
+        assert_eq!(nonces, vec![1]);
+        assert_eq!(
+            db_client
+                .get_last_synced(CHAIN as i64, HeightKindEnum::Block)
+                .await
+                .unwrap(),
+            Some(100)
+        );
+        assert_eq!(writer.last_persisted(), 100);
+        assert_eq!(indexer_state.get_last_processed_block(), 100);
+    }
+
+    /// Backfills 1000 blocks that hold no logs, counting how many indexers
+    /// are fetching logs at once
+    struct BackfillIndexer {
+        db_client: Arc<DbClient>,
+        limiter: Arc<Semaphore>,
+        shutdown: CancellationToken,
+        fetching: Arc<AtomicUsize>,
+        max_fetching: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl ChainIndexer for BackfillIndexer {
+        type EventHandler = NoopHandler;
+
+        async fn get_initial_state(&self) -> eyre::Result<u64> {
+            Ok(0)
+        }
+
+        async fn get_historical_logs(&self, _from: u64, _to: u64) -> eyre::Result<Vec<u64>> {
+            let fetching = self.fetching.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_fetching.fetch_max(fetching, Ordering::SeqCst);
+            sleep(Duration::from_millis(10)).await;
+            self.fetching.fetch_sub(1, Ordering::SeqCst);
+            Ok(Vec::new())
+        }
+
+        async fn get_current_chain_height(&self) -> eyre::Result<u64> {
+            Ok(1000)
+        }
+
+        fn get_block_number_from_log(&self, log: &u64) -> Option<u64> {
+            Some(*log)
+        }
+
+        fn get_event_handler(&self) -> NoopHandler {
+            NoopHandler
+        }
+
+        fn get_indexer_settings(&self) -> IndexerSettings {
+            settings()
+        }
+
+        fn get_db_client(&self) -> Arc<DbClient> {
+            self.db_client.clone()
+        }
+
+        fn get_backfill_limiter(&self) -> Option<Arc<Semaphore>> {
+            Some(self.limiter.clone())
+        }
+
+        fn get_shutdown_token(&self) -> Option<CancellationToken> {
+            Some(self.shutdown.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn historical_syncs_beyond_the_limit_wait_for_a_slot() {
+        let db_client = Arc::new(DbClient::new(DatabaseConnection::Disconnected, None));
+        let limiter = Arc::new(Semaphore::new(2));
+        let shutdown = CancellationToken::new();
+        let fetching = Arc::new(AtomicUsize::new(0));
+        let max_fetching = Arc::new(AtomicUsize::new(0));
+
+        let mut indexers = JoinSet::new();
+        for _ in 0..4 {
+            let indexer = BackfillIndexer {
+                db_client: db_client.clone(),
+                limiter: limiter.clone(),
+                shutdown: shutdown.clone(),
+                fetching: fetching.clone(),
+                max_fetching: max_fetching.clone(),
+            };
+            indexers.spawn(async move {
+                let mut indexer_state = IndexerState::new(0, CHAIN, 1000);
+                indexer.sync_chain(&mut indexer_state).await
+            });
+        }
+        sleep(Duration::from_millis(500)).await;
+        shutdown.cancel();
+        while let Some(result) = indexers.join_next().await {
+            result.unwrap().unwrap();
+        }
+
+        assert_eq!(max_fetching.load(Ordering::SeqCst), 2);
+    }
+
+    /// Handles `(id, batch)` logs slowly, tracking how many run at once
+    /// overall and within batch 7, and the order batch 7 logs finish in
+    #[derive(Clone, Default)]
+    struct KeyedHandler {
+        running: Arc<AtomicUsize>,
+        max_running: Arc<AtomicUsize>,
+        running_in_batch: Arc<AtomicUsize>,
+        max_running_in_batch: Arc<AtomicUsize>,
+        batch_order: Arc<std::sync::Mutex<Vec<u64>>>,
+    }
+
+    #[async_trait]
+    impl ChainEventHandler for KeyedHandler {
+        type LogType = (u64, Option<i64>);
+
+        async fn handle_event(
+            &self,
+            (id, batch): (u64, Option<i64>),
+        ) -> eyre::Result<Vec<DbOperations>> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running.fetch_max(running, Ordering::SeqCst);
+            if batch.is_some() {
+                let running = self.running_in_batch.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_running_in_batch
+                    .fetch_max(running, Ordering::SeqCst);
             }
 
-            Err(e) => {
-                return Err(eyre::eyre!(
-                    "Error while bulking database operation {:?}",
-                    e
-                ));
+            sleep(Duration::from_millis(20)).await;
+
+            if batch.is_some() {
+                self.batch_order.lock().unwrap().push(id);
+                self.running_in_batch.fetch_sub(1, Ordering::SeqCst);
             }
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Ok(Vec::new())
         }
 
-        Ok(())
+        fn get_chain_config(&self) -> ChainConfig {
+            NoopHandler.get_chain_config()
+        }
+
+        fn ordering_key(&self, (_, batch): &(u64, Option<i64>)) -> OrderingKey {
+            batch.map_or(OrderingKey::Unordered, OrderingKey::Batch)
+        }
     }
-    async fn process_buffer(
-        &self,
-        buffer: &mut Vec<<Self::EventHandler as ChainEventHandler>::LogType>,
-        max_seen_block: i64,
-    ) -> Result<(), Error> {
-        let logs = std::mem::replace(buffer, Vec::with_capacity(100));
-        self.process_logs(logs, max_seen_block as u64).await?;
-        Ok(())
+
+    struct KeyedIndexer {
+        db_client: Arc<DbClient>,
+        handler: KeyedHandler,
+    }
+
+    #[async_trait]
+    impl ChainIndexer for KeyedIndexer {
+        type EventHandler = KeyedHandler;
+
+        async fn get_initial_state(&self) -> eyre::Result<u64> {
+            Ok(0)
+        }
+
+        async fn get_historical_logs(
+            &self,
+            _from: u64,
+            _to: u64,
+        ) -> eyre::Result<Vec<(u64, Option<i64>)>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_current_chain_height(&self) -> eyre::Result<u64> {
+            Ok(0)
+        }
+
+        fn get_block_number_from_log(&self, (id, _): &(u64, Option<i64>)) -> Option<u64> {
+            Some(*id)
+        }
+
+        fn get_event_handler(&self) -> KeyedHandler {
+            self.handler.clone()
+        }
+
+        fn get_indexer_settings(&self) -> IndexerSettings {
+            serde_json::from_value(json!({
+                "max_log_batch_size": 100,
+                "max_log_batch_time": 1000,
+                "max_concurrency_for_log_process": 8,
+            }))
+            .unwrap()
+        }
+
+        fn get_db_client(&self) -> Arc<DbClient> {
+            self.db_client.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn independent_logs_run_concurrently_and_a_batch_in_order() {
+        let indexer = KeyedIndexer {
+            db_client: Arc::new(DbClient::new(DatabaseConnection::Disconnected, None)),
+            handler: KeyedHandler::default(),
+        };
+        let logs = vec![
+            (1, Some(7)),
+            (2, None),
+            (3, Some(7)),
+            (4, None),
+            (5, None),
+            (6, Some(7)),
+        ];
+
+        let prepared = indexer.prepare_logs(logs).await.unwrap();
+
+        let handler = &indexer.handler;
+        assert_eq!(prepared.len(), 6);
+        assert_eq!(handler.max_running.load(Ordering::SeqCst), 4);
+        assert_eq!(handler.max_running_in_batch.load(Ordering::SeqCst), 1);
+        assert_eq!(*handler.batch_order.lock().unwrap(), vec![1, 3, 6]);
     }
 }
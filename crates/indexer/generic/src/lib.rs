@@ -1,4 +1,11 @@
+pub mod deadletter;
 pub mod handler;
 pub mod indexer;
+pub mod metrics;
+pub mod progress;
+pub mod readiness;
+pub mod sink;
 pub mod state;
 pub mod types;
+pub mod watchdog;
+pub mod writer;
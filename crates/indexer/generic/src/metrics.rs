@@ -0,0 +1,231 @@
+use std::{
+    collections::BTreeMap,
+    sync::{LazyLock, RwLock},
+    time::Duration,
+};
+
+use database::DbOperations;
+use metrics::{
+    Label, counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram,
+};
+pub use metrics_exporter_prometheus::PrometheusHandle;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
+
+/// How handling a single log ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EventOutcome {
+    /// The log produced database operations
+    Parsed,
+    /// The log was recognised as nothing to index
+    Skipped,
+    /// The log could not be parsed and was stored in unparsed_logs
+    DeadLettered,
+}
+
+impl EventOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventOutcome::Parsed => "parsed",
+            EventOutcome::Skipped => "skipped",
+            EventOutcome::DeadLettered => "dead_lettered",
+        }
+    }
+
+    /// Classifies the operations a handler returned for one log. Cursor and
+    /// processed signature bookkeeping alone does not count as parsing anything.
+    pub fn from_operations(operations: &[DbOperations]) -> Self {
+        if operations.iter().all(|op| {
+            matches!(
+                op,
+                DbOperations::SvmCursor(_) | DbOperations::ProcessedSignature(_)
+            )
+        }) {
+            EventOutcome::Skipped
+        } else if operations
+            .iter()
+            .any(|op| matches!(op, DbOperations::UnparsedLog(_)))
+        {
+            EventOutcome::DeadLettered
+        } else {
+            EventOutcome::Parsed
+        }
+    }
+}
+
+const EVENT_OUTCOMES: &str = "indexer_event_outcomes_total";
+const SYNC_DIVERGENCE: &str = "indexer_sync_divergence_blocks";
+const RPC_RETRIES: &str = "indexer_rpc_retries_total";
+const RPC_RECONNECTS: &str = "indexer_rpc_reconnects_total";
+const PARSE_FAILURES: &str = "indexer_parse_failures_total";
+const DB_WRITE_DURATION: &str = "indexer_db_write_duration_seconds";
+
+/// Upper bounds in seconds of the database write duration buckets
+const DB_WRITE_BUCKETS: [f64; 10] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 10.0];
+
+static CHAIN_NAMES: LazyLock<RwLock<BTreeMap<u64, String>>> =
+    LazyLock::new(|| RwLock::new(BTreeMap::new()));
+
+/// Installs the Prometheus recorder every metric below is recorded to. Until
+/// it is installed, recording is a no-op.
+pub fn install_recorder() -> eyre::Result<PrometheusHandle> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(DB_WRITE_DURATION.to_string()),
+            &DB_WRITE_BUCKETS,
+        )?
+        .install_recorder()?;
+
+    describe_counter!(
+        EVENT_OUTCOMES,
+        "Logs handled per chain, event type and outcome"
+    );
+    describe_gauge!(
+        SYNC_DIVERGENCE,
+        "Heights to index again to restore batches missing in blockscout"
+    );
+    describe_counter!(RPC_RETRIES, "Failed RPC calls retried per chain");
+    describe_counter!(
+        RPC_RECONNECTS,
+        "RPC providers rebuilt after a no data timeout per chain"
+    );
+    describe_counter!(
+        PARSE_FAILURES,
+        "Logs whose handler failed per chain and event type"
+    );
+    describe_histogram!(
+        DB_WRITE_DURATION,
+        metrics::Unit::Seconds,
+        "Time taken to write one batch of operations"
+    );
+    Ok(handle)
+}
+
+/// Names `chain` in the `chain_name` label of its series
+pub fn set_chain_name(chain: u64, name: &str) {
+    CHAIN_NAMES.write().unwrap().insert(chain, name.to_string());
+}
+
+/// Records how many heights `chain` has to index again to restore the batches blockscout lost
+pub fn set_sync_divergence(chain: u64, blocks: u64) {
+    gauge!(SYNC_DIVERGENCE, chain_labels(chain)).set(blocks as f64);
+}
+
+/// Counts one log of `event_type` on `chain` ending with `outcome`
+pub fn event_outcome(chain: u64, event_type: &str, outcome: EventOutcome) {
+    let mut labels = chain_labels(chain);
+    labels.push(Label::new("event_type", event_type.to_string()));
+    labels.push(Label::new("outcome", outcome.as_str()));
+    counter!(EVENT_OUTCOMES, labels).increment(1);
+}
+
+/// Counts a failed RPC call on `chain` that is about to be retried
+pub fn rpc_retry(chain: u64) {
+    counter!(RPC_RETRIES, chain_labels(chain)).increment(1);
+}
+
+/// Counts an RPC provider of `chain` rebuilt after its head stopped advancing
+pub fn rpc_reconnect(chain: u64) {
+    counter!(RPC_RECONNECTS, chain_labels(chain)).increment(1);
+}
+
+/// Counts a log of `event_type` on `chain` whose handler returned an error,
+/// failing its batch rather than being dead-lettered
+pub fn parse_failure(chain: u64, event_type: &str) {
+    let mut labels = chain_labels(chain);
+    labels.push(Label::new("event_type", event_type.to_string()));
+    counter!(PARSE_FAILURES, labels).increment(1);
+}
+
+/// Records how long writing one batch of operations for `chain` took
+pub fn observe_db_write(chain: u64, duration: Duration) {
+    histogram!(DB_WRITE_DURATION, chain_labels(chain)).record(duration.as_secs_f64());
+}
+
+/// The `chain` label, and `chain_name` once the chain has been named
+fn chain_labels(chain: u64) -> Vec<Label> {
+    let mut labels = vec![Label::new("chain", chain.to_string())];
+    if let Some(name) = CHAIN_NAMES.read().unwrap().get(&chain) {
+        labels.push(Label::new("chain_name", name.clone()));
+    }
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use metrics_util::{
+        CompositeKey, MetricKind,
+        debugging::{DebugValue, DebuggingRecorder},
+    };
+
+    use super::*;
+
+    fn recorded(record: impl FnOnce()) -> Vec<(CompositeKey, DebugValue)> {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, record);
+        snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| (key, value))
+            .collect()
+    }
+
+    fn labels(key: &CompositeKey) -> Vec<(String, String)> {
+        key.key()
+            .labels()
+            .map(|label| (label.key().to_string(), label.value().to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn outcomes_are_counted_per_labelled_series() {
+        set_chain_name(7001, "Ethereum");
+        let recorded = recorded(|| {
+            event_outcome(7001, "Deposit", EventOutcome::Parsed);
+            event_outcome(7001, "Deposit", EventOutcome::Parsed);
+            event_outcome(7001, "Deposit", EventOutcome::DeadLettered);
+        });
+
+        let parsed = recorded
+            .iter()
+            .find(|(key, _)| labels(key).contains(&("outcome".into(), "parsed".into())))
+            .unwrap();
+        assert_eq!(parsed.0.kind(), MetricKind::Counter);
+        assert_eq!(parsed.0.key().name(), EVENT_OUTCOMES);
+        assert_eq!(
+            labels(&parsed.0),
+            vec![
+                ("chain".into(), "7001".into()),
+                ("chain_name".into(), "Ethereum".into()),
+                ("event_type".into(), "Deposit".into()),
+                ("outcome".into(), "parsed".into()),
+            ]
+        );
+        assert_eq!(parsed.1, DebugValue::Counter(2));
+        assert_eq!(recorded.len(), 2);
+    }
+
+    #[test]
+    fn unnamed_chain_is_labelled_by_id_only() {
+        let recorded = recorded(|| rpc_retry(7002));
+
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].0.key().name(), RPC_RETRIES);
+        assert_eq!(
+            labels(&recorded[0].0),
+            vec![("chain".into(), "7002".into())]
+        );
+    }
+
+    #[test]
+    fn db_writes_are_recorded_in_seconds() {
+        let recorded = recorded(|| observe_db_write(7003, Duration::from_millis(250)));
+
+        assert_eq!(recorded[0].0.kind(), MetricKind::Histogram);
+        match &recorded[0].1 {
+            DebugValue::Histogram(values) => assert_eq!(values.len(), 1),
+            other => panic!("expected a histogram, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// Tracks a catchup over `start..=target` and reports every `interval` blocks
+pub struct SyncProgress {
+    start: u64,
+    target: u64,
+    interval: u64,
+    next_report: u64,
+    started_at: Instant,
+}
+
+/// Snapshot of a catchup's progress, formatted for the logs
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressReport {
+    pub current: u64,
+    pub start: u64,
+    pub target: u64,
+    pub percent: f64,
+    pub eta: Option<Duration>,
+}
+
+impl SyncProgress {
+    /// An `interval` of 0 disables reporting
+    pub fn new(start: u64, target: u64, interval: u64) -> Self {
+        Self {
+            start,
+            target,
+            interval,
+            next_report: start.saturating_add(interval),
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Records that blocks up to `current` are processed, returning a report
+    /// once at least `interval` blocks were processed since the last one
+    pub fn record(&mut self, current: u64) -> Option<ProgressReport> {
+        if self.interval == 0 || current < self.next_report {
+            return None;
+        }
+        self.next_report = current.saturating_add(self.interval);
+
+        let total = self.target.saturating_sub(self.start).max(1);
+        let done = current.saturating_sub(self.start).min(total);
+        let remaining = total - done;
+
+        let elapsed = self.started_at.elapsed();
+        let eta = (done > 0).then(|| elapsed.mul_f64(remaining as f64 / done as f64));
+
+        Some(ProgressReport {
+            current,
+            start: self.start,
+            target: self.target,
+            percent: done as f64 * 100.0 / total as f64,
+            eta,
+        })
+    }
+}
+
+impl fmt::Display for ProgressReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "block {} of {} (from {}), {:.1}% complete",
+            self.current, self.target, self.start, self.percent
+        )?;
+        match self.eta {
+            Some(eta) => write!(f, ", ETA {}s", eta.as_secs()),
+            None => write!(f, ", ETA unknown"),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{LazyLock, Mutex},
+};
+
+/// Where a chain indexer is in its sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncPhase {
+    /// Backfilling, further behind the head than `max_live_lag_ms` allows,
+    /// one sync batch when unset
+    Historical,
+    /// At the head or within the live lag of it
+    Live,
+}
+
+impl fmt::Display for SyncPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncPhase::Historical => write!(f, "Historical"),
+            SyncPhase::Live => write!(f, "Live"),
+        }
+    }
+}
+
+static PHASES: LazyLock<Mutex<BTreeMap<u64, SyncPhase>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+pub fn set_phase(chain: u64, phase: SyncPhase) {
+    PHASES.lock().unwrap().insert(chain, phase);
+}
+
+/// Phase of every chain indexer that has started
+pub fn phases() -> BTreeMap<u64, SyncPhase> {
+    PHASES.lock().unwrap().clone()
+}
+
+/// Ready once at least one chain indexer started and all of them are live
+pub fn is_ready() -> bool {
+    let phases = PHASES.lock().unwrap();
+    !phases.is_empty() && phases.values().all(|phase| *phase == SyncPhase::Live)
+}
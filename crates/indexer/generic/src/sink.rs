@@ -0,0 +1,97 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    sync::Mutex,
+};
+
+use async_trait::async_trait;
+use database::DbOperations;
+use serde_json::json;
+
+/// Destination for operations produced by the event handlers.
+#[async_trait]
+pub trait OperationSink: Send + Sync {
+    async fn write(&self, operations: Vec<Vec<DbOperations>>, checkpoint: u64) -> eyre::Result<()>;
+}
+
+/// Sink that keeps operations in memory instead of writing them, used to
+/// validate parsing over a block range without touching the database.
+#[derive(Default)]
+pub struct RecordingSink {
+    operations: Mutex<Vec<DbOperations>>,
+    last_checkpoint: Mutex<Option<u64>>,
+}
+
+impl RecordingSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn operations(&self) -> Vec<DbOperations> {
+        self.operations.lock().unwrap().clone()
+    }
+
+    pub fn last_checkpoint(&self) -> Option<u64> {
+        *self.last_checkpoint.lock().unwrap()
+    }
+
+    /// Number of recorded operations per operation kind
+    pub fn summary(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for operation in self.operations.lock().unwrap().iter() {
+            *counts.entry(operation.kind()).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+#[async_trait]
+impl OperationSink for RecordingSink {
+    async fn write(&self, operations: Vec<Vec<DbOperations>>, checkpoint: u64) -> eyre::Result<()> {
+        self.operations
+            .lock()
+            .unwrap()
+            .extend(operations.into_iter().flatten());
+        *self.last_checkpoint.lock().unwrap() = Some(checkpoint);
+        Ok(())
+    }
+}
+
+/// Sink that prints each operation as one JSON line on stdout, for piping
+/// into local tooling. A batch is written under a single stdout lock so its
+/// lines never interleave with other output.
+pub struct StdoutJsonSink {
+    chain_id: u64,
+}
+
+impl StdoutJsonSink {
+    pub fn new(chain_id: u64) -> Self {
+        Self { chain_id }
+    }
+
+    /// One JSON line per operation, each ending in a newline
+    pub fn render(&self, operations: &[Vec<DbOperations>], checkpoint: u64) -> String {
+        let mut lines = String::new();
+        for operation in operations.iter().flatten() {
+            let line = json!({
+                "chain_id": self.chain_id,
+                "checkpoint": checkpoint,
+                "operation": operation.to_json(),
+            });
+            lines.push_str(&line.to_string());
+            lines.push('\n');
+        }
+        lines
+    }
+}
+
+#[async_trait]
+impl OperationSink for StdoutJsonSink {
+    async fn write(&self, operations: Vec<Vec<DbOperations>>, checkpoint: u64) -> eyre::Result<()> {
+        let lines = self.render(&operations, checkpoint);
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(lines.as_bytes())?;
+        stdout.flush()?;
+        Ok(())
+    }
+}
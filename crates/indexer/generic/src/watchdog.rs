@@ -0,0 +1,115 @@
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use eyre::eyre;
+use tracing::warn;
+
+/// Notices when the head a chain's RPC reports stops advancing, which is how
+/// a connection that silently stopped delivering data shows up while polling.
+pub struct HeadWatchdog {
+    timeout: Duration,
+    /// Highest head seen and when it was first seen
+    last_advance: Mutex<Option<(u64, Instant)>>,
+    /// Stalls reported since startup, logged so reconnect frequency can be alerted on
+    stalls: AtomicU64,
+}
+
+impl HeadWatchdog {
+    /// Disabled when `timeout` is zero
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            last_advance: Mutex::new(None),
+            stalls: AtomicU64::new(0),
+        }
+    }
+
+    /// Stalls reported since startup
+    pub fn stall_count(&self) -> u64 {
+        self.stalls.load(Ordering::Relaxed)
+    }
+
+    /// Records a polled head. Fails once the head has not advanced for the
+    /// whole timeout, then starts a new window so a stall is reported once
+    /// per timeout rather than on every poll.
+    pub fn observe(&self, chain_id: u64, head: u64) -> eyre::Result<()> {
+        if self.timeout.is_zero() {
+            return Ok(());
+        }
+
+        let mut last_advance = self.last_advance.lock().unwrap();
+        let now = Instant::now();
+        match *last_advance {
+            Some((last_head, since)) if head <= last_head => {
+                if now.duration_since(since) < self.timeout {
+                    return Ok(());
+                }
+                *last_advance = Some((last_head, now));
+                let stall_count = self.stalls.fetch_add(1, Ordering::Relaxed) + 1;
+                warn!(
+                    chain_id,
+                    head = last_head,
+                    timeout = ?self.timeout,
+                    stall_count,
+                    "No data timeout: chain head has not advanced"
+                );
+                Err(eyre!(
+                    "Chain {} head stuck at {} for {:?}",
+                    chain_id,
+                    last_head,
+                    self.timeout
+                ))
+            }
+            _ => {
+                *last_advance = Some((head, now));
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread::sleep;
+
+    use super::*;
+
+    #[test]
+    fn stall_is_reported_once_per_timeout() {
+        let watchdog = HeadWatchdog::new(Duration::from_millis(20));
+        watchdog.observe(1, 10).unwrap();
+        watchdog.observe(1, 10).unwrap();
+
+        sleep(Duration::from_millis(30));
+        assert!(watchdog.observe(1, 10).is_err());
+        assert!(watchdog.observe(1, 10).is_ok());
+        assert_eq!(watchdog.stall_count(), 1);
+
+        sleep(Duration::from_millis(30));
+        assert!(watchdog.observe(1, 9).is_err());
+        assert_eq!(watchdog.stall_count(), 2);
+    }
+
+    #[test]
+    fn advancing_head_never_stalls() {
+        let watchdog = HeadWatchdog::new(Duration::from_millis(20));
+        for head in 0..3 {
+            sleep(Duration::from_millis(15));
+            watchdog.observe(1, head).unwrap();
+        }
+        assert_eq!(watchdog.stall_count(), 0);
+    }
+
+    #[test]
+    fn zero_timeout_disables_the_check() {
+        let watchdog = HeadWatchdog::new(Duration::ZERO);
+        watchdog.observe(1, 10).unwrap();
+        watchdog.observe(1, 10).unwrap();
+        assert_eq!(watchdog.stall_count(), 0);
+    }
+}
@@ -0,0 +1,893 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use common::config::IndexerSettings;
+use database::{
+    DbOperations, OrderingKey,
+    client::DbClient,
+    entities::{processed_signatures, sea_orm_active_enums::HeightKindEnum, svm_cursor},
+    is_check_violation,
+};
+use eyre::eyre;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, error};
+
+use crate::metrics;
+
+/// A slice of a batch routed to a single writer task.
+struct WriteJob {
+    operations: Vec<DbOperations>,
+    done: oneshot::Sender<eyre::Result<()>>,
+}
+
+/// Marks the height that can be persisted once every job submitted before it
+/// has been written.
+struct Checkpoint {
+    height: Option<u64>,
+    /// Logs the operations behind `height` came from
+    events: u64,
+    /// Solana signature cursors, upserted together with `height`
+    cursors: Vec<svm_cursor::ActiveModel>,
+    /// Solana transactions whose events are all behind `height`, marked
+    /// processed together with it
+    processed: Vec<processed_signatures::ActiveModel>,
+    acks: Vec<oneshot::Receiver<eyre::Result<()>>>,
+    flushed: Option<oneshot::Sender<()>>,
+}
+
+struct WriterState {
+    last_persisted: AtomicU64,
+    failure: Mutex<Option<String>>,
+}
+
+impl WriterState {
+    fn fail(&self, reason: String) {
+        let mut failure = self.failure.lock().unwrap();
+        if failure.is_none() {
+            *failure = Some(reason);
+        }
+    }
+
+    fn failure(&self) -> Option<String> {
+        self.failure.lock().unwrap().clone()
+    }
+}
+
+/// Applies `DbOperations` on a fixed set of writer tasks so that fetching and
+/// parsing logs is not blocked on database latency.
+///
+/// Operations are sharded by their ordering key, so everything touching the
+/// same `(chain_id, nonce)`, batch or pair is written in submission order by a
+/// single task. A batch spread over several workers is not written
+/// atomically: one shard can commit while another fails. `last_synced` is what
+/// makes this safe, it only advances once all writes below the checkpoint have
+/// succeeded, and after a failure the pool refuses new batches until
+/// [`Self::recover`] hands back the last persisted height. The caller resumes
+/// from there and the shards that did commit are written again, which every
+/// insert tolerates. Solana signature cursors and processed signature marks
+/// are held back the same way, a cursor or mark that committed while rows
+/// below it failed on another worker would make those signatures never be
+/// fetched again.
+pub struct DbWriterPool {
+    workers: Vec<mpsc::Sender<WriteJob>>,
+    checkpoints: mpsc::Sender<Checkpoint>,
+    state: Arc<WriterState>,
+}
+
+impl DbWriterPool {
+    pub fn new(
+        db_client: Arc<DbClient>,
+        chain_id: u64,
+        height_kind: HeightKindEnum,
+        initial_height: u64,
+        settings: &IndexerSettings,
+    ) -> Self {
+        let worker_count = settings.db_writer_workers.max(1);
+        let capacity = settings.db_writer_queue_capacity.max(1);
+
+        let workers = (0..worker_count)
+            .map(|worker| {
+                let (tx, rx) = mpsc::channel(capacity);
+                tokio::spawn(run_worker(worker, chain_id, db_client.clone(), rx));
+                tx
+            })
+            .collect();
+
+        let state = Arc::new(WriterState {
+            last_persisted: AtomicU64::new(initial_height),
+            failure: Mutex::new(None),
+        });
+
+        let (checkpoints, checkpoint_rx) = mpsc::channel(capacity * worker_count);
+        let debounce = CheckpointDebounce {
+            every_events: settings.checkpoint_every_events,
+            interval: (settings.checkpoint_interval_ms > 0)
+                .then(|| Duration::from_millis(settings.checkpoint_interval_ms)),
+        };
+        tokio::spawn(run_checkpoints(
+            db_client,
+            chain_id,
+            height_kind,
+            state.clone(),
+            debounce,
+            checkpoint_rx,
+        ));
+
+        Self {
+            workers,
+            checkpoints,
+            state,
+        }
+    }
+
+    /// Queues a batch of prepared operations and the height to checkpoint once
+    /// they are written. Waits only when the writer queues are full.
+    pub async fn submit(
+        &self,
+        operations: Vec<Vec<DbOperations>>,
+        checkpoint_height: u64,
+    ) -> eyre::Result<()> {
+        if let Some(reason) = self.state.failure() {
+            return Err(eyre!("A previous database write failed: {}", reason));
+        }
+
+        let events = operations.len() as u64;
+        let mut cursors = Vec::new();
+        let mut processed = Vec::new();
+        let mut sharded: Vec<Vec<DbOperations>> =
+            (0..self.workers.len()).map(|_| Vec::new()).collect();
+        for operation in operations.into_iter().flatten() {
+            match operation {
+                DbOperations::SvmCursor(cursor) => cursors.push(cursor),
+                DbOperations::ProcessedSignature(mark) => processed.push(mark),
+                operation => {
+                    let shard = self.shard_for(&operation.ordering_key());
+                    sharded[shard].push(operation);
+                }
+            }
+        }
+
+        let mut acks = Vec::new();
+        for (shard, operations) in sharded.into_iter().enumerate() {
+            if operations.is_empty() {
+                continue;
+            }
+
+            let (done, ack) = oneshot::channel();
+            self.workers[shard]
+                .send(WriteJob { operations, done })
+                .await
+                .map_err(|_| eyre!("Database writer {} has stopped", shard))?;
+            acks.push(ack);
+        }
+
+        self.checkpoints
+            .send(Checkpoint {
+                height: Some(checkpoint_height),
+                events,
+                cursors,
+                processed,
+                acks,
+                flushed: None,
+            })
+            .await
+            .map_err(|_| eyre!("Checkpoint writer has stopped"))?;
+
+        Ok(())
+    }
+
+    /// Waits until every operation submitted so far has been handled.
+    pub async fn flush(&self) -> eyre::Result<()> {
+        let (flushed, wait) = oneshot::channel();
+        self.checkpoints
+            .send(Checkpoint {
+                height: None,
+                events: 0,
+                cursors: Vec::new(),
+                processed: Vec::new(),
+                acks: Vec::new(),
+                flushed: Some(flushed),
+            })
+            .await
+            .map_err(|_| eyre!("Checkpoint writer has stopped"))?;
+
+        wait.await
+            .map_err(|_| eyre!("Checkpoint writer stopped before flushing"))
+    }
+
+    /// Drains outstanding writes after a failure and returns the last height
+    /// that was persisted, so the caller can resume from there.
+    pub async fn recover(&self) -> eyre::Result<u64> {
+        self.flush().await?;
+        *self.state.failure.lock().unwrap() = None;
+        Ok(self.last_persisted())
+    }
+
+    /// Waits up to `timeout` for everything submitted so far to be written
+    /// and checkpointed before the pool is dropped on shutdown, returning the
+    /// last persisted height.
+    pub async fn drain(&self, timeout: Duration) -> eyre::Result<u64> {
+        tokio::time::timeout(timeout, self.flush())
+            .await
+            .map_err(|_| eyre!("Queued database writes did not drain within {:?}", timeout))??;
+        Ok(self.last_persisted())
+    }
+
+    /// Moves the persisted height back after rows above it were rolled back
+    pub fn rewind(&self, height: u64) {
+        self.state.last_persisted.store(height, Ordering::SeqCst);
+    }
+
+    pub fn last_persisted(&self) -> u64 {
+        self.state.last_persisted.load(Ordering::SeqCst)
+    }
+
+    pub fn has_failed(&self) -> bool {
+        self.state.failure().is_some()
+    }
+
+    fn shard_for(&self, key: &OrderingKey) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.workers.len() as u64) as usize
+    }
+}
+
+async fn run_worker(
+    worker: usize,
+    chain_id: u64,
+    db_client: Arc<DbClient>,
+    mut jobs: mpsc::Receiver<WriteJob>,
+) {
+    while let Some(job) = jobs.recv().await {
+        let count = job.operations.len();
+        let started = Instant::now();
+        let result = match db_client
+            .process_bulk_l1_database_operations(vec![job.operations.clone()])
+            .await
+        {
+            Err(e) if is_check_violation(&e) => {
+                dead_letter_violations(worker, &db_client, job.operations).await
+            }
+            result => result,
+        }
+        .map_err(|e| eyre!("Error while bulking database operation {:?}", e));
+        metrics::observe_db_write(chain_id, started.elapsed());
+
+        if let Err(e) = &result {
+            error!(
+                "Database writer {} failed to apply {} operations: {:?}",
+                worker, count, e
+            );
+        } else {
+            debug!("Database writer {} applied {} operations", worker, count);
+        }
+
+        let _ = job.done.send(result);
+    }
+}
+
+/// Writes `operations` one at a time after their batch was rejected by a
+/// CHECK constraint, dead-lettering the ones the database rejects so one bad
+/// value does not stall the chain
+async fn dead_letter_violations(
+    worker: usize,
+    db_client: &DbClient,
+    operations: Vec<DbOperations>,
+) -> eyre::Result<()> {
+    for operation in operations {
+        let Err(e) = db_client
+            .process_bulk_l1_database_operations(vec![vec![operation.clone()]])
+            .await
+        else {
+            continue;
+        };
+        if !is_check_violation(&e) {
+            return Err(e);
+        }
+
+        let kind = operation.kind();
+        let reason = format!("Rejected by a CHECK constraint: {:?}", e);
+        let Some(dead_letter) = operation.into_dead_letter(reason) else {
+            return Err(e);
+        };
+        error!(
+            "Database writer {} dead-lettering a {} rejected by a CHECK constraint",
+            worker, kind
+        );
+        db_client
+            .process_bulk_l1_database_operations(vec![vec![dead_letter]])
+            .await?;
+    }
+    Ok(())
+}
+
+/// How often `last_synced` is written. With neither limit set it is written
+/// for every checkpoint.
+struct CheckpointDebounce {
+    every_events: u64,
+    interval: Option<Duration>,
+}
+
+impl CheckpointDebounce {
+    fn is_due(&self, events: u64, since_write: Duration) -> bool {
+        match (self.every_events, self.interval) {
+            (0, None) => true,
+            (every_events, interval) => {
+                (every_events > 0 && events >= every_events)
+                    || interval.is_some_and(|interval| since_write >= interval)
+            }
+        }
+    }
+}
+
+/// Writes `last_synced` once the writes below each checkpoint landed, at most
+/// as often as `debounce` allows. Heights held back are written on a flush,
+/// which shutdown and reorg handling both go through, so a crash re-processes
+/// at most the debounce window and the inserts are idempotent.
+async fn run_checkpoints<S: CheckpointStore>(
+    store: Arc<S>,
+    chain_id: u64,
+    height_kind: HeightKindEnum,
+    state: Arc<WriterState>,
+    debounce: CheckpointDebounce,
+    mut checkpoints: mpsc::Receiver<Checkpoint>,
+) {
+    let mut pending: Option<u64> = None;
+    let mut pending_events = 0;
+    let mut pending_cursors = Vec::new();
+    let mut pending_processed = Vec::new();
+    let mut last_write = Instant::now();
+
+    loop {
+        let next = match (pending, debounce.interval) {
+            (Some(_), Some(interval)) => {
+                let remaining = interval.saturating_sub(last_write.elapsed());
+                match tokio::time::timeout(remaining, checkpoints.recv()).await {
+                    Ok(next) => next,
+                    Err(_) => {
+                        if let Some(height) = pending.take() {
+                            let cursors = std::mem::take(&mut pending_cursors);
+                            let processed = std::mem::take(&mut pending_processed);
+                            persist_height(
+                                store.as_ref(),
+                                chain_id,
+                                height_kind,
+                                &state,
+                                height,
+                                cursors,
+                                processed,
+                            )
+                            .await;
+                        }
+                        pending_events = 0;
+                        last_write = Instant::now();
+                        continue;
+                    }
+                }
+            }
+            _ => checkpoints.recv().await,
+        };
+        let Some(checkpoint) = next else {
+            break;
+        };
+
+        for ack in checkpoint.acks {
+            match ack.await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => state.fail(e.to_string()),
+                Err(_) => state.fail("database writer dropped a pending write".to_string()),
+            }
+        }
+
+        if let Some(height) = checkpoint.height {
+            pending = Some(height);
+            pending_events += checkpoint.events;
+            pending_cursors.extend(checkpoint.cursors);
+            pending_processed.extend(checkpoint.processed);
+        }
+
+        let due =
+            checkpoint.flushed.is_some() || debounce.is_due(pending_events, last_write.elapsed());
+        if due {
+            if let Some(height) = pending.take() {
+                let cursors = std::mem::take(&mut pending_cursors);
+                let processed = std::mem::take(&mut pending_processed);
+                persist_height(
+                    store.as_ref(),
+                    chain_id,
+                    height_kind,
+                    &state,
+                    height,
+                    cursors,
+                    processed,
+                )
+                .await;
+                pending_events = 0;
+                last_write = Instant::now();
+            }
+        }
+
+        if let Some(flushed) = checkpoint.flushed {
+            let _ = flushed.send(());
+        }
+    }
+
+    if let Some(height) = pending {
+        persist_height(
+            store.as_ref(),
+            chain_id,
+            height_kind,
+            &state,
+            height,
+            pending_cursors,
+            pending_processed,
+        )
+        .await;
+    }
+}
+
+/// Where checkpoints are persisted, the database outside of tests
+#[async_trait]
+trait CheckpointStore: Send + Sync + 'static {
+    async fn upsert_svm_cursors(&self, cursors: Vec<svm_cursor::ActiveModel>) -> eyre::Result<()>;
+
+    async fn mark_signatures_processed(
+        &self,
+        processed: Vec<processed_signatures::ActiveModel>,
+    ) -> eyre::Result<()>;
+
+    async fn upsert_last_synced(
+        &self,
+        chain_id: u64,
+        height_kind: HeightKindEnum,
+        height: u64,
+    ) -> eyre::Result<()>;
+}
+
+#[async_trait]
+impl CheckpointStore for DbClient {
+    async fn upsert_svm_cursors(&self, cursors: Vec<svm_cursor::ActiveModel>) -> eyre::Result<()> {
+        DbClient::upsert_svm_cursors(self, cursors).await
+    }
+
+    async fn mark_signatures_processed(
+        &self,
+        processed: Vec<processed_signatures::ActiveModel>,
+    ) -> eyre::Result<()> {
+        DbClient::mark_signatures_processed(self, processed).await
+    }
+
+    async fn upsert_last_synced(
+        &self,
+        chain_id: u64,
+        height_kind: HeightKindEnum,
+        height: u64,
+    ) -> eyre::Result<()> {
+        DbClient::upsert_last_synced(self, chain_id as i64, height_kind, height as i64).await
+    }
+}
+
+/// Writes `processed` marks, `cursors` and then `height` as the chain's
+/// `last_synced` unless a write has failed
+async fn persist_height<S: CheckpointStore>(
+    store: &S,
+    chain_id: u64,
+    height_kind: HeightKindEnum,
+    state: &WriterState,
+    height: u64,
+    cursors: Vec<svm_cursor::ActiveModel>,
+    processed: Vec<processed_signatures::ActiveModel>,
+) {
+    if state.failure().is_some() {
+        return;
+    }
+
+    if !processed.is_empty() {
+        if let Err(e) = store.mark_signatures_processed(processed).await {
+            error!("Failed to mark signatures processed at {}: {:?}", height, e);
+            state.fail(e.to_string());
+            return;
+        }
+    }
+
+    if !cursors.is_empty() {
+        if let Err(e) = store.upsert_svm_cursors(cursors).await {
+            error!("Failed to persist signature cursors at {}: {:?}", height, e);
+            state.fail(e.to_string());
+            return;
+        }
+    }
+
+    match store
+        .upsert_last_synced(chain_id, height_kind, height)
+        .await
+    {
+        Ok(_) => state.last_persisted.store(height, Ordering::SeqCst),
+        Err(e) => {
+            error!("Failed to persist last synced height {}: {:?}", height, e);
+            state.fail(e.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingStore {
+        heights: Mutex<Vec<u64>>,
+        processed: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl CheckpointStore for RecordingStore {
+        async fn upsert_svm_cursors(
+            &self,
+            _cursors: Vec<svm_cursor::ActiveModel>,
+        ) -> eyre::Result<()> {
+            Ok(())
+        }
+
+        async fn mark_signatures_processed(
+            &self,
+            processed: Vec<processed_signatures::ActiveModel>,
+        ) -> eyre::Result<()> {
+            self.processed
+                .lock()
+                .unwrap()
+                .extend(processed.into_iter().map(|mark| mark.signature.unwrap()));
+            Ok(())
+        }
+
+        async fn upsert_last_synced(
+            &self,
+            _chain_id: u64,
+            _height_kind: HeightKindEnum,
+            height: u64,
+        ) -> eyre::Result<()> {
+            self.heights.lock().unwrap().push(height);
+            Ok(())
+        }
+    }
+
+    fn ack(result: eyre::Result<()>) -> oneshot::Receiver<eyre::Result<()>> {
+        let (done, ack) = oneshot::channel();
+        let _ = done.send(result);
+        ack
+    }
+
+    fn checkpoint(height: u64, acks: Vec<oneshot::Receiver<eyre::Result<()>>>) -> Checkpoint {
+        Checkpoint {
+            height: Some(height),
+            events: 1,
+            cursors: Vec::new(),
+            processed: Vec::new(),
+            acks,
+            flushed: None,
+        }
+    }
+
+    fn processed(signature: &str) -> processed_signatures::ActiveModel {
+        processed_signatures::ActiveModel {
+            signature: sea_orm::ActiveValue::Set(signature.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn spawn_checkpoints(
+        store: Arc<RecordingStore>,
+        state: Arc<WriterState>,
+    ) -> (mpsc::Sender<Checkpoint>, tokio::task::JoinHandle<()>) {
+        let debounce = CheckpointDebounce {
+            every_events: 0,
+            interval: None,
+        };
+        let (checkpoints, checkpoint_rx) = mpsc::channel(4);
+        let task = tokio::spawn(run_checkpoints(
+            store,
+            1,
+            HeightKindEnum::Slot,
+            state,
+            debounce,
+            checkpoint_rx,
+        ));
+        (checkpoints, task)
+    }
+
+    fn writer_state(last_persisted: u64) -> Arc<WriterState> {
+        Arc::new(WriterState {
+            last_persisted: AtomicU64::new(last_persisted),
+            failure: Mutex::new(None),
+        })
+    }
+
+    /// A pool over one worker whose jobs the test handles, checkpointing to
+    /// `store` from height 10
+    fn pool(
+        store: Arc<RecordingStore>,
+        debounce: CheckpointDebounce,
+    ) -> (DbWriterPool, mpsc::Receiver<WriteJob>) {
+        let state = writer_state(10);
+        let (worker, jobs) = mpsc::channel(16);
+        let (checkpoints, checkpoint_rx) = mpsc::channel(16);
+        tokio::spawn(run_checkpoints(
+            store,
+            1,
+            HeightKindEnum::Block,
+            state.clone(),
+            debounce,
+            checkpoint_rx,
+        ));
+        let pool = DbWriterPool {
+            workers: vec![worker],
+            checkpoints,
+            state,
+        };
+        (pool, jobs)
+    }
+
+    /// Acknowledges every job after a short delay, counting the operations
+    fn spawn_worker(mut jobs: mpsc::Receiver<WriteJob>) -> Arc<AtomicU64> {
+        let written = Arc::new(AtomicU64::new(0));
+        let counter = written.clone();
+        tokio::spawn(async move {
+            while let Some(job) = jobs.recv().await {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                counter.fetch_add(job.operations.len() as u64, Ordering::SeqCst);
+                let _ = job.done.send(Ok(()));
+            }
+        });
+        written
+    }
+
+    fn operation() -> DbOperations {
+        DbOperations::UnparsedLog(Default::default())
+    }
+
+    #[tokio::test]
+    async fn drain_persists_everything_queued_before_shutdown() {
+        let store = Arc::new(RecordingStore::default());
+        let debounce = CheckpointDebounce {
+            every_events: 100,
+            interval: None,
+        };
+        let (pool, jobs) = pool(store.clone(), debounce);
+        let written = spawn_worker(jobs);
+
+        for height in [20, 30, 40] {
+            pool.submit(vec![vec![operation()], vec![operation()]], height)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(pool.drain(Duration::from_secs(5)).await.unwrap(), 40);
+        assert_eq!(written.load(Ordering::SeqCst), 6);
+        assert_eq!(*store.heights.lock().unwrap(), vec![40]);
+    }
+
+    #[tokio::test]
+    async fn drain_gives_up_on_a_write_that_never_finishes() {
+        let store = Arc::new(RecordingStore::default());
+        let debounce = CheckpointDebounce {
+            every_events: 0,
+            interval: None,
+        };
+        // Jobs are queued but never acknowledged
+        let (pool, _jobs) = pool(store.clone(), debounce);
+
+        pool.submit(vec![vec![operation()]], 20).await.unwrap();
+
+        let e = pool.drain(Duration::from_millis(50)).await.unwrap_err();
+        assert!(e.to_string().contains("did not drain"), "{e}");
+        assert_eq!(pool.last_persisted(), 10);
+        assert!(store.heights.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn debounce_is_due_on_either_limit() {
+        let every_three = CheckpointDebounce {
+            every_events: 3,
+            interval: None,
+        };
+        assert!(!every_three.is_due(2, Duration::from_secs(3600)));
+        assert!(every_three.is_due(3, Duration::ZERO));
+
+        let every_second = CheckpointDebounce {
+            every_events: 0,
+            interval: Some(Duration::from_secs(1)),
+        };
+        assert!(!every_second.is_due(1000, Duration::from_millis(999)));
+        assert!(every_second.is_due(1, Duration::from_secs(1)));
+
+        let always = CheckpointDebounce {
+            every_events: 0,
+            interval: None,
+        };
+        assert!(always.is_due(0, Duration::ZERO));
+    }
+
+    #[tokio::test]
+    async fn heights_are_written_every_n_events_and_the_rest_on_flush() {
+        let store = Arc::new(RecordingStore::default());
+        let debounce = CheckpointDebounce {
+            every_events: 3,
+            interval: None,
+        };
+        let (pool, jobs) = pool(store.clone(), debounce);
+        spawn_worker(jobs);
+
+        for height in [20, 30, 40, 50] {
+            pool.submit(vec![vec![operation()]], height).await.unwrap();
+        }
+        pool.flush().await.unwrap();
+
+        // 40 after the third event, 50 held back until the flush
+        assert_eq!(*store.heights.lock().unwrap(), vec![40, 50]);
+        assert_eq!(pool.last_persisted(), 50);
+    }
+
+    #[tokio::test]
+    async fn held_back_height_is_written_once_the_interval_passes() {
+        let store = Arc::new(RecordingStore::default());
+        let debounce = CheckpointDebounce {
+            every_events: 0,
+            interval: Some(Duration::from_millis(100)),
+        };
+        let (pool, jobs) = pool(store.clone(), debounce);
+        spawn_worker(jobs);
+
+        pool.submit(vec![vec![operation()]], 20).await.unwrap();
+        pool.submit(vec![vec![operation()]], 30).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(store.heights.lock().unwrap().is_empty());
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(*store.heights.lock().unwrap(), vec![30]);
+    }
+
+    #[tokio::test]
+    async fn signatures_are_marked_once_every_shard_wrote() {
+        let store = Arc::new(RecordingStore::default());
+        let (checkpoints, task) = spawn_checkpoints(store.clone(), writer_state(10));
+
+        checkpoints
+            .send(Checkpoint {
+                processed: vec![processed("with-events"), processed("other-transaction")],
+                ..checkpoint(20, vec![ack(Ok(())), ack(Ok(()))])
+            })
+            .await
+            .unwrap();
+        // One of the transaction's events failed to write on another shard
+        checkpoints
+            .send(Checkpoint {
+                processed: vec![processed("half-written")],
+                ..checkpoint(30, vec![ack(Ok(())), ack(Err(eyre!("shard 1 failed")))])
+            })
+            .await
+            .unwrap();
+        drop(checkpoints);
+        task.await.unwrap();
+
+        assert_eq!(
+            *store.processed.lock().unwrap(),
+            vec!["with-events".to_string(), "other-transaction".to_string()]
+        );
+        assert_eq!(*store.heights.lock().unwrap(), vec![20]);
+    }
+
+    #[tokio::test]
+    async fn failed_shard_blocks_the_checkpoint() {
+        let store = Arc::new(RecordingStore::default());
+        let state = Arc::new(WriterState {
+            last_persisted: AtomicU64::new(10),
+            failure: Mutex::new(None),
+        });
+        let debounce = CheckpointDebounce {
+            every_events: 0,
+            interval: None,
+        };
+        let (checkpoints, checkpoint_rx) = mpsc::channel(4);
+        let task = tokio::spawn(run_checkpoints(
+            store.clone(),
+            1,
+            HeightKindEnum::Block,
+            state.clone(),
+            debounce,
+            checkpoint_rx,
+        ));
+
+        checkpoints
+            .send(checkpoint(20, vec![ack(Ok(()))]))
+            .await
+            .unwrap();
+        // The batch at 30 committed on one shard and failed on the other
+        checkpoints
+            .send(checkpoint(
+                30,
+                vec![ack(Ok(())), ack(Err(eyre!("shard 1 failed")))],
+            ))
+            .await
+            .unwrap();
+        checkpoints
+            .send(checkpoint(40, vec![ack(Ok(()))]))
+            .await
+            .unwrap();
+        drop(checkpoints);
+        task.await.unwrap();
+
+        assert_eq!(*store.heights.lock().unwrap(), vec![20]);
+        assert_eq!(state.last_persisted.load(Ordering::SeqCst), 20);
+        assert!(
+            state
+                .failure()
+                .is_some_and(|reason| reason.contains("shard 1 failed"))
+        );
+    }
+
+    fn source(nonce: i64, amount: sea_orm::prelude::Decimal) -> DbOperations {
+        use database::entities::{sea_orm_active_enums::TransactionTypeEnum, source_transactions};
+        use sea_orm::ActiveValue::Set;
+
+        DbOperations::BridgeSourceTransaction(source_transactions::ActiveModel {
+            chain_id: Set(1),
+            nonce: Set(nonce),
+            transaction_type: Set(TransactionTypeEnum::Deposit),
+            block_number: Set(100 + nonce),
+            l1_token: Set("0xl1".to_string()),
+            l2_token: Set("0xl2".to_string()),
+            l1_address: Set("0xfrom".to_string()),
+            twine_address: Set("0xto".to_string()),
+            amount: Set(amount),
+            transaction_hash: Set(Some(format!("0xdeposit{}", nonce))),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres at TEST_DATABASE_URL"]
+    async fn check_violation_is_dead_lettered_and_the_rest_written() {
+        use database::entities::{source_transactions, unparsed_logs};
+        use sea_orm::{EntityTrait, prelude::Decimal};
+
+        let db_client = database::test_db::client(false).await;
+        let operations = vec![source(1, Decimal::ONE), source(2, Decimal::NEGATIVE_ONE)];
+        let e = db_client
+            .process_bulk_l1_database_operations(vec![operations.clone()])
+            .await
+            .unwrap_err();
+        assert!(is_check_violation(&e));
+
+        dead_letter_violations(0, &db_client, operations)
+            .await
+            .unwrap();
+
+        let nonces: Vec<i64> = source_transactions::Entity::find()
+            .all(&db_client.primary)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|tx| tx.nonce)
+            .collect();
+        assert_eq!(nonces, vec![1]);
+        let dead_letters = unparsed_logs::Entity::find()
+            .all(&db_client.primary)
+            .await
+            .unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].transaction_hash, "0xdeposit2");
+        assert_eq!(
+            dead_letters[0].event_type.as_deref(),
+            Some("BridgeSourceTransaction")
+        );
+    }
+}
@@ -1,17 +1,21 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use base64::{engine::general_purpose, Engine};
 use borsh::BorshDeserialize;
 use chrono::{DateTime, Utc};
-use common::config::{ChainConfig, SvmConfig};
+use common::config::{ChainConfig, SolanaCommitment, SvmConfig};
 use database::{
     blockscout_entities::{twine_transaction_batch, twine_transaction_batch_detail},
+    bridge::{parse_amount, token_standard_for_solana_instruction},
     client::DbClient,
-    entities::{source_transactions, transaction_flows},
-    DbOperations,
+    entities::{
+        processed_signatures, sea_orm_active_enums::HeightKindEnum, source_transactions,
+        svm_cursor, transaction_flows, unparsed_logs,
+    },
+    DbOperations, OrderingKey,
 };
-use evm::provider::EvmProvider;
+use evm::{error::ParserError, handler::batch_block_range, provider::EvmProvider};
 use eyre::Error;
 use generic_indexer::handler::ChainEventHandler;
 use num_traits::FromPrimitive;
@@ -19,7 +23,6 @@ use sea_orm::{
     prelude::Decimal,
     strum::{self},
     ActiveValue::Set,
-    IntoActiveModel,
 };
 use solana_sdk::pubkey::Pubkey;
 use solana_transaction_status_client_types::{
@@ -29,8 +32,8 @@ use tracing::{debug, error, info, instrument, warn};
 
 use crate::parser::{
     BatchCommitmentAndFinalizationSuccessfulEvent, CommitBatchEvent, FinalizeBatchEvent,
-    ForcedWithdrawalSuccessfulEvent, L2WithdrawExecutedEvent, MessageTransactionEvent,
-    RefundSuccessfulEvent, SolanaEvent, SolanaLog,
+    ForcedWithdrawalSuccessfulEvent, L2WithdrawExecutedEvent, LogTruncatedEvent,
+    MessageTransactionEvent, RefundSuccessfulEvent, SolanaEvent, SolanaLog,
 };
 
 pub struct SolanaEventHandler {
@@ -43,6 +46,45 @@ pub struct SolanaEventHandler {
 impl ChainEventHandler for SolanaEventHandler {
     type LogType = SolanaLog;
 
+    fn height_kind(&self) -> HeightKindEnum {
+        HeightKindEnum::Slot
+    }
+
+    fn event_type(&self, log: &SolanaLog) -> String {
+        log.event.get_event_type().to_string()
+    }
+
+    fn dead_letter(&self, log: &SolanaLog, reason: String) -> Option<DbOperations> {
+        Some(self.unparsed_log(
+            log.signature.clone(),
+            log.slot_number,
+            self.event_type(log),
+            reason,
+            format!("{:?}", log.event),
+        ))
+    }
+
+    fn ordering_key(&self, log: &SolanaLog) -> OrderingKey {
+        let chain_id = self.chain_id() as i64;
+        let bridge = |nonce: u64| OrderingKey::Bridge {
+            chain_id,
+            nonce: nonce as i64,
+        };
+
+        match &log.event {
+            SolanaEvent::MessageTransaction(event) => bridge(event.nonce),
+            SolanaEvent::RefundSuccessful(event) => bridge(event.nonce),
+            SolanaEvent::ForcedWithdrawalSuccessful(event) => bridge(event.nonce),
+            SolanaEvent::L2WithdrawExecuted(event) => bridge(event.nonce),
+            SolanaEvent::CommitBatch(event) => OrderingKey::Batch(event.batch_number as i64),
+            SolanaEvent::FinalizeBatch(event) => OrderingKey::Batch(event.batch_number as i64),
+            SolanaEvent::BatchCommitmentAndFinalizationSuccessful(event) => {
+                OrderingKey::Batch(event.batch_number as i64)
+            }
+            SolanaEvent::LogTruncated(_) | SolanaEvent::Unknown(_) => OrderingKey::Unordered,
+        }
+    }
+
     fn get_chain_config(&self) -> ChainConfig {
         self.config.common.clone()
     }
@@ -58,16 +100,60 @@ impl ChainEventHandler for SolanaEventHandler {
             log.slot_number,
         );
 
+        let cursor = log.program.as_ref().map(|program| {
+            DbOperations::SvmCursor(svm_cursor::ActiveModel {
+                program_id: Set(program.clone()),
+                signature: Set(log.signature.clone()),
+                slot: Set(log.slot_number as i64),
+                updated_at: Set(Utc::now().fixed_offset()),
+            })
+        });
+        // Every event of the transaction carries the mark, `DbWriterPool` writes
+        // it once all of their rows are
+        let processed = log
+            .program
+            .as_ref()
+            .filter(|_| log.transaction_complete)
+            .map(|program| {
+                DbOperations::ProcessedSignature(processed_signatures::ActiveModel {
+                    signature: Set(log.signature.clone()),
+                    program_id: Set(program.clone()),
+                    slot: Set(log.slot_number as i64),
+                    ..Default::default()
+                })
+            });
+
         match log.event {
             SolanaEvent::MessageTransaction(event) => {
-                if event.message_type == "Withdraw" {
+                if let Err(reason) = parse_amount(&event.amount) {
+                    warn!("Dead-lettering {}: {}", event.event, reason);
+                    operations.push(self.unparsed_log(
+                        log.signature,
+                        log.slot_number,
+                        event.event.clone(),
+                        reason,
+                        format!("{:?}", event),
+                    ));
+                } else if event.message_type == "Withdraw" {
                     let operation = self
-                        .handle_withdrawal(event, log.signature, log.timestamp, log.slot_number)
+                        .handle_withdrawal(
+                            event,
+                            log.signature,
+                            log.timestamp,
+                            log.slot_number,
+                            log.instruction.as_deref(),
+                        )
                         .await?;
                     operations.push(operation);
                 } else if event.message_type == "Deposit" {
                     let operation = self
-                        .handle_deposit(event, log.signature, log.timestamp, log.slot_number)
+                        .handle_deposit(
+                            event,
+                            log.signature,
+                            log.timestamp,
+                            log.slot_number,
+                            log.instruction.as_deref(),
+                        )
                         .await?;
                     operations.push(operation);
                 } else {
@@ -104,17 +190,67 @@ impl ChainEventHandler for SolanaEventHandler {
                 operations.push(operation);
             }
             SolanaEvent::BatchCommitmentAndFinalizationSuccessful(event) => {
-                let operation = self
-                    .handle_commit_batch(event, log.signature, log.timestamp, log.slot_number)
-                    .await?;
+                let event_type = event.event.clone();
+                let payload = format!("{:?}", event);
+                match self
+                    .handle_commit_batch(
+                        event,
+                        log.signature.clone(),
+                        log.timestamp,
+                        log.slot_number,
+                    )
+                    .await
+                {
+                    Ok(operation) => operations.push(operation),
+                    Err(e) => match e.downcast_ref::<ParserError>() {
+                        Some(err @ ParserError::InvalidBlockRange { .. }) => {
+                            error!("Dead-lettering {}: {}", event_type, err);
+                            operations.push(self.unparsed_log(
+                                log.signature,
+                                log.slot_number,
+                                event_type,
+                                err.to_string(),
+                                payload,
+                            ));
+                        }
+                        _ => return Err(e),
+                    },
+                }
+            }
+            SolanaEvent::LogTruncated(event) => {
+                let operation = self.handle_truncated_logs(event, log.signature, log.slot_number);
                 operations.push(operation);
             }
 
+            SolanaEvent::Unknown(value) => {
+                let event_type = value
+                    .get("event")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unknown")
+                    .to_string();
+                warn!(
+                    "Dead-lettering unrecognized event '{}' in transaction {}",
+                    event_type, log.signature
+                );
+                operations.push(self.unparsed_log(
+                    log.signature,
+                    log.slot_number,
+                    event_type,
+                    "No handler matches the event name, map it in event_aliases if it was renamed"
+                        .to_string(),
+                    value.to_string(),
+                ));
+            }
+
             _ => {
                 info!("Unknown event to handle! {:?}", log.event)
             }
         }
 
+        // The writer holds the cursor back until every row below it landed
+        operations.extend(cursor);
+        operations.extend(processed);
+
         Ok(operations)
     }
 }
@@ -132,11 +268,28 @@ impl SolanaEventHandler {
         }
     }
 
+    pub fn event_aliases(&self) -> &HashMap<String, String> {
+        &self.config.event_aliases
+    }
+
+    pub fn historical_window_concurrency(&self) -> usize {
+        self.config.historical_window_concurrency.max(1)
+    }
+
+    pub fn commitment(&self) -> SolanaCommitment {
+        self.config.commitment
+    }
+
+    pub fn no_data_timeout(&self) -> Duration {
+        Duration::from_millis(self.config.no_data_timeout_ms)
+    }
+
+    /// Programs whose signatures are fetched. The token gateways are left
+    /// out, `get_logs` does not dedupe signatures across programs so a
+    /// transaction touching both would be indexed twice.
     pub fn get_program_addresses(&self) -> Vec<Pubkey> {
         let twine_chain_id =
             Pubkey::from_str_const(&self.config.twine_chain_program_address.clone());
-        let gateway_id =
-            Pubkey::from_str_const(&self.config.tokens_gateway_program_address.clone());
         return vec![twine_chain_id];
     }
 
@@ -146,6 +299,7 @@ impl SolanaEventHandler {
         signature: String,
         timestamp: DateTime<Utc>,
         slot_number: u64,
+        instruction: Option<&str>,
     ) -> eyre::Result<DbOperations> {
         let l2_chain_id = self.twine_provider.get_chain_id();
         let model = source_transactions::ActiveModel {
@@ -156,13 +310,14 @@ impl SolanaEventHandler {
             l1_address: Set(event.l1_pubkey),
             twine_address: Set(event.twine_address),
             l2_token: Set(event.l2_token),
+            token_standard: Set(instruction.and_then(token_standard_for_solana_instruction)),
             l1_token: Set(event.l1_token),
             transaction_hash: Set(Some(signature)),
             timestamp: Set(Some(timestamp.fixed_offset())),
             transaction_type: Set(
                 database::entities::sea_orm_active_enums::TransactionTypeEnum::Deposit,
             ),
-            amount: Set(event.amount.parse::<Decimal>().unwrap()),
+            amount: Set(parse_amount(&event.amount).map_err(|reason| eyre::eyre!(reason))?),
             ..Default::default()
         };
 
@@ -179,6 +334,7 @@ impl SolanaEventHandler {
         signature: String,
         timestamp: DateTime<Utc>,
         slot_number: u64,
+        instruction: Option<&str>,
     ) -> eyre::Result<DbOperations> {
         let l2_chain_id = self.twine_provider.get_chain_id();
         let model = source_transactions::ActiveModel {
@@ -189,10 +345,11 @@ impl SolanaEventHandler {
             l1_address: Set(event.l1_pubkey),
             twine_address: Set(event.twine_address),
             l2_token: Set(event.l2_token),
+            token_standard: Set(instruction.and_then(token_standard_for_solana_instruction)),
             l1_token: Set(event.l1_token),
             transaction_hash: Set(Some(signature)),
             timestamp: Set(Some(timestamp.fixed_offset())),
-            amount: Set(event.amount.parse::<Decimal>().unwrap()),
+            amount: Set(parse_amount(&event.amount).map_err(|reason| eyre::eyre!(reason))?),
             transaction_type: Set(
                 database::entities::sea_orm_active_enums::TransactionTypeEnum::ForcedWithdraw,
             ),
@@ -261,6 +418,8 @@ impl SolanaEventHandler {
             execute_tx_hash: Set(Some(signature)),
             executed_at: Set(Some(timestamp.fixed_offset())),
             is_executed: Set(Some(true)),
+            refund_amount: Set(Some(Decimal::from(event.amount))),
+            refund_token: Set(Some(event.l1_token)),
             ..Default::default()
         };
 
@@ -268,6 +427,48 @@ impl SolanaEventHandler {
         Ok(operation)
     }
 
+    fn handle_truncated_logs(
+        &self,
+        event: LogTruncatedEvent,
+        signature: String,
+        slot_number: u64,
+    ) -> DbOperations {
+        warn!(
+            "Sending transaction {} with truncated logs to the dead-letter table",
+            signature
+        );
+
+        self.unparsed_log(
+            signature,
+            slot_number,
+            event.event,
+            format!("RPC truncated log output for program {}", event.program),
+            event.logs.join("\n"),
+        )
+    }
+
+    /// Builds a dead-letter operation for an event that cannot be indexed
+    fn unparsed_log(
+        &self,
+        signature: String,
+        slot_number: u64,
+        event_type: String,
+        reason: String,
+        payload: String,
+    ) -> DbOperations {
+        let model = unparsed_logs::ActiveModel {
+            chain_id: Set(self.chain_id() as i64),
+            block_number: Set(slot_number as i64),
+            transaction_hash: Set(signature),
+            event_type: Set(Some(event_type)),
+            reason: Set(reason),
+            payload: Set(Some(payload)),
+            ..Default::default()
+        };
+
+        DbOperations::UnparsedLog(model)
+    }
+
     async fn handle_commit_batch(
         &self,
         event: BatchCommitmentAndFinalizationSuccessfulEvent,
@@ -283,8 +484,7 @@ impl SolanaEventHandler {
             .await?;
 
         // Extract start and end block numbers from the blocks vector
-        let start_block = blocks.iter().min().copied().unwrap_or(0);
-        let end_block = blocks.iter().max().copied().unwrap_or(0);
+        let (start_block, end_block) = batch_block_range(batch_number, &blocks)?;
 
         let root_hash = format!("{:?}", event.batch_hash);
         let batch_length = end_block - start_block + 1; // as both end block and start block is inclusive
@@ -311,66 +511,40 @@ impl SolanaEventHandler {
             ..Default::default()
         };
 
-        let mut l2_blocks = Vec::new();
-        let mut l2_txs = Vec::new();
-
         //1. Check if batch already exists
-        match self.db_client.get_batch_by_id(batch_number as i64).await? {
-            Some(existing_batch) => {
+        let l2_blocks = match self.db_client.get_batch_by_id(batch_number as i64).await? {
+            Some(_) => {
                 //If the batch already exists, it means it already has its corresponding transactions and blocks as welll.
                 // So we just need to put twine commit information in the table
 
                 debug!("Batch already exists so don't need to fetch blocks and transactions");
+                None
             }
             None => {
-                // info!("First time encoutering this batch, so need to fecth blocks and transactions from twine");
-
-                let blocks = self.db_client.get_blocks(start_block, end_block).await?;
-
-                if blocks.len() != batch_length as usize {
+                // Blocks and transactions are tagged a window at a time as the
+                // batch is written, here we only make sure blockscout has them all
+                let stored_blocks = self.db_client.count_blocks(start_block, end_block).await?;
+                if stored_blocks != batch_length {
                     error!(
                         "Fetched blocks length {:?} mismatched with batch length {:?}",
-                        blocks.len(),
-                        batch_length
+                        stored_blocks, batch_length
                     );
 
                     return Err(eyre::eyre!(
                         "Fetched blocks length {:?} mismatched with batch length {:?}",
-                        blocks.len(),
+                        stored_blocks,
                         batch_length
                     ));
-                } else {
-                    let transactions = self
-                        .db_client
-                        .get_transactions(start_block, end_block)
-                        .await?;
-
-                    l2_blocks = blocks
-                        .into_iter()
-                        .map(|model| {
-                            let mut am = model.into_active_model();
-                            am.batch_number = Set(Some(batch_number as i64));
-                            am
-                        })
-                        .collect();
-
-                    l2_txs = transactions
-                        .into_iter()
-                        .map(|model| {
-                            let mut am = model.into_active_model();
-                            am.batch_number = Set(Some(batch_number as i64));
-                            am
-                        })
-                        .collect();
                 }
+                Some(start_block..=end_block)
             }
-        }
+        };
 
         let operation = DbOperations::CommitBatch {
             batch: batch_model,
             details: detail_model,
-            blocks: l2_blocks,
-            transactions: l2_txs,
+            l2_blocks,
+            l1_block_number: slot_number as i64,
         };
 
         Ok(operation)
@@ -387,3 +561,100 @@ impl Clone for SolanaEventHandler {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::DatabaseConnection;
+    use serde_json::json;
+
+    use super::*;
+    use crate::parser::LOG_TRUNCATED_MARKER;
+
+    const PROGRAM: &str = "Bridge111";
+
+    fn handler() -> SolanaEventHandler {
+        let config: SvmConfig = serde_json::from_value(json!({
+            "common": {
+                "http_rpc_url": "http://localhost:8899",
+                "chain_id": 900,
+                "start_block": 0,
+                "block_sync_batch_size": 100,
+                "block_time_ms": 400,
+            },
+            "tokens_gateway_program_addresses": ["Gateway111"],
+            "twine_chain_program_address": PROGRAM,
+            "chain": "solana",
+        }))
+        .unwrap();
+        SolanaEventHandler::new(
+            Arc::new(DbClient::new(DatabaseConnection::Disconnected, None)),
+            config,
+            Arc::new(EvmProvider::new("http://localhost:8545", 1)),
+        )
+    }
+
+    fn log(event: SolanaEvent) -> SolanaLog {
+        SolanaLog {
+            event,
+            timestamp: Utc::now(),
+            slot_number: 100,
+            signature: "signature".to_string(),
+            program: Some(PROGRAM.to_string()),
+            transaction_complete: false,
+            instruction: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn truncated_logs_are_dead_lettered() {
+        let logs = vec![
+            format!("Program {} invoke [1]", PROGRAM),
+            LOG_TRUNCATED_MARKER.to_string(),
+        ];
+        let event = LogTruncatedEvent::new(PROGRAM.to_string(), logs.clone());
+
+        let operations = handler()
+            .handle_event(log(SolanaEvent::LogTruncated(event)))
+            .await
+            .unwrap();
+
+        let dead_letter = operations
+            .iter()
+            .find_map(|operation| match operation {
+                DbOperations::UnparsedLog(model) => Some(model),
+                _ => None,
+            })
+            .expect("expected a dead-letter");
+        assert_eq!(dead_letter.chain_id, Set(900));
+        assert_eq!(dead_letter.block_number, Set(100));
+        assert_eq!(dead_letter.transaction_hash, Set("signature".to_string()));
+        assert_eq!(
+            dead_letter.event_type,
+            Set(Some("LogTruncated".to_string()))
+        );
+        assert_eq!(dead_letter.payload, Set(Some(logs.join("\n"))));
+    }
+
+    #[tokio::test]
+    async fn renamed_event_is_dead_lettered() {
+        let value = json!({ "event": "RefundCompleted", "nonce": 1 });
+
+        let operations = handler()
+            .handle_event(log(SolanaEvent::Unknown(value.clone())))
+            .await
+            .unwrap();
+
+        let dead_letter = operations
+            .iter()
+            .find_map(|operation| match operation {
+                DbOperations::UnparsedLog(model) => Some(model),
+                _ => None,
+            })
+            .expect("expected a dead-letter");
+        assert_eq!(
+            dead_letter.event_type,
+            Set(Some("RefundCompleted".to_string()))
+        );
+        assert_eq!(dead_letter.payload, Set(Some(value.to_string())));
+    }
+}
@@ -1,17 +1,29 @@
-use std::{pin::Pin, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
 
 use async_trait::async_trait;
 use common::config::{ChainConfig, IndexerSettings};
 use database::client::DbClient;
 use eyre::Error;
 use futures_util::{stream::select_all, Stream, StreamExt};
-use generic_indexer::{handler::ChainEventHandler, indexer::ChainIndexer, state::IndexerState};
+use generic_indexer::{
+    handler::ChainEventHandler, indexer::ChainIndexer, metrics, state::IndexerState,
+    watchdog::HeadWatchdog,
+};
 use solana_client::rpc_response::{Response, RpcLogsResponse};
 use solana_sdk::pubkey::Pubkey;
 use solana_transaction_status_client_types::EncodedConfirmedTransactionWithStatusMeta;
 use tokio::{sync::Semaphore, task::JoinSet};
 use tokio_stream::wrappers::ReceiverStream;
-use tracing::{error, info, instrument};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, instrument, warn};
 
 use crate::{
     handler::SolanaEventHandler,
@@ -20,12 +32,22 @@ use crate::{
 };
 
 pub struct SolanaIndexer {
-    provider: SvmProvider,
+    provider: RwLock<SvmProvider>,
     handler: SolanaEventHandler,
     max_batch_size: usize,
     db_client: Arc<DbClient>,
     config: ChainConfig,
     settings: IndexerSettings,
+    backfill_limiter: Option<Arc<Semaphore>>,
+    window_concurrency: usize,
+    /// `last_synced` found on startup, cursors are ignored up to it while the
+    /// startup reprocess window is indexed again
+    startup_checkpoint: AtomicU64,
+    shutdown: Option<CancellationToken>,
+    head_watchdog: HeadWatchdog,
+    /// Fetch every signature in a range, ignoring cursors and processed
+    /// signatures, for runs that must see the whole range
+    read_only: bool,
 }
 
 #[async_trait]
@@ -42,22 +64,62 @@ impl ChainIndexer for SolanaIndexer {
         self.settings.clone()
     }
 
+    fn get_backfill_limiter(&self) -> Option<Arc<Semaphore>> {
+        self.backfill_limiter.clone()
+    }
+
+    fn get_shutdown_token(&self) -> Option<CancellationToken> {
+        self.shutdown.clone()
+    }
+
     async fn get_initial_state(&self) -> eyre::Result<u64> {
         let last_synced = self
             .db_client
-            .get_last_synced_height(self.handler.chain_id() as i64, self.config.start_block)
-            .await
+            .get_last_synced(self.handler.chain_id() as i64, self.handler.height_kind())
+            .await?
             .unwrap_or(self.config.start_block as i64);
+        self.startup_checkpoint
+            .store(last_synced as u64, Ordering::SeqCst);
         Ok(last_synced as u64)
     }
 
     async fn get_current_chain_height(&self) -> eyre::Result<u64> {
-        self.provider.get_slot().await
+        let slot = self.provider().get_slot().await?;
+        if let Err(err) = self.head_watchdog.observe(self.config.chain_id, slot) {
+            self.reconnect(slot);
+            return Err(err);
+        }
+        Ok(slot)
     }
 
     async fn get_historical_logs(&self, from: u64, to: u64) -> eyre::Result<Vec<SolanaLog>> {
-        self.provider
-            .get_logs(self.handler.get_program_addresses(), from, to)
+        let programs = self.handler.get_program_addresses();
+
+        // Only the slot a cursor sits in can be partially processed, unless
+        // the range is being reprocessed on startup. Reprocessing also fetches
+        // transactions already recorded as processed again.
+        let reprocessing = self.read_only
+            || (self.config.startup_reprocess_window > 0
+                && from <= self.startup_checkpoint.load(Ordering::SeqCst));
+        let mut cursors = HashMap::new();
+        for program in programs.iter().filter(|_| !self.read_only) {
+            let program_id = program.to_string();
+            if let Some(cursor) = self.db_client.get_svm_cursor(&program_id).await? {
+                if cursor.slot as u64 >= from && !reprocessing {
+                    cursors.insert(program_id, cursor.signature);
+                }
+            }
+        }
+
+        self.provider()
+            .get_logs_in_windows(
+                programs,
+                from,
+                to,
+                self.window_concurrency,
+                &cursors,
+                !reprocessing,
+            )
             .await
     }
 
@@ -67,18 +129,77 @@ impl ChainIndexer for SolanaIndexer {
 }
 
 impl SolanaIndexer {
-    pub fn new(handler: SolanaEventHandler, db: Arc<DbClient>, settings: IndexerSettings) -> Self {
+    pub fn new(
+        handler: SolanaEventHandler,
+        db: Arc<DbClient>,
+        settings: IndexerSettings,
+    ) -> eyre::Result<Self> {
         let config = handler.get_chain_config();
 
-        let provider = SvmProvider::new(&config.http_rpc_url, config.chain_id);
+        let provider = SvmProvider::new(&config.http_rpc_url, config.chain_id)?
+            .with_event_aliases(handler.event_aliases().clone())
+            .with_commitment(handler.commitment())
+            .with_processed_signatures(db.clone());
+        let window_concurrency = handler.historical_window_concurrency();
+        let head_watchdog = HeadWatchdog::new(handler.no_data_timeout());
 
-        Self {
-            provider,
+        Ok(Self {
+            provider: RwLock::new(provider),
             handler,
             max_batch_size: config.block_sync_batch_size as usize,
             db_client: db,
             config,
             settings,
-        }
+            backfill_limiter: None,
+            window_concurrency,
+            startup_checkpoint: AtomicU64::new(0),
+            shutdown: None,
+            head_watchdog,
+            read_only: false,
+        })
+    }
+
+    /// Ignore cursors and processed signatures, so a parse-only run sees the
+    /// whole range and reads nothing the live indexer left behind
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    fn provider(&self) -> SvmProvider {
+        self.provider.read().unwrap().clone()
+    }
+
+    /// Replaces the provider with one over a new connection after the slot
+    /// stopped advancing at `slot`
+    fn reconnect(&self, slot: u64) {
+        let mut provider = self.provider.write().unwrap();
+        *provider = match provider.reconnected() {
+            Ok(reconnected) => reconnected,
+            Err(e) => {
+                error!(
+                    "Failed to reconnect RPC provider, keeping the current one: {:?}",
+                    e
+                );
+                return;
+            }
+        };
+        metrics::rpc_reconnect(self.config.chain_id);
+        warn!(
+            chain_id = self.config.chain_id,
+            slot,
+            stall_count = self.head_watchdog.stall_count(),
+            "Reconnecting RPC provider after no data timeout"
+        );
+    }
+
+    pub fn with_backfill_limiter(mut self, limiter: Option<Arc<Semaphore>>) -> Self {
+        self.backfill_limiter = limiter;
+        self
+    }
+
+    pub fn with_shutdown(mut self, shutdown: CancellationToken) -> Self {
+        self.shutdown = Some(shutdown);
+        self
     }
 }
@@ -1,4 +1,4 @@
 pub mod handler;
 pub mod indexer;
 mod parser;
-mod provider;
+pub mod provider;
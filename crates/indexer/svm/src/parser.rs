@@ -12,9 +12,10 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use solana_client::rpc_response::{Response, RpcLogsResponse};
 use solana_sdk::native_token::Sol;
+use std::collections::HashMap;
 use std::env;
 use tokio::time::{sleep, Duration};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct SolanaLog {
@@ -22,6 +23,17 @@ pub struct SolanaLog {
     pub timestamp: DateTime<Utc>,
     pub slot_number: u64,
     pub signature: String,
+    /// Program whose signature history the log was found in, when known
+    #[serde(default)]
+    pub program: Option<String>,
+    /// Whether every log of the transaction was read, so it can be marked
+    /// processed once its events are written. False when unknown.
+    #[serde(default)]
+    pub transaction_complete: bool,
+    /// Instruction that emitted the log, e.g. `NativeTokenDeposit`, when the
+    /// program announced it
+    #[serde(default)]
+    pub instruction: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -102,6 +114,71 @@ pub struct L2WithdrawExecutedEvent {
     pub slot_number: u64,
 }
 
+/// Marker left by the RPC node when a transaction's log output exceeded the
+/// log limit. Anything emitted after the marker is lost.
+pub const LOG_TRUNCATED_MARKER: &str = "Log truncated";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LogTruncatedEvent {
+    pub event: String,
+    pub program: String,
+    pub logs: Vec<String>,
+}
+
+impl LogTruncatedEvent {
+    pub fn new(program: String, logs: Vec<String>) -> Self {
+        Self {
+            event: "LogTruncated".to_string(),
+            program,
+            logs,
+        }
+    }
+}
+
+/// Prefix of the line Anchor programs log when an instruction starts
+const INSTRUCTION_LOG_PREFIX: &str = "Program log: Instruction: ";
+
+/// Follows which instruction each log line of a transaction belongs to, from
+/// the invoke and success lines the runtime writes around every program call
+#[derive(Debug, Default)]
+pub struct InstructionTracker {
+    /// Instruction of each program call in progress, innermost last
+    calls: Vec<Option<String>>,
+}
+
+impl InstructionTracker {
+    /// Feeds the next log line, returns whether it announced an instruction
+    /// and so carries no event
+    pub fn observe(&mut self, log: &str) -> bool {
+        if let Some(instruction) = log.strip_prefix(INSTRUCTION_LOG_PREFIX) {
+            match self.calls.last_mut() {
+                Some(call) => *call = Some(instruction.to_string()),
+                None => self.calls.push(Some(instruction.to_string())),
+            }
+            return true;
+        }
+
+        if let Some(line) = log.strip_prefix("Program ") {
+            if line.contains(" invoke [") {
+                self.calls.push(None);
+            } else if line.ends_with(" success") || line.contains(" failed") {
+                self.calls.pop();
+            }
+        }
+        false
+    }
+
+    /// Instruction of the innermost program call
+    pub fn current(&self) -> Option<&str> {
+        self.calls.last().and_then(Option::as_deref)
+    }
+}
+
+/// Returns true when the RPC node truncated the transaction's logs
+pub fn is_log_truncated(logs: &[String]) -> bool {
+    logs.iter().any(|log| log.contains(LOG_TRUNCATED_MARKER))
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub enum SolanaEvent {
     MessageTransaction(MessageTransactionEvent),
@@ -111,11 +188,13 @@ pub enum SolanaEvent {
     RefundSuccessful(RefundSuccessfulEvent),
     ForcedWithdrawalSuccessful(ForcedWithdrawalSuccessfulEvent),
     L2WithdrawExecuted(L2WithdrawExecutedEvent),
+    LogTruncated(LogTruncatedEvent),
     Unknown(Value), // For events we don't have specific structs for
 }
 
-/// Parse a JSON log string by first extracting the event type and then deserializing appropriately
-pub fn parse_json_log(log: &str) -> Result<SolanaEvent> {
+/// Parse a JSON log string by first extracting the event type and then deserializing appropriately.
+/// `aliases` maps event names emitted by the programs onto the names matched below.
+pub fn parse_json_log(log: &str, aliases: &HashMap<String, String>) -> Result<SolanaEvent> {
     let actual_log = log.trim_start_matches("Program log: ");
 
     // First, parse as generic JSON to extract the event field
@@ -123,10 +202,11 @@ pub fn parse_json_log(log: &str) -> Result<SolanaEvent> {
         serde_json::from_str(actual_log).map_err(|e| eyre::eyre!("Failed to parse JSON: {}", e))?;
 
     // Extract the event field
-    let event_type = json_value
+    let emitted = json_value
         .get("event")
         .and_then(|v| v.as_str())
         .ok_or_else(|| eyre::eyre!("No 'event' field found in JSON"))?;
+    let event_type = aliases.get(emitted).map(String::as_str).unwrap_or(emitted);
 
     info!("Parsing event type: {}", event_type);
     match event_type {
@@ -167,10 +247,7 @@ pub fn parse_json_log(log: &str) -> Result<SolanaEvent> {
             Ok(SolanaEvent::L2WithdrawExecuted(event))
         }
         _ => {
-            debug!(
-                "Unknown event type: {}, storing as generic JSON",
-                event_type
-            );
+            warn!("Unrecognized event type: {}", event_type);
             Ok(SolanaEvent::Unknown(json_value))
         }
     }
@@ -186,18 +263,26 @@ impl SolanaEvent {
             SolanaEvent::RefundSuccessful(event) => &event.event,
             SolanaEvent::ForcedWithdrawalSuccessful(event) => &event.event,
             SolanaEvent::L2WithdrawExecuted(event) => &event.event,
+            SolanaEvent::LogTruncated(event) => &event.event,
             SolanaEvent::Unknown(event) => "Unknown",
         }
     }
 }
 
-pub fn parse_log(response: Response<RpcLogsResponse>) -> eyre::Result<SolanaLog> {
+pub fn parse_log(
+    response: Response<RpcLogsResponse>,
+    aliases: &HashMap<String, String>,
+) -> eyre::Result<SolanaLog> {
     let signature = response.value.signature;
     let logs = response.value.logs;
     let slot = response.context.slot;
 
+    let mut instructions = InstructionTracker::default();
     for log in logs {
-        let event = parse_json_log(&log);
+        if instructions.observe(&log) {
+            continue;
+        }
+        let event = parse_json_log(&log, aliases);
 
         if let Ok(event) = event {
             return Ok(SolanaLog {
@@ -205,8 +290,71 @@ pub fn parse_log(response: Response<RpcLogsResponse>) -> eyre::Result<SolanaLog>
                 signature: signature,
                 slot_number: slot,
                 timestamp: Utc::now(), // Live events use current time
+                program: None,
+                transaction_complete: false,
+                instruction: instructions.current().map(str::to_string),
             });
         }
     }
     Err(eyre::eyre!("No relevant events found"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_takes_the_instruction_of_its_program_call() {
+        let logs = [
+            "Program Bridge111 invoke [1]",
+            "Program log: Instruction: SplTokensDeposit",
+            "Program Tokenkeg invoke [2]",
+            "Program log: Instruction: TransferChecked",
+            "Program Tokenkeg success",
+            r#"Program log: {"event":"MessageTransaction"}"#,
+        ];
+        let mut instructions = InstructionTracker::default();
+        let consumed: Vec<bool> = logs.iter().map(|log| instructions.observe(log)).collect();
+
+        assert_eq!(consumed, [false, true, false, true, false, false]);
+        assert_eq!(instructions.current(), Some("SplTokensDeposit"));
+    }
+
+    #[test]
+    fn instruction_ends_with_its_program_call() {
+        let mut instructions = InstructionTracker::default();
+        instructions.observe("Program Bridge111 invoke [1]");
+        instructions.observe("Program log: Instruction: NativeTokenDeposit");
+        assert_eq!(instructions.current(), Some("NativeTokenDeposit"));
+
+        instructions.observe("Program Bridge111 success");
+        assert_eq!(instructions.current(), None);
+    }
+
+    const RENAMED_REFUND: &str = r#"Program log: {"event":"RefundCompleted","nonce":1,"l1_receiver":"receiver","l1_token":"token","chain_id":900,"amount":5,"slot_number":100}"#;
+
+    #[test]
+    fn renamed_event_is_unknown_without_an_alias() {
+        let event = parse_json_log(RENAMED_REFUND, &HashMap::new()).unwrap();
+
+        let SolanaEvent::Unknown(value) = event else {
+            panic!("expected an unknown event, got {:?}", event);
+        };
+        assert_eq!(value["event"], "RefundCompleted");
+    }
+
+    #[test]
+    fn alias_restores_matching_of_a_renamed_event() {
+        let aliases = HashMap::from([(
+            "RefundCompleted".to_string(),
+            "RefundSuccessful".to_string(),
+        )]);
+
+        let event = parse_json_log(RENAMED_REFUND, &aliases).unwrap();
+
+        let SolanaEvent::RefundSuccessful(refund) = event else {
+            panic!("expected a refund, got {:?}", event);
+        };
+        assert_eq!(refund.nonce, 1);
+    }
+}
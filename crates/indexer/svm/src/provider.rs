@@ -1,15 +1,19 @@
 use std::{
+    collections::{HashMap, HashSet},
     sync::{mpsc, Arc},
     time::{Duration, Instant},
 };
 
 use chrono::{DateTime, Utc};
-use eyre::eyre;
-use futures_util::StreamExt;
+use common::config::SolanaCommitment;
+use database::client::DbClient;
+use eyre::{eyre, WrapErr};
+use futures_util::{stream, StreamExt, TryStreamExt};
 use serde_json::json;
 use solana_client::{
+    http_sender::HttpSender,
     nonblocking::rpc_client::RpcClient,
-    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClientConfig},
     rpc_config::{
         RpcSignaturesForAddressConfig, RpcTransactionConfig, RpcTransactionLogsConfig,
         RpcTransactionLogsFilter,
@@ -25,32 +29,186 @@ use solana_transaction_status_client_types::{
 };
 use tokio::sync::mpsc::Receiver;
 use tokio_stream::wrappers::ReceiverStream;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
-use crate::parser::{parse_json_log, SolanaLog};
+use crate::parser::{
+    is_log_truncated, parse_json_log, InstructionTracker, LogTruncatedEvent, SolanaEvent, SolanaLog,
+};
+
+/// Upper bound on a single RPC request
+const RPC_TIMEOUT: Duration = Duration::from_secs(60);
+/// Idle connections kept open to the RPC node for reuse
+const RPC_POOL_MAX_IDLE_PER_HOST: usize = 16;
+const RPC_POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+const RPC_TCP_KEEPALIVE: Duration = Duration::from_secs(30);
+
+/// Builds the HTTP client behind the provider's `RpcClient`. It is created
+/// once per provider and every RPC call goes through it, so connections to
+/// the node are pooled and kept alive instead of being re-established.
+fn build_http_client() -> eyre::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(RPC_TIMEOUT)
+        .pool_max_idle_per_host(RPC_POOL_MAX_IDLE_PER_HOST)
+        .pool_idle_timeout(RPC_POOL_IDLE_TIMEOUT)
+        .tcp_keepalive(RPC_TCP_KEEPALIVE)
+        .build()
+        .wrap_err("Failed to build HTTP client for the Solana RPC")
+}
+
+fn connect(http_url: &str, commitment: CommitmentConfig) -> eyre::Result<Arc<RpcClient>> {
+    let sender = HttpSender::new_with_client(http_url, build_http_client()?);
+    Ok(Arc::new(RpcClient::new_sender(
+        sender,
+        RpcClientConfig::with_commitment(commitment),
+    )))
+}
+
+/// Events a program logged in one transaction
+struct TransactionEvents {
+    events: Vec<SolanaLog>,
+    /// Whether every log was read. A transaction without meta or with a log
+    /// that failed to parse is incomplete and never marked processed.
+    complete: bool,
+}
+
+/// Reads the events `program` logged in the transaction `signature`
+fn read_transaction_events(
+    transaction: EncodedConfirmedTransactionWithStatusMeta,
+    signature: &str,
+    program: &str,
+    aliases: &HashMap<String, String>,
+) -> TransactionEvents {
+    let slot = transaction.slot;
+    let timestamp = transaction
+        .block_time
+        .and_then(|ts| DateTime::<Utc>::from_timestamp(ts, 0))
+        .unwrap_or_else(Utc::now);
+    let log = |event: SolanaEvent, instruction: Option<String>| SolanaLog {
+        event,
+        slot_number: slot,
+        signature: signature.to_string(),
+        timestamp,
+        program: Some(program.to_string()),
+        transaction_complete: false,
+        instruction,
+    };
+
+    let Some(meta) = transaction.transaction.meta else {
+        // Dropped or expired transactions come back without meta, there are
+        // no logs to read from them
+        warn!(
+            "Skipping transaction {} in slot {} without meta",
+            signature, slot
+        );
+        return TransactionEvents {
+            events: Vec::new(),
+            complete: false,
+        };
+    };
+    let OptionSerializer::Some(logs) = meta.log_messages else {
+        return TransactionEvents {
+            events: Vec::new(),
+            complete: true,
+        };
+    };
+
+    let mut events = Vec::new();
+    let mut complete = true;
+    if is_log_truncated(&logs) {
+        // The node dropped the tail of the logs, so events may be missing.
+        // Flag the transaction instead of skipping it.
+        warn!(
+            "Logs truncated for transaction {} in slot {}",
+            signature, slot
+        );
+        events.push(log(
+            SolanaEvent::LogTruncated(LogTruncatedEvent::new(program.to_string(), logs.clone())),
+            None,
+        ));
+    }
+
+    let mut instructions = InstructionTracker::default();
+    for line in logs {
+        if instructions.observe(&line) || !line.starts_with("Program log:") {
+            continue;
+        }
+
+        match parse_json_log(&line, aliases) {
+            Ok(event) => events.push(log(event, instructions.current().map(str::to_string))),
+            Err(e) => {
+                error!("Failed to parse log '{}': {}", line, e);
+                complete = false;
+            }
+        }
+    }
+
+    for event in &mut events {
+        event.transaction_complete = complete;
+    }
+    TransactionEvents { events, complete }
+}
 
 #[derive(Clone)]
 pub struct SvmProvider {
     http: Arc<RpcClient>,
+    http_url: String,
     commitment: CommitmentConfig,
+    event_aliases: Arc<HashMap<String, String>>,
+    /// Where already processed signatures are looked up, so their
+    /// transactions are not fetched again
+    processed_signatures: Option<Arc<DbClient>>,
 }
 
 impl SvmProvider {
-    pub fn new(http_url: &str, chain_id: u64) -> Self {
+    pub fn new(http_url: &str, chain_id: u64) -> eyre::Result<Self> {
         // let pubsub_client = Arc::new(
         //     PubsubClient::new(ws_url)
         //         .await
         //         .map_err(|e| eyre::eyre!("Failed to connect to WebSocket: {}", e))?,
         // );
 
-        Self {
-            http: Arc::new(RpcClient::new_with_timeout(
-                http_url.to_string(),
-                Duration::from_secs(60),
-            )),
+        let commitment = CommitmentConfig::finalized();
 
-            commitment: CommitmentConfig::finalized(),
-        }
+        Ok(Self {
+            http: connect(http_url, commitment)?,
+            http_url: http_url.to_string(),
+            commitment,
+            event_aliases: Arc::new(HashMap::new()),
+            processed_signatures: None,
+        })
+    }
+
+    /// A copy of this provider over a new HTTP client, for when the current
+    /// one silently stopped serving data
+    pub fn reconnected(&self) -> eyre::Result<Self> {
+        Ok(Self {
+            http: connect(&self.http_url, self.commitment)?,
+            ..self.clone()
+        })
+    }
+
+    /// Commitment slots, signatures and transactions are fetched at
+    pub fn with_commitment(mut self, commitment: SolanaCommitment) -> Self {
+        self.commitment = match commitment {
+            SolanaCommitment::Processed => CommitmentConfig::processed(),
+            SolanaCommitment::Confirmed => CommitmentConfig::confirmed(),
+            SolanaCommitment::Finalized => CommitmentConfig::finalized(),
+        };
+        self
+    }
+
+    /// Event names to translate before matching a program log to an event
+    pub fn with_event_aliases(mut self, aliases: HashMap<String, String>) -> Self {
+        self.event_aliases = Arc::new(aliases);
+        self
+    }
+
+    /// Skips fetching transactions whose signature `db` has recorded as
+    /// processed. Transactions without events are recorded while fetching,
+    /// the writer records the others once their events are written.
+    pub fn with_processed_signatures(mut self, db: Arc<DbClient>) -> Self {
+        self.processed_signatures = Some(db);
+        self
     }
 
     pub async fn get_slot(&self) -> eyre::Result<u64> {
@@ -66,6 +224,7 @@ impl SvmProvider {
         start_slot: u64,
         end_slot: u64,
         batch_size: u64,
+        until: Option<String>,
     ) -> eyre::Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
         let mut all_signatures = Vec::new();
         let mut unique_signatures: std::collections::HashMap<
@@ -80,7 +239,7 @@ impl SvmProvider {
                 limit: Some(batch_size as usize),
                 commitment: Some(self.commitment),
                 min_context_slot: Some(start_slot),
-                until: None,
+                until: until.clone(),
             };
 
             let mut signatures: Vec<RpcConfirmedTransactionStatusWithSignature> = self
@@ -150,18 +309,86 @@ impl SvmProvider {
             .map_err(Into::into)
     }
 
+    /// Whether the node still has `signature` in its transaction history
+    pub async fn is_signature_retained(&self, signature: &str) -> eyre::Result<bool> {
+        let signature: Signature = signature.parse()?;
+        let statuses = self
+            .http
+            .get_signature_statuses_with_history(&[signature])
+            .await?;
+        Ok(statuses.value.into_iter().next().flatten().is_some())
+    }
+
+    /// Fetches the events of `programs` in slots `from..=to` like `get_logs`,
+    /// split into `concurrency` windows fetched at once. `buffered` yields
+    /// them in window order, so the events stay sorted by slot.
+    pub async fn get_logs_in_windows(
+        &self,
+        programs: Vec<Pubkey>,
+        from: u64,
+        to: u64,
+        concurrency: usize,
+        cursors: &HashMap<String, String>,
+        skip_processed: bool,
+    ) -> eyre::Result<Vec<SolanaLog>> {
+        let window_size = (to.saturating_sub(from) + 1).div_ceil(concurrency as u64);
+        let windows = (from..=to)
+            .step_by(window_size as usize)
+            .map(|start| (start, (start + window_size - 1).min(to)));
+
+        let events: Vec<Vec<SolanaLog>> = stream::iter(windows)
+            .map(|(start, end)| {
+                self.get_logs(programs.clone(), start, end, cursors, skip_processed)
+            })
+            .buffered(concurrency)
+            .try_collect()
+            .await?;
+
+        Ok(events.into_iter().flatten().collect())
+    }
+
+    /// Fetches the events of `programs` in slots `from..=to`. A program with an
+    /// entry in `cursors` only gets signatures newer than its cursor signature,
+    /// unless the node has pruned that signature. With `skip_processed`,
+    /// transactions already recorded as processed are not fetched and those
+    /// without events are recorded.
     pub async fn get_logs(
         &self,
         programs: Vec<Pubkey>,
         from: u64,
         to: u64,
+        cursors: &HashMap<String, String>,
+        skip_processed: bool,
     ) -> eyre::Result<Vec<SolanaLog>> {
         let mut all_found_events = Vec::new();
 
         for program in programs {
+            let until = match cursors.get(&program.to_string()) {
+                Some(signature) if self.is_signature_retained(signature).await? => {
+                    debug!("Resuming program {} after signature {}", program, signature);
+                    Some(signature.clone())
+                }
+                Some(signature) => {
+                    warn!(
+                        "Cursor signature {} for program {} was pruned, falling back to slot range",
+                        signature, program
+                    );
+                    None
+                }
+                None => None,
+            };
+
+            // A failure for one program fails the whole range, so the indexer
+            // retries it instead of silently indexing only the other program
             let signatures = self
-                .get_signature_for_address(&program, from, to, 1000)
-                .await?;
+                .get_signature_for_address(&program, from, to, 1000, until)
+                .await
+                .wrap_err_with(|| {
+                    format!(
+                        "Failed to fetch signatures for program {} in slots {}..={}",
+                        program, from, to
+                    )
+                })?;
 
             debug!(
                 "Found {} signatures for program {}",
@@ -173,8 +400,27 @@ impl SvmProvider {
                 continue;
             }
 
+            let processed = match &self.processed_signatures {
+                Some(db) if skip_processed => {
+                    let candidates: Vec<String> =
+                        signatures.iter().map(|sig| sig.signature.clone()).collect();
+                    db.find_processed_signatures(&candidates).await?
+                }
+                _ => HashSet::new(),
+            };
+            if !processed.is_empty() {
+                debug!(
+                    "Skipping {} already processed signatures for program {}",
+                    processed.len(),
+                    program
+                );
+            }
+
             for sig in signatures {
                 let signature_str = &sig.signature;
+                if processed.contains(signature_str) {
+                    continue;
+                }
                 let signature: Signature = match signature_str.parse() {
                     Ok(s) => s,
                     Err(e) => {
@@ -188,47 +434,38 @@ impl SvmProvider {
 
                 match self.get_transaction(&signature).await {
                     Ok(tx_with_meta) => {
-                        let current_slot = tx_with_meta.slot;
-                        let block_time = tx_with_meta.block_time;
-
-                        let timestamp = block_time
-                            .and_then(|ts| DateTime::<Utc>::from_timestamp(ts as i64, 0))
-                            .unwrap_or_else(|| {
-                                // tracing::warn!(
-                                //     "Missing or invalid block timestamp in {}. Using now.",
-                                //     event_name
-                                // );
-                                Utc::now()
-                            });
-
-                        if let Some(meta) = tx_with_meta.transaction.meta {
-                            if let OptionSerializer::Some(logs) = meta.log_messages {
-                                for log in logs {
-                                    if !log.starts_with("Program log:") {
-                                        continue;
-                                    }
-
-                                    match parse_json_log(&log) {
-                                        Ok(event) => {
-                                            all_found_events.push(SolanaLog {
-                                                event: event,
-                                                slot_number: current_slot,
-                                                signature: signature_str.clone(),
-                                                timestamp: timestamp,
-                                            });
-                                        }
-                                        Err(e) => {
-                                            error!("Failed to parse log '{}': {}", log, e);
-                                        }
-                                    }
-                                }
+                        let slot = tx_with_meta.slot;
+                        let transaction = read_transaction_events(
+                            tx_with_meta,
+                            signature_str,
+                            &program.to_string(),
+                            &self.event_aliases,
+                        );
+                        // No rows will be written for a transaction without
+                        // events, so it is marked right away
+                        if transaction.complete && transaction.events.is_empty() && skip_processed {
+                            if let Some(db) = &self.processed_signatures {
+                                db.mark_signature_processed(
+                                    signature_str,
+                                    &program.to_string(),
+                                    slot as i64,
+                                )
+                                .await?;
                             }
                         }
+                        all_found_events.extend(transaction.events);
                     }
 
                     Err(e) => {
-                        error!("Erorr while fetching the transaction from signature: {}", e);
-                        return Err(eyre!("Erorr while fetching the transaction from signature"));
+                        error!(
+                            "Error while fetching transaction {} of program {}: {}",
+                            signature_str, program, e
+                        );
+                        return Err(eyre!(
+                            "Error while fetching transaction {} of program {}",
+                            signature_str,
+                            program
+                        ));
                     }
                 }
             }
@@ -239,3 +476,354 @@ impl SvmProvider {
         Ok(all_found_events)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ops::RangeInclusive,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+    };
+
+    use async_trait::async_trait;
+    use solana_client::{
+        client_error::Result as ClientResult,
+        rpc_sender::{RpcSender, RpcTransportStats},
+    };
+
+    use super::*;
+
+    const SIGNATURE: &str = "signature";
+    const PROGRAM: &str = "program";
+    const REFUND: &str = r#"Program log: {"event":"RefundSuccessful","nonce":1,"l1_receiver":"receiver","l1_token":"token","chain_id":900,"amount":5,"slot_number":100}"#;
+
+    fn transaction(logs: Option<&[&str]>) -> EncodedConfirmedTransactionWithStatusMeta {
+        let meta = logs.map(|logs| {
+            json!({
+                "err": null,
+                "status": { "Ok": null },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "logMessages": logs,
+            })
+        });
+        serde_json::from_value(json!({
+            "slot": 100,
+            "blockTime": 1_700_000_000,
+            "transaction": ["", "base64"],
+            "meta": meta,
+        }))
+        .unwrap()
+    }
+
+    fn read(logs: Option<&[&str]>) -> TransactionEvents {
+        read_transaction_events(transaction(logs), SIGNATURE, PROGRAM, &HashMap::new())
+    }
+
+    fn signature(n: u8) -> String {
+        Signature::from([n; 64]).to_string()
+    }
+
+    /// What the provider asked `MockRpc` for
+    #[derive(Default)]
+    struct MockCalls {
+        fetched: Mutex<Vec<String>>,
+        signature_fetches: AtomicUsize,
+        max_signature_fetches: AtomicUsize,
+    }
+
+    /// Serves a program's signature history, one refund per transaction.
+    /// Signatures of earlier slots take longer to serve, so concurrent
+    /// windows finish out of order.
+    struct MockRpc {
+        /// `(signature, slot)`, oldest first
+        history: Vec<(String, u64)>,
+        pruned: HashSet<String>,
+        calls: Arc<MockCalls>,
+    }
+
+    #[async_trait]
+    impl RpcSender for MockRpc {
+        async fn send(
+            &self,
+            request: RpcRequest,
+            params: serde_json::Value,
+        ) -> ClientResult<serde_json::Value> {
+            let slot_of = |signature: &str| {
+                self.history
+                    .iter()
+                    .find(|(known, _)| known == signature)
+                    .map(|(_, slot)| *slot)
+            };
+            Ok(match request {
+                RpcRequest::GetSignaturesForAddress => {
+                    let in_flight = self.calls.signature_fetches.fetch_add(1, Ordering::SeqCst) + 1;
+                    self.calls
+                        .max_signature_fetches
+                        .fetch_max(in_flight, Ordering::SeqCst);
+                    let start_slot = params[1]["minContextSlot"].as_u64().unwrap_or(0);
+                    tokio::time::sleep(Duration::from_millis(200u64.saturating_sub(start_slot)))
+                        .await;
+                    self.calls.signature_fetches.fetch_sub(1, Ordering::SeqCst);
+
+                    let until = params[1]["until"].as_str();
+                    let before = params[1]["before"].as_str();
+                    let limit = params[1]["limit"].as_u64().unwrap_or(1000) as usize;
+                    // Newest first, like the node
+                    let statuses: Vec<_> = self
+                        .history
+                        .iter()
+                        .rev()
+                        .skip_while(|(signature, _)| {
+                            before.is_some_and(|before| before != signature)
+                        })
+                        .skip(usize::from(before.is_some()))
+                        .take_while(|(signature, _)| Some(signature.as_str()) != until)
+                        .take(limit)
+                        .map(|(signature, slot)| {
+                            json!({ "signature": signature, "slot": slot, "err": null })
+                        })
+                        .collect();
+                    json!(statuses)
+                }
+                RpcRequest::GetSignatureStatuses => {
+                    let signature = params[0][0].as_str().unwrap();
+                    let status = slot_of(signature)
+                        .filter(|_| !self.pruned.contains(signature))
+                        .map(|slot| {
+                            json!({
+                                "slot": slot,
+                                "confirmations": null,
+                                "err": null,
+                                "status": { "Ok": null },
+                                "confirmationStatus": "finalized",
+                            })
+                        });
+                    json!({ "context": { "slot": 200 }, "value": [status] })
+                }
+                RpcRequest::GetTransaction => {
+                    let signature = params[0].as_str().unwrap();
+                    self.calls
+                        .fetched
+                        .lock()
+                        .unwrap()
+                        .push(signature.to_string());
+                    let mut transaction = transaction(Some(&[REFUND]));
+                    transaction.slot = slot_of(signature).unwrap();
+                    serde_json::to_value(transaction).unwrap()
+                }
+                request => panic!("unexpected {} request", request),
+            })
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            "mock".to_string()
+        }
+    }
+
+    /// A provider over a program with one transaction in each slot
+    /// `100 + transactions`, and what it asked the RPC for
+    fn mock_provider(
+        transactions: RangeInclusive<u8>,
+        pruned: &[String],
+    ) -> (SvmProvider, Arc<MockCalls>) {
+        let calls = Arc::new(MockCalls::default());
+        let rpc = MockRpc {
+            history: transactions
+                .map(|n| (signature(n), 100 + n as u64))
+                .collect(),
+            pruned: pruned.iter().cloned().collect(),
+            calls: calls.clone(),
+        };
+        let provider = SvmProvider {
+            http: Arc::new(RpcClient::new_sender(
+                rpc,
+                RpcClientConfig::with_commitment(CommitmentConfig::finalized()),
+            )),
+            ..SvmProvider::new("http://localhost:8899", 900).unwrap()
+        };
+        (provider, calls)
+    }
+
+    #[test]
+    fn every_event_of_a_fully_read_transaction_is_complete() {
+        let transaction = read(Some(&[REFUND, REFUND]));
+
+        assert!(transaction.complete);
+        assert_eq!(transaction.events.len(), 2);
+        assert!(transaction
+            .events
+            .iter()
+            .all(|event| event.transaction_complete));
+    }
+
+    #[test]
+    fn unparseable_log_leaves_the_transaction_incomplete() {
+        let transaction = read(Some(&[REFUND, "Program log: {not json"]));
+
+        assert!(!transaction.complete);
+        assert_eq!(transaction.events.len(), 1);
+        assert!(!transaction.events[0].transaction_complete);
+    }
+
+    #[test]
+    fn transaction_without_meta_is_skipped() {
+        let transaction = read(None);
+
+        assert!(!transaction.complete);
+        assert!(transaction.events.is_empty());
+    }
+
+    #[test]
+    fn transaction_without_events_is_complete() {
+        let transaction = read(Some(&[
+            "Program Bridge111 invoke [1]",
+            "Program Bridge111 success",
+        ]));
+
+        assert!(transaction.complete);
+        assert!(transaction.events.is_empty());
+    }
+
+    #[test]
+    fn truncated_logs_are_flagged_along_with_the_events_before_the_marker() {
+        let transaction = read(Some(&[REFUND, "Log truncated"]));
+
+        assert_eq!(transaction.events.len(), 2);
+        let SolanaEvent::LogTruncated(truncated) = &transaction.events[0].event else {
+            panic!("expected the truncation to be flagged first");
+        };
+        assert_eq!(truncated.program, PROGRAM);
+        assert_eq!(truncated.logs, vec![REFUND, "Log truncated"]);
+        assert!(matches!(
+            transaction.events[1].event,
+            SolanaEvent::RefundSuccessful(_)
+        ));
+    }
+
+    #[test]
+    fn logs_without_the_marker_are_not_truncated() {
+        let transaction = read(Some(&[REFUND]));
+
+        assert!(!transaction
+            .events
+            .iter()
+            .any(|event| matches!(event.event, SolanaEvent::LogTruncated(_))));
+    }
+
+    #[tokio::test]
+    async fn resume_only_processes_signatures_after_the_cursor() {
+        let (provider, calls) = mock_provider(1..=5, &[]);
+        let program = Pubkey::new_unique();
+        let cursors = HashMap::from([(program.to_string(), signature(3))]);
+
+        let logs = provider
+            .get_logs(vec![program], 100, 110, &cursors, false)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *calls.fetched.lock().unwrap(),
+            vec![signature(4), signature(5)]
+        );
+        let slots: Vec<u64> = logs.iter().map(|log| log.slot_number).collect();
+        assert_eq!(slots, vec![104, 105]);
+    }
+
+    #[tokio::test]
+    async fn pruned_cursor_falls_back_to_the_slot_range() {
+        let (provider, calls) = mock_provider(1..=5, &[signature(3)]);
+        let program = Pubkey::new_unique();
+        let cursors = HashMap::from([(program.to_string(), signature(3))]);
+
+        let logs = provider
+            .get_logs(vec![program], 102, 110, &cursors, false)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *calls.fetched.lock().unwrap(),
+            vec![signature(2), signature(3), signature(4), signature(5)]
+        );
+        assert_eq!(logs.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn windows_are_fetched_one_at_a_time_by_default() {
+        let (provider, calls) = mock_provider(1..=20, &[]);
+
+        provider
+            .get_logs_in_windows(
+                vec![Pubkey::new_unique()],
+                101,
+                120,
+                1,
+                &HashMap::new(),
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(calls.max_signature_fetches.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn concurrent_windows_are_bounded_and_keep_slot_order() {
+        let (provider, calls) = mock_provider(1..=20, &[]);
+
+        let logs = provider
+            .get_logs_in_windows(
+                vec![Pubkey::new_unique()],
+                101,
+                120,
+                4,
+                &HashMap::new(),
+                false,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(calls.max_signature_fetches.load(Ordering::SeqCst), 4);
+        let slots: Vec<u64> = logs.iter().map(|log| log.slot_number).collect();
+        assert_eq!(slots, (101..=120).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn configured_and_cloned_providers_share_one_client() {
+        let provider = SvmProvider::new("http://localhost:8899", 900).unwrap();
+        let configured = provider
+            .clone()
+            .with_commitment(SolanaCommitment::Confirmed)
+            .with_event_aliases(HashMap::new());
+
+        assert!(Arc::ptr_eq(&provider.http, &configured.http));
+        assert!(Arc::ptr_eq(&provider.http, &provider.clone().http));
+    }
+
+    #[test]
+    fn reconnected_provider_keeps_its_settings_over_a_new_client() {
+        let provider = SvmProvider::new("http://localhost:8899", 900)
+            .unwrap()
+            .with_commitment(SolanaCommitment::Confirmed)
+            .with_event_aliases(HashMap::from([(
+                "RefundDone".to_string(),
+                "RefundSuccessful".to_string(),
+            )]));
+
+        let reconnected = provider.reconnected().unwrap();
+        assert!(!Arc::ptr_eq(&provider.http, &reconnected.http));
+        assert_eq!(reconnected.http_url, provider.http_url);
+        assert_eq!(reconnected.commitment, CommitmentConfig::confirmed());
+        assert!(Arc::ptr_eq(
+            &provider.event_aliases,
+            &reconnected.event_aliases
+        ));
+    }
+}
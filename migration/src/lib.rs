@@ -4,6 +4,24 @@ mod m20250306_000002_create_last_synced_table;
 mod m20250507_085332_create_da_table;
 mod m20250530_074724_create_bridge_transaction_table;
 mod m20250926_174334_create_uniswap_table;
+mod m20251016_090000_add_transaction_flows_covering_index;
+mod m20251016_100000_create_unparsed_logs_table;
+mod m20251016_110000_add_transaction_hash_indexes;
+mod m20251016_120000_add_token_standard_to_source_transactions;
+mod m20251016_130000_add_height_kind_to_last_synced;
+mod m20251016_140000_create_svm_cursor_table;
+mod m20251016_150000_add_non_negative_checks;
+mod m20251016_160000_add_refund_details_to_transaction_flows;
+mod m20251016_170000_recompute_transaction_flows_is_completed;
+mod m20251016_180000_make_uniswap_token_decimals_nullable;
+mod m20251016_190000_create_block_hashes_table;
+mod m20251016_200000_create_batch_da_references_table;
+mod m20251016_210000_add_chain_head_to_last_synced;
+mod m20251016_220000_create_processed_signatures_table;
+mod m20251016_230000_add_updated_at_triggers;
+mod m20251016_235000_create_pending_finalizations_table;
+mod m20251017_000000_add_unparsed_logs_unique_index;
+mod m20251017_010000_create_batch_l1_blocks_table;
 pub struct Migrator;
 
 #[async_trait::async_trait]
@@ -14,6 +32,24 @@ impl MigratorTrait for Migrator {
             Box::new(m20250507_085332_create_da_table::Migration),
             Box::new(m20250530_074724_create_bridge_transaction_table::Migration),
             Box::new(m20250926_174334_create_uniswap_table::Migration),
+            Box::new(m20251016_090000_add_transaction_flows_covering_index::Migration),
+            Box::new(m20251016_100000_create_unparsed_logs_table::Migration),
+            Box::new(m20251016_110000_add_transaction_hash_indexes::Migration),
+            Box::new(m20251016_120000_add_token_standard_to_source_transactions::Migration),
+            Box::new(m20251016_130000_add_height_kind_to_last_synced::Migration),
+            Box::new(m20251016_140000_create_svm_cursor_table::Migration),
+            Box::new(m20251016_150000_add_non_negative_checks::Migration),
+            Box::new(m20251016_160000_add_refund_details_to_transaction_flows::Migration),
+            Box::new(m20251016_170000_recompute_transaction_flows_is_completed::Migration),
+            Box::new(m20251016_180000_make_uniswap_token_decimals_nullable::Migration),
+            Box::new(m20251016_190000_create_block_hashes_table::Migration),
+            Box::new(m20251016_200000_create_batch_da_references_table::Migration),
+            Box::new(m20251016_210000_add_chain_head_to_last_synced::Migration),
+            Box::new(m20251016_220000_create_processed_signatures_table::Migration),
+            Box::new(m20251016_230000_add_updated_at_triggers::Migration),
+            Box::new(m20251016_235000_create_pending_finalizations_table::Migration),
+            Box::new(m20251017_000000_add_unparsed_logs_unique_index::Migration),
+            Box::new(m20251017_010000_create_batch_l1_blocks_table::Migration),
         ]
     }
 }
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Covering index for the source_transactions -> transaction_flows join.
+        // Includes the flow columns read by the bridge views so the keyset
+        // queries can be answered with an index-only scan on transaction_flows.
+        // Uniqueness stays with unique_flow_chain_nonce.
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_transaction_flows_chain_nonce_covering")
+                    .table(TransactionFlows::Table)
+                    .col(TransactionFlows::ChainId)
+                    .col(TransactionFlows::Nonce)
+                    .include(TransactionFlows::IsHandled)
+                    .include(TransactionFlows::IsExecuted)
+                    .include(TransactionFlows::IsCompleted)
+                    .include(TransactionFlows::HandleStatus)
+                    .include(TransactionFlows::HandledAt)
+                    .include(TransactionFlows::HandleTxHash)
+                    .include(TransactionFlows::HandleBlockNumber)
+                    .include(TransactionFlows::ExecutedAt)
+                    .include(TransactionFlows::ExecuteTxHash)
+                    .include(TransactionFlows::ExecuteBlockNumber)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_transaction_flows_chain_nonce_covering")
+                    .table(TransactionFlows::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum TransactionFlows {
+    Table,
+    ChainId,
+    Nonce,
+    IsHandled,
+    IsExecuted,
+    IsCompleted,
+    HandleStatus,
+    HandledAt,
+    HandleTxHash,
+    HandleBlockNumber,
+    ExecutedAt,
+    ExecuteTxHash,
+    ExecuteBlockNumber,
+}
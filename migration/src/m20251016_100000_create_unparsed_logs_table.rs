@@ -0,0 +1,85 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Dead-letter table for logs the indexer saw but could not turn into rows
+        manager
+            .create_table(
+                Table::create()
+                    .table(UnparsedLogs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UnparsedLogs::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(UnparsedLogs::ChainId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(UnparsedLogs::BlockNumber)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(UnparsedLogs::TransactionHash)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(UnparsedLogs::LogIndex).big_integer())
+                    .col(ColumnDef::new(UnparsedLogs::EventType).string())
+                    .col(ColumnDef::new(UnparsedLogs::Reason).text().not_null())
+                    .col(ColumnDef::new(UnparsedLogs::Payload).text())
+                    .col(
+                        ColumnDef::new(UnparsedLogs::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_unparsed_logs_chain_block")
+                    .table(UnparsedLogs::Table)
+                    .col(UnparsedLogs::ChainId)
+                    .col(UnparsedLogs::BlockNumber)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UnparsedLogs::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum UnparsedLogs {
+    Table,
+    Id,
+    ChainId,
+    BlockNumber,
+    TransactionHash,
+    LogIndex,
+    EventType,
+    Reason,
+    Payload,
+    CreatedAt,
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Exact hash lookups used to skip already processed transactions
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_source_transactions_transaction_hash")
+                    .table(SourceTransactions::Table)
+                    .col(SourceTransactions::TransactionHash)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_transaction_flows_execute_tx_hash")
+                    .table(TransactionFlows::Table)
+                    .col(TransactionFlows::ExecuteTxHash)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_transaction_flows_execute_tx_hash")
+                    .table(TransactionFlows::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_source_transactions_transaction_hash")
+                    .table(SourceTransactions::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum SourceTransactions {
+    Table,
+    TransactionHash,
+}
+
+#[derive(DeriveIden)]
+enum TransactionFlows {
+    Table,
+    ExecuteTxHash,
+}
@@ -0,0 +1,75 @@
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{EnumIter, Iterable},
+};
+use sea_query::extension::postgres::Type as PostgresType;
+
+#[derive(DeriveIden)]
+struct TokenStandardEnum;
+
+#[derive(DeriveIden, EnumIter)]
+pub enum TokenStandardVariants {
+    #[sea_orm(iden = "Native")]
+    Native,
+    #[sea_orm(iden = "Spl")]
+    Spl,
+    #[sea_orm(iden = "Erc20")]
+    Erc20,
+}
+
+#[derive(DeriveIden)]
+enum SourceTransactions {
+    #[sea_orm(iden = "source_transactions")]
+    Table,
+    TokenStandard,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                PostgresType::create()
+                    .as_enum(TokenStandardEnum)
+                    .values(TokenStandardVariants::iter())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Nullable so rows indexed before this migration stay valid
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SourceTransactions::Table)
+                    .add_column(
+                        ColumnDef::new(SourceTransactions::TokenStandard)
+                            .enumeration(TokenStandardEnum, TokenStandardVariants::iter())
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SourceTransactions::Table)
+                    .drop_column(SourceTransactions::TokenStandard)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_type(PostgresType::drop().name(TokenStandardEnum).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
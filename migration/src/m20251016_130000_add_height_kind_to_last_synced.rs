@@ -0,0 +1,73 @@
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{EnumIter, Iterable},
+};
+use sea_query::extension::postgres::Type as PostgresType;
+
+#[derive(DeriveIden)]
+struct HeightKindEnum;
+
+#[derive(DeriveIden, EnumIter)]
+pub enum HeightKindVariants {
+    #[sea_orm(iden = "Block")]
+    Block,
+    #[sea_orm(iden = "Slot")]
+    Slot,
+}
+
+#[derive(DeriveIden)]
+enum LastSynced {
+    #[sea_orm(iden = "last_synced")]
+    Table,
+    HeightKind,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                PostgresType::create()
+                    .as_enum(HeightKindEnum)
+                    .values(HeightKindVariants::iter())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Nullable until each chain next checkpoints and records its kind
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LastSynced::Table)
+                    .add_column(
+                        ColumnDef::new(LastSynced::HeightKind)
+                            .enumeration(HeightKindEnum, HeightKindVariants::iter())
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LastSynced::Table)
+                    .drop_column(LastSynced::HeightKind)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_type(PostgresType::drop().name(HeightKindEnum).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Last processed signature per Solana program, used to resume mid-slot
+        manager
+            .create_table(
+                Table::create()
+                    .table(SvmCursor::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SvmCursor::ProgramId)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(SvmCursor::Signature).string().not_null())
+                    .col(ColumnDef::new(SvmCursor::Slot).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(SvmCursor::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SvmCursor::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum SvmCursor {
+    Table,
+    ProgramId,
+    Signature,
+    Slot,
+    UpdatedAt,
+}
@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Nonces and amounts come from unsigned on-chain values, a negative one
+        // can only be a parser bug
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE source_transactions
+                ADD CONSTRAINT chk_source_transactions_nonce_non_negative CHECK (nonce >= 0),
+                ADD CONSTRAINT chk_source_transactions_amount_non_negative CHECK (amount >= 0);
+
+            ALTER TABLE transaction_flows
+                ADD CONSTRAINT chk_transaction_flows_nonce_non_negative CHECK (nonce >= 0);
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE transaction_flows
+                DROP CONSTRAINT IF EXISTS chk_transaction_flows_nonce_non_negative;
+
+            ALTER TABLE source_transactions
+                DROP CONSTRAINT IF EXISTS chk_source_transactions_amount_non_negative,
+                DROP CONSTRAINT IF EXISTS chk_source_transactions_nonce_non_negative;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
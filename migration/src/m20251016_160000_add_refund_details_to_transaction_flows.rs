@@ -0,0 +1,93 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveIden)]
+enum TransactionFlows {
+    #[sea_orm(iden = "transaction_flows")]
+    Table,
+    RefundAmount,
+    RefundToken,
+}
+
+const REFUNDED_DEPOSITS_COLUMNS: &str = "
+                    st.*,
+                    tf.handled_at,
+                    tf.handle_tx_hash,
+                    tf.handle_block_number,
+                    tf.handle_status,
+                    tf.transaction_output,
+                    tf.executed_at AS refunded_at,
+                    tf.execute_tx_hash AS refund_tx_hash,
+                    tf.execute_block_number AS refund_block_number";
+
+const REFUNDED_DEPOSITS_FILTER: &str = "
+                FROM source_transactions st
+                JOIN transaction_flows tf ON st.chain_id = tf.chain_id AND st.nonce = tf.nonce
+                WHERE st.transaction_type = 'Deposit'::transaction_type_enum
+                  AND tf.handle_status = 0
+                  AND tf.is_executed = TRUE
+                  AND tf.execute_tx_hash IS NOT NULL";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Nullable, only refunds carry them
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TransactionFlows::Table)
+                    .add_column(
+                        ColumnDef::new(TransactionFlows::RefundAmount)
+                            .decimal_len(78, 0)
+                            .null(),
+                    )
+                    .add_column(
+                        ColumnDef::new(TransactionFlows::RefundToken)
+                            .string()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        db.execute_unprepared(&format!(
+            "CREATE OR REPLACE VIEW refunded_deposits AS
+                SELECT {},
+                    tf.refund_amount,
+                    tf.refund_token
+                {}",
+            REFUNDED_DEPOSITS_COLUMNS, REFUNDED_DEPOSITS_FILTER
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP VIEW IF EXISTS refunded_deposits")
+            .await?;
+        db.execute_unprepared(&format!(
+            "CREATE VIEW refunded_deposits AS
+                SELECT {}
+                {}",
+            REFUNDED_DEPOSITS_COLUMNS, REFUNDED_DEPOSITS_FILTER
+        ))
+        .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TransactionFlows::Table)
+                    .drop_column(TransactionFlows::RefundToken)
+                    .drop_column(TransactionFlows::RefundAmount)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
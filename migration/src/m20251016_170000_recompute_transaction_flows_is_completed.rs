@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Rows written before the update_is_completed trigger existed, or while
+        // it was disabled, can hold a stale value. Same rules as the trigger.
+        db.execute_unprepared(
+            r#"
+            UPDATE transaction_flows tf
+            SET is_completed = computed.is_completed
+            FROM (
+                SELECT f.id,
+                    CASE
+                        WHEN st.transaction_type = 'Deposit'::transaction_type_enum THEN f.is_handled OR f.is_executed
+                        WHEN st.transaction_type = 'Withdraw'::transaction_type_enum THEN f.is_handled
+                        WHEN st.transaction_type = 'ForcedWithdraw'::transaction_type_enum THEN f.is_handled AND f.is_executed
+                        ELSE FALSE
+                    END AS is_completed
+                FROM transaction_flows f
+                LEFT JOIN source_transactions st ON st.chain_id = f.chain_id AND st.nonce = f.nonce
+            ) computed
+            WHERE tf.id = computed.id
+              AND tf.is_completed IS DISTINCT FROM computed.is_completed;
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // Data fix only, the previous values were wrong
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveIden)]
+enum UniswapTokens {
+    #[sea_orm(iden = "uniswap_tokens")]
+    Table,
+    Decimals,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Tokens that don't implement decimals() are stored without it
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UniswapTokens::Table)
+                    .modify_column(ColumnDef::new(UniswapTokens::Decimals).integer().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("UPDATE uniswap_tokens SET decimals = 18 WHERE decimals IS NULL")
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UniswapTokens::Table)
+                    .modify_column(ColumnDef::new(UniswapTokens::Decimals).integer().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+}
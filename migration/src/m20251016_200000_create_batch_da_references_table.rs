@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Celestia blob each Twine batch was posted in, filled by the DA indexer
+        manager
+            .create_table(
+                Table::create()
+                    .table(BatchDaReferences::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BatchDaReferences::BatchNumber)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(BatchDaReferences::CelestiaHeight)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BatchDaReferences::Namespace)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BatchDaReferences::Commitment)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BatchDaReferences::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BatchDaReferences::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BatchDaReferences {
+    Table,
+    BatchNumber,
+    CelestiaHeight,
+    Namespace,
+    Commitment,
+    CreatedAt,
+}
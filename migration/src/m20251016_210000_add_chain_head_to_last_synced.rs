@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveIden)]
+enum LastSynced {
+    #[sea_orm(iden = "last_synced")]
+    Table,
+    ChainHead,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Latest head each indexer saw on its chain, so the API can report lag
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LastSynced::Table)
+                    .add_column(ColumnDef::new(LastSynced::ChainHead).big_integer().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LastSynced::Table)
+                    .drop_column(LastSynced::ChainHead)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,73 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Solana transactions already fetched and indexed, so a restart does
+        // not fetch them from the RPC again
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProcessedSignatures::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ProcessedSignatures::Signature)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ProcessedSignatures::ProgramId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ProcessedSignatures::Slot)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ProcessedSignatures::ProcessedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The primary key already indexes the signature, this one serves
+        // lookups and cleanups by slot
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_processed_signatures_slot")
+                    .table(ProcessedSignatures::Table)
+                    .col(ProcessedSignatures::Slot)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ProcessedSignatures::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ProcessedSignatures {
+    Table,
+    Signature,
+    ProgramId,
+    Slot,
+    ProcessedAt,
+}
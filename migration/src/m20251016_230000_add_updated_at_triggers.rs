@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+
+/// Tables with an `updated_at` column, bumped by the trigger on every UPDATE.
+/// The blockscout tables the indexer writes, `twine_transaction_batch_detail`
+/// among them, are left out: their schema belongs to blockscout's own
+/// migrations, which these never run against. Every update the indexer makes
+/// to them sets `updated_at` explicitly instead.
+const TABLES: [&str; 4] = [
+    "source_transactions",
+    "transaction_flows",
+    "uniswap_tokens",
+    "svm_cursor",
+];
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // `updated_at` was only current when the writing code remembered to
+        // set it, the trigger keeps it current whatever the code path
+        db.execute_unprepared(
+            r#"
+            CREATE OR REPLACE FUNCTION set_updated_at()
+            RETURNS TRIGGER AS $$
+            BEGIN
+                NEW.updated_at = CURRENT_TIMESTAMP;
+                RETURN NEW;
+            END;
+            $$ LANGUAGE plpgsql;
+            "#,
+        )
+        .await?;
+
+        for table in TABLES {
+            db.execute_unprepared(&format!(
+                r#"
+                DROP TRIGGER IF EXISTS trg_{table}_set_updated_at ON {table};
+                CREATE TRIGGER trg_{table}_set_updated_at
+                    BEFORE UPDATE ON {table}
+                    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+                "#
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        for table in TABLES {
+            db.execute_unprepared(&format!(
+                "DROP TRIGGER IF EXISTS trg_{table}_set_updated_at ON {table};"
+            ))
+            .await?;
+        }
+        db.execute_unprepared("DROP FUNCTION IF EXISTS set_updated_at();")
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Finalizations seen before their batch was committed, applied once
+        // the commit lands
+        manager
+            .create_table(
+                Table::create()
+                    .table(PendingFinalizations::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PendingFinalizations::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(PendingFinalizations::ChainId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PendingFinalizations::BatchNumber)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PendingFinalizations::FinalizeHash)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(PendingFinalizations::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .index(
+                        Index::create()
+                            .name("uq_pending_finalizations_chain_batch")
+                            .col(PendingFinalizations::ChainId)
+                            .col(PendingFinalizations::BatchNumber)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Commits look their batch up, whichever chain finalized it
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_pending_finalizations_batch_number")
+                    .table(PendingFinalizations::Table)
+                    .col(PendingFinalizations::BatchNumber)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PendingFinalizations::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PendingFinalizations {
+    Table,
+    Id,
+    ChainId,
+    BatchNumber,
+    FinalizeHash,
+    CreatedAt,
+}
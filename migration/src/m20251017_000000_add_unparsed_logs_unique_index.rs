@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // A log re-read after a restart or a replay used to be dead-lettered
+        // again, keep its first row only
+        db.execute_unprepared(
+            r#"
+            DELETE FROM unparsed_logs newer
+            USING unparsed_logs older
+            WHERE newer.chain_id = older.chain_id
+              AND newer.transaction_hash = older.transaction_hash
+              AND newer.log_index = older.log_index
+              AND newer.id > older.id;
+            "#,
+        )
+        .await?;
+
+        // Solana rows have no log index and stay unconstrained, NULLs never
+        // conflict
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_unparsed_logs_chain_tx_log_index")
+                    .table(UnparsedLogs::Table)
+                    .col(UnparsedLogs::ChainId)
+                    .col(UnparsedLogs::TransactionHash)
+                    .col(UnparsedLogs::LogIndex)
+                    .unique()
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_unparsed_logs_chain_tx_log_index")
+                    .table(UnparsedLogs::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum UnparsedLogs {
+    Table,
+    ChainId,
+    TransactionHash,
+    LogIndex,
+}
@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // L1 blocks the commit and finalize of a batch were logged at, so the
+        // blockscout rows they wrote can be undone when those blocks reorg
+        manager
+            .create_table(
+                Table::create()
+                    .table(BatchL1Blocks::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BatchL1Blocks::ChainId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BatchL1Blocks::BatchNumber)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(BatchL1Blocks::CommitBlockNumber).big_integer())
+                    .col(ColumnDef::new(BatchL1Blocks::FinalizeBlockNumber).big_integer())
+                    .primary_key(
+                        Index::create()
+                            .col(BatchL1Blocks::ChainId)
+                            .col(BatchL1Blocks::BatchNumber),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Buffered finalizations are dropped with the blocks they came from.
+        // Rows buffered before this column existed are left alone.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PendingFinalizations::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(PendingFinalizations::BlockNumber).big_integer(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PendingFinalizations::Table)
+                    .drop_column(PendingFinalizations::BlockNumber)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(BatchL1Blocks::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum BatchL1Blocks {
+    Table,
+    ChainId,
+    BatchNumber,
+    CommitBlockNumber,
+    FinalizeBlockNumber,
+}
+
+#[derive(DeriveIden)]
+enum PendingFinalizations {
+    Table,
+    BlockNumber,
+}